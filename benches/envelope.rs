@@ -0,0 +1,108 @@
+//! Compare memory usage of the envelope serialization paths.
+//!
+//! ```bash
+//! cargo bench --bench envelope
+//! ```
+//!
+//! The tree path builds the whole envelope as a JSON value and
+//! serializes it, the raw path serializes the payload once and
+//! embeds it into the envelope as is. Both paths produce the
+//! same bytes. Reported peak is the maximal amount of heap
+//! memory allocated at once during serialization.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use serde_json::{json, Value as Json};
+
+use hyperelm::client::{canonical_json, canonical_envelope, raw_canonical_json};
+
+struct CountingAllocator;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static TOTAL: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let current = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+
+        PEAK.fetch_max(current, Ordering::Relaxed);
+        TOTAL.fetch_add(layout.size(), Ordering::Relaxed);
+
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn measure(name: &str, iterations: usize, f: impl Fn() -> Vec<u8>) -> Vec<u8> {
+    let baseline = CURRENT.load(Ordering::Relaxed);
+
+    PEAK.store(baseline, Ordering::Relaxed);
+    TOTAL.store(0, Ordering::Relaxed);
+
+    let started_at = Instant::now();
+
+    let mut result = Vec::new();
+
+    for _ in 0..iterations {
+        result = std::hint::black_box(f());
+    }
+
+    let elapsed = started_at.elapsed() / iterations as u32;
+
+    let peak = PEAK.load(Ordering::Relaxed) - baseline;
+    let total = TOTAL.load(Ordering::Relaxed) / iterations;
+
+    println!("{name:>6}: {elapsed:?} per envelope, {} KB peak, {} KB allocated per envelope", peak / 1024, total / 1024);
+
+    result
+}
+
+fn main() {
+    for size in [16 * 1024, 256 * 1024, 1024 * 1024] {
+        let text = "a".repeat(size);
+
+        // User payload is converted to JSON in both paths
+        let payload = || -> Json {
+            json!({
+                "text": text.clone(),
+                "chunks": (0..size / 1024).collect::<Vec<_>>()
+            })
+        };
+
+        println!("Payload of {} KB:", size / 1024);
+
+        let tree = measure("tree", 20, || {
+            let envelope = json!({
+                "message": payload(),
+                "priority": 0,
+                "nonce": 123
+            });
+
+            canonical_json(&envelope).unwrap()
+        });
+
+        let raw = measure("raw", 20, || {
+            let envelope = json!({
+                "priority": 0,
+                "nonce": 123
+            });
+
+            let payload = raw_canonical_json(&payload()).unwrap();
+
+            canonical_envelope(&envelope, "message", &payload).unwrap()
+        });
+
+        assert_eq!(tree, raw, "serialization paths must produce the same bytes");
+    }
+}
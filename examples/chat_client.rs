@@ -0,0 +1,114 @@
+//! Chat client connecting to the `chat_server` example.
+//!
+//! ```bash
+//! cargo run --example chat_server -- chat
+//! cargo run --example chat_client -- chat
+//! ```
+//!
+//! Every line read from stdin is sent to the peer with
+//! the public key given as the second argument, or to
+//! the client itself if it's not set.
+
+use std::sync::Arc;
+
+use hyperelm::prelude::*;
+use hyperelm::scaffold;
+use hyperelm::http::StatusHttpClient;
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum ChatRequest {
+    Ping
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum ChatResponse {
+    Pong
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum ChatMessage {
+    Text(String)
+}
+
+hyperborealib::impl_as_json!(ChatRequest ChatResponse ChatMessage);
+
+struct ChatClient {
+    params: ClientAppParams,
+    runtime: ClientRuntime,
+    middleware: ClientMiddleware<StatusHttpClient>
+}
+
+impl ClientApp for ChatClient {
+    build_client!(
+        input: ChatRequest => ChatResponse, ChatMessage;
+        output: ChatRequest => ChatResponse, ChatMessage;
+
+        client: StatusHttpClient;
+        state: ();
+        error: std::io::Error;
+
+        requests: {
+            ChatRequest::Ping => |_, _| async {
+                Ok(ChatResponse::Pong)
+            }
+        };
+
+        messages: {
+            ChatMessage::Text(text) => |_, ctx: MessageContext<Self>| async move {
+                println!("[{}] {text}", ctx.info().sender.client.public_key.to_base64());
+
+                Ok(())
+            }
+        };
+    );
+
+    fn get_params(&self) -> &ClientAppParams {
+        &self.params
+    }
+
+    fn get_runtime(&self) -> &ClientRuntime {
+        &self.runtime
+    }
+
+    fn get_middleware(&self) -> &ClientMiddleware<Self::HttpClient> {
+        &self.middleware
+    }
+
+    fn get_state(&self) -> Arc<Self::State> {
+        Arc::new(())
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::args().nth(1)
+        .unwrap_or_else(|| String::from("chat"));
+
+    let params = scaffold::load_client_params(std::path::Path::new(&dir).join("client.json"))?
+        .build()
+        .ok_or("Invalid client params")?;
+
+    let middleware = params.build_middleware()?;
+
+    let peer = match std::env::args().nth(2) {
+        Some(key) => PublicKey::from_base64(key)?,
+        None => params.identity.secret().public()
+    };
+
+    let endpoint = ClientEndpoint::new(&params.server_address, peer);
+
+    let client = hyperelm::client::run(ChatClient {
+        runtime: ClientRuntime::new(&params)?,
+        params,
+        middleware
+    }).await?;
+
+    for line in std::io::stdin().lines() {
+        client.send(endpoint.clone(), ChatMessage::Text(line?)).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+//! Chat server used by the `chat_client` example.
+//!
+//! ```bash
+//! cargo run --example chat_server -- chat
+//! ```
+//!
+//! Default params are generated in the given folder
+//! on the first run.
+
+use hyperelm::prelude::*;
+use hyperelm::scaffold;
+
+struct ChatServer(ServerAppParams);
+
+impl BasicServerApp for ChatServer {
+    fn get_params(&self) -> &ServerAppParams {
+        &self.0
+    }
+}
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let dir = std::env::args().nth(1)
+        .unwrap_or_else(|| String::from("chat"));
+
+    if !std::path::Path::new(&dir).join("server.json").exists() {
+        scaffold::write_default_params(&dir)?;
+    }
+
+    let params = scaffold::load_server_params(std::path::Path::new(&dir).join("server.json"))?;
+
+    println!("Server public key: {}", params.secret_key.public().to_base64());
+    println!("Listening on {}", params.local_addresses.join(", "));
+
+    hyperelm::server::run(ChatServer(params)).await
+        .map_err(|err| std::io::Error::other(format!("{err:?}")))
+}
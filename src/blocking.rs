@@ -0,0 +1,139 @@
+//! Blocking facade over the client applications
+//! for use in non-async environments.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::runtime::{Runtime, Builder};
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use crate::client::{ClientApp, ClientAppError, ClientEndpoint, LookupResult, MessageId};
+
+type MessageCallback = Box<dyn Fn(&MessageInfo) + Send + Sync>;
+
+/// Wrapper over the client application which owns
+/// a dedicated tokio runtime and exposes blocking methods.
+///
+/// Incoming messages are processed in the background
+/// using the application's handlers. Dropping the client
+/// will shut down its runtime.
+///
+/// Note that this struct must not be created or dropped
+/// from within an async context.
+pub struct BlockingClient<T> {
+    client: Arc<T>,
+    callbacks: Arc<Mutex<Vec<MessageCallback>>>,
+    runtime: Option<Runtime>
+}
+
+impl<T> BlockingClient<T>
+where
+    T: ClientApp + Send + Sync + 'static,
+    T::Error: std::fmt::Display
+{
+    /// Start the client application in a dedicated runtime.
+    pub fn new(app: T) -> std::io::Result<Self> {
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("hyperelm-blocking-client")
+            .enable_all()
+            .build()?;
+
+        let client = Arc::new(app);
+        let callbacks = Arc::new(Mutex::new(Vec::<MessageCallback>::new()));
+
+        // Start background updates task
+        {
+            let client = client.clone();
+            let callbacks = callbacks.clone();
+
+            runtime.spawn(async move {
+                let params = client.get_params();
+
+                loop {
+                    if let Err(_err) = Self::update(&client, &callbacks).await {
+                        #[cfg(feature = "tracing")]
+                        tracing::error!("[client] Update error: {_err}");
+                    }
+
+                    tokio::time::sleep(params.delay).await;
+                }
+            });
+        }
+
+        Ok(Self {
+            client,
+            callbacks,
+            runtime: Some(runtime)
+        })
+    }
+
+    async fn update(client: &T, callbacks: &Mutex<Vec<MessageCallback>>) -> Result<(), ClientAppError<T::Error>> {
+        let runtime = client.get_runtime();
+
+        client.fetch_messages().await?;
+
+        while let Some((message, content)) = runtime.incoming_queue.pop() {
+            if let Ok(callbacks) = callbacks.lock() {
+                for callback in callbacks.iter() {
+                    callback(&message);
+                }
+            }
+
+            client.process_message(message, content).await?;
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn runtime(&self) -> &Runtime {
+        // Runtime is taken only when the client is dropped
+        self.runtime.as_ref().expect("Runtime is already shut down")
+    }
+
+    /// Get wrapped client application.
+    #[inline]
+    pub fn client(&self) -> &Arc<T> {
+        &self.client
+    }
+
+    /// Call given callback for every incoming message
+    /// before it is processed by the application.
+    pub fn on_message(&self, callback: impl Fn(&MessageInfo) + Send + Sync + 'static) {
+        if let Ok(mut callbacks) = self.callbacks.lock() {
+            callbacks.push(Box::new(callback));
+        }
+    }
+
+    /// Perform client searching in the network.
+    pub fn lookup(&self, public_key: PublicKey, client_type: Option<ClientType>) -> Result<Option<ClientEndpoint>, ClientAppError<T::Error>> {
+        self.runtime().block_on(self.client.lookup(public_key, client_type))
+    }
+
+    /// Same as `lookup`, but returns found client
+    /// and server records.
+    pub fn lookup_full(&self, public_key: PublicKey, client_type: Option<ClientType>) -> Result<Option<LookupResult>, ClientAppError<T::Error>> {
+        self.runtime().block_on(self.client.lookup_full(public_key, client_type))
+    }
+
+    /// Send request to given endpoint.
+    pub fn request(&self, endpoint: ClientEndpoint, request: T::OutputRequest) -> Result<T::OutputResponse, ClientAppError<T::Error>> {
+        self.runtime().block_on(self.client.request(endpoint, request))
+    }
+
+    /// Send message to given endpoint.
+    pub fn send(&self, endpoint: ClientEndpoint, message: T::OutputMessage) -> Result<MessageId, ClientAppError<T::Error>> {
+        self.runtime().block_on(self.client.send(endpoint, message))
+    }
+}
+
+impl<T> Drop for BlockingClient<T> {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_timeout(Duration::from_secs(1));
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::collections::HashSet;
+
+use hyperborealib::crypto::asymmetric::PublicKey;
+
+/// Access control list of the messaging channel.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ChannelAcl {
+    /// Senders allowed to send messages to the channel.
+    ///
+    /// Everybody is allowed if not set.
+    pub allowed: Option<HashSet<PublicKey>>,
+
+    /// Senders which can't send messages to the channel.
+    pub blocked: HashSet<PublicKey>
+}
+
+impl ChannelAcl {
+    /// Allow only listed senders.
+    pub fn allow(senders: impl IntoIterator<Item = PublicKey>) -> Self {
+        Self {
+            allowed: Some(senders.into_iter().collect()),
+            blocked: HashSet::new()
+        }
+    }
+
+    /// Block listed senders.
+    pub fn block(senders: impl IntoIterator<Item = PublicKey>) -> Self {
+        Self {
+            allowed: None,
+            blocked: senders.into_iter().collect()
+        }
+    }
+
+    /// Check if given sender can send messages to the channel.
+    pub fn is_allowed(&self, sender: &PublicKey) -> bool {
+        if self.blocked.contains(sender) {
+            return false;
+        }
+
+        match &self.allowed {
+            Some(allowed) => allowed.contains(sender),
+            None => true
+        }
+    }
+}
@@ -1,12 +1,31 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
 use serde_json::{json, Value as Json};
 
+use futures::stream::{self, BoxStream, FuturesUnordered, StreamExt};
+
+#[cfg(feature = "group-encryption")]
+use base64::Engine;
+
+#[cfg(feature = "group-encryption")]
+use base64::engine::general_purpose::STANDARD as BASE64;
+
 use hyperborealib::exports::tokio;
 
 use hyperborealib::crypto::prelude::*;
+use hyperborealib::drivers::prelude::*;
 use hyperborealib::rest_api::prelude::*;
 
 use hyperborealib::http::HttpClient;
 
+#[cfg(feature = "opentelemetry")]
+use opentelemetry::trace::FutureExt;
+
+use crate::server::{relay_channel, fetch_startup_token, MulticastEnvelope, MulticastResponse};
+
 use super::*;
 
 #[derive(Debug, thiserror::Error)]
@@ -23,10 +42,257 @@ pub enum ClientAppError<E: Send + Sync> {
     #[error(transparent)]
     MessagesError(#[from] MessagesError),
 
+    #[cfg(feature = "group-encryption")]
+    #[error(transparent)]
+    Group(#[from] GroupError),
+
+    #[error(transparent)]
+    ChannelConflict(#[from] ChannelConflict),
+
+    #[error(transparent)]
+    Pagination(#[from] PaginationError),
+
+    #[error("Validation error: {0}")]
+    Validation(ValidationError),
+
+    #[error(transparent)]
+    Remote(RemoteError),
+
+    #[error("Interceptor error: {0}")]
+    Interceptor(InterceptorError),
+
+    #[error("Message is larger than the server limit of {server_limit} bytes")]
+    PayloadTooLarge {
+        server_limit: usize
+    },
+
+    #[error("Too many in-flight requests")]
+    Overloaded,
+
+    #[error("Outbound rate limit exceeded, retry after {retry_after:?}")]
+    RateLimited {
+        retry_after: Duration
+    },
+
+    #[error("Priority message sender is stopped")]
+    SenderStopped,
+
+    #[error("Request handler timed out")]
+    Timeout,
+
+    #[error("Server address of the pinned peer changed from {previous} to {current}")]
+    EndpointChanged {
+        previous: String,
+        current: String
+    },
+
+    #[error("Circuit of the server {address} is open")]
+    CircuitOpen {
+        address: String
+    },
+
+    #[error("Field {field} of the incoming message must be {expected}, got {got}")]
+    SchemaViolation {
+        field: String,
+        expected: String,
+        got: String
+    },
+
+    #[error("File transfer error: {0}")]
+    FileTransfer(std::io::Error),
+
+    #[error("Failed to multicast message: {0}")]
+    Multicast(String),
+
+    #[error("Peer rejected the file transfer")]
+    FileRejected,
+
+    #[error("State store is not set")]
+    NoStateStore,
+
+    #[error("State store error: {0}")]
+    StateStore(std::io::Error),
+
+    #[error("Failed to reconnect to the server after {attempts} attempts")]
+    ReconnectFailed {
+        attempts: u32
+    },
+
+    #[error("Client middleware is not connected with the session key {session}")]
+    SessionKeyMismatch {
+        session: String
+    },
+
+    #[error("Request {id} sent by another caller failed: {reason}")]
+    DeduplicatedRequest {
+        id: RequestId,
+        reason: String
+    },
+
+    #[error("{}", describe_contextual(.error, .peer.as_ref(), .channel.as_deref()))]
+    Contextual {
+        /// Peer the failed operation was related to.
+        peer: Option<PublicKey>,
+
+        /// Channel the failed operation was related to.
+        channel: Option<String>,
+
+        error: Box<ClientAppError<E>>
+    },
+
     #[error(transparent)]
     Custom(E)
 }
 
+impl<E: Send + Sync> ClientAppError<E> {
+    /// Attach peer the error is related to.
+    ///
+    /// Context which is already set is kept.
+    ///
+    /// ```rust
+    /// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+    /// use hyperelm::client::ClientAppError;
+    ///
+    /// let peer = SecretKey::random().public();
+    ///
+    /// let error = ClientAppError::<std::io::Error>::Timeout
+    ///     .with_peer(&peer)
+    ///     .with_channel("hyperelm/default");
+    ///
+    /// assert_eq!(error.peer(), Some(&peer));
+    /// assert_eq!(error.channel(), Some("hyperelm/default"));
+    /// assert!(matches!(error.into_inner(), ClientAppError::Timeout));
+    /// ```
+    pub fn with_peer(self, peer: &PublicKey) -> Self {
+        match self {
+            Self::Contextual { peer: None, channel, error } => Self::Contextual {
+                peer: Some(peer.clone()),
+                channel,
+                error
+            },
+
+            Self::Contextual { .. } => self,
+
+            error => Self::Contextual {
+                peer: Some(peer.clone()),
+                channel: None,
+                error: Box::new(error)
+            }
+        }
+    }
+
+    /// Attach channel the error is related to.
+    ///
+    /// Context which is already set is kept.
+    pub fn with_channel(self, channel: impl ToString) -> Self {
+        match self {
+            Self::Contextual { peer, channel: None, error } => Self::Contextual {
+                peer,
+                channel: Some(channel.to_string()),
+                error
+            },
+
+            Self::Contextual { .. } => self,
+
+            error => Self::Contextual {
+                peer: None,
+                channel: Some(channel.to_string()),
+                error: Box::new(error)
+            }
+        }
+    }
+
+    /// Peer the error is related to, if known.
+    pub fn peer(&self) -> Option<&PublicKey> {
+        match self {
+            Self::Contextual { peer, .. } => peer.as_ref(),
+            _ => None
+        }
+    }
+
+    /// Channel the error is related to, if known.
+    pub fn channel(&self) -> Option<&str> {
+        match self {
+            Self::Contextual { channel, .. } => channel.as_deref(),
+            _ => None
+        }
+    }
+
+    /// Get the error without its context.
+    pub fn inner(&self) -> &Self {
+        match self {
+            Self::Contextual { error, .. } => error.inner(),
+            error => error
+        }
+    }
+
+    /// Remove context of the error.
+    pub fn into_inner(self) -> Self {
+        match self {
+            Self::Contextual { error, .. } => error.into_inner(),
+            error => error
+        }
+    }
+}
+
+/// Attach context to the errors of the client methods.
+pub trait ClientAppErrorContext {
+    fn with_peer(self, peer: &PublicKey) -> Self;
+    fn with_channel(self, channel: impl ToString) -> Self;
+}
+
+impl<T, E: Send + Sync> ClientAppErrorContext for Result<T, ClientAppError<E>> {
+    #[inline]
+    fn with_peer(self, peer: &PublicKey) -> Self {
+        self.map_err(|err| err.with_peer(peer))
+    }
+
+    #[inline]
+    fn with_channel(self, channel: impl ToString) -> Self {
+        self.map_err(|err| err.with_channel(channel))
+    }
+}
+
+/// `<error> (peer: abcdefgh, channel: ...)`
+fn describe_contextual<E: Send + Sync>(error: &ClientAppError<E>, peer: Option<&PublicKey>, channel: Option<&str>) -> String
+where
+    ClientAppError<E>: std::fmt::Display
+{
+    let mut context = Vec::with_capacity(2);
+
+    if let Some(peer) = peer {
+        context.push(format!("peer: {}", peer.to_base64().chars().take(8).collect::<String>()));
+    }
+
+    if let Some(channel) = channel {
+        context.push(format!("channel: {channel}"));
+    }
+
+    if context.is_empty() {
+        error.to_string()
+    } else {
+        format!("{error} ({})", context.join(", "))
+    }
+}
+
+/// Find `413 Payload Too Large` server response with
+/// `{ "error": "payload_too_large", "limit": N }` body
+/// in the error sources and return the limit.
+///
+/// Server responses are reported by the `StatusHttpClient`.
+fn payload_too_large_limit(error: &(dyn std::error::Error + 'static)) -> Option<usize> {
+    let error = HttpStatusError::find(error)?;
+
+    if error.status != 413 {
+        return None;
+    }
+
+    error.body.as_ref()?
+        .get("limit")?
+        .as_u64()
+        .map(|limit| limit as usize)
+}
+
 #[async_trait::async_trait]
 pub trait ClientApp {
     /// Request which can be received from other clients.
@@ -47,200 +313,2833 @@ pub trait ClientApp {
     /// Message which can be sent to other clients.
     type OutputMessage: AsJson + Send;
 
-    type HttpClient: HttpClient;
+    type HttpClient: HttpClient + Send + Sync + 'static;
     type State;
     type Error: Send + Sync;
 
     /// Get params of the client app.
     fn get_params(&self) -> &ClientAppParams;
 
+    /// Get runtime state of the client app.
+    ///
+    /// Runtime should be created once from the app's
+    /// params using the `ClientRuntime::new` method.
+    fn get_runtime(&self) -> &ClientRuntime;
+
     /// Get client app HTTP REST API client middleware.
     fn get_middleware(&self) -> &ClientMiddleware<Self::HttpClient>;
 
     /// Get client app's state.
     fn get_state(&self) -> Arc<Self::State>;
 
+    /// Get round trip time statistics of the connected server.
+    ///
+    /// Updated by `request` and `ping` calls.
+    #[inline]
+    fn get_latency_tracker(&self) -> Arc<LatencyTracker> {
+        self.get_runtime().latency_tracker.clone()
+    }
+
     /// Get connected client middleware.
     ///
     /// It is highly recommended to re-implement this method
     /// to reuse some local cache with some TTL.
+    ///
+    /// If `warmup_window` param is set, failed connections
+    /// are retried with exponential backoff within this window.
     async fn get_connected_middleware(&self) -> Result<ConnectedClientMiddleware<Self::HttpClient>, ClientAppError<Self::Error>> {
         let params = self.get_params();
 
-        Ok(self.get_middleware().connect_to(
-            &params.server_address,
-            params.server_public.clone()
-        ).await?)
+        let started_at = Instant::now();
+        let mut backoff = Duration::from_millis(100);
+
+        loop {
+            let result = self.get_middleware().connect_to(
+                &params.server_address,
+                params.server_public.clone()
+            ).await;
+
+            match result {
+                Ok(middleware) => {
+                    self.notify_connected().await;
+
+                    return Ok(middleware);
+                }
+
+                Err(err) => match params.warmup_window {
+                    Some(window) if started_at.elapsed() + backoff < window => {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!("[client] Failed to connect to the server, retrying in {backoff:?}: {err}");
+
+                        tokio::time::sleep(backoff).await;
+
+                        backoff = (backoff * 2).min(Duration::from_secs(2));
+                    }
+
+                    _ => {
+                        self.notify_disconnected(&err).await;
+
+                        return Err(err.into());
+                    }
+                }
+            }
+        }
     }
 
-    /// Perform client searching in the network.
-    async fn lookup(&self, public_key: PublicKey, client_type: Option<ClientType>) -> Result<Option<ClientEndpoint>, ClientAppError<Self::Error>> {
-        let result = self.get_connected_middleware().await?
-            .lookup(public_key, client_type).await?
-            .map(|(client, server, _)| {
-                ClientEndpoint {
-                    server_address: server.address,
-                    client_public: client.public_key
+    /// Initialize the application before processing
+    /// any incoming message.
+    ///
+    /// Called once by the `run` and `run_with_shutdown` functions,
+    /// which fail if this method fails. Use it to open databases
+    /// or fetch configs.
+    ///
+    /// Unlike the `warmup_window` param, which only retries
+    /// failed connections to the server, this method performs
+    /// arbitrary application-specific work and is not retried.
+    /// Does nothing by default.
+    async fn init(&self) -> Result<(), ClientAppError<Self::Error>> {
+        Ok(())
+    }
+
+    /// Spawn background tasks of the application
+    /// like heartbeats or periodic syncs.
+    ///
+    /// Called once after `init`. Does nothing by default.
+    async fn start_background_tasks(&self) {}
+
+    /// Mark connection to the server as established, calling
+    /// `on_connected` or `on_reconnected` hook if it was not.
+    async fn notify_connected(&self) {
+        let params = self.get_params();
+        let runtime = self.get_runtime();
+
+        let previous = runtime.connection.connect();
+
+        if previous != ConnectionState::Connected {
+            if let Some(handler) = &params.event_handler {
+                handler.on_connected(&params.server_endpoint()).await;
+            }
+
+            self.record_server_fingerprint().await;
+        }
+
+        match previous {
+            ConnectionState::NotConnected => {
+                let server = ServerInfo {
+                    public_key: params.server_public.clone(),
+                    address: params.server_address.clone()
+                };
+
+                self.on_connected(&server).await;
+            }
+
+            ConnectionState::Disconnected => self.on_reconnected().await,
+            ConnectionState::Connected => ()
+        }
+    }
+
+    /// Remember fingerprint of the just connected server
+    /// if restart detection is enabled.
+    ///
+    /// Calls `on_server_restarted` if the server was restarted
+    /// while the client was disconnected from it.
+    async fn record_server_fingerprint(&self) {
+        let detector = &self.get_runtime().restart_detector;
+
+        if detector.interval().is_none() {
+            return;
+        }
+
+        match self.server_fingerprint().await {
+            Ok(fingerprint) => {
+                if detector.observe(fingerprint) {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!("[client] Server was restarted while disconnected");
+
+                    self.on_server_restarted().await;
                 }
-            });
+            }
 
-        Ok(result)
+            // Checked again by the `check_server_restart` method
+            Err(_) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!("[client] Failed to get server fingerprint");
+            }
+        }
     }
 
-    /// Send request to given endpoint.
-    async fn request(&self, endpoint: ClientEndpoint, request: Self::OutputRequest) -> Result<Self::OutputResponse, ClientAppError<Self::Error>> {
+    /// Mark connection to the server as lost, calling
+    /// `on_disconnected` hook if it was established.
+    async fn notify_disconnected(&self, err: &MiddlewareError) {
         let params = self.get_params();
-        let middleware = self.get_connected_middleware().await?;
+        let runtime = self.get_runtime();
 
-        // Prepare request
-        let request_id = safe_random_u64();
+        if runtime.connection.disconnect() {
+            if let Some(handler) = &params.event_handler {
+                handler.on_disconnected(&params.server_endpoint(), DisconnectReason::ConnectionLost(err.to_string())).await;
+            }
 
-        let request = json!({
-            "id": request_id,
-            "request": request.to_json()?
-        });
+            self.on_disconnected(err).await;
+        }
+    }
 
-        // Send request
-        let request = Message::create(
-            &params.client_secret,
-            &endpoint.client_public,
-            serde_json::to_vec(&request)?,
-            params.encoding,
-            params.compression_level
-        )?;
+    /// Reconnect to the server using the `reconnect_policy` param.
+    ///
+    /// Waits before each attempt so that clients disconnected
+    /// at the same time don't reconnect all at once. Calls
+    /// `on_reconnect_failed` hooks if all the attempts failed.
+    async fn reconnect(&self) -> Result<ConnectedClientMiddleware<Self::HttpClient>, ClientAppError<Self::Error>> {
+        let params = self.get_params();
+        let policy = &params.reconnect_policy;
 
-        middleware.send(
-            endpoint.server_address,
-            endpoint.client_public,
-            &params.channel,
-            request
-        ).await?;
+        let mut attempts = 0;
 
-        // Receive response
         loop {
-            let (messages, _) = middleware.poll(
-                format!("{}@{request_id}", params.channel),
-                Some(1)
-            ).await?;
+            tokio::time::sleep(policy.delay(attempts)).await;
+
+            if let Some(handler) = &params.event_handler {
+                handler.on_reconnecting(&params.server_endpoint(), attempts + 1).await;
+            }
+
+            let result = self.get_middleware().connect_to(
+                &params.server_address,
+                params.server_public.clone()
+            ).await;
 
-            // If there's an incoming message
-            if let Some(message) = messages.first() {
-                // Decode the message and verify its validity
-                let response = message.message.read(
-                    &params.client_secret,
-                    &message.sender.client.public_key
-                )?;
+            attempts += 1;
 
-                // Deserialize it and return
-                let response = serde_json::from_slice::<Json>(&response)?;
+            match result {
+                Ok(middleware) => {
+                    self.notify_connected().await;
 
-                let response = Self::OutputResponse::from_json(&response)?;
+                    return Ok(middleware);
+                }
+
+                Err(err) if policy.can_retry(attempts) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!("[client] Reconnection attempt {attempts} failed: {err}");
+
+                    self.notify_disconnected(&err).await;
+                }
 
-                return Ok(response);
+                Err(_) => {
+                    if let Some(handler) = &params.event_handler {
+                        handler.on_reconnect_failed(&params.server_endpoint(), attempts).await;
+                    }
+
+                    self.on_reconnect_failed(attempts).await;
+
+                    return Err(ClientAppError::ReconnectFailed {
+                        attempts
+                    });
+                }
             }
+        }
+    }
+
+    /// Get fingerprint of the connected server.
+    ///
+    /// Changed fingerprint means that the server was restarted.
+    /// By default it's the startup token reported by hyperelm
+    /// servers, or a hash of the server info response for
+    /// the other ones.
+    async fn server_fingerprint(&self) -> Result<u64, ClientAppError<Self::Error>> {
+        let params = self.get_params();
+        let middleware = self.get_middleware();
 
-            // Sleep otherwise and try again
-            tokio::time::sleep(params.delay).await;
+        match fetch_startup_token(middleware.http_client_ref(), &params.server_address).await {
+            Ok(token) => return Ok(token),
+
+            Err(_err) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("[client] Server doesn't report startup token, using info hash: {_err}");
+            }
         }
+
+        let info = middleware
+            .get_info(&params.server_address).await?;
+
+        let info = canonical_json(&info.to_json()?)?;
+
+        Ok(message_id(&params.server_public, &info, 0))
     }
 
-    /// Send message to given endpoint.
-    async fn send(&self, endpoint: ClientEndpoint, message: Self::OutputMessage) -> Result<(), ClientAppError<Self::Error>> {
+    /// Check if the connected server was restarted
+    /// if the `restart_check_interval` is elapsed.
+    ///
+    /// Reconnects to the restarted server if `auto_reconnect`
+    /// param is enabled and calls `on_server_restarted`.
+    async fn check_server_restart(&self) -> Result<bool, ClientAppError<Self::Error>> {
+        let detector = &self.get_runtime().restart_detector;
+
+        if !detector.is_due() {
+            return Ok(false);
+        }
+
+        let fingerprint = self.server_fingerprint().await?;
+
+        if !detector.observe(fingerprint) {
+            return Ok(false);
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::warn!("[client] Server restart detected");
+
         let params = self.get_params();
-        let middleware = self.get_connected_middleware().await?;
+        let runtime = self.get_runtime();
 
-        // Prepare message
-        let message = json!({
-            "message": message.to_json()?
-        });
+        if runtime.connection.disconnect() {
+            if let Some(handler) = &params.event_handler {
+                handler.on_disconnected(&params.server_endpoint(), DisconnectReason::ServerRestarted).await;
+            }
+        }
 
-        let message = Message::create(
-            &params.client_secret,
-            &endpoint.client_public,
-            serde_json::to_vec(&message)?,
-            params.encoding,
-            params.compression_level
-        )?;
+        if detector.auto_reconnect() {
+            self.reconnect().await?;
+        }
 
-        // Send message
-        middleware.send(
-            endpoint.server_address,
-            endpoint.client_public,
-            &params.channel,
-            message
-        ).await?;
+        self.on_server_restarted().await;
 
-        Ok(())
+        Ok(true)
     }
 
-    /// Try to poll a message from the connected hyperborea server.
-    async fn poll_message(&self) -> Result<Option<MessageInfo>, ClientAppError<Self::Error>> {
+    /// Perform client searching in the network.
+    ///
+    /// Resolved server address is compared with the pinned
+    /// one according to the `pin_policy` param.
+    async fn lookup(&self, public_key: PublicKey, client_type: Option<ClientType>) -> Result<Option<ClientEndpoint>, ClientAppError<Self::Error>> {
+        Ok(self.lookup_full(public_key, client_type).await?.map(ClientEndpoint::from))
+    }
+
+    /// Same as `lookup`, but returns found client and
+    /// server records instead of the endpoint only.
+    async fn lookup_full(&self, public_key: PublicKey, client_type: Option<ClientType>) -> Result<Option<LookupResult>, ClientAppError<Self::Error>> {
         let params = self.get_params();
+        let runtime = self.get_runtime();
 
-        // Implementers should poll all available messages and store them
-        // in a queue, polling from it and fulfilling it when it becomes empty.
-        let (mut messages, _) = self.get_connected_middleware().await?
-            .poll(&params.channel, Some(1)).await?;
+        let result = self.get_connected_middleware().await?
+            .lookup(public_key, client_type).await?
+            .map(LookupResult::from);
 
-        Ok(messages.pop())
+        let Some(result) = result else {
+            return Ok(None);
+        };
+
+        if params.pin_policy == PinPolicy::Off {
+            return Ok(Some(result));
+        }
+
+        let endpoint = ClientEndpoint::from(&result);
+
+        if let PinCheck::Changed { previous } = runtime.peer_pins.check(&endpoint.client_public, &endpoint.server_address) {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                "[client] Server address of the pinned peer {} changed from {previous} to {}",
+                endpoint.client_public.to_base64(),
+                endpoint.server_address
+            );
+
+            if params.pin_policy == PinPolicy::Strict {
+                return Err(ClientAppError::EndpointChanged {
+                    previous,
+                    current: endpoint.server_address
+                });
+            }
+
+            self.on_endpoint_changed(&endpoint.client_public, &previous, &endpoint.server_address).await?;
+        }
+
+        Ok(Some(result))
     }
 
-    /// Receive and process incoming messages.
-    async fn update(&self) -> Result<(), ClientAppError<Self::Error>> {
-        if let Some(message) = self.poll_message().await? {
-            let params = self.get_params();
+    /// Find client in the local peer cache, falling
+    /// back to the `lookup` method if it's not cached.
+    ///
+    /// Only verified peers of the cache are used, and their
+    /// addresses are compared with the pinned ones unless the
+    /// `pin_policy` param is `Off`. Cached peers of a different
+    /// known type are skipped. Endpoints found in the network
+    /// are added to the cache as verified.
+    async fn lookup_cached(&self, public_key: PublicKey, client_type: Option<ClientType>) -> Result<Option<ClientEndpoint>, ClientAppError<Self::Error>> {
+        let params = self.get_params();
+        let runtime = self.get_runtime();
+        let peer_cache = &runtime.peer_cache;
 
-            // Decode the message and verify its validity
-            let content = message.message.read(
-                &params.client_secret,
-                &message.sender.client.public_key
-            )?;
+        let cached = peer_cache.get(&public_key)
+            .filter(|_| peer_cache.is_verified(&public_key));
 
-            // Deserialize it and process
-            let content = serde_json::from_slice::<Json>(&content)?;
+        if let Some(endpoint) = cached {
+            let cached_type = peer_cache.client_type(&public_key);
 
-            // Handle request
-            if let Some(request) = content.get("request") {
-                if let Some(request_id) = content.get("id").and_then(Json::as_u64) {
-                    // Deserialize request
-                    let request = Self::InputRequest::from_json(request)?;
+            let matches = match (&client_type, &cached_type) {
+                (Some(client_type), Some(cached_type)) => client_type == cached_type,
+                _ => true
+            };
 
-                    // Process request
-                    let response = self.handle_request(request, message.clone()).await?;
+            // Resolve the peer again if its address differs from the pinned one
+            let pinned = params.pin_policy == PinPolicy::Off || !matches!(
+                runtime.peer_pins.check(&endpoint.client_public, &endpoint.server_address),
+                PinCheck::Changed { .. }
+            );
 
-                    // Send response
-                    let response = Message::create(
-                        &params.client_secret,
-                        &message.sender.client.public_key,
-                        serde_json::to_vec(&response.to_json()?)?,
-                        params.encoding,
-                        params.compression_level
-                    )?;
-
-                    self.get_connected_middleware().await?.send(
-                        &message.sender.server.address,
-                        message.sender.client.public_key,
-                        format!("{}@{request_id}", params.channel),
-                        response
-                    ).await?;
-                }
+            if matches && pinned {
+                return Ok(Some(endpoint));
             }
+        }
 
-            // Handle message
-            else if let Some(request) = content.get("message") {
-                let request = Self::InputMessage::from_json(request)?;
+        let Some(result) = self.lookup_full(public_key, client_type).await? else {
+            return Ok(None);
+        };
 
-                // Process message
-                self.handle_message(request, message).await?;
+        let endpoint = ClientEndpoint::from(&result);
+
+        peer_cache.insert(endpoint.clone());
+        peer_cache.set_client_type(&endpoint.client_public, result.client.info.client_type.clone());
+
+        Ok(Some(endpoint))
+    }
+
+    /// Connect to the server and resolve given peers in advance,
+    /// so requests to them don't wait for the connection and lookups.
+    ///
+    /// Peers are looked up using the `lookup_cached` method, which
+    /// puts found endpoints to the peer cache, and pinged if it's
+    /// enabled by the `warm_up` param. Warm up is limited by the
+    /// `warm_up` param's concurrency and timeout.
+    async fn warm_up(&self, peers: &[PublicKey]) -> WarmUpReport
+    where
+        Self::Error: std::fmt::Display
+    {
+        let policy = &self.get_params().warm_up;
+
+        if let Err(err) = self.get_connected_middleware().await {
+            return WarmUpReport {
+                peers: peers.iter()
+                    .map(|peer| (peer.clone(), WarmUpStatus::Failed(err.to_string())))
+                    .collect()
+            };
+        }
+
+        let warm_up_peer = |public_key: PublicKey| async move {
+            let status = match self.lookup_cached(public_key.clone(), None).await {
+                Ok(Some(endpoint)) if !policy.ping => WarmUpStatus::Resolved {
+                    endpoint,
+                    rtt: None
+                },
+
+                Ok(Some(endpoint)) => match self.ping_peer(&endpoint, policy.timeout).await {
+                    Ok(Some(rtt)) => WarmUpStatus::Resolved {
+                        endpoint,
+                        rtt: Some(rtt)
+                    },
+
+                    _ => WarmUpStatus::Unreachable {
+                        endpoint
+                    }
+                },
+
+                Ok(None) => WarmUpStatus::NotFound,
+                Err(err) => WarmUpStatus::Failed(err.to_string())
+            };
+
+            (public_key, status)
+        };
+
+        let mut report = WarmUpReport::default();
+
+        let _result = tokio::time::timeout(policy.timeout, async {
+            let mut results = stream::iter(peers.iter().cloned())
+                .map(warm_up_peer)
+                .buffer_unordered(policy.concurrency.max(1));
+
+            while let Some(result) = results.next().await {
+                report.peers.push(result);
+            }
+        }).await;
+
+        // Report peers which weren't resolved in time
+        for peer in peers {
+            if report.get(peer).is_none() {
+                report.peers.push((peer.clone(), WarmUpStatus::TimedOut));
             }
         }
 
-        Ok(())
+        report
     }
 
-    /// Handle incoming request.
-    async fn handle_request(&self, request: Self::InputRequest, info: MessageInfo) -> Result<Self::InputResponse, ClientAppError<Self::Error>>;
+    /// List peers from the local peer cache.
+    #[inline]
+    fn cached_peers(&self) -> Vec<ClientEndpoint> {
+        self.get_runtime().peer_cache.list()
+    }
 
-    /// Handle incoming message.
-    async fn handle_message(&self, message: Self::InputMessage, info: MessageInfo) -> Result<(), ClientAppError<Self::Error>>;
+    /// Request list of peers known by given endpoint.
+    ///
+    /// Sends `{ "id": N, "peer_list": { "filter": "..." }, "session": N }` envelope
+    /// and merges received peers into the local peer cache. Only
+    /// peers of the given type are returned if the filter is set.
+    async fn request_peer_list(&self, endpoint: ClientEndpoint, filter: Option<ClientType>) -> Result<Vec<ClientEndpoint>, ClientAppError<Self::Error>> {
+        request_peer_list(self, endpoint, filter).await
+    }
+
+    /// Pin server address of the peer, replacing the previous one.
+    ///
+    /// Use it to accept the changed address of the peer.
+    fn pin(&self, endpoint: &ClientEndpoint) -> std::io::Result<()> {
+        self.get_runtime().peer_pins.pin(endpoint.client_public.clone(), &endpoint.server_address)
+    }
+
+    /// Forget pinned server address of the peer.
+    fn unpin(&self, public_key: &PublicKey) -> std::io::Result<bool> {
+        self.get_runtime().peer_pins.unpin(public_key)
+    }
+
+    /// List pinned server addresses of the peers.
+    fn list_pins(&self) -> Vec<(PublicKey, PeerPin)> {
+        self.get_runtime().peer_pins.list()
+    }
+
+    /// Send request to given endpoint.
+    #[inline]
+    async fn request(&self, endpoint: ClientEndpoint, request: Self::OutputRequest) -> Result<Self::OutputResponse, ClientAppError<Self::Error>> {
+        self.request_with_priority(endpoint, request, DEFAULT_PRIORITY).await
+    }
+
+    /// Send request with given priority to given endpoint.
+    ///
+    /// Requests with higher priority are processed
+    /// by the receiver first.
+    #[inline]
+    async fn request_with_priority(&self, endpoint: ClientEndpoint, request: Self::OutputRequest, priority: u8) -> Result<Self::OutputResponse, ClientAppError<Self::Error>> {
+        self.request_with_options(endpoint, request, priority, None).await
+    }
+
+    /// Send request to given endpoint and wait
+    /// for the response at most `timeout`.
+    ///
+    /// The request expires after the timeout, so the
+    /// receiver drops it if it wasn't processed in time.
+    async fn request_with_timeout(&self, endpoint: ClientEndpoint, request: Self::OutputRequest, timeout: Duration) -> Result<Self::OutputResponse, ClientAppError<Self::Error>> {
+        tokio::time::timeout(timeout, self.request_with_options(endpoint, request, DEFAULT_PRIORITY, Some(timeout))).await
+            .map_err(|_| ClientAppError::Timeout)?
+    }
+
+    /// Send request with given priority and
+    /// optional time to live to given endpoint.
+    ///
+    /// Responses to the requests marked cacheable by the
+    /// `cache_policy` method are returned from the response
+    /// cache until their time to live expires.
+    async fn request_with_options(
+        &self,
+        endpoint: ClientEndpoint,
+        request: Self::OutputRequest,
+        priority: u8,
+        ttl: Option<Duration>
+    ) -> Result<Self::OutputResponse, ClientAppError<Self::Error>> {
+        let runtime = self.get_runtime();
+
+        let cache_ttl = self.cache_policy(&request);
+        let request = request.to_json()?;
+
+        let cache_key = match cache_ttl {
+            Some(_) => Some(canonical_json(&request)?),
+            None => None
+        };
+
+        // Return cached response
+        if let Some(key) = &cache_key {
+            if let Some(response) = runtime.response_cache.get(&endpoint.client_public, key) {
+                return Ok(Self::OutputResponse::from_json(&response)?);
+            }
+        }
+
+        let response = self.send_request(&endpoint, request, priority, ttl).await?;
+        let output = Self::OutputResponse::from_json(&response)?;
+
+        if let (Some(key), Some(cache_ttl)) = (cache_key, cache_ttl) {
+            runtime.response_cache.insert(&endpoint.client_public, key, response, cache_ttl);
+        }
+
+        Ok(output)
+    }
+
+    /// Send request with given id to given endpoint.
+    ///
+    /// If the request with the same id is in flight its response
+    /// is awaited instead of sending the request again, and if it
+    /// was completed within the `dedup_window` param its response
+    /// is returned. Failed requests are not remembered.
+    async fn request_with_id(&self, endpoint: ClientEndpoint, id: RequestId, request: Self::OutputRequest) -> Result<Self::OutputResponse, ClientAppError<Self::Error>>
+    where
+        Self::Error: std::fmt::Display
+    {
+        let runtime = self.get_runtime();
+
+        let response = match runtime.outgoing_requests.begin(id) {
+            DedupState::Completed(response) => response,
+
+            DedupState::InFlight(receiver) => OutgoingRequestDedup::wait(receiver).await
+                .map_err(|reason| ClientAppError::DeduplicatedRequest { id, reason })?,
+
+            DedupState::New(guard) => {
+                let result = self.send_request(&endpoint, request.to_json()?, DEFAULT_PRIORITY, None).await;
+
+                match &result {
+                    Ok(response) => guard.complete(Ok(response.clone())),
+                    Err(err) => guard.complete(Err(err.to_string()))
+                }
+
+                result?
+            }
+        };
+
+        Ok(Self::OutputResponse::from_json(&response)?)
+    }
+
+    /// Send request to given endpoint ignoring its cached
+    /// response, and cache the received one if the
+    /// request is cacheable.
+    async fn request_bypass_cache(&self, endpoint: ClientEndpoint, request: Self::OutputRequest) -> Result<Self::OutputResponse, ClientAppError<Self::Error>> {
+        let runtime = self.get_runtime();
+
+        let cache_ttl = self.cache_policy(&request);
+        let request = request.to_json()?;
+        let cache_key = canonical_json(&request)?;
+
+        let response = self.send_request(&endpoint, request, DEFAULT_PRIORITY, None).await?;
+        let output = Self::OutputResponse::from_json(&response)?;
+
+        if let Some(cache_ttl) = cache_ttl {
+            runtime.response_cache.insert(&endpoint.client_public, cache_key, response, cache_ttl);
+        }
+
+        Ok(output)
+    }
+
+    /// Stream items of the paginated response.
+    ///
+    /// Requests are built by `req_builder` from the cursor
+    /// of the previous page (`None` for the first one) and sent
+    /// until the response without cursor is received. Responses
+    /// must be serialized as `Paginated<T>`.
+    ///
+    /// The stream ends after the first error.
+    fn request_paginated<'a, T>(
+        &'a self,
+        endpoint: ClientEndpoint,
+        req_builder: impl Fn(Option<String>) -> Self::OutputRequest + Send + 'a
+    ) -> BoxStream<'a, Result<T, ClientAppError<Self::Error>>>
+    where
+        Self: Sync,
+        T: serde::de::DeserializeOwned + Send + 'a
+    {
+        struct PageState<T, F> {
+            endpoint: ClientEndpoint,
+            req_builder: F,
+            cursor: Option<String>,
+            items: VecDeque<T>,
+            finished: bool
+        }
+
+        let state = PageState {
+            endpoint,
+            req_builder,
+            cursor: None,
+            items: VecDeque::new(),
+            finished: false
+        };
+
+        stream::unfold(state, move |mut state| async move {
+            loop {
+                if let Some(item) = state.items.pop_front() {
+                    return Some((Ok(item), state));
+                }
+
+                if state.finished {
+                    return None;
+                }
+
+                // Stop streaming if the page can't be received
+                state.finished = true;
+
+                let request = (state.req_builder)(state.cursor.clone());
+
+                let response = match self.request(state.endpoint.clone(), request).await {
+                    Ok(response) => response,
+                    Err(err) => return Some((Err(err), state))
+                };
+
+                let page = response.to_json()
+                    .map_err(ClientAppError::from)
+                    .and_then(|response| {
+                        serde_json::from_value::<Paginated<T>>(response)
+                            .map_err(ClientAppError::from)
+                    });
+
+                let page = match page {
+                    Ok(page) => page,
+                    Err(err) => return Some((Err(err), state))
+                };
+
+                // Don't request the same page forever
+                if page.cursor.is_some() && page.cursor == state.cursor {
+                    let cursor = page.cursor.unwrap_or_default();
+
+                    return Some((Err(PaginationError::InvalidCursor(cursor).into()), state));
+                }
+
+                state.finished = page.cursor.is_none();
+                state.cursor = page.cursor;
+
+                state.items.extend(page.items);
+            }
+        }).boxed()
+    }
+
+    /// Send serialized request to given endpoint
+    /// and return its serialized response.
+    ///
+    /// Amount of the in-flight requests is limited by the
+    /// `max_inflight_requests` param. Only one of the pending
+    /// requests polls the server at a time, receiving
+    /// responses for all the others.
+    async fn send_request(
+        &self,
+        endpoint: &ClientEndpoint,
+        request: Json,
+        priority: u8,
+        ttl: Option<Duration>
+    ) -> Result<Json, ClientAppError<Self::Error>> {
+        let params = self.get_params();
+        let runtime = self.get_runtime();
+
+        // Reserve in-flight request slot
+        let _permit = runtime.inflight_requests.acquire().await
+            .map_err(|_| ClientAppError::Overloaded)?;
+
+        self.throttle(endpoint).await?;
+
+        let middleware = self.get_connected_middleware().await?;
+
+        // Prepare request
+        let request_id = safe_random_u64();
+
+        let session = runtime.inflight_requests.session();
+
+        let mut envelope = json!({
+            "id": request_id,
+            "priority": priority,
+            "nonce": monotonic_nonce(),
+            "session": session
+        });
+
+        if let Some(ttl) = ttl {
+            envelope["expires_at"] = json!(expires_at(ttl));
+        }
+
+        // Ask the receiver to use the shared replies channel
+        if params.reply_channel_strategy == ReplyChannelStrategy::Shared {
+            envelope["reply"] = json!("shared");
+        }
+
+        #[cfg(feature = "opentelemetry")]
+        inject_trace_context(&mut envelope);
+
+        let request = self.prepare_envelope(envelope, "request", request, endpoint).await?;
+
+        // Send request
+        let pending = runtime.inflight_requests.register(
+            request_id,
+            params.channel.reply_to_session(request_id, session)
+        );
+
+        let started_at = Instant::now();
+
+        self.send_raw_envelope(&middleware, endpoint, &params.channel, request).await?;
+
+        // Receive response
+        let response = self.receive_response(&middleware, request_id, pending).await
+            .with_peer(&endpoint.client_public)?;
+
+        // Surface error reported by the remote client
+        if let Some(error) = response.get("remote_error") {
+            let error = serde_json::from_value::<RemoteError>(error.clone())?;
+
+            return Err(ClientAppError::Remote(error).with_peer(&endpoint.client_public));
+        }
+
+        runtime.latency_tracker.record(started_at.elapsed());
+        runtime.presence.seen(&endpoint.client_public);
+
+        Ok(response)
+    }
+
+    /// Send multiple requests to given endpoint
+    /// packed into batch envelopes.
+    ///
+    /// Requests are split into batches according to the
+    /// `batch_max_items` and `batch_max_bytes` params. Each batch
+    /// is a single message with a single reply.
+    ///
+    /// Responses are returned in order of the requests. Failure
+    /// of a separate request is returned in place of its response,
+    /// while the outer error means that a batch couldn't be sent.
+    async fn request_batch(
+        &self,
+        endpoint: ClientEndpoint,
+        requests: Vec<Self::OutputRequest>
+    ) -> Result<Vec<Result<Self::OutputResponse, ClientAppError<Self::Error>>>, ClientAppError<Self::Error>> {
+        let params = self.get_params();
+
+        // Split requests into batches
+        let mut batches = Vec::new();
+        let mut batch = Vec::new();
+        let mut batch_size = 0;
+
+        for request in requests {
+            let request = request.to_json()?;
+            let size = serde_json::to_vec(&request)?.len();
+
+            if !batch.is_empty() && (batch.len() >= params.batch_max_items || batch_size + size > params.batch_max_bytes) {
+                batches.push(std::mem::take(&mut batch));
+
+                batch_size = 0;
+            }
+
+            batch.push(request);
+
+            batch_size += size;
+        }
+
+        if !batch.is_empty() {
+            batches.push(batch);
+        }
+
+        // Send batches
+        let mut responses = Vec::new();
+
+        for batch in batches {
+            responses.extend(self.send_batch(&endpoint, batch).await?);
+        }
+
+        Ok(responses)
+    }
+
+    /// Send single batch of the serialized requests
+    /// and receive responses to them.
+    async fn send_batch(
+        &self,
+        endpoint: &ClientEndpoint,
+        requests: Vec<Json>
+    ) -> Result<Vec<Result<Self::OutputResponse, ClientAppError<Self::Error>>>, ClientAppError<Self::Error>> {
+        let params = self.get_params();
+        let runtime = self.get_runtime();
+
+        // Reserve in-flight request slot
+        let _permit = runtime.inflight_requests.acquire().await
+            .map_err(|_| ClientAppError::Overloaded)?;
+
+        self.throttle(endpoint).await?;
+
+        let middleware = self.get_connected_middleware().await?;
+
+        // Prepare batch
+        let batch_id = safe_random_u64();
+        let batch_len = requests.len();
+
+        let session = runtime.inflight_requests.session();
+
+        let mut envelope = json!({
+            "id": batch_id,
+            "priority": DEFAULT_PRIORITY,
+            "nonce": monotonic_nonce(),
+            "session": session
+        });
+
+        if params.reply_channel_strategy == ReplyChannelStrategy::Shared {
+            envelope["reply"] = json!("shared");
+        }
+
+        #[cfg(feature = "opentelemetry")]
+        inject_trace_context(&mut envelope);
+
+        let requests = requests.into_iter()
+            .enumerate()
+            .map(|(id, request)| json!({
+                "id": id,
+                "request": request
+            }))
+            .collect::<Vec<_>>();
+
+        let batch = self.prepare_envelope(envelope, "batch", Json::Array(requests), endpoint).await?;
+
+        // Send batch
+        let pending = runtime.inflight_requests.register(
+            batch_id,
+            params.channel.reply_to_session(batch_id, session)
+        );
+
+        let started_at = Instant::now();
+
+        self.send_raw_envelope(&middleware, endpoint, &params.channel, batch).await?;
+
+        // Receive responses
+        let response = self.receive_response(&middleware, batch_id, pending).await
+            .with_peer(&endpoint.client_public)?;
+
+        if let Some(error) = response.get("remote_error") {
+            let error = serde_json::from_value::<RemoteError>(error.clone())?;
+
+            return Err(ClientAppError::Remote(error).with_peer(&endpoint.client_public));
+        }
+
+        runtime.latency_tracker.record(started_at.elapsed());
+        runtime.presence.seen(&endpoint.client_public);
+
+        // Match responses with requests by their ids
+        let mut responses = (0..batch_len)
+            .map(|_| Err(ClientAppError::Remote(RemoteError::new("batch", "Response is missing"))))
+            .collect::<Vec<_>>();
+
+        let items = response.get("batch")
+            .and_then(Json::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        for item in items {
+            let Some(id) = item.get("id").and_then(Json::as_u64).map(|id| id as usize) else {
+                continue;
+            };
+
+            if id >= batch_len {
+                continue;
+            }
+
+            responses[id] = match (item.get("remote_error"), item.get("response")) {
+                (Some(error), _) => match serde_json::from_value::<RemoteError>(error.clone()) {
+                    Ok(error) => Err(ClientAppError::Remote(error)),
+                    Err(err) => Err(err.into())
+                },
+
+                (None, Some(response)) => Self::OutputResponse::from_json(response)
+                    .map_err(ClientAppError::from),
+
+                (None, None) => continue
+            };
+        }
+
+        Ok(responses)
+    }
+
+    /// Wait for the response to the sent request
+    /// and decode it.
+    ///
+    /// Responses received from the shared replies
+    /// channel are unwrapped.
+    async fn receive_response(
+        &self,
+        middleware: &ConnectedClientMiddleware<Self::HttpClient>,
+        request_id: u64,
+        mut pending: PendingRequest
+    ) -> Result<Json, ClientAppError<Self::Error>> {
+        let params = self.get_params();
+        let runtime = self.get_runtime();
+
+        let message = loop {
+            if let Some(message) = pending.try_receive() {
+                break message;
+            }
+
+            // Poll responses for all the pending requests
+            // if no other request is doing it now
+            if let Some(_lock) = runtime.inflight_requests.try_lock_poller() {
+                if params.reply_channel_strategy == ReplyChannelStrategy::Shared {
+                    // One poll receives responses for all the pending requests
+                    let (messages, _) = middleware.poll(&params.channel.replies(), None).await?;
+
+                    let session = runtime.inflight_requests.session();
+
+                    // Match responses with requests by their ids,
+                    // discarding responses to the previous sessions
+                    for message in messages {
+                        let response = params.identity.read(&message.message, &message.sender.client.public_key).ok()
+                            .and_then(|response| serde_json::from_slice::<Json>(&response).ok());
+
+                        let Some(response) = response else {
+                            continue;
+                        };
+
+                        let response_session = response.get("session")
+                            .and_then(Json::as_u64);
+
+                        if response_session.is_some_and(|response_session| response_session != session) {
+                            #[cfg(feature = "tracing")]
+                            tracing::debug!("[client] Discarded response to the request of another session");
+
+                            continue;
+                        }
+
+                        if let Some(id) = response.get("id").and_then(Json::as_u64) {
+                            runtime.inflight_requests.resolve(id, message);
+                        }
+                    }
+
+                    // Peers which don't support shared replies respond on the
+                    // per-request channels, which are polled one per iteration
+                    if let Some((id, channel)) = runtime.inflight_requests.next_fallback() {
+                        let (mut messages, _) = middleware.poll(channel, Some(1)).await?;
+
+                        if let Some(message) = messages.pop() {
+                            runtime.inflight_requests.resolve(id, message);
+                        }
+                    }
+                }
+
+                // Every request has its own reply channel
+                else {
+                    for (id, channel) in runtime.inflight_requests.pending() {
+                        let (mut messages, _) = middleware.poll(channel, Some(1)).await?;
+
+                        if let Some(message) = messages.pop() {
+                            runtime.inflight_requests.resolve(id, message);
+                        }
+                    }
+                }
+
+                if let Some(message) = pending.try_receive() {
+                    break message;
+                }
+            }
+
+            // Wait for the response or try again
+            tokio::select! {
+                message = pending.receiver() => match message {
+                    Ok(message) => break message,
+
+                    // Register the request again if it was lost
+                    Err(_) => {
+                        let channel = pending.channel().clone();
+
+                        drop(pending);
+
+                        pending = runtime.inflight_requests.register(request_id, channel);
+                    }
+                },
+
+                _ = tokio::time::sleep(params.delay) => ()
+            }
+        };
+
+        // Decode the message and verify its validity
+        let response = params.identity.read(
+            &message.message,
+            &message.sender.client.public_key
+        )?;
+
+        self.on_envelope(Direction::Incoming, &response, &message.sender.client.public_key);
+        self.journal_envelope(Direction::Incoming, &response, &message.sender.client.public_key, &message.channel);
+
+        // Deserialize it and return
+        let mut response = serde_json::from_slice::<Json>(&response)?;
+
+        // Unwrap responses received from the shared replies channel
+        if message.channel == params.channel.replies().as_str() {
+            if let Some(inner) = response.get_mut("response") {
+                response = inner.take();
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Send message to given endpoint.
+    ///
+    /// Returns id of the sent message. Callers which don't
+    /// need it can keep using `client.send(..).await?;`, and
+    /// the ones matching on `Ok(())` should match on `Ok(_)`.
+    #[inline]
+    async fn send(&self, endpoint: ClientEndpoint, message: Self::OutputMessage) -> Result<MessageId, ClientAppError<Self::Error>> {
+        self.send_with_priority(endpoint, message, DEFAULT_PRIORITY).await
+    }
+
+    /// Send message with given priority to given endpoint.
+    ///
+    /// Messages with higher priority are processed
+    /// by the receiver first.
+    #[inline]
+    async fn send_with_priority(&self, endpoint: ClientEndpoint, message: Self::OutputMessage, priority: u8) -> Result<MessageId, ClientAppError<Self::Error>> {
+        self.send_with_options(endpoint, message, priority, None).await
+    }
+
+    /// Send message which expires after given time to live.
+    ///
+    /// Expired messages are dropped by the receiver
+    /// if it was offline until then.
+    #[inline]
+    async fn send_with_ttl(&self, endpoint: ClientEndpoint, message: Self::OutputMessage, ttl: Duration) -> Result<MessageId, ClientAppError<Self::Error>> {
+        self.send_with_options(endpoint, message, DEFAULT_PRIORITY, Some(ttl)).await
+    }
+
+    /// Schedule message to be sent to given endpoint at given time.
+    ///
+    /// Scheduled messages are sent by the background updates
+    /// task using the `send` method, and are persisted in the
+    /// state store if it's set.
+    fn schedule_send(&self, endpoint: ClientEndpoint, message: Self::OutputMessage, at: SystemTime) -> Result<ScheduleId, ClientAppError<Self::Error>> {
+        Ok(self.get_runtime().scheduler.schedule(endpoint, message.to_json()?, at))
+    }
+
+    /// Cancel scheduled message.
+    ///
+    /// Returns `false` if the message was already sent or canceled.
+    #[inline]
+    fn cancel_scheduled(&self, id: ScheduleId) -> bool {
+        self.get_runtime().scheduler.cancel(id)
+    }
+
+    /// Send scheduled messages whose time has come.
+    ///
+    /// Returns amount of sent messages.
+    async fn send_scheduled(&self) -> usize {
+        let runtime = self.get_runtime();
+
+        let mut sent = 0;
+
+        for scheduled in runtime.scheduler.take_due(SystemTime::now()) {
+            let result = match Self::OutputMessage::from_json(&scheduled.message) {
+                Ok(message) => self.send(scheduled.endpoint, message).await,
+                Err(err) => Err(err.into())
+            };
+
+            match result {
+                Ok(_) => sent += 1,
+
+                Err(_err) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::error!("[client] Failed to send scheduled message {}: {_err}", scheduled.id);
+                }
+            }
+        }
+
+        sent
+    }
+
+    /// Send message with given priority and
+    /// optional time to live to given endpoint.
+    ///
+    /// Returns id of the sent message.
+    async fn send_with_options(
+        &self,
+        endpoint: ClientEndpoint,
+        message: Self::OutputMessage,
+        priority: u8,
+        ttl: Option<Duration>
+    ) -> Result<MessageId, ClientAppError<Self::Error>> {
+        let params = self.get_params();
+
+        self.throttle(&endpoint).await?;
+
+        let middleware = self.get_connected_middleware().await?;
+
+        // Prepare message
+        let nonce = monotonic_nonce();
+        let message = message.to_json()?;
+
+        let id = MessageId(message_id(
+            &params.identity.public(),
+            &canonical_json(&message)?,
+            nonce
+        ));
+
+        let mut envelope = json!({
+            "priority": priority,
+            "nonce": nonce
+        });
+
+        if let Some(ttl) = ttl {
+            envelope["expires_at"] = json!(expires_at(ttl));
+        }
+
+        #[cfg(feature = "opentelemetry")]
+        inject_trace_context(&mut envelope);
+
+        let archived = params.archive.is_some()
+            .then(|| message.clone());
+
+        let message = self.prepare_envelope(envelope, "message", message, &endpoint).await?;
+
+        // Send message
+        self.send_raw_envelope(&middleware, &endpoint, &params.channel, message).await?;
+
+        if let (Some(archive), Some(message)) = (&params.archive, archived) {
+            archive.archive_sent(&endpoint, &message, id).await;
+        }
+
+        Ok(id)
+    }
+
+    /// Add payload to the envelope and serialize it.
+    ///
+    /// The payload is serialized once and embedded into the
+    /// envelope as is, without building another JSON tree.
+    /// Send interceptors can modify the whole envelope, so
+    /// if there are some the payload is inserted into the
+    /// envelope before calling them. Both ways produce
+    /// the same bytes.
+    async fn prepare_envelope(
+        &self,
+        mut envelope: Json,
+        field: &str,
+        payload: Json,
+        endpoint: &ClientEndpoint
+    ) -> Result<Vec<u8>, ClientAppError<Self::Error>> {
+        let params = self.get_params();
+
+        if params.send_interceptors.is_empty() {
+            let payload = raw_canonical_json(&payload)?;
+
+            return Ok(canonical_envelope(&envelope, field, &payload)?);
+        }
+
+        envelope[field] = payload;
+
+        for interceptor in &params.send_interceptors {
+            interceptor.before_send(&mut envelope, endpoint).await
+                .map_err(ClientAppError::Interceptor)?;
+        }
+
+        Ok(canonical_json(&envelope)?)
+    }
+
+    /// Send message to all the given members of the group.
+    ///
+    /// The message is encrypted once with the group key,
+    /// so every member must know it to read the message.
+    #[cfg(feature = "group-encryption")]
+    async fn send_group(&self, group: &GroupChannel, endpoints: &[ClientEndpoint], message: Self::OutputMessage) -> Result<(), ClientAppError<Self::Error>> {
+        let params = self.get_params();
+        let middleware = self.get_connected_middleware().await?;
+
+        // Encrypt message
+        let message = canonical_json(&json!({
+            "message": message.to_json()?,
+            "nonce": monotonic_nonce()
+        }))?;
+
+        let message = BASE64.encode(group.encrypt(&message)?);
+
+        // Send it to all the members
+        for endpoint in endpoints {
+            self.throttle(endpoint).await?;
+
+            let mut envelope = json!({
+                "group_channel_id": group.id.as_str(),
+                "group_message": message,
+                "priority": DEFAULT_PRIORITY
+            });
+
+            #[cfg(feature = "opentelemetry")]
+            inject_trace_context(&mut envelope);
+
+            for interceptor in &params.send_interceptors {
+                interceptor.before_send(&mut envelope, endpoint).await
+                    .map_err(ClientAppError::Interceptor)?;
+            }
+
+            self.send_envelope(&middleware, endpoint, &params.channel, &envelope).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Send message to all the given endpoints at once.
+    ///
+    /// Message is encrypted for the connected server which stores
+    /// its copy for every recipient connected to it. The content is
+    /// signed so recipients can verify the sender. Recipients of
+    /// other servers, or all of them if `multicast_address` param
+    /// is not set, receive the message using the `send` method.
+    ///
+    /// Connected server must have the multicast endpoint enabled.
+    async fn multicast(&self, endpoints: &[ClientEndpoint], message: Self::OutputMessage) -> Result<(), ClientAppError<Self::Error>> {
+        let params = self.get_params();
+
+        let message = message.to_json()?;
+
+        let (local, remote) = endpoints.iter()
+            .partition::<Vec<_>, _>(|endpoint| {
+                params.multicast_address.is_some() && endpoint.server_address == params.server_address
+            });
+
+        for endpoint in remote {
+            self.send(endpoint.clone(), Self::OutputMessage::from_json(&message)?).await?;
+        }
+
+        let Some(multicast_address) = &params.multicast_address else {
+            return Ok(());
+        };
+
+        if local.is_empty() {
+            return Ok(());
+        }
+
+        for endpoint in &local {
+            self.throttle(endpoint).await?;
+        }
+
+        // Build message content
+        let server = ClientEndpoint::new(&params.server_address, params.server_public.clone());
+
+        let envelope = json!({
+            "priority": DEFAULT_PRIORITY,
+            "nonce": monotonic_nonce()
+        });
+
+        let content = self.prepare_envelope(envelope, "message", message, &server).await?;
+
+        let secret = params.identity.secret();
+        let signature = secret.create_signature(&content);
+
+        let request = MulticastEnvelope {
+            sender: self.loopback_sender(),
+            recipients: local.iter()
+                .map(|endpoint| endpoint.client_public.clone())
+                .collect(),
+            channel: params.channel.to_string(),
+            message: Message::create(
+                &secret,
+                &params.server_public,
+                content,
+                params.encoding,
+                params.compression_level
+            )?,
+            signature
+        };
+
+        // Send it to the server
+        let url = if multicast_address.starts_with("http://") || multicast_address.starts_with("https://") {
+            format!("{multicast_address}/multicast")
+        } else {
+            format!("http://{multicast_address}/multicast")
+        };
+
+        let client = params.build_http_client()
+            .map_err(|err| ClientAppError::Multicast(err.to_string()))?;
+
+        let _response = client.post_request::<_, MulticastResponse>(&url, request).await
+            .map_err(|err| ClientAppError::Multicast(err.to_string()))?;
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!("[client] Multicasted message to {} recipients", _response.delivered);
+
+        Ok(())
+    }
+
+    /// Send message to the members of the channel
+    /// using its security settings.
+    ///
+    /// On shared secret channels the message is encrypted once
+    /// with the channel key and sent to the members on this
+    /// channel, which they poll if it's listed in their
+    /// `channel_security` param. Otherwise the message is
+    /// sent to every member using the `send` method.
+    #[cfg(feature = "group-encryption")]
+    async fn send_to_channel(&self, channel: &Channel, endpoints: &[ClientEndpoint], message: Self::OutputMessage) -> Result<(), ClientAppError<Self::Error>> {
+        let params = self.get_params();
+
+        let Some(ChannelSecurity::SharedSecret(secret)) = params.channel_security.get(channel) else {
+            let message = message.to_json()?;
+
+            for endpoint in endpoints {
+                self.send(endpoint.clone(), Self::OutputMessage::from_json(&message)?).await?;
+            }
+
+            return Ok(());
+        };
+
+        let middleware = self.get_connected_middleware().await?;
+
+        // Encrypt message once
+        let message = canonical_json(&json!({
+            "message": message.to_json()?,
+            "nonce": monotonic_nonce()
+        }))?;
+
+        let (key_id, message) = secret.encrypt(channel, &message)?;
+
+        let message = BASE64.encode(message);
+
+        // Send it to all the members
+        for endpoint in endpoints {
+            self.throttle(endpoint).await?;
+
+            let mut envelope = json!({
+                "group_channel_id": channel.as_str(),
+                "group_key_id": key_id,
+                "group_message": message,
+                "priority": DEFAULT_PRIORITY
+            });
+
+            #[cfg(feature = "opentelemetry")]
+            inject_trace_context(&mut envelope);
+
+            for interceptor in &params.send_interceptors {
+                interceptor.before_send(&mut envelope, endpoint).await
+                    .map_err(ClientAppError::Interceptor)?;
+            }
+
+            self.send_envelope(&middleware, endpoint, channel, &envelope).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Rotate shared key of the channel, returning id of the new key.
+    ///
+    /// Previous key is kept for its grace period. The new key
+    /// must be distributed to the members of the channel.
+    #[cfg(feature = "group-encryption")]
+    fn rotate_channel_key(&self, channel: &Channel, key: [u8; 32]) -> Result<u32, ClientAppError<Self::Error>> {
+        match self.get_params().channel_security.get(channel) {
+            Some(ChannelSecurity::SharedSecret(secret)) => Ok(secret.rotate(key)),
+
+            _ => Err(GroupError::NotShared(channel.to_string()).into())
+        }
+    }
+
+    /// Serialize, encrypt and send given envelope
+    /// to the endpoint using given channel.
+    async fn send_envelope(
+        &self,
+        middleware: &ConnectedClientMiddleware<Self::HttpClient>,
+        endpoint: &ClientEndpoint,
+        channel: &Channel,
+        envelope: &Json
+    ) -> Result<(), ClientAppError<Self::Error>> {
+        self.send_raw_envelope(middleware, endpoint, channel, canonical_json(envelope)?).await
+    }
+
+    /// Encrypt and send already serialized envelope
+    /// to the endpoint using given channel.
+    async fn send_raw_envelope(
+        &self,
+        middleware: &ConnectedClientMiddleware<Self::HttpClient>,
+        endpoint: &ClientEndpoint,
+        channel: &Channel,
+        envelope: Vec<u8>
+    ) -> Result<(), ClientAppError<Self::Error>> {
+        let params = self.get_params();
+        let runtime = self.get_runtime();
+
+        let with_context = |err: ClientAppError<Self::Error>| {
+            err.with_peer(&endpoint.client_public)
+                .with_channel(channel)
+        };
+
+        // Deliver messages sent to the current client in-process
+        if params.loopback && endpoint.client_public == params.identity.public() && self.deliver_loopback(channel, &envelope).await.map_err(with_context)? {
+            return Ok(());
+        }
+
+        // Send message through the connected server
+        // if the peer is connected to another one
+        let relay = params.relay_through_home && endpoint.server_address != params.server_address;
+
+        let (server_address, channel) = if relay {
+            (&params.server_address, relay_channel(&endpoint.server_address, channel.as_str()))
+        } else {
+            (&endpoint.server_address, channel.to_string())
+        };
+
+        // Fail fast if the peer's server is unavailable
+        if !runtime.circuit_breaker.try_acquire(server_address) {
+            return Err(with_context(ClientAppError::CircuitOpen {
+                address: server_address.clone()
+            }));
+        }
+
+        self.on_envelope(Direction::Outgoing, &envelope, &endpoint.client_public);
+        self.journal_envelope(Direction::Outgoing, &envelope, &endpoint.client_public, &channel);
+
+        let message = Message::create(
+            &params.identity.secret(),
+            &endpoint.client_public,
+            envelope,
+            params.encoding,
+            params.compression_level
+        ).map_err(|err| with_context(err.into()))?;
+
+        let result = middleware.send(
+            server_address,
+            endpoint.client_public.clone(),
+            &channel,
+            message
+        ).await;
+
+        if let Err(err) = result {
+            // Surface server's payload size limit
+            if let Some(server_limit) = payload_too_large_limit(&err) {
+                runtime.circuit_breaker.record_success(server_address);
+
+                return Err(with_context(ClientAppError::PayloadTooLarge {
+                    server_limit
+                }));
+            }
+
+            runtime.circuit_breaker.record_failure(server_address);
+
+            return Err(with_context(err.into()));
+        }
+
+        runtime.circuit_breaker.record_success(server_address);
+
+        Ok(())
+    }
+
+    /// Sender of the messages which the current client sends to itself.
+    fn loopback_sender(&self) -> Sender {
+        let params = self.get_params();
+
+        let certificate = ConnectionCertificate::new(
+            &params.identity.secret(),
+            params.server_public.clone()
+        );
+
+        Sender::new(
+            Client::new(params.identity.public(), certificate, ClientInfo::thin()),
+            Server::new(params.server_public.clone(), &params.server_address)
+        )
+    }
+
+    /// Deliver envelope sent to the current client without
+    /// sending it through the server.
+    ///
+    /// Responses are passed to the pending requests, and messages
+    /// sent to the client's channel are processed right away by
+    /// the same pipeline as the ones received from the server.
+    ///
+    /// Returns `false` if nothing listens on the channel locally
+    /// and the envelope should be sent through the server.
+    async fn deliver_loopback(&self, channel: &Channel, envelope: &[u8]) -> Result<bool, ClientAppError<Self::Error>> {
+        let params = self.get_params();
+        let runtime = self.get_runtime();
+        let public_key = params.identity.public();
+
+        let reply_id = runtime.inflight_requests.pending()
+            .into_iter()
+            .find(|(_, reply_channel)| reply_channel == channel)
+            .map(|(id, _)| id);
+
+        let shared_replies = *channel == params.channel.replies();
+
+        // Pings measure round trip time to the server
+        if channel.is_ping() || (reply_id.is_none() && !shared_replies && *channel != params.channel) {
+            return Ok(false);
+        }
+
+        self.on_envelope(Direction::Outgoing, envelope, &public_key);
+        self.journal_envelope(Direction::Outgoing, envelope, &public_key, channel.as_str());
+
+        let message = Message::create(
+            &params.identity.secret(),
+            &public_key,
+            envelope.to_vec(),
+            params.encoding,
+            params.compression_level
+        )?;
+
+        let message = MessageInfo {
+            sender: self.loopback_sender(),
+            channel: channel.to_string(),
+            message,
+            received_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        };
+
+        // Pass responses to the pending requests
+        if let Some(id) = reply_id {
+            runtime.inflight_requests.resolve(id, message);
+        }
+
+        else if shared_replies {
+            let id = serde_json::from_slice::<Json>(envelope).ok()
+                .and_then(|response| response.get("id").and_then(Json::as_u64));
+
+            if let Some(id) = id {
+                runtime.inflight_requests.resolve(id, message);
+            }
+        }
+
+        // Process messages sent to the client's channel right away
+        else {
+            let content = self.decode_incoming(&message).await?;
+
+            if !self.is_replayed(&message, &content) {
+                self.process_message(message, content).await?;
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!("[client] Delivered message to {channel} in-process");
+
+        Ok(true)
+    }
+
+    /// Wait for the outbound rate limiter to allow
+    /// sending a message to the endpoint.
+    ///
+    /// Fails with `ClientAppError::RateLimited` in the fail-fast mode.
+    async fn throttle(&self, endpoint: &ClientEndpoint) -> Result<(), ClientAppError<Self::Error>> {
+        self.get_runtime().rate_limiter.acquire(&endpoint.client_public).await
+            .map_err(|retry_after| ClientAppError::RateLimited {
+                retry_after
+            })
+            .with_peer(&endpoint.client_public)
+    }
+
+    /// Get counters of the outbound rate limiter.
+    #[inline]
+    fn rate_limiter_stats(&self) -> RateLimiterStats {
+        self.get_runtime().rate_limiter.stats()
+    }
+
+    /// Record envelope to the journal if it's set.
+    ///
+    /// Journal failures are logged and never returned.
+    fn journal_envelope(&self, direction: Direction, raw: &[u8], peer: &PublicKey, channel: &str) {
+        let Some(journal) = &self.get_params().journal else {
+            return;
+        };
+
+        let entry = JournalEntry::new(direction, peer.clone(), channel, raw, journal.record_body(direction));
+
+        if let Err(_err) = journal.record(&entry) {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("[client] Failed to record envelope to the journal: {_err}");
+        }
+    }
+
+    /// Register channel used by the current client
+    /// in the process-global `ChannelRegistry`.
+    #[inline]
+    fn register_channel(&self, channel: &Channel) -> Result<(), ClientAppError<Self::Error>> {
+        ChannelRegistry::register(channel, &self.get_params().identity.public())?;
+
+        Ok(())
+    }
+
+    /// Get state of the circuit of the endpoint's server.
+    #[inline]
+    fn circuit_state(&self, endpoint: &ClientEndpoint) -> CircuitState {
+        self.get_runtime().circuit_breaker.state(&endpoint.server_address)
+    }
+
+    /// Close circuit of the endpoint's server
+    /// forgetting its delivery failures.
+    #[inline]
+    fn reset_circuit(&self, endpoint: &ClientEndpoint) {
+        self.get_runtime().circuit_breaker.reset(&endpoint.server_address);
+    }
+
+    /// Subscribe to the topic published by given provider.
+    ///
+    /// Subscription is renewed automatically by the client's
+    /// run loop before its TTL is elapsed.
+    async fn subscribe(&self, topic: &str, provider: ClientEndpoint) -> Result<(), ClientAppError<Self::Error>> {
+        subscribe(self, opic, provider).await
+    }
+
+    /// Unsubscribe from the topic published by given provider.
+    async fn unsubscribe(&self, topic: &str, provider: ClientEndpoint) -> Result<(), ClientAppError<Self::Error>> {
+        unsubscribe(self, opic, provider).await
+    }
+
+    /// Renew subscriptions whose TTL is about to elapse.
+    ///
+    /// Failed renewals are logged and retried on the next call,
+    /// so one unreachable provider doesn't block the others.
+    /// Returns amount of renewed subscriptions.
+    async fn renew_subscriptions(&self) -> usize
+    where
+        Self::Error: std::fmt::Display
+    {
+        renew_subscriptions(self).await
+    }
+
+    /// Send message to all the subscribers of the topic.
+    ///
+    /// Subscribers are removed after `topic_max_failures`
+    /// failed deliveries in a row. Returns amount of
+    /// subscribers which received the message.
+    async fn publish(&self, topic: &str, message: Self::OutputMessage) -> Result<usize, ClientAppError<Self::Error>> {
+        publish(self, opic, essage).await
+    }
+
+    /// Measure round trip time to the connected server.
+    ///
+    /// Sends `{ "id": N, "ping": true }` message to the current
+    /// client on the `{channel}@_ping-{id}` sub-channel and waits
+    /// until it is received back as the response of an in-flight
+    /// request, at most the `ping_timeout` param.
+    async fn ping(&self) -> Result<Duration, ClientAppError<Self::Error>> {
+        let params = self.get_params();
+        let runtime = self.get_runtime();
+
+        // Reserve in-flight request slot
+        let _permit = runtime.inflight_requests.acquire().await
+            .map_err(|_| ClientAppError::Overloaded)?;
+
+        let endpoint = ClientEndpoint::new(&params.server_address, params.identity.public());
+
+        self.throttle(&endpoint).await?;
+
+        let middleware = self.get_connected_middleware().await?;
+
+        // Every ping has its own channel, so
+        // concurrent pings don't take each other's messages
+        let ping_id = safe_random_u64();
+        let channel = params.channel.ping(ping_id);
+
+        let pending = runtime.inflight_requests.register(ping_id, channel.clone());
+
+        // Send message to ourselves
+        let started_at = Instant::now();
+
+        self.send_envelope(&middleware, &endpoint, &channel, &json!({
+            "id": ping_id,
+            "ping": true
+        })).await?;
+
+        // Wait until it is returned back
+        tokio::time::timeout(
+            params.ping_timeout,
+            self.receive_response(&middleware, ping_id, pending)
+        ).await
+            .unwrap_or(Err(ClientAppError::Timeout))?;
+
+        let rtt = started_at.elapsed();
+
+        runtime.latency_tracker.record(rtt);
+
+        Ok(rtt)
+    }
+
+    /// Check if the connected server is reachable.
+    ///
+    /// Returns `false` if `ping` failed or took
+    /// more than the `ping_timeout` param.
+    async fn is_reachable(&self) -> bool {
+        self.ping().await.is_ok()
+    }
+
+    /// Measure round trip time to the given peer.
+    ///
+    /// Sends `{ "id": N, "ping": true, "session": N }` envelope to the
+    /// peer and waits for the response on the `{channel}@{session}-{id}` channel.
+    /// Returns `None` if the peer didn't respond within timeout.
+    async fn ping_peer(&self, endpoint: &ClientEndpoint, timeout: Duration) -> Result<Option<Duration>, ClientAppError<Self::Error>> {
+        ping_peer(self, endpoint, imeout).await
+    }
+
+    /// Exchange known peers with random peers from the cache.
+    ///
+    /// Sends `{ "id": N, "gossip": { "our_peers": [...] }, "session": N }` envelope
+    /// to `gossip_fanout` random cached peers and merges peers from
+    /// their responses to the cache. Peers which didn't respond
+    /// within the `gossip_timeout` param are skipped.
+    async fn gossip_peers(&self) -> Result<(), ClientAppError<Self::Error>> {
+        gossip_peers(self).await
+    }
+
+    /// Providers of the state which can be
+    /// requested by other clients.
+    #[inline]
+    fn state_providers(&self) -> &StateProviderRegistry {
+        &self.get_runtime().state_providers
+    }
+
+    /// Request named piece of state from given endpoint.
+    ///
+    /// Values marked as cacheable by the peer are stored in the
+    /// `remote_state_cache` and returned without a network round
+    /// trip until their time to live expires. Unknown keys are
+    /// reported as `ClientAppError::Remote` with `not_found` kind.
+    async fn get_remote_state(&self, endpoint: ClientEndpoint, key: &str) -> Result<Json, ClientAppError<Self::Error>> {
+        get_remote_state(self, endpoint, key).await
+    }
+
+    /// Ping peers from the presence watch-list
+    /// if the probes interval is elapsed.
+    async fn probe_presence(&self) {
+        probe_presence(self).await
+    }
+
+    /// Send file to the given endpoint.
+    ///
+    /// The file is offered to the receiver with its manifest
+    /// first, and the receiver responds with indexes of the
+    /// chunks it already has. Only missing chunks are sent,
+    /// so calling this method again resumes interrupted transfer.
+    async fn send_file(&self, endpoint: ClientEndpoint, path: PathBuf, options: TransferOptions) -> Result<FileManifest, ClientAppError<Self::Error>> {
+        send_file(self, endpoint, path, options).await
+    }
+
+    /// Try to poll a message from the connected hyperborea server.
+    async fn poll_message(&self) -> Result<Option<MessageInfo>, ClientAppError<Self::Error>> {
+        let params = self.get_params();
+
+        // Implementers should poll all available messages and store them
+        // in a queue, polling from it and fulfilling it when it becomes empty.
+        let (mut messages, _) = self.get_connected_middleware().await?
+            .poll(&params.channel, Some(1)).await?;
+
+        Ok(messages.pop())
+    }
+
+    /// Poll all the available messages from the connected hyperborea server.
+    ///
+    /// Inbox of the previous identity is polled as well
+    /// within its grace period after identity rotation.
+    async fn poll_messages(&self) -> Result<Vec<MessageInfo>, ClientAppError<Self::Error>> {
+        let params = self.get_params();
+        let runtime = self.get_runtime();
+
+        let middleware = self.get_connected_middleware().await?;
+
+        let (mut messages, _) = middleware.poll(&params.channel, None).await?;
+
+        // Messages of the shared secret channels are sent to these channels
+        #[cfg(feature = "group-encryption")]
+        for (channel, security) in &params.channel_security {
+            if matches!(security, ChannelSecurity::SharedSecret(_)) && *channel != params.channel {
+                let (shared_messages, _) = middleware.poll(channel, None).await?;
+
+                messages.extend(shared_messages);
+            }
+        }
+
+        if let Some(previous) = runtime.previous_inbox.get() {
+            if params.identity.previous().is_none() {
+                runtime.previous_inbox.set(None);
+            }
+
+            else {
+                match previous.poll(&params.channel, None).await {
+                    Ok((previous_messages, _)) => messages.extend(previous_messages),
+
+                    Err(_err) => {
+                        #[cfg(feature = "tracing")]
+                        tracing::warn!("[client] Failed to poll inbox of the previous identity: {_err}");
+                    }
+                }
+            }
+        }
+
+        Ok(messages)
+    }
+
+    /// List channels of the connected server
+    /// starting with the given prefix.
+    ///
+    /// Hyperborea servers can't list their channels, so `None`
+    /// is returned by default and registered polled channels are
+    /// used instead. Override this method if the connected server
+    /// provides such an API.
+    #[allow(unused_variables)]
+    async fn list_channels(&self, prefix: &str) -> Result<Option<Vec<Channel>>, ClientAppError<Self::Error>> {
+        Ok(None)
+    }
+
+    /// Drain all the channels starting with the given prefix,
+    /// passing received messages to the `dispatch` callback
+    /// together with the channel they were received from.
+    ///
+    /// Channels are listed using `list_channels`, falling back
+    /// to the registered polled channels.
+    ///
+    /// Returns amount of dispatched messages.
+    async fn poll_channels<F>(&self, prefix: &str, mut dispatch: F) -> Result<usize, ClientAppError<Self::Error>>
+    where
+        F: FnMut(&Channel, MessageInfo) + Send
+    {
+        let params = self.get_params();
+        let runtime = self.get_runtime();
+
+        let channels = match self.list_channels(prefix).await? {
+            Some(channels) => channels,
+            None => runtime.polled_channels.matching(prefix)
+        };
+
+        let middleware = self.get_connected_middleware().await?;
+
+        let mut dispatched = 0;
+
+        for channel in channels {
+            let acl = params.channel_acl.get(&channel);
+
+            loop {
+                let (messages, remaining) = middleware.poll(&channel, None).await?;
+
+                if messages.is_empty() {
+                    break;
+                }
+
+                for message in messages {
+                    // Drop messages from not allowed senders
+                    if let Some(acl) = acl {
+                        if !acl.is_allowed(&message.sender.client.public_key) {
+                            continue;
+                        }
+                    }
+
+                    dispatch(&channel, message);
+
+                    dispatched += 1;
+                }
+
+                if remaining == 0 {
+                    break;
+                }
+            }
+        }
+
+        Ok(dispatched)
+    }
+
+    /// Poll all the available messages, decode them
+    /// and put to the incoming messages queue.
+    ///
+    /// Messages which can't be decoded are dropped and
+    /// counted by the `undecodable_message_count` method.
+    ///
+    /// Returns amount of queued messages.
+    async fn fetch_messages(&self) -> Result<usize, ClientAppError<Self::Error>> {
+        let params = self.get_params();
+        let runtime = self.get_runtime();
+
+        let messages = self.poll_messages().await?;
+        let mut queued = 0;
+
+        for message in messages {
+            let acl = params.channel_acl.iter()
+                .find(|(channel, _)| channel.as_str() == message.channel)
+                .map(|(_, acl)| acl);
+
+            // Drop messages from not allowed senders
+            if let Some(acl) = acl {
+                let sender = &message.sender.client.public_key;
+
+                if !acl.is_allowed(sender) {
+                    #[cfg(feature = "tracing")]
+                    tracing::info!(
+                        sender = %sender.to_base64().chars().take(8).collect::<String>(),
+                        channel = %message.channel,
+                        "[client] Dropped message from not allowed sender"
+                    );
+
+                    continue;
+                }
+            }
+
+            // Decode the message and put it to the queue
+            let result = self.decode_incoming(&message).await;
+
+            if result.is_err() {
+                runtime.undecodable_messages.fetch_add(1, Ordering::Relaxed);
+            }
+
+            match result {
+                Ok(content) => {
+                    if self.queue_incoming(message, content) {
+                        queued += 1;
+                    }
+                }
+
+                Err(ClientAppError::MessagesError(_err)) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(
+                        sender = %message.sender.client.public_key.to_base64(),
+                        "[client] Dropped incoming message which can't be decoded: {_err}"
+                    );
+                }
+
+                Err(ClientAppError::SerdeJsonError(_err)) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(
+                        sender = %message.sender.client.public_key.to_base64(),
+                        "[client] Dropped incoming message which can't be deserialized: {_err}"
+                    );
+                }
+
+                Err(_) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(
+                        sender = %message.sender.client.public_key.to_base64(),
+                        "[client] Dropped incoming message which can't be decoded"
+                    );
+                }
+            }
+        }
+
+        Ok(queued)
+    }
+
+    /// Check if the decoded incoming message was already
+    /// received, remembering its nonce otherwise.
+    ///
+    /// Messages are never replayed if
+    /// the `replay_protection` param is disabled.
+    fn is_replayed(&self, message: &MessageInfo, content: &Json) -> bool {
+        let params = self.get_params();
+        let runtime = self.get_runtime();
+
+        if !params.replay_protection {
+            return false;
+        }
+
+        let Some(nonce) = content.get("nonce").and_then(Json::as_u64) else {
+            return false;
+        };
+
+        if !runtime.nonces.check(&message.sender.client.public_key, nonce) {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("[client] Dropped replayed message with nonce {nonce}");
+
+            return true;
+        }
+
+        false
+    }
+
+    /// Put decoded incoming message to the queue.
+    ///
+    /// Returns `false` if the message was dropped as replayed.
+    fn queue_incoming(&self, message: MessageInfo, content: Json) -> bool {
+        let runtime = self.get_runtime();
+
+        if self.is_replayed(&message, &content) {
+            return false;
+        }
+
+        let priority = content.get("priority")
+            .and_then(Json::as_u64)
+            .map(|priority| priority.min(u8::MAX as u64) as u8)
+            .unwrap_or(DEFAULT_PRIORITY);
+
+        runtime.incoming_queue.push(priority, (message, content));
+
+        true
+    }
+
+    /// Receive and process single incoming message.
+    ///
+    /// New messages are fetched from the server
+    /// when the incoming queue becomes empty.
+    async fn update(&self) -> Result<(), ClientAppError<Self::Error>> {
+        let runtime = self.get_runtime();
+
+        if runtime.incoming_queue.is_empty() {
+            self.fetch_messages().await?;
+        }
+
+        if let Some((message, content)) = runtime.incoming_queue.pop() {
+            self.process_message(message, content).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Receive and process all the available incoming messages
+    /// in order of their priority.
+    ///
+    /// Up to `handler_concurrency` messages are processed
+    /// concurrently. If some message fails to be processed no
+    /// new messages are taken, the error is returned when the
+    /// running handlers are finished, and the rest of the
+    /// messages are kept in the queue.
+    async fn update_batch(&self) -> Result<(), ClientAppError<Self::Error>> {
+        let params = self.get_params();
+        let runtime = self.get_runtime();
+
+        self.fetch_messages().await?;
+
+        let concurrency = params.handler_concurrency.max(1);
+
+        let mut handlers = FuturesUnordered::new();
+        let mut result = Ok(());
+
+        loop {
+            while result.is_ok() && handlers.len() < concurrency {
+                let Some((message, content)) = runtime.incoming_queue.pop() else {
+                    break;
+                };
+
+                handlers.push(self.process_message(message, content));
+            }
+
+            let Some(handled) = handlers.next().await else {
+                break;
+            };
+
+            if let Err(err) = handled {
+                runtime.handler_errors.fetch_add(1, Ordering::Relaxed);
+
+                // Keep the first error
+                if result.is_ok() {
+                    result = Err(err);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Fetch and process incoming messages until there are
+    /// no more messages on the server or the timeout is elapsed.
+    ///
+    /// Returns amount of processed messages.
+    async fn drain(&self, timeout: Duration) -> Result<u64, ClientAppError<Self::Error>> {
+        let runtime = self.get_runtime();
+
+        let started_at = Instant::now();
+        let mut processed = 0;
+
+        loop {
+            let remaining = timeout.saturating_sub(started_at.elapsed());
+
+            if remaining.is_zero() {
+                break;
+            }
+
+            if runtime.incoming_queue.is_empty() && self.fetch_messages().await? == 0 {
+                break;
+            }
+
+            let Some((message, content)) = runtime.incoming_queue.pop() else {
+                break;
+            };
+
+            match tokio::time::timeout(remaining, self.process_message(message, content)).await {
+                Ok(result) => result?,
+                Err(_) => break
+            }
+
+            processed += 1;
+        }
+
+        Ok(processed)
+    }
+
+    /// Start background task fetching and processing
+    /// incoming messages using the trait handlers.
+    ///
+    /// New messages are not fetched while `poller_buffer_size`
+    /// or more messages are waiting in the incoming queue.
+    /// The poller is stopped when its handle is dropped.
+    fn spawn_poller(self: Arc<Self>) -> PollerHandle
+    where
+        Self: Sized + Send + Sync + 'static,
+        Self::Error: std::fmt::Display + 'static
+    {
+        let (state, mut state_receiver) = tokio::sync::watch::channel(PollerState::Running);
+
+        let subscribers = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let task = {
+            let subscribers = subscribers.clone();
+
+            tokio::spawn(async move {
+                let params = self.get_params();
+                let runtime = self.get_runtime();
+
+                loop {
+                    // Wait until the poller is resumed
+                    let state = state_receiver.wait_for(|state| *state != PollerState::Paused).await
+                        .map(|state| *state);
+
+                    if !matches!(state, Ok(PollerState::Running)) {
+                        break;
+                    }
+
+                    // Fetch new messages if the queue is not full
+                    if runtime.incoming_queue.len() < params.poller_buffer_size {
+                        if let Err(_err) = self.fetch_messages().await {
+                            #[cfg(feature = "tracing")]
+                            tracing::error!("[client] Failed to fetch messages: {_err}");
+                        }
+                    }
+
+                    // Process queued messages
+                    while *state_receiver.borrow() == PollerState::Running {
+                        let Some((message, content)) = runtime.incoming_queue.pop() else {
+                            break;
+                        };
+
+                        notify_subscribers(&subscribers, &message);
+
+                        if let Err(_err) = self.process_message(message, content).await {
+                            #[cfg(feature = "tracing")]
+                            tracing::error!("[client] Failed to process incoming message: {_err}");
+                        }
+                    }
+
+                    tokio::select! {
+                        _ = tokio::time::sleep(params.delay) => (),
+                        _ = state_receiver.changed() => ()
+                    }
+                }
+            })
+        };
+
+        PollerHandle::new(state, subscribers, task)
+    }
+
+    /// Amount of failed handlers of
+    /// the processed messages.
+    #[inline]
+    fn handler_error_count(&self) -> u64 {
+        self.get_runtime().handler_errors.load(Ordering::Relaxed)
+    }
+
+    /// Amount of the incoming messages dropped
+    /// because they couldn't be decoded.
+    #[inline]
+    fn undecodable_message_count(&self) -> u64 {
+        self.get_runtime().undecodable_messages.load(Ordering::Relaxed)
+    }
+
+    /// Decrypt incoming message and deserialize its envelope.
+    ///
+    /// Messages encrypted to the previous identity are
+    /// accepted within the identity grace period.
+    async fn decode_incoming(&self, info: &MessageInfo) -> Result<Json, ClientAppError<Self::Error>> {
+        let params = self.get_params();
+
+        // Decode the message and verify its validity
+        let content = params.identity.read(
+            &info.message,
+            &info.sender.client.public_key
+        )?;
+
+        self.on_envelope(Direction::Incoming, &content, &info.sender.client.public_key);
+        self.journal_envelope(Direction::Incoming, &content, &info.sender.client.public_key, &info.channel);
+
+        let content = serde_json::from_slice::<Json>(&content)?;
+
+        // Decrypt messages of the shared secret channels
+        #[cfg(feature = "group-encryption")]
+        if let Envelope::Group { id, key_id: Some(key_id), message } = Envelope::classify(&content) {
+            let shared = params.channel_security.iter()
+                .find(|(channel, _)| channel.as_str() == id);
+
+            if let Some((channel, ChannelSecurity::SharedSecret(secret))) = shared {
+                let content = secret.decrypt(channel, key_id, &message)?;
+
+                return Ok(serde_json::from_slice::<Json>(&content)?);
+            }
+        }
+
+        Ok(content)
+    }
+
+    /// Determine kind of the decoded envelope.
+    #[inline]
+    fn classify_envelope(&self, envelope: &Json) -> Envelope {
+        Envelope::classify(envelope)
+    }
+
+    /// Process decoded incoming message.
+    async fn process_message(&self, message: MessageInfo, mut content: Json) -> Result<(), ClientAppError<Self::Error>> {
+        let params = self.get_params();
+        let runtime = self.get_runtime();
+
+        runtime.presence.seen(&message.sender.client.public_key);
+
+        for interceptor in &params.receive_interceptors {
+            interceptor.after_receive(&mut content, &message).await
+                .map_err(ClientAppError::Interceptor)?;
+        }
+
+        // Drop expired envelopes
+        if is_expired(&content, params.expiry_grace) {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("[client] Dropped expired envelope");
+
+            self.on_expired(&content, &message).await;
+
+            return Ok(());
+        }
+
+        // Validate payload of the incoming message
+        if let Some(schema) = &params.input_envelope_schema {
+            let payload = content.get("message")
+                .or_else(|| content.get("request"));
+
+            if let Some(Err(violation)) = payload.map(|payload| schema.validate(payload)) {
+                runtime.invalid_message_stats.record(&violation);
+
+                return Err(ClientAppError::SchemaViolation {
+                    field: violation.field,
+                    expected: violation.expected,
+                    got: violation.got
+                });
+            }
+        }
+
+        let envelope = self.classify_envelope(&content);
+
+        let sender = message.sender.client.public_key.clone();
+        let channel = message.channel.clone();
+
+        let dispatch = async move {
+            self.dispatch(envelope, message).await
+                .with_peer(&sender)
+                .with_channel(channel)
+        };
+
+        // Restore trace context of the sender
+        #[cfg(feature = "opentelemetry")]
+        let dispatch = dispatch.with_context(extract_trace_context(&content));
+
+        dispatch.await
+    }
+
+    /// Handle single request of the incoming batch.
+    ///
+    /// Deferred responses are not supported in batches.
+    async fn handle_batch_request(&self, request: Json, info: &MessageInfo) -> Result<Json, RemoteError> {
+        let request = Self::InputRequest::from_json(&request)
+            .map_err(|err| RemoteError::new("format", err))?;
+
+        self.validate_request(&request, info)?;
+
+        let timeout = self.request_timeout_for(&request);
+
+        match tokio::time::timeout(timeout, self.handle_request(request, info.clone())).await {
+            Ok(Ok(Respond::Now(response))) => response.to_json()
+                .map_err(|err| RemoteError::new("format", err)),
+
+            Ok(Ok(Respond::Later(_))) => Err(RemoteError::new("handler", "Deferred responses are not supported in batches")),
+            Ok(Err(_)) => Err(RemoteError::new("handler", "Failed to handle request")),
+            Err(_) => Err(RemoteError::new("timeout", "Request handling timed out"))
+        }
+    }
+
+    /// Process classified incoming envelope.
+    async fn dispatch(&self, envelope: Envelope, message: MessageInfo) -> Result<(), ClientAppError<Self::Error>> {
+        let params = self.get_params();
+        let runtime = self.get_runtime();
+
+        match envelope {
+            // Handle request
+            Envelope::Request { id: request_id, request, reply, session } => {
+                // Echo session token of the requester
+                let per_request_channel = params.channel.reply_for(request_id, session);
+
+                // Deserialize request
+                let request = Self::InputRequest::from_json(&request)?;
+
+                // Validate request and report the error to the sender
+                if let Err(error) = self.validate_request(&request, &message) {
+                    let middleware = self.get_connected_middleware().await?;
+
+                    let endpoint = ClientEndpoint::new(
+                        &message.sender.server.address,
+                        message.sender.client.public_key.clone()
+                    );
+
+                    let (reply_channel, response) = match reply {
+                        ReplyChannelStrategy::Shared => (params.channel.replies(), json!({
+                            "id": request_id,
+                            "session": session,
+                            "response": {
+                                "remote_error": RemoteError::from(error.clone())
+                            }
+                        })),
+
+                        ReplyChannelStrategy::PerRequest => (per_request_channel, json!({
+                            "remote_error": RemoteError::from(error.clone())
+                        }))
+                    };
+
+                    self.send_envelope(&middleware, &endpoint, &reply_channel, &response).await?;
+
+                    return Err(ClientAppError::Validation(error));
+                }
+
+                // Process request
+                let timeout = self.request_timeout_for(&request);
+
+                let handler = self.handle_request(request, message.clone());
+
+                #[cfg(feature = "opentelemetry")]
+                let handler = handler.with_context(handler_context("handle_request", &message));
+
+                let response = tokio::time::timeout(timeout, handler).await
+                    .map_err(|_| ClientAppError::Timeout)??;
+
+                let middleware = self.get_connected_middleware().await?;
+
+                let reply_channel = match reply {
+                    ReplyChannelStrategy::Shared => params.channel.replies(),
+                    ReplyChannelStrategy::PerRequest => per_request_channel
+                };
+
+                match response {
+                    // Send response
+                    Respond::Now(response) => {
+                        let endpoint = ClientEndpoint::new(
+                            &message.sender.server.address,
+                            message.sender.client.public_key.clone()
+                        );
+
+                        let response = match reply {
+                            ReplyChannelStrategy::Shared => json!({
+                                "id": request_id,
+                                "session": session,
+                                "response": response.to_json()?
+                            }),
+
+                            ReplyChannelStrategy::PerRequest => response.to_json()?
+                        };
+
+                        self.send_envelope(
+                            &middleware,
+                            &endpoint,
+                            &reply_channel,
+                            &response
+                        ).await?;
+                    }
+
+                    // Bind response token to the current request
+                    Respond::Later(token) => {
+                        token.bind(ResponseBinding {
+                            endpoint: ClientEndpoint::new(
+                                &message.sender.server.address,
+                                message.sender.client.public_key.clone()
+                            ),
+                            channel: reply_channel,
+                            reply_id: (reply == ReplyChannelStrategy::Shared).then_some(request_id),
+                            reply_session: session,
+                            queue: runtime.deferred_responses.clone()
+                        });
+                    }
+                }
+            }
+
+            // Handle batch of requests
+            Envelope::Batch { id: batch_id, requests, reply, session } => {
+                let response = if requests.len() > params.batch_max_items {
+                    json!({
+                        "remote_error": RemoteError::new("validation", format!(
+                            "Batch must have at most {} requests, got {}",
+                            params.batch_max_items,
+                            requests.len()
+                        ))
+                    })
+                } else {
+                    let mut responses = Vec::with_capacity(requests.len());
+
+                    // Failed requests are reported separately
+                    for (id, request) in requests {
+                        let response = match self.handle_batch_request(request, &message).await {
+                            Ok(response) => json!({
+                                "id": id,
+                                "response": response
+                            }),
+
+                            Err(error) => json!({
+                                "id": id,
+                                "remote_error": error
+                            })
+                        };
+
+                        responses.push(response);
+                    }
+
+                    json!({
+                        "batch": responses
+                    })
+                };
+
+                let middleware = self.get_connected_middleware().await?;
+
+                let endpoint = ClientEndpoint::new(
+                    &message.sender.server.address,
+                    message.sender.client.public_key.clone()
+                );
+
+                let (reply_channel, response) = match reply {
+                    ReplyChannelStrategy::Shared => (params.channel.replies(), json!({
+                        "id": batch_id,
+                        "response": response
+                    })),
+
+                    ReplyChannelStrategy::PerRequest => (params.channel.reply_for(batch_id, session), response)
+                };
+
+                self.send_envelope(&middleware, &endpoint, &reply_channel, &response).await?;
+            }
+
+            // Answer presence probe
+            Envelope::Ping { id: ping_id, session } => {
+                answer_ping(self, message, ping_id, session).await?;
+            }
+
+            // Exchange known peers
+            Envelope::Gossip { id: gossip_id, request, session } => {
+                answer_gossip(self, message, gossip_id, request, session).await?;
+            }
+
+            // Share known peers
+            Envelope::PeerList { id: request_id, request, session } => {
+                answer_peer_list(self, message, request_id, request, session).await?;
+            }
+
+            // Answer remote state request
+            Envelope::GetState { id: request_id, key, session } => {
+                answer_state_request(self, message, request_id, key, session).await?;
+            }
+
+            // Handle topic subscription
+            Envelope::Subscribe { topic, ttl } => {
+                let subscriber = ClientEndpoint::new(
+                    &message.sender.server.address,
+                    message.sender.client.public_key.clone()
+                );
+
+                runtime.topics.add_subscriber(&topic, subscriber, ttl.unwrap_or(params.topic_ttl));
+            }
+
+            Envelope::Unsubscribe { topic } => {
+                let subscriber = ClientEndpoint::new(
+                    &message.sender.server.address,
+                    message.sender.client.public_key.clone()
+                );
+
+                runtime.topics.remove_subscriber(&topic, &subscriber);
+            }
+
+            // Handle identity rotation notice
+            Envelope::Moved { previous, timestamp, signature } => {
+                handle_moved_notice(self, message, previous, timestamp, signature).await?;
+            }
+
+            // Report already received chunks of the offered file
+            Envelope::FileOffer { id: offer_id, manifest, session } => {
+                answer_file_offer(self, message, offer_id, manifest, session).await?;
+            }
+
+            // Store received file chunk
+            Envelope::FileChunk { transfer, index, data } => {
+                store_file_chunk(self, message, transfer, index, data).await?;
+            }
+
+            // Decrypt group message
+            //
+            // Messages of the shared secret channels
+            // are decrypted by the `decode_incoming` method
+            #[cfg(feature = "group-encryption")]
+            Envelope::Group { id, message: group_message, .. } => {
+                let Some(group) = params.groups.iter().find(|group| group.id.as_str() == id) else {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("[client] Received message of unknown group {id}");
+
+                    return Ok(());
+                };
+
+                let content = group.decrypt(&group_message)?;
+
+                let content = serde_json::from_slice::<Json>(&content)?;
+
+                // Only plain messages can be sent to groups
+                if let envelope @ Envelope::Message { .. } = self.classify_envelope(&content) {
+                    self.dispatch(envelope, message).await?;
+                }
+            }
+
+            #[cfg(not(feature = "group-encryption"))]
+            Envelope::Group { .. } => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("[client] Received group message but the group-encryption feature is disabled");
+            }
+
+            // Unwrap message relayed by the sender's server
+            Envelope::Relayed { sender, server_address, server_public, message: relayed } => {
+                let relayed = Message::from_json(&relayed)?;
+
+                // Relayed message is encrypted by its original sender
+                let mut info = message;
+
+                info.sender.client.public_key = sender;
+                info.sender.server.address = server_address;
+                info.sender.server.public_key = server_public;
+                info.message = relayed;
+
+                let content = self.decode_incoming(&info).await?;
+
+                self.process_message(info, content).await?;
+            }
+
+            // Unwrap message multicasted by the sender's server
+            Envelope::Multicast { sender, server_address, server_public, content, signature } => {
+                // Multicasted content is signed by its original sender
+                let verified = sender.verify_signature(&content, signature)
+                    .unwrap_or(false);
+
+                if !verified {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!("[client] Received multicast message with invalid signature");
+
+                    return Ok(());
+                }
+
+                let mut info = message;
+
+                info.sender.client.public_key = sender;
+                info.sender.server.address = server_address;
+                info.sender.server.public_key = server_public;
+
+                let content = serde_json::from_slice::<Json>(&content)?;
+
+                // Only plain messages can be multicasted
+                if let envelope @ Envelope::Message { .. } = self.classify_envelope(&content) {
+                    self.dispatch(envelope, info).await?;
+                }
+            }
+
+            // Handle message
+            Envelope::Message { message: request, nonce } => {
+                let payload = canonical_json(&request)?;
+
+                // Suppress duplicated deliveries of the same envelope
+                let id = message_id(&message.sender.client.public_key, &payload, nonce.unwrap_or_default());
+
+                if !runtime.seen_messages.insert(id) {
+                    let request = Self::InputMessage::from_json(&request)?;
+
+                    return self.on_duplicate(request, message).await;
+                }
+
+                if let Some(archive) = &params.archive {
+                    archive.archive_received(&message, &request).await;
+                }
+
+                let request = Self::InputMessage::from_json(&request)?;
+
+                self.validate_message(&request, &message)
+                    .map_err(ClientAppError::Validation)?;
+
+                #[cfg(feature = "opentelemetry")]
+                let context = handler_context("handle_message", &message);
+
+                // Process message
+                let message_context = MessageContext::new(self, message, payload.len());
+
+                let handler = self.handle_message(request, message_context);
+
+                #[cfg(feature = "opentelemetry")]
+                let handler = handler.with_context(context);
+
+                handler.await?;
+            }
+
+            Envelope::Unknown => ()
+        }
+
+        Ok(())
+    }
+
+    /// Serialize value and save it to the state store.
+    async fn save_state<T>(&self, key: &str, value: &T) -> Result<(), ClientAppError<Self::Error>>
+    where
+        T: serde::Serialize + Sync
+    {
+        let store = self.get_params().state_store.as_ref()
+            .ok_or(ClientAppError::NoStateStore)?;
+
+        store.save_json(key, value).await
+            .map_err(ClientAppError::StateStore)
+    }
+
+    /// Load value from the state store and deserialize it.
+    async fn load_state<T>(&self, key: &str) -> Result<Option<T>, ClientAppError<Self::Error>>
+    where
+        T: serde::de::DeserializeOwned
+    {
+        let store = self.get_params().state_store.as_ref()
+            .ok_or(ClientAppError::NoStateStore)?;
+
+        store.load_json(key).await
+            .map_err(ClientAppError::StateStore)
+    }
+
+    /// Replace secret key of the client with a new one.
+    ///
+    /// Messages encrypted to the previous key are still accepted
+    /// within the identity grace period. Listed peers are notified
+    /// about the new public key with a notice signed by the previous
+    /// secret key, and can update their endpoints in `on_peer_moved`.
+    ///
+    /// Note that `get_middleware` must return middleware
+    /// of the current identity to reconnect to the server.
+    async fn rotate_identity(&self, new_secret: SecretKey, notify: &[ClientEndpoint]) -> Result<(), ClientAppError<Self::Error>> {
+        rotate_identity(self, new_secret, notify).await
+    }
+
+    /// Get time to live of the cached response to the request.
+    ///
+    /// Only idempotent requests should be cached.
+    /// Returns `None` by default, so nothing is cached.
+    #[allow(unused_variables)]
+    fn cache_policy(&self, request: &Self::OutputRequest) -> Option<Duration> {
+        None
+    }
+
+    /// Called when some peer has rotated its identity.
+    ///
+    /// Does nothing by default.
+    #[allow(unused_variables)]
+    async fn on_peer_moved(&self, previous: PublicKey, current: PublicKey, info: &MessageInfo) -> Result<(), ClientAppError<Self::Error>> {
+        Ok(())
+    }
+
+    /// Called when lookup of the pinned peer resolves a server
+    /// address differing from the pinned one and the `pin_policy`
+    /// is `Warn`. The pinned address is kept.
+    ///
+    /// Does nothing by default.
+    #[allow(unused_variables)]
+    async fn on_endpoint_changed(&self, peer: &PublicKey, previous: &str, current: &str) -> Result<(), ClientAppError<Self::Error>> {
+        Ok(())
+    }
+
+    /// Called by the background updates task every
+    /// `periodic_task_interval`.
+    ///
+    /// Use it to send heartbeats, flush statistics
+    /// or refresh peers lists. Does nothing by default.
+    async fn periodic_task(&self) -> Result<(), ClientAppError<Self::Error>> {
+        Ok(())
+    }
+
+    /// Called when the connection to the server
+    /// is established for the first time.
+    ///
+    /// Does nothing by default.
+    #[allow(unused_variables)]
+    async fn on_connected(&self, server: &ServerInfo) {}
+
+    /// Called once when the connection to the server is lost.
+    ///
+    /// Does nothing by default.
+    #[allow(unused_variables)]
+    async fn on_disconnected(&self, err: &MiddlewareError) {}
+
+    /// Called once when the lost connection
+    /// to the server is restored.
+    ///
+    /// Does nothing by default.
+    async fn on_reconnected(&self) {}
+
+    /// Called when all the reconnection attempts
+    /// allowed by the `reconnect_policy` param have failed.
+    ///
+    /// Does nothing by default.
+    #[allow(unused_variables)]
+    async fn on_reconnect_failed(&self, attempts: u32) {}
+
+    /// Called when an incoming envelope is dropped
+    /// because its time to live has elapsed.
+    ///
+    /// Does nothing by default.
+    #[allow(unused_variables)]
+    async fn on_expired(&self, envelope: &Json, info: &MessageInfo) {}
+
+    /// Called when the connected server restart is detected.
+    ///
+    /// Use it to re-announce subscriptions or presence.
+    /// Does nothing by default.
+    async fn on_server_restarted(&self) {}
+
+    /// Called when already processed message is delivered again.
+    ///
+    /// Messages are matched by their `message_id`.
+    /// Does nothing by default, dropping the message.
+    #[allow(unused_variables)]
+    async fn on_duplicate(&self, message: Self::InputMessage, info: MessageInfo) -> Result<(), ClientAppError<Self::Error>> {
+        Ok(())
+    }
+
+    /// Called with exact bytes of every envelope before
+    /// it is encrypted or right after it is decrypted.
+    ///
+    /// Envelopes are serialized using `canonical_json`
+    /// so the same envelope always has the same bytes.
+    /// Does nothing by default.
+    #[allow(unused_variables)]
+    fn on_envelope(&self, direction: Direction, raw: &[u8], peer: &PublicKey) {}
+
+    /// Called after every sent or received file chunk.
+    ///
+    /// Does nothing by default.
+    #[allow(unused_variables)]
+    async fn on_transfer_progress(&self, progress: &TransferProgress) {}
+
+    /// Decide whether to receive the offered file.
+    ///
+    /// Called only if the `download_dir` param is set and the file
+    /// is not larger than the `max_file_size` param. Rejects all
+    /// the files by default.
+    #[allow(unused_variables)]
+    fn accept_file_offer(&self, manifest: &FileManifest, info: &MessageInfo) -> bool {
+        false
+    }
+
+    /// Handle completely received file with verified hash.
+    ///
+    /// Does nothing by default.
+    #[allow(unused_variables)]
+    async fn handle_file_received(&self, path: PathBuf, manifest: FileManifest, info: MessageInfo) -> Result<(), ClientAppError<Self::Error>> {
+        Ok(())
+    }
+
+    /// Validate incoming request before passing it to the handler.
+    ///
+    /// Sender of the invalid request receives a `RemoteError`
+    /// of the `validation` kind instead of the response.
+    #[allow(unused_variables)]
+    fn validate_request(&self, request: &Self::InputRequest, info: &MessageInfo) -> Result<(), ValidationError> {
+        Ok(())
+    }
+
+    /// Validate incoming message before passing it to the handler.
+    ///
+    /// Invalid messages are dropped.
+    #[allow(unused_variables)]
+    fn validate_message(&self, message: &Self::InputMessage, info: &MessageInfo) -> Result<(), ValidationError> {
+        Ok(())
+    }
+
+    /// Get maximal processing time of the given request.
+    ///
+    /// Requests are not limited by default.
+    #[allow(unused_variables)]
+    fn request_timeout_for(&self, request: &Self::InputRequest) -> Duration {
+        Duration::MAX
+    }
+
+    /// Handle incoming request.
+    ///
+    /// Return `Respond::Later` to send the response
+    /// from another task using the `ResponseToken`.
+    async fn handle_request(&self, request: Self::InputRequest, info: MessageInfo) -> Result<Respond<Self::InputResponse>, ClientAppError<Self::Error>>;
+
+    /// Handle incoming message.
+    ///
+    /// Use the `MessageContext` to reply to the sender.
+    async fn handle_message(&self, message: Self::InputMessage, ctx: MessageContext<'_, Self>) -> Result<(), ClientAppError<Self::Error>>;
 }
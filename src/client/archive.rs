@@ -0,0 +1,26 @@
+use serde_json::Value as Json;
+
+use hyperborealib::rest_api::prelude::*;
+
+use super::{ClientEndpoint, MessageId};
+
+/// Archive of the sent and received messages.
+///
+/// Unlike the `MessageJournal` it records deserialized messages
+/// instead of the envelopes, so it can be used for debugging
+/// and compliance. Archiving is best-effort and never fails
+/// sending or receiving.
+#[async_trait::async_trait]
+pub trait MessageArchive: Send + Sync {
+    /// Record message sent to the endpoint.
+    async fn archive_sent(&self, endpoint: &ClientEndpoint, message: &Json, id: MessageId);
+
+    /// Record message received from the sender of the info.
+    async fn archive_received(&self, info: &MessageInfo, message: &Json);
+}
+
+impl std::fmt::Debug for dyn MessageArchive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MessageArchive")
+    }
+}
@@ -0,0 +1,236 @@
+use std::str::FromStr;
+
+/// Maximal length of the base channel name.
+pub const CHANNEL_MAX_LENGTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ChannelError {
+    #[error("Channel name can't be empty")]
+    Empty,
+
+    #[error("Channel name is too long: {length} > {max}")]
+    TooLong {
+        length: usize,
+        max: usize
+    },
+
+    #[error("Channel name contains invalid character: {0:?}")]
+    InvalidCharacter(char)
+}
+
+/// Name of the messaging channel.
+///
+/// Base channel names can contain only ASCII alphanumeric
+/// characters and `-`, `_`, `.`, `/`, `:` symbols. The `@`
+/// symbol is reserved for derived channels like
+/// `{channel}@{request_id}`.
+///
+/// ```rust
+/// use hyperelm::client::Channel;
+///
+/// let channel = Channel::new("hyperelm").unwrap();
+/// let reply = channel.reply_to(123);
+///
+/// assert_eq!(reply.to_string(), "hyperelm@123");
+/// assert_eq!(reply.base(), channel.as_str());
+/// assert_eq!(reply.reply_id(), Some(123));
+///
+/// assert!(Channel::new("hyper elm").is_err());
+/// assert!(Channel::new("hyper@elm").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub struct Channel(String);
+
+impl Channel {
+    /// Validate given channel name and wrap it.
+    pub fn new(name: impl Into<String>) -> Result<Self, ChannelError> {
+        let name = name.into();
+
+        Self::validate(&name)?;
+
+        Ok(Self(name))
+    }
+
+    /// Verify that given string can be used as a base channel name.
+    pub fn validate(name: &str) -> Result<(), ChannelError> {
+        if name.is_empty() {
+            return Err(ChannelError::Empty);
+        }
+
+        if name.len() > CHANNEL_MAX_LENGTH {
+            return Err(ChannelError::TooLong {
+                length: name.len(),
+                max: CHANNEL_MAX_LENGTH
+            });
+        }
+
+        let invalid = name.chars().find(|char| {
+            !char.is_ascii_alphanumeric() && !['-', '_', '.', '/', ':'].contains(char)
+        });
+
+        if let Some(char) = invalid {
+            return Err(ChannelError::InvalidCharacter(char));
+        }
+
+        Ok(())
+    }
+
+    /// Channel used to send response to the request with given id.
+    ///
+    /// Formatted as `{channel}@{id}`.
+    pub fn reply_to(&self, id: u64) -> Self {
+        Self(format!("{}@{id}", self.base()))
+    }
+
+    /// Channel used to send response to the request with
+    /// given id sent by the client with given session token.
+    ///
+    /// Formatted as `{channel}@{session}-{id}`
+    /// with hex encoded session token.
+    pub fn reply_to_session(&self, id: u64, session: u64) -> Self {
+        Self(format!("{}@{session:016x}-{id}", self.base()))
+    }
+
+    /// Channel used to send response to the request with given id,
+    /// echoing the session token of the requester if it was sent.
+    pub fn reply_for(&self, id: u64, session: Option<u64>) -> Self {
+        match session {
+            Some(session) => self.reply_to_session(id, session),
+            None => self.reply_to(id)
+        }
+    }
+
+    /// Channel used to acknowledge the message with given id.
+    ///
+    /// Formatted as `{channel}@ack-{id}`.
+    pub fn ack(&self, id: u64) -> Self {
+        Self(format!("{}@ack-{id}", self.base()))
+    }
+
+    /// Channel shared by responses to all the requests.
+    ///
+    /// Formatted as `{channel}@replies`.
+    pub fn replies(&self) -> Self {
+        Self(format!("{}@replies", self.base()))
+    }
+
+    /// Channel used to measure round trip time
+    /// to the connected server by the ping with given id.
+    ///
+    /// Formatted as `{channel}@_ping-{id}`.
+    pub fn ping(&self, id: u64) -> Self {
+        Self(format!("{}@_ping-{id}", self.base()))
+    }
+
+    /// Check if the current channel is a ping channel.
+    pub fn is_ping(&self) -> bool {
+        self.suffix().is_some_and(|suffix| suffix.starts_with("_ping-"))
+    }
+
+    /// Base channel name without derived suffix.
+    pub fn base(&self) -> &str {
+        match self.0.split_once('@') {
+            Some((base, _)) => base,
+            None => &self.0
+        }
+    }
+
+    /// Derived channel suffix, if there's some.
+    pub fn suffix(&self) -> Option<&str> {
+        self.0.split_once('@').map(|(_, suffix)| suffix)
+    }
+
+    /// Check if the current channel is derived from another one.
+    #[inline]
+    pub fn is_derived(&self) -> bool {
+        self.suffix().is_some()
+    }
+
+    /// Request id of the reply channel, if it is one.
+    pub fn reply_id(&self) -> Option<u64> {
+        self.suffix()?.parse().ok()
+    }
+
+    /// Message id of the ack channel, if it is one.
+    pub fn ack_id(&self) -> Option<u64> {
+        self.suffix()?.strip_prefix("ack-")?.parse().ok()
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Channel on which responses to the requests are received.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReplyChannelStrategy {
+    /// Every request gets its own `{channel}@{session}-{id}` channel.
+    #[default]
+    PerRequest,
+
+    /// All the responses are sent to the `{channel}@replies`
+    /// channel and matched with requests by their ids.
+    ///
+    /// Peers which don't support this strategy
+    /// still respond using per-request channels.
+    Shared
+}
+
+impl Default for Channel {
+    #[inline]
+    fn default() -> Self {
+        Self(String::from("hyperelm"))
+    }
+}
+
+impl std::fmt::Display for Channel {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for Channel {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for Channel {
+    type Err = ChannelError;
+
+    #[inline]
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::new(name)
+    }
+}
+
+impl TryFrom<&str> for Channel {
+    type Error = ChannelError;
+
+    #[inline]
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        Self::new(name)
+    }
+}
+
+impl TryFrom<String> for Channel {
+    type Error = ChannelError;
+
+    #[inline]
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        Self::new(name)
+    }
+}
+
+impl From<Channel> for String {
+    #[inline]
+    fn from(channel: Channel) -> Self {
+        channel.0
+    }
+}
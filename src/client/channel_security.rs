@@ -0,0 +1,176 @@
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use super::{Channel, GroupChannel, GroupError};
+
+/// Encryption of the messages sent to the channel.
+#[derive(Debug, Default, Clone)]
+pub enum ChannelSecurity {
+    /// Messages are encrypted for every receiver
+    /// using its public key.
+    #[default]
+    Pairwise,
+
+    /// Messages are encrypted once with the symmetric key
+    /// shared by all the members of the channel.
+    SharedSecret(Arc<SharedSecret>)
+}
+
+impl ChannelSecurity {
+    /// Create shared secret security with given key.
+    #[inline]
+    pub fn shared_secret(key: [u8; 32]) -> Self {
+        Self::SharedSecret(Arc::new(SharedSecret::new(key)))
+    }
+}
+
+struct SharedKeys {
+    current: (u32, [u8; 32]),
+    previous: Vec<(u32, [u8; 32], Instant)>
+}
+
+/// Rotatable symmetric key of the channel.
+///
+/// Every key has an id sent together with the encrypted
+/// message. After rotation the previous keys are kept for
+/// the grace period so in-flight messages can be decrypted.
+///
+/// ```rust
+/// use hyperelm::client::{Channel, SharedSecret};
+///
+/// let channel = Channel::new("friends").unwrap();
+/// let secret = SharedSecret::new(rand::random());
+///
+/// let (key_id, encrypted) = secret.encrypt(&channel, b"Hello, World!").unwrap();
+///
+/// // Messages encrypted with the previous key are still readable
+/// let new_key_id = secret.rotate(rand::random());
+///
+/// assert_ne!(key_id, new_key_id);
+/// assert_eq!(secret.decrypt(&channel, key_id, &encrypted).unwrap(), b"Hello, World!");
+///
+/// // Other members don't know the previous key
+/// let member = SharedSecret::with_key_id(new_key_id, secret.key());
+///
+/// assert!(member.decrypt(&channel, key_id, &encrypted).is_err());
+/// ```
+pub struct SharedSecret {
+    keys: RwLock<SharedKeys>,
+    grace_period: Duration
+}
+
+impl std::fmt::Debug for SharedSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedSecret")
+            .field("key_id", &self.key_id())
+            .field("key", &"<redacted>")
+            .field("grace_period", &self.grace_period)
+            .finish()
+    }
+}
+
+impl SharedSecret {
+    /// Create shared secret with given key and id 0.
+    #[inline]
+    pub fn new(key: [u8; 32]) -> Self {
+        Self::with_key_id(0, key)
+    }
+
+    /// Create shared secret with given key and its id.
+    ///
+    /// Use it to join the channel whose key was rotated.
+    pub fn with_key_id(key_id: u32, key: [u8; 32]) -> Self {
+        Self {
+            keys: RwLock::new(SharedKeys {
+                current: (key_id, key),
+                previous: Vec::new()
+            }),
+            grace_period: Duration::from_secs(60 * 60)
+        }
+    }
+
+    /// Change time during which messages encrypted
+    /// with the previous keys can be decrypted.
+    pub fn with_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+
+        self
+    }
+
+    #[inline]
+    pub fn grace_period(&self) -> Duration {
+        self.grace_period
+    }
+
+    /// Get id of the current key.
+    pub fn key_id(&self) -> u32 {
+        match self.keys.read() {
+            Ok(keys) => keys.current.0,
+            Err(err) => err.into_inner().current.0
+        }
+    }
+
+    /// Get current key.
+    pub fn key(&self) -> [u8; 32] {
+        match self.keys.read() {
+            Ok(keys) => keys.current.1,
+            Err(err) => err.into_inner().current.1
+        }
+    }
+
+    /// Replace current key with a new one,
+    /// returning id of the new key.
+    pub fn rotate(&self, key: [u8; 32]) -> u32 {
+        let mut keys = match self.keys.write() {
+            Ok(keys) => keys,
+            Err(err) => err.into_inner()
+        };
+
+        let (old_id, old_key) = keys.current;
+        let new_id = old_id.wrapping_add(1);
+
+        keys.current = (new_id, key);
+
+        // Remove keys with elapsed grace period
+        keys.previous.retain(|(_, _, rotated_at)| rotated_at.elapsed() < self.grace_period);
+        keys.previous.push((old_id, old_key, Instant::now()));
+
+        new_id
+    }
+
+    /// Encrypt message with the current key,
+    /// returning the key's id and the encrypted message.
+    pub fn encrypt(&self, channel: &Channel, message: &[u8]) -> Result<(u32, Vec<u8>), GroupError> {
+        let (key_id, key) = match self.keys.read() {
+            Ok(keys) => keys.current,
+            Err(err) => err.into_inner().current
+        };
+
+        let encrypted = GroupChannel::new(channel.clone(), key)
+            .encrypt(message)?;
+
+        Ok((key_id, encrypted))
+    }
+
+    /// Decrypt message encrypted with the key with given id.
+    pub fn decrypt(&self, channel: &Channel, key_id: u32, message: &[u8]) -> Result<Vec<u8>, GroupError> {
+        let key = {
+            let keys = match self.keys.read() {
+                Ok(keys) => keys,
+                Err(err) => err.into_inner()
+            };
+
+            if keys.current.0 == key_id {
+                Some(keys.current.1)
+            } else {
+                keys.previous.iter()
+                    .find(|(id, _, rotated_at)| *id == key_id && rotated_at.elapsed() < self.grace_period)
+                    .map(|(_, key, _)| *key)
+            }
+        };
+
+        let key = key.ok_or(GroupError::UnknownKey(key_id))?;
+
+        GroupChannel::new(channel.clone(), key).decrypt(message)
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// State of the circuit of the remote server.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum CircuitState {
+    /// Messages are sent normally.
+    #[default]
+    Closed,
+
+    /// Messages fail fast without contacting the server.
+    Open,
+
+    /// Cooldown is elapsed and the next message
+    /// will probe the server.
+    HalfOpen
+}
+
+/// Params of the per-server circuit breaker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CircuitBreakerConfig {
+    /// Amount of consecutive failures within the `window`
+    /// after which the circuit is opened.
+    ///
+    /// Circuit breaker is disabled if set to 0.
+    pub failure_threshold: u32,
+
+    /// Time window in which failures are counted.
+    pub window: Duration,
+
+    /// Time after which opened circuit lets
+    /// a single probe message through.
+    pub cooldown: Duration
+}
+
+impl Default for CircuitBreakerConfig {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            window: Duration::from_secs(30),
+            cooldown: Duration::from_secs(30)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Circuit {
+    failures: u32,
+    first_failure_at: Instant,
+    opened_at: Option<Instant>,
+    probe_started_at: Option<Instant>
+}
+
+/// Circuit breaker of the remote servers.
+///
+/// After `failure_threshold` consecutive failed deliveries to
+/// the same server within the window its circuit is opened and
+/// messages to it fail fast. After the cooldown one probe message
+/// is let through, closing the circuit on success or opening it
+/// again on failure.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use hyperelm::client::{CircuitBreaker, CircuitBreakerConfig, CircuitState};
+///
+/// let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+///     failure_threshold: 2,
+///     window: Duration::from_secs(30),
+///     cooldown: Duration::from_millis(50)
+/// });
+///
+/// assert!(breaker.try_acquire("dead.server:8001"));
+///
+/// breaker.record_failure("dead.server:8001");
+/// breaker.record_failure("dead.server:8001");
+///
+/// assert_eq!(breaker.state("dead.server:8001"), CircuitState::Open);
+/// assert!(!breaker.try_acquire("dead.server:8001"));
+///
+/// std::thread::sleep(Duration::from_millis(60));
+///
+/// // Only one probe is let through
+/// assert_eq!(breaker.state("dead.server:8001"), CircuitState::HalfOpen);
+/// assert!(breaker.try_acquire("dead.server:8001"));
+/// assert!(!breaker.try_acquire("dead.server:8001"));
+///
+/// breaker.record_success("dead.server:8001");
+///
+/// assert_eq!(breaker.state("dead.server:8001"), CircuitState::Closed);
+/// ```
+#[derive(Debug, Default)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    circuits: Mutex<HashMap<String, Circuit>>
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            circuits: Mutex::new(HashMap::new())
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> &CircuitBreakerConfig {
+        &self.config
+    }
+
+    /// Get state of the server's circuit.
+    pub fn state(&self, address: &str) -> CircuitState {
+        let Ok(circuits) = self.circuits.lock() else {
+            return CircuitState::Closed;
+        };
+
+        match circuits.get(address).and_then(|circuit| circuit.opened_at) {
+            Some(opened_at) if opened_at.elapsed() >= self.config.cooldown => CircuitState::HalfOpen,
+            Some(_) => CircuitState::Open,
+            None => CircuitState::Closed
+        }
+    }
+
+    /// Check if a message can be sent to the server.
+    ///
+    /// Returns `true` for the half-open circuit only once
+    /// per cooldown so only one probe message is sent.
+    pub fn try_acquire(&self, address: &str) -> bool {
+        let Ok(mut circuits) = self.circuits.lock() else {
+            return true;
+        };
+
+        let Some(circuit) = circuits.get_mut(address) else {
+            return true;
+        };
+
+        let Some(opened_at) = circuit.opened_at else {
+            return true;
+        };
+
+        if opened_at.elapsed() < self.config.cooldown {
+            return false;
+        }
+
+        // Let another probe through if the previous one
+        // was never finished
+        let probing = circuit.probe_started_at
+            .is_some_and(|started_at| started_at.elapsed() < self.config.cooldown);
+
+        if probing {
+            return false;
+        }
+
+        circuit.probe_started_at = Some(Instant::now());
+
+        true
+    }
+
+    /// Close the server's circuit.
+    #[inline]
+    pub fn record_success(&self, address: &str) {
+        self.reset(address);
+    }
+
+    /// Count failed delivery to the server.
+    pub fn record_failure(&self, address: &str) {
+        if self.config.failure_threshold == 0 {
+            return;
+        }
+
+        let Ok(mut circuits) = self.circuits.lock() else {
+            return;
+        };
+
+        let now = Instant::now();
+
+        let circuit = circuits.entry(address.to_string())
+            .or_insert(Circuit {
+                failures: 0,
+                first_failure_at: now,
+                opened_at: None,
+                probe_started_at: None
+            });
+
+        // Open the circuit again if the probe has failed
+        if circuit.opened_at.is_some() {
+            circuit.opened_at = Some(now);
+            circuit.probe_started_at = None;
+
+            return;
+        }
+
+        if circuit.first_failure_at.elapsed() > self.config.window {
+            circuit.failures = 0;
+            circuit.first_failure_at = now;
+        }
+
+        circuit.failures += 1;
+
+        if circuit.failures >= self.config.failure_threshold {
+            circuit.opened_at = Some(now);
+        }
+    }
+
+    /// Forget failures of the server closing its circuit.
+    pub fn reset(&self, address: &str) {
+        if let Ok(mut circuits) = self.circuits.lock() {
+            circuits.remove(address);
+        }
+    }
+}
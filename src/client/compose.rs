@@ -0,0 +1,398 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde_json::{json, Value as Json};
+
+use hyperborealib::http::HttpClient;
+use hyperborealib::rest_api::prelude::*;
+
+use super::{ClientApp, ClientAppError, ClientAppParams, ClientEndpoint, MessageContext, MessageId, Respond};
+
+/// Error returned by the sub-application handlers.
+pub type SubAppError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ComposeError {
+    #[error("Sub-application '{0}' is not mounted")]
+    UnknownApp(String),
+
+    #[error("Sub-application '{app}' failed: {error}")]
+    SubApp {
+        app: String,
+        error: SubAppError
+    },
+
+    #[error("Failed to convert payload of the sub-application '{app}': {error}")]
+    Payload {
+        app: String,
+        error: AsJsonError
+    }
+}
+
+/// Independent module of the `ComposedClientApp`.
+///
+/// Each sub-application has its own input and output
+/// types, handlers and state, and receives only the
+/// envelopes addressed to its `NAME`.
+#[async_trait::async_trait]
+pub trait SubApp: Send + Sync + 'static {
+    /// Name of the sub-application used to route envelopes.
+    ///
+    /// Must be the same for all the clients using it.
+    const NAME: &'static str;
+
+    type InputRequest: AsJson + Send;
+    type InputResponse: AsJson + Send;
+    type InputMessage: AsJson + Send;
+
+    type OutputRequest: AsJson + Send;
+    type OutputResponse: AsJson + Send;
+    type OutputMessage: AsJson + Send;
+
+    type State: Send + Sync + 'static;
+
+    fn get_state(&self) -> Arc<Self::State>;
+
+    async fn handle_request(&self, request: Self::InputRequest, info: MessageInfo) -> Result<Self::InputResponse, SubAppError>;
+    async fn handle_message(&self, message: Self::InputMessage, info: MessageInfo) -> Result<(), SubAppError>;
+}
+
+/// Statistics of the envelopes handled by a sub-application.
+#[derive(Debug, Default)]
+pub struct SubAppMetrics {
+    requests: AtomicU64,
+    messages: AtomicU64,
+    errors: AtomicU64
+}
+
+impl SubAppMetrics {
+    #[inline]
+    pub fn requests_handled(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn messages_handled(&self) -> u64 {
+        self.messages.load(Ordering::Relaxed)
+    }
+
+    /// Amount of requests and messages failed to be handled.
+    #[inline]
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+}
+
+/// Payload of the composed client app envelopes.
+///
+/// ```rust
+/// use serde_json::json;
+///
+/// use hyperelm::exports::hyperborealib::rest_api::prelude::*;
+/// use hyperelm::client::ComposedPayload;
+///
+/// let payload = ComposedPayload {
+///     app: String::from("chat"),
+///     body: json!("Hello, World!")
+/// };
+///
+/// assert_eq!(payload.to_json().unwrap(), json!({
+///     "app": "chat",
+///     "body": "Hello, World!"
+/// }));
+///
+/// assert_eq!(ComposedPayload::from_json(&payload.to_json().unwrap()).unwrap(), payload);
+/// assert!(ComposedPayload::from_json(&json!({ "body": null })).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComposedPayload {
+    pub app: String,
+    pub body: Json
+}
+
+impl AsJson for ComposedPayload {
+    fn to_json(&self) -> Result<Json, AsJsonError> {
+        Ok(json!({
+            "app": self.app,
+            "body": self.body
+        }))
+    }
+
+    fn from_json(json: &Json) -> Result<Self, AsJsonError> where Self: Sized {
+        Ok(Self {
+            app: json.get("app")
+                .and_then(Json::as_str)
+                .map(String::from)
+                .ok_or_else(|| AsJsonError::FieldNotFound("app"))?,
+
+            body: json.get("body")
+                .cloned()
+                .ok_or_else(|| AsJsonError::FieldNotFound("body"))?
+        })
+    }
+}
+
+/// Type-erased sub-application.
+#[async_trait::async_trait]
+trait MountedApp: Send + Sync {
+    async fn handle_request(&self, request: Json, info: MessageInfo) -> Result<Json, ComposeError>;
+    async fn handle_message(&self, message: Json, info: MessageInfo) -> Result<(), ComposeError>;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+#[async_trait::async_trait]
+impl<A: SubApp> MountedApp for A {
+    async fn handle_request(&self, request: Json, info: MessageInfo) -> Result<Json, ComposeError> {
+        let payload_error = |error| ComposeError::Payload {
+            app: A::NAME.to_string(),
+            error
+        };
+
+        let request = A::InputRequest::from_json(&request)
+            .map_err(payload_error)?;
+
+        let response = SubApp::handle_request(self, request, info).await
+            .map_err(|error| ComposeError::SubApp {
+                app: A::NAME.to_string(),
+                error
+            })?;
+
+        response.to_json().map_err(payload_error)
+    }
+
+    async fn handle_message(&self, message: Json, info: MessageInfo) -> Result<(), ComposeError> {
+        let message = A::InputMessage::from_json(&message)
+            .map_err(|error| ComposeError::Payload {
+                app: A::NAME.to_string(),
+                error
+            })?;
+
+        SubApp::handle_message(self, message, info).await
+            .map_err(|error| ComposeError::SubApp {
+                app: A::NAME.to_string(),
+                error
+            })
+    }
+
+    #[inline]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+struct Mounted {
+    app: Box<dyn MountedApp>,
+    metrics: Arc<SubAppMetrics>
+}
+
+/// Client application made of multiple sub-applications.
+///
+/// Payloads of the sub-applications are wrapped into
+/// `{ "app": "<name>", "body": ... }` envelopes, and incoming
+/// ones are routed to the sub-application by its name.
+/// Envelopes of not mounted sub-applications are rejected
+/// with `ComposeError::UnknownApp`.
+///
+/// ```rust,ignore
+/// let middleware = params.build_middleware()?;
+///
+/// let client = ComposedClientApp::new(params, middleware)?
+///     .mount(Chat::default())
+///     .mount(Presence::default());
+///
+/// let response = client.app::<Chat>().unwrap()
+///     .request(endpoint, ChatRequest::History)
+///     .await?;
+/// ```
+pub struct ComposedClientApp<T: HttpClient> {
+    params: ClientAppParams,
+    runtime: ClientRuntime,
+    middleware: ClientMiddleware<T>,
+    apps: HashMap<&'static str, Mounted>
+}
+
+impl<T: HttpClient> std::fmt::Debug for ComposedClientApp<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ComposedClientApp")
+            .field("params", &self.params)
+            .field("apps", &self.apps.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<T: HttpClient> ComposedClientApp<T> {
+    /// Create client application without sub-applications.
+    ///
+    /// Returns error if persisted runtime state can't be loaded.
+    pub fn new(params: ClientAppParams, middleware: ClientMiddleware<T>) -> Result<Self, ClientRuntimeError> {
+        Ok(Self {
+            runtime: ClientRuntime::new(&params)?,
+            params,
+            middleware,
+            apps: HashMap::new()
+        })
+    }
+
+    /// Mount sub-application, replacing the
+    /// previously mounted one with the same name.
+    pub fn mount<A: SubApp>(mut self, app: A) -> Self {
+        self.apps.insert(A::NAME, Mounted {
+            app: Box::new(app),
+            metrics: Arc::default()
+        });
+
+        self
+    }
+
+    /// List names of the mounted sub-applications.
+    pub fn apps(&self) -> Vec<&'static str> {
+        self.apps.keys().copied().collect()
+    }
+
+    /// Get handle of the mounted sub-application.
+    pub fn app<A: SubApp>(&self) -> Option<SubAppHandle<'_, A, T>> {
+        let mounted = self.apps.get(A::NAME)?;
+
+        Some(SubAppHandle {
+            client: self,
+            app: mounted.app.as_any().downcast_ref::<A>()?,
+            metrics: &mounted.metrics
+        })
+    }
+
+    /// Get metrics of the sub-application with given name.
+    pub fn metrics(&self, app: &str) -> Option<Arc<SubAppMetrics>> {
+        self.apps.get(app)
+            .map(|mounted| mounted.metrics.clone())
+    }
+
+    fn mounted(&self, app: &str) -> Result<&Mounted, ClientAppError<ComposeError>> {
+        self.apps.get(app)
+            .ok_or_else(|| ClientAppError::Custom(ComposeError::UnknownApp(app.to_string())))
+    }
+}
+
+/// Typed handle of the sub-application
+/// mounted to the `ComposedClientApp`.
+pub struct SubAppHandle<'a, A: SubApp, T: HttpClient> {
+    client: &'a ComposedClientApp<T>,
+    app: &'a A,
+    metrics: &'a SubAppMetrics
+}
+
+impl<A, T> SubAppHandle<'_, A, T>
+where
+    A: SubApp,
+    T: HttpClient + Send + Sync + 'static
+{
+    #[inline]
+    pub fn app(&self) -> &A {
+        self.app
+    }
+
+    #[inline]
+    pub fn state(&self) -> Arc<A::State> {
+        self.app.get_state()
+    }
+
+    #[inline]
+    pub fn metrics(&self) -> &SubAppMetrics {
+        self.metrics
+    }
+
+    /// Send request of the sub-application to the same
+    /// sub-application of another client.
+    pub async fn request(&self, endpoint: ClientEndpoint, request: A::OutputRequest) -> Result<A::OutputResponse, ClientAppError<ComposeError>> {
+        let request = ComposedPayload {
+            app: A::NAME.to_string(),
+            body: request.to_json()?
+        };
+
+        let response = self.client.request(endpoint, request).await?;
+
+        Ok(A::OutputResponse::from_json(&response.body)?)
+    }
+
+    /// Send message of the sub-application to the same
+    /// sub-application of another client.
+    pub async fn send(&self, endpoint: ClientEndpoint, message: A::OutputMessage) -> Result<MessageId, ClientAppError<ComposeError>> {
+        let message = ComposedPayload {
+            app: A::NAME.to_string(),
+            body: message.to_json()?
+        };
+
+        self.client.send(endpoint, message).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> ClientApp for ComposedClientApp<T>
+where
+    T: HttpClient + Send + Sync + 'static
+{
+    type InputRequest = ComposedPayload;
+    type InputResponse = ComposedPayload;
+    type InputMessage = ComposedPayload;
+
+    type OutputRequest = ComposedPayload;
+    type OutputResponse = ComposedPayload;
+    type OutputMessage = ComposedPayload;
+
+    type HttpClient = T;
+    type State = ();
+    type Error = ComposeError;
+
+    #[inline]
+    fn get_params(&self) -> &ClientAppParams {
+        &self.params
+    }
+
+    #[inline]
+    fn get_runtime(&self) -> &ClientRuntime {
+        &self.runtime
+    }
+
+    #[inline]
+    fn get_middleware(&self) -> &ClientMiddleware<Self::HttpClient> {
+        &self.middleware
+    }
+
+    #[inline]
+    fn get_state(&self) -> Arc<Self::State> {
+        Arc::new(())
+    }
+
+    async fn handle_request(&self, request: Self::InputRequest, info: MessageInfo) -> Result<Respond<Self::InputResponse>, ClientAppError<Self::Error>> {
+        let mounted = self.mounted(&request.app)?;
+
+        mounted.metrics.requests.fetch_add(1, Ordering::Relaxed);
+
+        match mounted.app.handle_request(request.body, info).await {
+            Ok(body) => Ok(Respond::Now(ComposedPayload {
+                app: request.app,
+                body
+            })),
+
+            Err(err) => {
+                mounted.metrics.errors.fetch_add(1, Ordering::Relaxed);
+
+                Err(ClientAppError::Custom(err))
+            }
+        }
+    }
+
+    async fn handle_message(&self, message: Self::InputMessage, ctx: MessageContext<'_, Self>) -> Result<(), ClientAppError<Self::Error>> {
+        let mounted = self.mounted(&message.app)?;
+
+        mounted.metrics.messages.fetch_add(1, Ordering::Relaxed);
+
+        mounted.app.handle_message(message.body, ctx.into_info()).await
+            .inspect_err(|_| {
+                mounted.metrics.errors.fetch_add(1, Ordering::Relaxed);
+            })
+            .map_err(ClientAppError::Custom)
+    }
+}
@@ -0,0 +1,141 @@
+use std::sync::Mutex;
+
+use hyperborealib::crypto::asymmetric::PublicKey;
+
+/// State of the connection to the server.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConnectionState {
+    /// Client has never connected to the server.
+    #[default]
+    NotConnected,
+
+    Connected,
+
+    /// Connection to the server was lost.
+    Disconnected
+}
+
+/// Tracker of the connection state used to call
+/// lifecycle hooks only when the state is changed.
+///
+/// ```rust
+/// use hyperelm::client::{ConnectionTracker, ConnectionState};
+///
+/// let tracker = ConnectionTracker::default();
+///
+/// assert_eq!(tracker.connect(), ConnectionState::NotConnected);
+/// assert_eq!(tracker.connect(), ConnectionState::Connected);
+///
+/// assert!(tracker.disconnect());
+/// assert!(!tracker.disconnect());
+///
+/// assert_eq!(tracker.connect(), ConnectionState::Disconnected);
+/// ```
+#[derive(Debug, Default)]
+pub struct ConnectionTracker {
+    state: Mutex<ConnectionState>
+}
+
+impl ConnectionTracker {
+    #[inline]
+    pub fn state(&self) -> ConnectionState {
+        self.state.lock()
+            .map(|state| *state)
+            .unwrap_or_default()
+    }
+
+    /// Mark connection as established, returning previous state.
+    pub fn connect(&self) -> ConnectionState {
+        match self.state.lock() {
+            Ok(mut state) => std::mem::replace(&mut *state, ConnectionState::Connected),
+            Err(_) => ConnectionState::Connected
+        }
+    }
+
+    /// Mark connection as lost.
+    ///
+    /// Returns `true` if the client was connected before.
+    pub fn disconnect(&self) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+
+        if *state != ConnectionState::Connected {
+            return false;
+        }
+
+        *state = ConnectionState::Disconnected;
+
+        true
+    }
+}
+
+/// Server the client is connected to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ServerEndpoint {
+    pub server_address: String,
+    pub server_public: PublicKey
+}
+
+impl ServerEndpoint {
+    #[inline]
+    pub fn new(server_address: impl ToString, server_public: PublicKey) -> Self {
+        Self {
+            server_address: server_address.to_string(),
+            server_public
+        }
+    }
+}
+
+/// Reason of the lost connection to the server.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DisconnectReason {
+    /// Request to the server failed with given error.
+    ConnectionLost(String),
+
+    /// Server was restarted and could forget the client.
+    ServerRestarted,
+
+    /// Client was asked to stop.
+    Shutdown
+}
+
+/// Handler of the client connection lifecycle events.
+///
+/// All the methods do nothing by default.
+#[async_trait::async_trait]
+pub trait ConnectionEventHandler: Send + Sync {
+    /// Called when the connection to the server
+    /// is established or restored.
+    #[allow(unused_variables)]
+    async fn on_connected(&self, server: &ServerEndpoint) {}
+
+    /// Called when the connection to the server is lost.
+    #[allow(unused_variables)]
+    async fn on_disconnected(&self, server: &ServerEndpoint, reason: DisconnectReason) {}
+
+    /// Called before every reconnection attempt, starting from 1.
+    #[allow(unused_variables)]
+    async fn on_reconnecting(&self, server: &ServerEndpoint, attempt: u32) {}
+
+    /// Called when all the reconnection attempts allowed
+    /// by the `reconnect_policy` param have failed.
+    ///
+    /// Client running in the background keeps reconnecting
+    /// after `max_delay` of the policy.
+    #[allow(unused_variables)]
+    async fn on_reconnect_failed(&self, server: &ServerEndpoint, attempts: u32) {}
+
+    /// Called when the application switches to another server.
+    ///
+    /// The client itself is bound to a single server, so this
+    /// method is only called by applications managing failover.
+    #[allow(unused_variables)]
+    async fn on_failover(&self, from: &ServerEndpoint, to: &ServerEndpoint) {}
+}
+
+impl std::fmt::Debug for dyn ConnectionEventHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ConnectionEventHandler")
+    }
+}
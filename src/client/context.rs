@@ -0,0 +1,98 @@
+use hyperborealib::rest_api::prelude::*;
+
+use super::{ClientApp, ClientAppError, ClientEndpoint, MessageId};
+
+/// Context of the incoming message passed to the handler.
+///
+/// ```rust,ignore
+/// async fn handle_message(&self, message: InMsg, ctx: MessageContext<'_, Self>) -> Result<(), ClientAppError<()>> {
+///     match message {
+///         InMsg::Ping => {
+///             ctx.reply(OutMsg::Pong).await?;
+///         }
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub struct MessageContext<'a, A: ?Sized> {
+    app: &'a A,
+    info: MessageInfo,
+    payload_size: usize
+}
+
+impl<A: ?Sized> std::fmt::Debug for MessageContext<'_, A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageContext")
+            .field("info", &self.info)
+            .field("payload_size", &self.payload_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, A: ?Sized> MessageContext<'a, A> {
+    #[inline]
+    pub fn new(app: &'a A, info: MessageInfo, payload_size: usize) -> Self {
+        Self {
+            app,
+            info,
+            payload_size
+        }
+    }
+
+    #[inline]
+    pub fn app(&self) -> &'a A {
+        self.app
+    }
+
+    #[inline]
+    pub fn info(&self) -> &MessageInfo {
+        &self.info
+    }
+
+    #[inline]
+    pub fn into_info(self) -> MessageInfo {
+        self.info
+    }
+
+    /// Get endpoint of the message sender.
+    #[inline]
+    pub fn sender_endpoint(&self) -> ClientEndpoint {
+        ClientEndpoint::new(
+            &self.info.sender.server.address,
+            self.info.sender.client.public_key.clone()
+        )
+    }
+
+    #[inline]
+    pub fn channel(&self) -> &str {
+        &self.info.channel
+    }
+
+    /// UTC timestamp in seconds when the message
+    /// was received by the server of the current client.
+    #[inline]
+    pub fn received_at(&self) -> u64 {
+        self.info.received_at
+    }
+
+    /// Size of the decrypted message payload in bytes.
+    #[inline]
+    pub fn payload_size(&self) -> usize {
+        self.payload_size
+    }
+}
+
+impl<A: ClientApp + Sync> MessageContext<'_, A> {
+    /// Send message back to the sender.
+    #[inline]
+    pub async fn reply(&self, message: A::OutputMessage) -> Result<MessageId, ClientAppError<A::Error>> {
+        self.app.send(self.sender_endpoint(), message).await
+    }
+
+    /// Send request back to the sender and wait for its response.
+    #[inline]
+    pub async fn reply_request(&self, request: A::OutputRequest) -> Result<A::OutputResponse, ClientAppError<A::Error>> {
+        self.app.request(self.sender_endpoint(), request).await
+    }
+}
@@ -0,0 +1,535 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde_json::Value as Json;
+
+use sha2::{Digest, Sha256};
+
+use hyperborealib::exports::tokio;
+use hyperborealib::crypto::prelude::*;
+
+use tokio::sync::watch;
+
+/// Compute id of the message sent by the given client.
+///
+/// Id is a hash of the sender's public key, the message
+/// payload and the nonce which the sender puts into every
+/// envelope, so the same envelope re-delivered by the server
+/// has the same id, while equal payloads sent by separate
+/// `send` calls are different messages.
+///
+/// ```rust
+/// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+/// use hyperelm::client::message_id;
+///
+/// let sender = SecretKey::random().public();
+///
+/// assert_eq!(message_id(&sender, b"hello", 1), message_id(&sender, b"hello", 1));
+/// assert_ne!(message_id(&sender, b"hello", 1), message_id(&sender, b"hello", 2));
+/// assert_ne!(message_id(&sender, b"hello", 1), message_id(&sender, b"world", 1));
+/// ```
+pub fn message_id(sender: &PublicKey, payload: &[u8], nonce: u64) -> u64 {
+    let mut hasher = Sha256::new();
+
+    hasher.update(sender.to_base64().as_bytes());
+    hasher.update(payload);
+    hasher.update(nonce.to_be_bytes());
+
+    let hash = hasher.finalize();
+
+    let mut id = [0; 8];
+
+    id.copy_from_slice(&hash[..8]);
+
+    u64::from_be_bytes(id)
+}
+
+/// Id of the sent message.
+///
+/// Equal to the `message_id` computed by the receiver
+/// to suppress duplicated deliveries, so it can be
+/// used to correlate acknowledgements of the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MessageId(pub u64);
+
+impl From<u64> for MessageId {
+    #[inline]
+    fn from(id: u64) -> Self {
+        Self(id)
+    }
+}
+
+impl From<MessageId> for u64 {
+    #[inline]
+    fn from(id: MessageId) -> Self {
+        id.0
+    }
+}
+
+impl std::fmt::Display for MessageId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+#[inline]
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[derive(Debug, Default)]
+struct SeenMessagesInner {
+    /// Seen ids with the time they were seen first.
+    ids: HashMap<u64, u64>,
+
+    /// Seen ids in order of insertion.
+    order: VecDeque<u64>,
+
+    /// Amount of records appended to the file
+    /// since it was compacted last time.
+    appended: usize
+}
+
+/// Bounded set of the recently processed messages ids.
+///
+/// Ids are forgotten after `ttl` is elapsed or when
+/// the set exceeds its capacity, starting from the oldest.
+/// When persistence is enabled seen ids are appended
+/// to the file and restored from it on start.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use hyperelm::client::SeenMessages;
+///
+/// let path = std::env::temp_dir().join("hyperelm-seen-messages-doctest");
+///
+/// # let _ = std::fs::remove_file(&path);
+/// let seen = SeenMessages::new(16, Duration::from_secs(60))
+///     .with_persistence(&path)
+///     .unwrap();
+///
+/// assert!(seen.insert(123));
+/// assert!(!seen.insert(123));
+///
+/// drop(seen);
+///
+/// let seen = SeenMessages::new(16, Duration::from_secs(60))
+///     .with_persistence(&path)
+///     .unwrap();
+///
+/// assert!(seen.contains(123));
+/// assert!(!seen.insert(123));
+/// # let _ = std::fs::remove_file(&path);
+/// ```
+#[derive(Debug)]
+pub struct SeenMessages {
+    inner: Mutex<SeenMessagesInner>,
+    capacity: usize,
+    ttl: Duration,
+    path: Option<PathBuf>
+}
+
+impl Default for SeenMessages {
+    #[inline]
+    fn default() -> Self {
+        Self::new(4096, Duration::from_secs(60 * 10))
+    }
+}
+
+impl SeenMessages {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            inner: Mutex::new(SeenMessagesInner::default()),
+            capacity: capacity.max(1),
+            ttl,
+            path: None
+        }
+    }
+
+    /// Store seen ids in the given file.
+    ///
+    /// Not expired ids are loaded from the file
+    /// if it already exists.
+    pub fn with_persistence(mut self, path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+
+        if path.exists() {
+            let file = BufReader::new(File::open(&path)?);
+            let now = timestamp();
+
+            if let Ok(mut inner) = self.inner.lock() {
+                for line in file.lines() {
+                    let line = line?;
+
+                    let Some((id, seen_at)) = line.split_once(' ') else {
+                        continue;
+                    };
+
+                    let (Ok(id), Ok(seen_at)) = (u64::from_str_radix(id, 16), seen_at.parse::<u64>()) else {
+                        continue;
+                    };
+
+                    if now.saturating_sub(seen_at) < self.ttl.as_secs() && inner.ids.insert(id, seen_at).is_none() {
+                        inner.order.push_back(id);
+                    }
+                }
+
+                Self::evict(&mut inner, self.capacity, self.ttl);
+            }
+        }
+
+        self.path = Some(path);
+
+        // Drop expired and duplicated records from the file
+        self.compact()?;
+
+        Ok(self)
+    }
+
+    /// Forget expired ids and ids exceeding the capacity.
+    fn evict(inner: &mut SeenMessagesInner, capacity: usize, ttl: Duration) {
+        let now = timestamp();
+
+        while let Some(id) = inner.order.front().copied() {
+            let expired = inner.ids.get(&id)
+                .map(|seen_at| now.saturating_sub(*seen_at) >= ttl.as_secs())
+                .unwrap_or(true);
+
+            if !expired && inner.order.len() <= capacity {
+                break;
+            }
+
+            inner.order.pop_front();
+            inner.ids.remove(&id);
+        }
+    }
+
+    /// Rewrite the persistence file with the currently stored ids.
+    fn compact(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let Ok(mut inner) = self.inner.lock() else {
+            return Ok(());
+        };
+
+        let mut file = BufWriter::new(File::create(path)?);
+
+        for id in &inner.order {
+            if let Some(seen_at) = inner.ids.get(id) {
+                writeln!(file, "{id:016x} {seen_at}")?;
+            }
+        }
+
+        file.flush()?;
+
+        inner.appended = 0;
+
+        Ok(())
+    }
+
+    /// Append id to the persistence file.
+    fn append(&self, path: &Path, id: u64, seen_at: u64) -> std::io::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+
+        writeln!(file, "{id:016x} {seen_at}")
+    }
+
+    /// Remember given message id.
+    ///
+    /// Returns `false` if this id was already seen.
+    pub fn insert(&self, id: u64) -> bool {
+        let seen_at = timestamp();
+
+        let compact = {
+            let Ok(mut inner) = self.inner.lock() else {
+                return true;
+            };
+
+            Self::evict(&mut inner, self.capacity, self.ttl);
+
+            if inner.ids.contains_key(&id) {
+                return false;
+            }
+
+            inner.ids.insert(id, seen_at);
+            inner.order.push_back(id);
+
+            Self::evict(&mut inner, self.capacity, self.ttl);
+
+            inner.appended += 1;
+
+            inner.appended > self.capacity
+        };
+
+        if let Some(path) = &self.path {
+            let _result = if compact {
+                self.compact()
+            } else {
+                self.append(path, id, seen_at)
+            };
+
+            #[cfg(feature = "tracing")]
+            if let Err(err) = _result {
+                tracing::error!("[client] Failed to persist seen message id: {err}");
+            }
+        }
+
+        true
+    }
+
+    /// Check if given message id was already seen.
+    pub fn contains(&self, id: u64) -> bool {
+        let now = timestamp();
+
+        self.inner.lock()
+            .map(|inner| {
+                inner.ids.get(&id)
+                    .map(|seen_at| now.saturating_sub(*seen_at) < self.ttl.as_secs())
+                    .unwrap_or(false)
+            })
+            .unwrap_or(false)
+    }
+
+    /// Maximal amount of remembered ids.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Time during which seen id is remembered.
+    #[inline]
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Path to the file storing seen ids.
+    #[inline]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inner.lock()
+            .map(|inner| inner.ids.len())
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Caller-provided id of the outgoing request.
+///
+/// Requests sent by the `request_with_id` method with
+/// the same id are sent only once, see `OutgoingRequestDedup`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RequestId(pub u64);
+
+impl From<u64> for RequestId {
+    #[inline]
+    fn from(id: u64) -> Self {
+        Self(id)
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Result of the deduplicated request shared with
+/// all the callers. Errors are passed as strings.
+type SharedResponse = Option<Result<Json, String>>;
+
+/// State of the outgoing request with given id.
+#[derive(Debug)]
+pub enum DedupState<'a> {
+    /// Request wasn't sent yet. Caller must send it
+    /// and pass the result to the guard.
+    New(DedupGuard<'a>),
+
+    /// Request is sent by another caller.
+    InFlight(watch::Receiver<SharedResponse>),
+
+    /// Request was completed within the dedup window.
+    Completed(Json)
+}
+
+/// Guard of the request sent by the current caller.
+///
+/// If dropped without completion the request is forgotten
+/// and callers waiting for it receive an error.
+#[derive(Debug)]
+pub struct DedupGuard<'a> {
+    dedup: &'a OutgoingRequestDedup,
+    id: RequestId,
+    completed: bool
+}
+
+impl DedupGuard<'_> {
+    /// Share result of the request with the waiting callers.
+    ///
+    /// Successful responses are remembered for the dedup window,
+    /// failed requests are forgotten so they can be retried.
+    pub fn complete(mut self, result: Result<Json, String>) {
+        self.completed = true;
+
+        let Ok(mut requests) = self.dedup.requests.lock() else {
+            return;
+        };
+
+        let Some((completed_at, sender)) = requests.get_mut(&self.id) else {
+            return;
+        };
+
+        let failed = result.is_err();
+
+        sender.send_replace(Some(result));
+
+        if failed {
+            requests.remove(&self.id);
+        } else {
+            *completed_at = Some(Instant::now());
+        }
+    }
+}
+
+impl Drop for DedupGuard<'_> {
+    fn drop(&mut self) {
+        if !self.completed {
+            if let Ok(mut requests) = self.dedup.requests.lock() {
+                requests.remove(&self.id);
+            }
+        }
+    }
+}
+
+/// Registry of the outgoing requests with caller-provided ids.
+///
+/// Callers sending a request with an id of the in-flight one
+/// wait for its response instead of sending it again, and
+/// completed responses are returned for `window` after the
+/// request is finished. This gives at-most-once semantics
+/// to retried requests.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use serde_json::json;
+///
+/// use hyperelm::client::{OutgoingRequestDedup, DedupState, RequestId};
+///
+/// let dedup = OutgoingRequestDedup::new(Duration::from_secs(60));
+///
+/// let DedupState::New(guard) = dedup.begin(RequestId(1)) else {
+///     panic!("request must be new");
+/// };
+///
+/// // Request is in flight until the guard is completed
+/// assert!(matches!(dedup.begin(RequestId(1)), DedupState::InFlight(_)));
+///
+/// guard.complete(Ok(json!({ "pong": true })));
+///
+/// let DedupState::Completed(response) = dedup.begin(RequestId(1)) else {
+///     panic!("request must be completed");
+/// };
+///
+/// assert_eq!(response, json!({ "pong": true }));
+/// ```
+#[derive(Debug)]
+pub struct OutgoingRequestDedup {
+    /// Requests with the time they were completed at.
+    requests: Mutex<HashMap<RequestId, (Option<Instant>, watch::Sender<SharedResponse>)>>,
+    window: Duration
+}
+
+impl Default for OutgoingRequestDedup {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Duration::from_secs(60 * 5))
+    }
+}
+
+impl OutgoingRequestDedup {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            requests: Mutex::new(HashMap::new()),
+            window
+        }
+    }
+
+    /// Time during which completed requests are remembered.
+    #[inline]
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Get state of the request with given id,
+    /// registering it if it's not known.
+    pub fn begin(&self, id: RequestId) -> DedupState<'_> {
+        let mut requests = match self.requests.lock() {
+            Ok(requests) => requests,
+            Err(err) => err.into_inner()
+        };
+
+        // Forget requests completed before the window
+        requests.retain(|_, (completed_at, _)| {
+            completed_at.map(|completed_at| completed_at.elapsed() < self.window)
+                .unwrap_or(true)
+        });
+
+        if let Some((_, sender)) = requests.get(&id) {
+            return match &*sender.borrow() {
+                Some(Ok(response)) => DedupState::Completed(response.clone()),
+                _ => DedupState::InFlight(sender.subscribe())
+            };
+        }
+
+        requests.insert(id, (None, watch::Sender::new(None)));
+
+        DedupState::New(DedupGuard {
+            dedup: self,
+            id,
+            completed: false
+        })
+    }
+
+    /// Wait for the result of the in-flight request.
+    pub async fn wait(mut receiver: watch::Receiver<SharedResponse>) -> Result<Json, String> {
+        match receiver.wait_for(Option::is_some).await {
+            Ok(result) => result.clone().unwrap_or_else(|| Err(String::from("Request was not completed"))),
+            Err(_) => Err(String::from("Request was cancelled"))
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.requests.lock()
+            .map(|requests| requests.len())
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,707 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value as Json};
+use serde_json::value::RawValue;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+
+use hyperborealib::crypto::asymmetric::PublicKey;
+use hyperborealib::rest_api::prelude::AsJson;
+
+use super::{FileManifest, ReplyChannelStrategy, GossipRequest, PeerListRequest, GET_STATE_FIELD};
+
+/// Direction of the message envelope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    Incoming,
+    Outgoing
+}
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Incoming => f.write_str("incoming"),
+            Self::Outgoing => f.write_str("outgoing")
+        }
+    }
+}
+
+/// Kind of the incoming envelope.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Envelope {
+    /// `{ "id": N, "request": ..., "reply": "shared", "session": N }`
+    Request {
+        id: u64,
+        request: Json,
+        reply: ReplyChannelStrategy,
+
+        /// Session token of the sender echoed in the response.
+        session: Option<u64>
+    },
+
+    /// `{ "id": N, "batch": [{ "id": N, "request": ... }], "reply": "shared", "session": N }`
+    Batch {
+        id: u64,
+        requests: Vec<(u64, Json)>,
+        reply: ReplyChannelStrategy,
+        session: Option<u64>
+    },
+
+    /// `{ "id": N, "ping": true, "session": N }`
+    Ping {
+        id: u64,
+        session: Option<u64>
+    },
+
+    /// `{ "id": N, "gossip": { "our_peers": [...] }, "session": N }`
+    Gossip {
+        id: u64,
+        request: GossipRequest,
+        session: Option<u64>
+    },
+
+    /// `{ "id": N, "peer_list": { "filter": "..." }, "session": N }`
+    PeerList {
+        id: u64,
+        request: PeerListRequest,
+        session: Option<u64>
+    },
+
+    /// `{ "id": N, "__hyperelm_get_state": { "key": "..." }, "session": N }`
+    GetState {
+        id: u64,
+        key: String,
+        session: Option<u64>
+    },
+
+    /// `{ "subscribe": { "topic": "...", "ttl": N } }`
+    Subscribe {
+        topic: String,
+        ttl: Option<Duration>
+    },
+
+    /// `{ "unsubscribe": { "topic": "..." } }`
+    Unsubscribe {
+        topic: String
+    },
+
+    /// `{ "moved": { "previous": "...", "timestamp": N, "signature": "..." } }`
+    Moved {
+        previous: Option<PublicKey>,
+        timestamp: Option<u64>,
+        signature: Option<Vec<u8>>
+    },
+
+    /// `{ "id": N, "file_offer": { ... }, "session": N }`
+    FileOffer {
+        id: u64,
+        manifest: FileManifest,
+        session: Option<u64>
+    },
+
+    /// `{ "file_chunk": { "transfer": N, "index": N, "data": "..." } }`
+    FileChunk {
+        transfer: u64,
+        index: u64,
+        data: Vec<u8>
+    },
+
+    /// `{ "group_channel_id": "...", "group_key_id": N, "group_message": "..." }`
+    ///
+    /// Key id is set for the messages of the shared secret channels.
+    Group {
+        id: String,
+        key_id: Option<u32>,
+        message: Vec<u8>
+    },
+
+    /// `{ "relayed": { "sender": "...", "server_address": "...", "server_public": "...", "message": ... } }`
+    Relayed {
+        sender: PublicKey,
+        server_address: String,
+        server_public: PublicKey,
+        message: Json
+    },
+
+    /// `{ "multicast": { "sender": "...", "server_address": "...", "server_public": "...", "content": "...", "signature": "..." } }`
+    Multicast {
+        sender: PublicKey,
+        server_address: String,
+        server_public: PublicKey,
+        content: Vec<u8>,
+        signature: Vec<u8>
+    },
+
+    /// `{ "message": ..., "nonce": N }`
+    Message {
+        message: Json,
+        nonce: Option<u64>
+    },
+
+    /// Envelope of unknown format.
+    Unknown
+}
+
+impl Envelope {
+    /// Determine kind of the given envelope.
+    pub fn classify(envelope: &Json) -> Self {
+        let id = envelope.get("id").and_then(Json::as_u64);
+        let session = envelope.get("session").and_then(Json::as_u64);
+
+        let reply = match envelope.get("reply").and_then(Json::as_str) {
+            Some("shared") => ReplyChannelStrategy::Shared,
+            _ => ReplyChannelStrategy::PerRequest
+        };
+
+        if let Some(request) = envelope.get("request") {
+            return match id {
+                Some(id) => Self::Request {
+                    id,
+                    request: request.clone(),
+                    reply,
+                    session
+                },
+
+                None => Self::Unknown
+            };
+        }
+
+        if let Some(batch) = envelope.get("batch").and_then(Json::as_array) {
+            let requests = batch.iter()
+                .map(|item| Some((item.get("id")?.as_u64()?, item.get("request")?.clone())))
+                .collect::<Option<Vec<_>>>();
+
+            return match (id, requests) {
+                (Some(id), Some(requests)) => Self::Batch {
+                    id,
+                    requests,
+                    reply,
+                    session
+                },
+
+                _ => Self::Unknown
+            };
+        }
+
+        if envelope.get("ping").and_then(Json::as_bool) == Some(true) {
+            return match id {
+                Some(id) => Self::Ping { id, session },
+                None => Self::Unknown
+            };
+        }
+
+        if let Some(gossip) = envelope.get("gossip") {
+            return match (id, GossipRequest::from_json(gossip)) {
+                (Some(id), Ok(request)) => Self::Gossip {
+                    id,
+                    request,
+                    session
+                },
+
+                _ => Self::Unknown
+            };
+        }
+
+        if let Some(request) = envelope.get("peer_list") {
+            return match (id, PeerListRequest::from_json(request)) {
+                (Some(id), Ok(request)) => Self::PeerList {
+                    id,
+                    request,
+                    session
+                },
+
+                _ => Self::Unknown
+            };
+        }
+
+        if let Some(request) = envelope.get(GET_STATE_FIELD) {
+            return match (id, request.get("key").and_then(Json::as_str)) {
+                (Some(id), Some(key)) => Self::GetState {
+                    id,
+                    key: key.to_string(),
+                    session
+                },
+
+                _ => Self::Unknown
+            };
+        }
+
+        if let Some(subscription) = envelope.get("subscribe") {
+            return match subscription.get("topic").and_then(Json::as_str) {
+                Some(topic) => Self::Subscribe {
+                    topic: topic.to_string(),
+                    ttl: subscription.get("ttl")
+                        .and_then(Json::as_u64)
+                        .map(Duration::from_secs)
+                },
+
+                None => Self::Unknown
+            };
+        }
+
+        if let Some(subscription) = envelope.get("unsubscribe") {
+            return match subscription.get("topic").and_then(Json::as_str) {
+                Some(topic) => Self::Unsubscribe {
+                    topic: topic.to_string()
+                },
+
+                None => Self::Unknown
+            };
+        }
+
+        if let Some(notice) = envelope.get("moved") {
+            return Self::Moved {
+                previous: notice.get("previous")
+                    .and_then(Json::as_str)
+                    .and_then(|key| PublicKey::from_base64(key).ok()),
+
+                timestamp: notice.get("timestamp")
+                    .and_then(Json::as_u64),
+
+                signature: notice.get("signature")
+                    .and_then(Json::as_str)
+                    .and_then(|signature| BASE64.decode(signature).ok())
+            };
+        }
+
+        if let Some(manifest) = envelope.get("file_offer") {
+            let manifest = serde_json::from_value::<FileManifest>(manifest.clone());
+
+            return match (id, manifest) {
+                (Some(id), Ok(manifest)) => Self::FileOffer {
+                    id,
+                    manifest,
+                    session
+                },
+
+                _ => Self::Unknown
+            };
+        }
+
+        if let Some(chunk) = envelope.get("file_chunk") {
+            let transfer = chunk.get("transfer").and_then(Json::as_u64);
+            let index = chunk.get("index").and_then(Json::as_u64);
+
+            let data = chunk.get("data")
+                .and_then(Json::as_str)
+                .and_then(|data| BASE64.decode(data).ok());
+
+            return match (transfer, index, data) {
+                (Some(transfer), Some(index), Some(data)) => Self::FileChunk {
+                    transfer,
+                    index,
+                    data
+                },
+
+                _ => Self::Unknown
+            };
+        }
+
+        if let Some(group) = envelope.get("group_channel_id").and_then(Json::as_str) {
+            let message = envelope.get("group_message")
+                .and_then(Json::as_str)
+                .and_then(|message| BASE64.decode(message).ok());
+
+            return match message {
+                Some(message) => Self::Group {
+                    id: group.to_string(),
+                    key_id: envelope.get("group_key_id")
+                        .and_then(Json::as_u64)
+                        .map(|key_id| key_id as u32),
+                    message
+                },
+
+                None => Self::Unknown
+            };
+        }
+
+        if let Some(relayed) = envelope.get("relayed") {
+            let sender = relayed.get("sender")
+                .and_then(Json::as_str)
+                .and_then(|key| PublicKey::from_base64(key).ok());
+
+            let server_address = relayed.get("server_address")
+                .and_then(Json::as_str);
+
+            let server_public = relayed.get("server_public")
+                .and_then(Json::as_str)
+                .and_then(|key| PublicKey::from_base64(key).ok());
+
+            return match (sender, server_address, server_public, relayed.get("message")) {
+                (Some(sender), Some(server_address), Some(server_public), Some(message)) => Self::Relayed {
+                    sender,
+                    server_address: server_address.to_string(),
+                    server_public,
+                    message: message.clone()
+                },
+
+                _ => Self::Unknown
+            };
+        }
+
+        if let Some(multicast) = envelope.get("multicast") {
+            let key = |field: &str| multicast.get(field)
+                .and_then(Json::as_str)
+                .and_then(|key| PublicKey::from_base64(key).ok());
+
+            let bytes = |field: &str| multicast.get(field)
+                .and_then(Json::as_str)
+                .and_then(|value| BASE64.decode(value).ok());
+
+            let server_address = multicast.get("server_address")
+                .and_then(Json::as_str);
+
+            return match (key("sender"), server_address, key("server_public"), bytes("content"), bytes("signature")) {
+                (Some(sender), Some(server_address), Some(server_public), Some(content), Some(signature)) => Self::Multicast {
+                    sender,
+                    server_address: server_address.to_string(),
+                    server_public,
+                    content,
+                    signature
+                },
+
+                _ => Self::Unknown
+            };
+        }
+
+        if let Some(message) = envelope.get("message") {
+            return Self::Message {
+                message: message.clone(),
+                nonce: envelope.get("nonce").and_then(Json::as_u64)
+            };
+        }
+
+        Self::Unknown
+    }
+}
+
+/// Get UTC timestamp after which the envelope
+/// sent now with given time to live expires.
+pub fn expires_at(ttl: Duration) -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .saturating_add(ttl)
+        .as_secs()
+}
+
+/// Check if the envelope's `expires_at` timestamp has passed
+/// more than `grace` ago. Envelopes without it never expire.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use serde_json::json;
+///
+/// use hyperelm::client::{expires_at, is_expired};
+///
+/// let grace = Duration::from_secs(30);
+///
+/// assert!(!is_expired(&json!({ "message": 1 }), grace));
+/// assert!(!is_expired(&json!({ "expires_at": expires_at(Duration::from_secs(10)) }), grace));
+///
+/// let now = expires_at(Duration::ZERO);
+///
+/// assert!(!is_expired(&json!({ "expires_at": now - 10 }), grace));
+/// assert!(is_expired(&json!({ "expires_at": now - 60 }), grace));
+/// ```
+pub fn is_expired(envelope: &Json, grace: Duration) -> bool {
+    let Some(expires_at) = envelope.get("expires_at").and_then(Json::as_u64) else {
+        return false;
+    };
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    now > expires_at.saturating_add(grace.as_secs())
+}
+
+/// Serialize given JSON value with sorted object keys
+/// and without whitespaces.
+///
+/// The same value always produces the same bytes.
+pub fn canonical_json(value: &Json) -> Result<Vec<u8>, serde_json::Error> {
+    let mut buf = Vec::new();
+
+    write_canonical_json(value, &mut buf)?;
+
+    Ok(buf)
+}
+
+/// Serialize given JSON value using `canonical_json`
+/// and wrap it into the raw JSON value.
+pub fn raw_canonical_json(value: &Json) -> Result<Box<RawValue>, serde_json::Error> {
+    let json = String::from_utf8(canonical_json(value)?)
+        .map_err(<serde_json::Error as serde::ser::Error>::custom)?;
+
+    RawValue::from_string(json)
+}
+
+/// Serialize given envelope with the pre-serialized payload
+/// stored in the `field` without parsing it again.
+///
+/// If the payload was serialized using `canonical_json`
+/// the result is the same as `canonical_json` of the envelope
+/// with the payload inserted into it.
+///
+/// ```rust
+/// use serde_json::json;
+///
+/// use hyperelm::client::{canonical_json, canonical_envelope, raw_canonical_json};
+///
+/// let payload = json!({ "text": "Hello, World!", "attachments": [1, 2, 3] });
+/// let envelope = json!({ "priority": 0, "nonce": 123 });
+///
+/// let raw = canonical_envelope(&envelope, "message", &raw_canonical_json(&payload).unwrap()).unwrap();
+///
+/// let expected = canonical_json(&json!({
+///     "message": payload,
+///     "priority": 0,
+///     "nonce": 123
+/// })).unwrap();
+///
+/// assert_eq!(raw, expected);
+/// ```
+pub fn canonical_envelope(envelope: &Json, field: &str, payload: &RawValue) -> Result<Vec<u8>, serde_json::Error> {
+    let mut entries = envelope.as_object()
+        .map(|object| {
+            object.iter()
+                .filter(|(key, _)| key.as_str() != field)
+                .map(|(key, value)| (key.as_str(), Some(value)))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    entries.push((field, None));
+
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut buf = Vec::with_capacity(payload.get().len() + 128);
+
+    buf.push(b'{');
+
+    for (i, (key, value)) in entries.into_iter().enumerate() {
+        if i > 0 {
+            buf.push(b',');
+        }
+
+        serde_json::to_writer(&mut buf, key)?;
+
+        buf.push(b':');
+
+        match value {
+            Some(value) => write_canonical_json(value, &mut buf)?,
+            None => buf.extend_from_slice(payload.get().as_bytes())
+        }
+    }
+
+    buf.push(b'}');
+
+    Ok(buf)
+}
+
+fn write_canonical_json(value: &Json, buf: &mut Vec<u8>) -> Result<(), serde_json::Error> {
+    match value {
+        Json::Object(object) => {
+            let mut entries = object.iter().collect::<Vec<_>>();
+
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+
+            buf.push(b'{');
+
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    buf.push(b',');
+                }
+
+                serde_json::to_writer(&mut *buf, key)?;
+
+                buf.push(b':');
+
+                write_canonical_json(value, buf)?;
+            }
+
+            buf.push(b'}');
+        }
+
+        Json::Array(array) => {
+            buf.push(b'[');
+
+            for (i, value) in array.iter().enumerate() {
+                if i > 0 {
+                    buf.push(b',');
+                }
+
+                write_canonical_json(value, buf)?;
+            }
+
+            buf.push(b']');
+        }
+
+        _ => serde_json::to_writer(&mut *buf, value)?
+    }
+
+    Ok(())
+}
+
+/// Append-only file rotated by size.
+///
+/// When the file exceeds `max_size` bytes it is renamed
+/// to `{path}.1`, previous `{path}.1` to `{path}.2` and so on,
+/// keeping at most `max_files` rotated files.
+#[derive(Debug)]
+pub(crate) struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    file: Mutex<Option<File>>
+}
+
+impl RotatingFile {
+    pub fn new(path: impl Into<PathBuf>, max_size: u64, max_files: usize) -> Self {
+        Self {
+            path: path.into(),
+            max_size,
+            max_files,
+            file: Mutex::new(None)
+        }
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get path to the rotated file with given index.
+    pub fn rotated_path(&self, index: usize) -> PathBuf {
+        rotated_path(&self.path, index)
+    }
+
+    /// Append bytes to the file, rotating it
+    /// if it would exceed the max size.
+    pub fn append(&self, bytes: &[u8]) -> std::io::Result<()> {
+        let mut file = self.file.lock()
+            .map_err(|_| std::io::Error::other("rotating file lock is poisoned"))?;
+
+        // Rotate the file if it's too large
+        if self.path.exists() && self.path.metadata()?.len() + bytes.len() as u64 > self.max_size {
+            *file = None;
+
+            self.rotate()?;
+        }
+
+        if file.is_none() {
+            *file = Some(OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?);
+        }
+
+        if let Some(file) = file.as_mut() {
+            file.write_all(bytes)?;
+            file.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn rotate(&self) -> std::io::Result<()> {
+        if self.max_files == 0 {
+            return std::fs::remove_file(&self.path);
+        }
+
+        let oldest = self.rotated_path(self.max_files);
+
+        if oldest.exists() {
+            std::fs::remove_file(oldest)?;
+        }
+
+        for i in (1..self.max_files).rev() {
+            let path = self.rotated_path(i);
+
+            if path.exists() {
+                std::fs::rename(path, self.rotated_path(i + 1))?;
+            }
+        }
+
+        std::fs::rename(&self.path, self.rotated_path(1))
+    }
+}
+
+/// Get path to the rotated file with given index.
+pub(crate) fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut path = path.to_path_buf().into_os_string();
+
+    path.push(format!(".{index}"));
+
+    PathBuf::from(path)
+}
+
+/// Append-only JSONL log of message envelopes
+/// with rotation by size.
+///
+/// Each line contains the envelope direction, peer's public key,
+/// unix timestamp and base64 encoded envelope bytes.
+///
+/// When the log file exceeds `max_size` bytes it is renamed
+/// to `{path}.1`, previous `{path}.1` to `{path}.2` and so on,
+/// keeping at most `max_files` rotated files.
+///
+/// ```rust,ignore
+/// fn on_envelope(&self, direction: Direction, raw: &[u8], peer: &PublicKey) {
+///     let _ = self.envelope_log.write(direction, raw, peer);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct FileEnvelopeLog {
+    file: RotatingFile
+}
+
+impl FileEnvelopeLog {
+    #[inline]
+    pub fn new(path: impl Into<PathBuf>, max_size: u64, max_files: usize) -> Self {
+        Self {
+            file: RotatingFile::new(path, max_size, max_files)
+        }
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        self.file.path()
+    }
+
+    /// Get path to the rotated log file with given index.
+    #[inline]
+    pub fn rotated_path(&self, index: usize) -> PathBuf {
+        self.file.rotated_path(index)
+    }
+
+    /// Append the envelope to the log.
+    pub fn write(&self, direction: Direction, raw: &[u8], peer: &PublicKey) -> std::io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let record = json!({
+            "direction": direction.to_string(),
+            "peer": peer.to_base64(),
+            "timestamp": timestamp,
+            "envelope": BASE64.encode(raw)
+        });
+
+        let mut line = canonical_json(&record)?;
+
+        line.push(b'\n');
+
+        self.file.append(&line)
+    }
+}
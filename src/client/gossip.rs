@@ -0,0 +1,525 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value as Json};
+
+use rand::seq::SliceRandom;
+
+use hyperborealib::exports::tokio;
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use super::*;
+
+/// Maximal amount of peers sent in one gossip envelope.
+pub const GOSSIP_MAX_PEERS: usize = 64;
+
+/// Time to wait for the peer list response.
+pub const PEER_LIST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `{ "id": N, "gossip": { "our_peers": [...] }, "session": N }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GossipRequest {
+    pub our_peers: Vec<ClientEndpoint>
+}
+
+/// `{ "gossip": { "their_peers": [...] } }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GossipResponse {
+    pub their_peers: Vec<ClientEndpoint>
+}
+
+impl AsJson for GossipRequest {
+    #[inline]
+    fn to_json(&self) -> Result<Json, AsJsonError> {
+        Ok(json!({
+            "our_peers": endpoints_to_json(&self.our_peers)
+        }))
+    }
+
+    #[inline]
+    fn from_json(json: &Json) -> Result<Self, AsJsonError> where Self: Sized {
+        Ok(Self {
+            our_peers: endpoints_from_json(json.get("our_peers")
+                .ok_or_else(|| AsJsonError::FieldNotFound("our_peers"))?)
+        })
+    }
+}
+
+impl AsJson for GossipResponse {
+    #[inline]
+    fn to_json(&self) -> Result<Json, AsJsonError> {
+        Ok(json!({
+            "their_peers": endpoints_to_json(&self.their_peers)
+        }))
+    }
+
+    #[inline]
+    fn from_json(json: &Json) -> Result<Self, AsJsonError> where Self: Sized {
+        Ok(Self {
+            their_peers: endpoints_from_json(json.get("their_peers")
+                .ok_or_else(|| AsJsonError::FieldNotFound("their_peers"))?)
+        })
+    }
+}
+
+/// `{ "id": N, "peer_list": { "filter": "thin" }, "session": N }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerListRequest {
+    /// Return only peers of given type.
+    pub filter: Option<ClientType>
+}
+
+/// `{ "peer_list": { "peers": [...] } }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerListResponse {
+    pub peers: Vec<ClientEndpoint>
+}
+
+impl AsJson for PeerListRequest {
+    fn to_json(&self) -> Result<Json, AsJsonError> {
+        Ok(json!({
+            "filter": self.filter.as_ref().map(ClientType::to_string)
+        }))
+    }
+
+    fn from_json(json: &Json) -> Result<Self, AsJsonError> where Self: Sized {
+        let filter = match json.get("filter").and_then(Json::as_str) {
+            Some(filter) => Some(filter.parse::<ClientType>()
+                .map_err(|_| AsJsonError::FieldNotFound("filter"))?),
+
+            None => None
+        };
+
+        Ok(Self {
+            filter
+        })
+    }
+}
+
+impl AsJson for PeerListResponse {
+    #[inline]
+    fn to_json(&self) -> Result<Json, AsJsonError> {
+        Ok(json!({
+            "peers": endpoints_to_json(&self.peers)
+        }))
+    }
+
+    #[inline]
+    fn from_json(json: &Json) -> Result<Self, AsJsonError> where Self: Sized {
+        Ok(Self {
+            peers: endpoints_from_json(json.get("peers")
+                .ok_or_else(|| AsJsonError::FieldNotFound("peers"))?)
+        })
+    }
+}
+
+fn endpoints_to_json(endpoints: &[ClientEndpoint]) -> Json {
+    endpoints.iter()
+        .take(GOSSIP_MAX_PEERS)
+        .map(|endpoint| json!({
+            "server_address": endpoint.server_address,
+            "client_public": endpoint.client_public.to_base64()
+        }))
+        .collect()
+}
+
+/// Parse list of endpoints, skipping invalid ones.
+fn endpoints_from_json(endpoints: &Json) -> Vec<ClientEndpoint> {
+    let Some(endpoints) = endpoints.as_array() else {
+        return vec![];
+    };
+
+    endpoints.iter()
+        .take(GOSSIP_MAX_PEERS)
+        .filter_map(|endpoint| {
+            let server_address = endpoint.get("server_address")?.as_str()?;
+
+            let client_public = endpoint.get("client_public")?.as_str()
+                .and_then(|key| PublicKey::from_base64(key).ok())?;
+
+            Some(ClientEndpoint::new(server_address, client_public))
+        })
+        .collect()
+}
+
+/// Cache of the peers known by the client.
+///
+/// Filled by the gossip and peer list exchanges with other
+/// peers. Types of the peers are known only for the ones
+/// which sent messages to the client directly.
+///
+/// Peers received from other ones are unverified: their
+/// endpoints could be forged, so they must be resolved by the
+/// network lookup before use. Verified peers are never replaced
+/// by the unverified ones.
+///
+/// ```rust
+/// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+/// use hyperelm::exports::hyperborealib::rest_api::prelude::*;
+/// use hyperelm::client::{ClientEndpoint, PeerCache};
+///
+/// let cache = PeerCache::default();
+///
+/// let peers = (0..5)
+///     .map(|i| ClientEndpoint::new(format!("127.0.0.1:800{i}"), SecretKey::random().public()))
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(cache.merge(peers.clone()), 5);
+/// assert_eq!(cache.merge(peers.clone()), 0);
+///
+/// assert!(!cache.is_verified(&peers[0].client_public));
+///
+/// cache.insert(peers[0].clone());
+///
+/// // Verified peers are not replaced by the gossip
+/// cache.merge([ClientEndpoint::new("127.0.0.1:9000", peers[0].client_public.clone())]);
+///
+/// assert!(cache.is_verified(&peers[0].client_public));
+/// assert_eq!(cache.get(&peers[0].client_public), Some(peers[0].clone()));
+///
+/// assert_eq!(cache.len(), 5);
+/// assert_eq!(cache.random_subset(3).len(), 3);
+/// assert_eq!(cache.random_subset(10).len(), 5);
+///
+/// cache.set_client_type(&peers[0].client_public, ClientType::Thin);
+///
+/// assert_eq!(cache.list_filtered(Some(ClientType::Thin)), vec![peers[0].clone()]);
+/// assert_eq!(cache.list_filtered(None).len(), 5);
+/// ```
+#[derive(Debug, Default)]
+pub struct PeerCache {
+    peers: RwLock<HashMap<PublicKey, ClientEndpoint>>,
+    verified: RwLock<HashSet<PublicKey>>,
+    client_types: RwLock<HashMap<PublicKey, ClientType>>
+}
+
+impl PeerCache {
+    /// Add verified peer to the cache, replacing its previous endpoint.
+    ///
+    /// Use it for the peers resolved by the network lookup
+    /// or the ones which sent messages to the client directly.
+    pub fn insert(&self, endpoint: ClientEndpoint) {
+        if let Ok(mut verified) = self.verified.write() {
+            verified.insert(endpoint.client_public.clone());
+        }
+
+        if let Ok(mut peers) = self.peers.write() {
+            peers.insert(endpoint.client_public.clone(), endpoint);
+        }
+    }
+
+    /// Add unverified peers to the cache.
+    ///
+    /// Endpoints of the verified peers are not replaced.
+    /// Returns amount of previously unknown peers.
+    pub fn merge(&self, endpoints: impl IntoIterator<Item = ClientEndpoint>) -> usize {
+        let (Ok(mut peers), Ok(verified)) = (self.peers.write(), self.verified.read()) else {
+            return 0;
+        };
+
+        let mut added = 0;
+
+        for endpoint in endpoints {
+            if verified.contains(&endpoint.client_public) {
+                continue;
+            }
+
+            if peers.insert(endpoint.client_public.clone(), endpoint).is_none() {
+                added += 1;
+            }
+        }
+
+        added
+    }
+
+    /// Check if the peer's endpoint is verified.
+    pub fn is_verified(&self, public_key: &PublicKey) -> bool {
+        self.verified.read()
+            .map(|verified| verified.contains(public_key))
+            .unwrap_or_default()
+    }
+
+    pub fn remove(&self, public_key: &PublicKey) -> Option<ClientEndpoint> {
+        if let Ok(mut client_types) = self.client_types.write() {
+            client_types.remove(public_key);
+        }
+
+        if let Ok(mut verified) = self.verified.write() {
+            verified.remove(public_key);
+        }
+
+        self.peers.write().ok()?
+            .remove(public_key)
+    }
+
+    /// Remember type of the peer.
+    pub fn set_client_type(&self, public_key: &PublicKey, client_type: ClientType) {
+        if let Ok(mut client_types) = self.client_types.write() {
+            client_types.insert(public_key.clone(), client_type);
+        }
+    }
+
+    /// Get type of the peer if it's known.
+    pub fn client_type(&self, public_key: &PublicKey) -> Option<ClientType> {
+        self.client_types.read().ok()?
+            .get(public_key)
+            .cloned()
+    }
+
+    pub fn get(&self, public_key: &PublicKey) -> Option<ClientEndpoint> {
+        self.peers.read().ok()?
+            .get(public_key)
+            .cloned()
+    }
+
+    pub fn list(&self) -> Vec<ClientEndpoint> {
+        self.peers.read()
+            .map(|peers| peers.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// List peers of given type.
+    ///
+    /// Peers of unknown type are skipped if the filter is set.
+    pub fn list_filtered(&self, filter: Option<ClientType>) -> Vec<ClientEndpoint> {
+        let Some(filter) = filter else {
+            return self.list();
+        };
+
+        self.list()
+            .into_iter()
+            .filter(|peer| self.client_type(&peer.client_public).as_ref() == Some(&filter))
+            .collect()
+    }
+
+    /// Choose up to `amount` random peers.
+    pub fn random_subset(&self, amount: usize) -> Vec<ClientEndpoint> {
+        let peers = self.list();
+
+        peers.choose_multiple(&mut rand::thread_rng(), amount)
+            .cloned()
+            .collect()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.peers.read()
+            .map(|peers| peers.len())
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Request peers known by the endpoint
+/// and merge them into the peer cache.
+pub(crate) async fn request_peer_list<T: ClientApp + Sync + ?Sized>(client: &T, endpoint: ClientEndpoint, filter: Option<ClientType>) -> Result<Vec<ClientEndpoint>, ClientAppError<T::Error>> {
+    let params = client.get_params();
+    let runtime = client.get_runtime();
+
+    // Reserve in-flight request slot
+    let _permit = runtime.inflight_requests.acquire().await
+        .map_err(|_| ClientAppError::Overloaded)?;
+
+    client.throttle(&endpoint).await?;
+
+    let middleware = client.get_connected_middleware().await?;
+
+    // Send request
+    let request_id = safe_random_u64();
+    let session = runtime.inflight_requests.session();
+
+    let pending = runtime.inflight_requests.register(
+        request_id,
+        params.channel.reply_to_session(request_id, session)
+    );
+
+    let request = PeerListRequest {
+        filter
+    };
+
+    client.send_envelope(&middleware, &endpoint, &params.channel, &json!({
+        "id": request_id,
+        "peer_list": request.to_json()?,
+        "session": session
+    })).await?;
+
+    // Receive response
+    let response = tokio::time::timeout(
+        PEER_LIST_TIMEOUT,
+        client.receive_response(&middleware, request_id, pending)
+    ).await
+        .unwrap_or(Err(ClientAppError::Timeout))
+        .with_peer(&endpoint.client_public)?;
+
+    let response = response.get("peer_list")
+        .ok_or_else(|| AsJsonError::FieldNotFound("peer_list"))?;
+
+    let public_key = params.identity.public();
+
+    let peers = PeerListResponse::from_json(response)?.peers
+        .into_iter()
+        .filter(|peer| peer.client_public != public_key)
+        .collect::<Vec<_>>();
+
+    let _added = runtime.peer_cache.merge(peers.clone());
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!("[client] Learned {_added} new peers from {}", endpoint.client_public.to_base64());
+
+    runtime.presence.seen(&endpoint.client_public);
+
+    Ok(peers)
+}
+
+/// Respond with the locally known peers
+/// matching the request filter.
+pub(crate) async fn answer_peer_list<T: ClientApp + Sync + ?Sized>(client: &T, message: MessageInfo, request_id: u64, request: PeerListRequest, session: Option<u64>) -> Result<(), ClientAppError<T::Error>> {
+    let params = client.get_params();
+    let runtime = client.get_runtime();
+
+    let middleware = client.get_connected_middleware().await?;
+
+    let endpoint = ClientEndpoint::new(
+        &message.sender.server.address,
+        message.sender.client.public_key.clone()
+    );
+
+    let response = PeerListResponse {
+        peers: runtime.peer_cache.list_filtered(request.filter)
+            .into_iter()
+            .filter(|peer| *peer != endpoint)
+            .collect()
+    };
+
+    runtime.peer_cache.insert(endpoint.clone());
+    runtime.peer_cache.set_client_type(&endpoint.client_public, message.sender.client.info.client_type.clone());
+
+    client.send_envelope(
+        &middleware,
+        &endpoint,
+        &params.channel.reply_for(request_id, session),
+        &json!({ "peer_list": response.to_json()? })
+    ).await?;
+
+    Ok(())
+}
+
+/// Exchange known peers with random
+/// peers from the peer cache.
+pub(crate) async fn gossip_peers<T: ClientApp + Sync + ?Sized>(client: &T) -> Result<(), ClientAppError<T::Error>> {
+    let params = client.get_params();
+    let runtime = client.get_runtime();
+
+    let peers = runtime.peer_cache.random_subset(params.gossip_fanout);
+
+    if peers.is_empty() {
+        return Ok(());
+    }
+
+    let middleware = client.get_connected_middleware().await?;
+    let public_key = params.identity.public();
+
+    for endpoint in peers {
+        let gossip_id = safe_random_u64();
+        let session = runtime.inflight_requests.session();
+
+        let channel = params.channel.reply_to_session(gossip_id, session);
+
+        let request = GossipRequest {
+            our_peers: runtime.peer_cache.list()
+                .into_iter()
+                .filter(|peer| *peer != endpoint)
+                .collect()
+        };
+
+        let result = client.send_envelope(&middleware, &endpoint, &params.channel, &json!({
+            "id": gossip_id,
+            "gossip": request.to_json()?,
+            "session": session
+        })).await;
+
+        if let Err(_err) = result {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("[client] Failed to send gossip to {}: {_err}", endpoint.client_public.to_base64());
+
+            continue;
+        }
+
+        let started_at = Instant::now();
+
+        while started_at.elapsed() < params.gossip_timeout {
+            let (mut messages, _) = middleware.poll(&channel, Some(1)).await?;
+
+            if let Some(message) = messages.pop() {
+                let response = client.decode_incoming(&message).await?;
+
+                let response = response.get("gossip")
+                    .and_then(|response| GossipResponse::from_json(response).ok());
+
+                if let Some(response) = response {
+                    let peers = response.their_peers.into_iter()
+                        .filter(|peer| peer.client_public != public_key);
+
+                    let _added = runtime.peer_cache.merge(peers);
+
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("[client] Learned {_added} new peers from {}", endpoint.client_public.to_base64());
+                }
+
+                runtime.presence.seen(&endpoint.client_public);
+
+                break;
+            }
+
+            tokio::time::sleep(params.delay).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Merge peers received from the message sender
+/// and respond with the locally known ones.
+pub(crate) async fn answer_gossip<T: ClientApp + Sync + ?Sized>(client: &T, message: MessageInfo, gossip_id: u64, request: GossipRequest, session: Option<u64>) -> Result<(), ClientAppError<T::Error>> {
+    let params = client.get_params();
+    let runtime = client.get_runtime();
+
+    let middleware = client.get_connected_middleware().await?;
+    let public_key = params.identity.public();
+
+    let endpoint = ClientEndpoint::new(
+        &message.sender.server.address,
+        message.sender.client.public_key.clone()
+    );
+
+    let response = GossipResponse {
+        their_peers: runtime.peer_cache.list()
+            .into_iter()
+            .filter(|peer| *peer != endpoint)
+            .collect()
+    };
+
+    let peers = request.our_peers.into_iter()
+        .filter(|peer| peer.client_public != public_key);
+
+    runtime.peer_cache.merge(peers);
+
+    runtime.peer_cache.insert(endpoint.clone());
+    runtime.peer_cache.set_client_type(&endpoint.client_public, message.sender.client.info.client_type.clone());
+
+    client.send_envelope(
+        &middleware,
+        &endpoint,
+        &params.channel.reply_for(gossip_id, session),
+        &json!({ "gossip": response.to_json()? })
+    ).await?;
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+
+use super::Channel;
+
+/// Size of the AES-GCM nonce prepended to the group messages.
+const NONCE_SIZE: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum GroupError {
+    #[error("Failed to encrypt group message")]
+    Encryption,
+
+    #[error("Failed to decrypt group message")]
+    Decryption,
+
+    #[error("Unknown shared key {0}")]
+    UnknownKey(u32),
+
+    #[error("Channel {0} doesn't use a shared secret")]
+    NotShared(String)
+}
+
+/// Messaging group sharing a symmetric key.
+///
+/// Group messages are encrypted once with AES-256-GCM
+/// and sent to every member of the group. The group id
+/// is authenticated together with the message, so messages
+/// of one group can't be replayed to another one.
+///
+/// ```rust
+/// use hyperelm::client::{Channel, GroupChannel};
+///
+/// let group = GroupChannel::random(Channel::new("friends").unwrap());
+///
+/// let encrypted = group.encrypt(b"Hello, World!").unwrap();
+///
+/// assert_eq!(group.decrypt(&encrypted).unwrap(), b"Hello, World!");
+///
+/// let another = GroupChannel::random(Channel::new("friends").unwrap());
+///
+/// assert!(another.decrypt(&encrypted).is_err());
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct GroupChannel {
+    pub id: Channel,
+    pub key: [u8; 32]
+}
+
+impl GroupChannel {
+    #[inline]
+    pub fn new(id: Channel, key: [u8; 32]) -> Self {
+        Self {
+            id,
+            key
+        }
+    }
+
+    /// Create group with random key.
+    #[inline]
+    pub fn random(id: Channel) -> Self {
+        Self::new(id, rand::random())
+    }
+
+    /// Encrypt message with the group key.
+    ///
+    /// Random nonce is prepended to the encrypted message.
+    pub fn encrypt(&self, message: &[u8]) -> Result<Vec<u8>, GroupError> {
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.key));
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+        let encrypted = cipher.encrypt(&nonce, Payload {
+            msg: message,
+            aad: self.id.as_str().as_bytes()
+        }).map_err(|_| GroupError::Encryption)?;
+
+        let mut result = nonce.to_vec();
+
+        result.extend(encrypted);
+
+        Ok(result)
+    }
+
+    /// Decrypt message encrypted with the group key.
+    pub fn decrypt(&self, message: &[u8]) -> Result<Vec<u8>, GroupError> {
+        if message.len() < NONCE_SIZE {
+            return Err(GroupError::Decryption);
+        }
+
+        let (nonce, encrypted) = message.split_at(NONCE_SIZE);
+
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.key));
+
+        cipher.decrypt(Nonce::from_slice(nonce), Payload {
+            msg: encrypted,
+            aad: self.id.as_str().as_bytes()
+        }).map_err(|_| GroupError::Decryption)
+    }
+}
+
+impl std::fmt::Debug for GroupChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GroupChannel")
+            .field("id", &self.id)
+            .field("key", &"<redacted>")
+            .finish()
+    }
+}
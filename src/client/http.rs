@@ -0,0 +1,69 @@
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+use hyperborealib::drivers::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+pub use crate::http::{HttpClientConfig, HttpClientError, HttpStatusError, StatusHttpClient};
+
+use super::ClientAppParams;
+
+impl ClientAppParams {
+    /// Build HTTP client using the current params.
+    ///
+    /// Returned client is configured by the `http_config` param
+    /// and includes `extra_request_headers` in every request
+    /// made by the client middleware.
+    ///
+    /// Use it to wrap the HTTP client into another one,
+    /// otherwise the `build_middleware` method is simpler.
+    ///
+    /// ```rust,ignore
+    /// let middleware = ClientMiddleware::new(
+    ///     CountingHttpClient::new(params.build_http_client()?),
+    ///     driver
+    /// );
+    /// ```
+    pub fn build_http_client(&self) -> Result<StatusHttpClient, HttpClientError> {
+        let mut headers = HeaderMap::new();
+
+        for (name, value) in &self.extra_request_headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| HttpClientError::InvalidHeaderName(name.clone()))?;
+
+            let value = HeaderValue::from_str(value)
+                .map_err(|_| HttpClientError::InvalidHeaderValue(name.to_string()))?;
+
+            headers.insert(name, value);
+        }
+
+        let client = self.http_config.reqwest_builder()?
+            .default_headers(headers)
+            .build()?;
+
+        Ok(StatusHttpClient::new(client).with_https(self.http_config.https))
+    }
+
+    /// Build client middleware of the params' identity
+    /// using the HTTP client of the `build_http_client` method,
+    /// so `extra_request_headers` are included in every request.
+    ///
+    /// ```rust
+    /// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+    /// use hyperelm::client::ClientAppParams;
+    ///
+    /// let params = ClientAppParams::builder()
+    ///     .client(SecretKey::random())
+    ///     .server(SecretKey::random().public(), "127.0.0.1:8001")
+    ///     .header("x-api-key", "secret")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let middleware = params.build_middleware().unwrap();
+    /// ```
+    pub fn build_middleware(&self) -> Result<ClientMiddleware<StatusHttpClient>, HttpClientError> {
+        Ok(ClientMiddleware::new(
+            self.build_http_client()?,
+            ClientDriver::new(ClientInfo::thin(), self.identity.secret())
+        ))
+    }
+}
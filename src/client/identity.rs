@@ -0,0 +1,344 @@
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde_json::json;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::drivers::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use super::*;
+
+/// Maximal age of the accepted identity rotation notice.
+pub const MOVED_NOTICE_MAX_AGE: Duration = Duration::from_secs(5 * 60);
+
+/// Get bytes signed by the previous secret key
+/// in the identity rotation notice.
+///
+/// The notice is bound to its creation time so it
+/// can't be replayed after `MOVED_NOTICE_MAX_AGE`.
+#[inline]
+pub fn moved_notice_payload(current: &PublicKey, timestamp: u64) -> Vec<u8> {
+    format!("{}:{timestamp}", current.to_base64()).into_bytes()
+}
+
+/// Check if the identity rotation notice
+/// created at given time can be accepted.
+///
+/// ```rust
+/// use std::time::{SystemTime, UNIX_EPOCH};
+///
+/// use hyperelm::client::is_moved_notice_fresh;
+///
+/// let now = SystemTime::now()
+///     .duration_since(UNIX_EPOCH)
+///     .unwrap()
+///     .as_secs();
+///
+/// assert!(is_moved_notice_fresh(now));
+/// assert!(!is_moved_notice_fresh(now - 3600));
+/// ```
+pub fn is_moved_notice_fresh(timestamp: u64) -> bool {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    now.abs_diff(timestamp) <= MOVED_NOTICE_MAX_AGE.as_secs()
+}
+
+/// Rotatable identity of the client.
+///
+/// After rotation the previous secret key is kept for the grace
+/// period so messages encrypted to it can still be decoded.
+///
+/// Long-term secret key is kept separately when an ephemeral
+/// session key is used, so it's the one which gets persisted.
+pub struct ClientIdentity {
+    current: RwLock<SecretKey>,
+    previous: RwLock<Option<(SecretKey, Instant)>>,
+    long_term: RwLock<Option<SecretKey>>,
+    grace_period: Duration
+}
+
+impl std::fmt::Debug for ClientIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientIdentity")
+            .field("public_key", &self.public().to_base64())
+            .field("ephemeral", &self.is_ephemeral())
+            .field("grace_period", &self.grace_period)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ClientIdentity {
+    pub fn new(secret_key: SecretKey, grace_period: Duration) -> Self {
+        Self {
+            current: RwLock::new(secret_key),
+            previous: RwLock::new(None),
+            long_term: RwLock::new(None),
+            grace_period
+        }
+    }
+
+    /// Get current secret key of the client.
+    pub fn secret(&self) -> SecretKey {
+        match self.current.read() {
+            Ok(secret) => secret.clone(),
+            Err(err) => err.into_inner().clone()
+        }
+    }
+
+    /// Get current public key of the client.
+    #[inline]
+    pub fn public(&self) -> PublicKey {
+        self.secret().public()
+    }
+
+    /// Get previous secret key of the client
+    /// if its grace period is not elapsed yet.
+    pub fn previous(&self) -> Option<SecretKey> {
+        let previous = match self.previous.read() {
+            Ok(previous) => previous.clone(),
+            Err(err) => err.into_inner().clone()
+        };
+
+        previous.and_then(|(secret, rotated_at)| {
+            (rotated_at.elapsed() < self.grace_period).then_some(secret)
+        })
+    }
+
+    /// Get long-term secret key of the client.
+    ///
+    /// Equals to the current one unless an
+    /// ephemeral session key is used.
+    pub fn long_term(&self) -> SecretKey {
+        let long_term = match self.long_term.read() {
+            Ok(long_term) => long_term.clone(),
+            Err(err) => err.into_inner().clone()
+        };
+
+        long_term.unwrap_or_else(|| self.secret())
+    }
+
+    /// Check if ephemeral session key is used.
+    pub fn is_ephemeral(&self) -> bool {
+        match self.long_term.read() {
+            Ok(long_term) => long_term.is_some(),
+            Err(err) => err.into_inner().is_some()
+        }
+    }
+
+    #[inline]
+    pub fn grace_period(&self) -> Duration {
+        self.grace_period
+    }
+
+    /// Replace current secret key with a new one,
+    /// returning the previous key.
+    pub fn rotate(&self, secret_key: SecretKey) -> SecretKey {
+        let mut current = match self.current.write() {
+            Ok(current) => current,
+            Err(err) => err.into_inner()
+        };
+
+        let mut previous = match self.previous.write() {
+            Ok(previous) => previous,
+            Err(err) => err.into_inner()
+        };
+
+        let old = std::mem::replace(&mut *current, secret_key);
+
+        *previous = Some((old.clone(), Instant::now()));
+
+        old
+    }
+
+    /// Replace current secret key with a freshly generated
+    /// session key, returning its public key.
+    ///
+    /// Unlike `rotate`, the replaced key is not kept for
+    /// decoding, and the long-term key is remembered.
+    pub fn start_session(&self) -> PublicKey {
+        let mut current = match self.current.write() {
+            Ok(current) => current,
+            Err(err) => err.into_inner()
+        };
+
+        let mut previous = match self.previous.write() {
+            Ok(previous) => previous,
+            Err(err) => err.into_inner()
+        };
+
+        let mut long_term = match self.long_term.write() {
+            Ok(long_term) => long_term,
+            Err(err) => err.into_inner()
+        };
+
+        let session = SecretKey::random();
+        let public_key = session.public();
+
+        let old = std::mem::replace(&mut *current, session);
+
+        if long_term.is_none() {
+            *long_term = Some(old);
+        }
+
+        *previous = None;
+
+        public_key
+    }
+
+    /// Decode the message using the current secret key,
+    /// or using the previous one within its grace period.
+    pub fn read(&self, message: &Message, sender: &PublicKey) -> Result<Vec<u8>, MessagesError> {
+        match message.read(&self.secret(), sender) {
+            Ok(content) => Ok(content),
+
+            Err(err) => match self.previous() {
+                Some(previous) => message.read(&previous, sender),
+                None => Err(err)
+            }
+        }
+    }
+}
+
+/// Connection to the server under the previous identity.
+///
+/// Peers which haven't received the rotation notice yet still
+/// send messages to the previous key, so its inbox is polled
+/// until the identity grace period is elapsed.
+#[derive(Default)]
+pub struct PreviousInbox {
+    connection: RwLock<Option<Arc<ConnectedClientMiddleware<StatusHttpClient>>>>
+}
+
+impl std::fmt::Debug for PreviousInbox {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PreviousInbox")
+            .field("connected", &self.get().is_some())
+            .finish()
+    }
+}
+
+impl PreviousInbox {
+    /// Get connection to the server under the previous identity.
+    pub fn get(&self) -> Option<Arc<ConnectedClientMiddleware<StatusHttpClient>>> {
+        match self.connection.read() {
+            Ok(connection) => connection.clone(),
+            Err(err) => err.into_inner().clone()
+        }
+    }
+
+    /// Replace connection of the previous identity.
+    pub fn set(&self, connection: Option<ConnectedClientMiddleware<StatusHttpClient>>) {
+        let mut current = match self.connection.write() {
+            Ok(current) => current,
+            Err(err) => err.into_inner()
+        };
+
+        *current = connection.map(Arc::new);
+    }
+}
+
+/// Replace identity of the client and
+/// send rotation notices to given peers.
+pub(crate) async fn rotate_identity<T: ClientApp + Sync + ?Sized>(client: &T, new_secret: SecretKey, notify: &[ClientEndpoint]) -> Result<(), ClientAppError<T::Error>> {
+    let params = client.get_params();
+    let runtime = client.get_runtime();
+
+    let new_public = new_secret.public();
+    let previous = params.identity.rotate(new_secret);
+
+    // Don't accept responses to the requests sent under the previous key
+    runtime.inflight_requests.regenerate_session();
+
+    // Re-connect to the server under the new key
+    let middleware = client.get_connected_middleware().await?;
+
+    // Keep polling the previous key's inbox within its grace period
+    let previous_connection = match params.build_http_client() {
+        Ok(http) => {
+            let result = ClientMiddleware::new(http, ClientDriver::new(ClientInfo::thin(), previous.clone()))
+                .connect_to(&params.server_address, params.server_public.clone()).await;
+
+            match result {
+                Ok(connection) => Some(connection),
+
+                Err(_err) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!("[client] Failed to connect under the previous identity: {_err}");
+
+                    None
+                }
+            }
+        }
+
+        Err(_err) => {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("[client] Failed to build HTTP client of the previous identity: {_err}");
+
+            None
+        }
+    };
+
+    runtime.previous_inbox.set(previous_connection);
+
+    // Notify known peers
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let notice = json!({
+        "moved": {
+            "previous": previous.public().to_base64(),
+            "timestamp": timestamp,
+            "signature": BASE64.encode(previous.create_signature(moved_notice_payload(&new_public, timestamp)))
+        }
+    });
+
+    for endpoint in notify {
+        client.send_envelope(&middleware, endpoint, &params.channel, &notice).await?;
+    }
+
+    Ok(())
+}
+
+/// Verify identity rotation notice of the message sender
+/// and call the `on_peer_moved` method if it's valid.
+pub(crate) async fn handle_moved_notice<T: ClientApp + Sync + ?Sized>(client: &T, message: MessageInfo, previous: Option<PublicKey>, timestamp: Option<u64>, signature: Option<Vec<u8>>) -> Result<(), ClientAppError<T::Error>> {
+    let runtime = client.get_runtime();
+
+    let sender = &message.sender.client.public_key;
+
+    if let (Some(previous), Some(timestamp), Some(signature)) = (previous, timestamp, signature) {
+        // Verify that the new key and the notice time
+        // are signed by the previous key
+        let verified = previous.verify_signature(moved_notice_payload(sender, timestamp), signature)
+            .unwrap_or(false);
+
+        // Drop replayed notices
+        let fresh = is_moved_notice_fresh(timestamp);
+
+        if verified && fresh {
+            runtime.response_cache.invalidate_peer(&previous);
+
+            client.on_peer_moved(previous, sender.clone(), &message).await?;
+        }
+
+        #[cfg(feature = "tracing")]
+        if !verified {
+            tracing::warn!("[client] Received identity rotation notice with invalid signature");
+        }
+
+        else if !fresh {
+            tracing::warn!("[client] Received outdated identity rotation notice");
+        }
+    }
+
+    Ok(())
+}
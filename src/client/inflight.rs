@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use hyperborealib::exports::tokio;
+
+use tokio::sync::{oneshot, Semaphore, OwnedSemaphorePermit, TryAcquireError};
+
+use hyperborealib::rest_api::prelude::*;
+
+use super::Channel;
+
+/// Behavior of the `request` method when
+/// the in-flight requests limit is reached.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OverloadBehavior {
+    /// Wait until some of the in-flight requests is finished.
+    #[default]
+    Wait,
+
+    /// Return `ClientAppError::Overloaded` error.
+    Fail
+}
+
+/// Requests limit is reached and `OverloadBehavior::Fail` is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Too many in-flight requests")]
+pub struct Overloaded;
+
+type PendingMap = Mutex<HashMap<u64, (Channel, oneshot::Sender<MessageInfo>)>>;
+
+/// Registry of the requests waiting for response.
+///
+/// Limits amount of the in-flight requests and allows
+/// only one of them to poll the server at a time. The
+/// polling request receives responses for all the other
+/// pending requests and passes them to their owners.
+///
+/// With the `ReplyChannelStrategy::Shared` strategy all the
+/// responses are received by one poll of the `{channel}@replies`
+/// channel and matched with requests by their ids, so N
+/// outstanding requests cost one poll instead of N. Per-request
+/// channels of the peers not supporting this strategy are then
+/// polled one at a time using the `next_fallback` method.
+///
+/// Requests are sent with a random session token which is
+/// echoed by the receiver, so responses to the requests sent
+/// before the client was restarted are not accepted even
+/// if their ids are the same.
+#[derive(Debug)]
+pub struct InflightRequests {
+    semaphore: Option<Arc<Semaphore>>,
+    max_inflight: Option<usize>,
+    behavior: OverloadBehavior,
+    session: AtomicU64,
+    pending: Arc<PendingMap>,
+    fallback_cursor: AtomicUsize,
+    poll_lock: tokio::sync::Mutex<()>
+}
+
+impl Default for InflightRequests {
+    #[inline]
+    fn default() -> Self {
+        Self::new(None, OverloadBehavior::default())
+    }
+}
+
+impl InflightRequests {
+    /// Create new registry with given in-flight requests limit.
+    ///
+    /// Amount of requests is unlimited if `max_inflight` is not set.
+    pub fn new(max_inflight: Option<usize>, behavior: OverloadBehavior) -> Self {
+        Self {
+            semaphore: max_inflight.map(|max| Arc::new(Semaphore::new(max.max(1)))),
+            max_inflight,
+            behavior,
+            session: AtomicU64::new(rand::random()),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            fallback_cursor: AtomicUsize::new(0),
+            poll_lock: tokio::sync::Mutex::new(())
+        }
+    }
+
+    /// Maximal amount of requests waiting for a response.
+    #[inline]
+    pub fn max_inflight(&self) -> Option<usize> {
+        self.max_inflight
+    }
+
+    #[inline]
+    pub fn behavior(&self) -> OverloadBehavior {
+        self.behavior
+    }
+
+    /// Session token of the sent requests.
+    #[inline]
+    pub fn session(&self) -> u64 {
+        self.session.load(Ordering::Relaxed)
+    }
+
+    /// Generate new session token.
+    ///
+    /// Responses to the requests sent with the
+    /// previous token will not be accepted.
+    #[inline]
+    pub fn regenerate_session(&self) {
+        self.session.store(rand::random(), Ordering::Relaxed);
+    }
+
+    /// Reserve a slot for the new request.
+    ///
+    /// Returned permit must be kept until
+    /// the request is finished.
+    pub async fn acquire(&self) -> Result<Option<OwnedSemaphorePermit>, Overloaded> {
+        let Some(semaphore) = &self.semaphore else {
+            return Ok(None);
+        };
+
+        match self.behavior {
+            OverloadBehavior::Wait => semaphore.clone().acquire_owned().await
+                .map(Some)
+                .map_err(|_| Overloaded),
+
+            OverloadBehavior::Fail => match semaphore.clone().try_acquire_owned() {
+                Ok(permit) => Ok(Some(permit)),
+
+                Err(TryAcquireError::NoPermits) |
+                Err(TryAcquireError::Closed) => Err(Overloaded)
+            }
+        }
+    }
+
+    /// Register request waiting for a response on the given channel.
+    ///
+    /// Request is unregistered when returned value is dropped.
+    pub fn register(&self, id: u64, channel: Channel) -> PendingRequest {
+        let (sender, receiver) = oneshot::channel();
+
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.insert(id, (channel.clone(), sender));
+        }
+
+        PendingRequest {
+            id,
+            channel,
+            receiver,
+            pending: self.pending.clone()
+        }
+    }
+
+    /// List ids and reply channels of the pending requests.
+    pub fn pending(&self) -> Vec<(u64, Channel)> {
+        self.pending.lock()
+            .map(|pending| {
+                pending.iter()
+                    .map(|(id, (channel, _))| (*id, channel.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Choose the next pending request whose own reply
+    /// channel should be polled, cycling over all of them.
+    pub fn next_fallback(&self) -> Option<(u64, Channel)> {
+        let mut pending = self.pending();
+
+        if pending.is_empty() {
+            return None;
+        }
+
+        pending.sort_by_key(|(id, _)| *id);
+
+        let cursor = self.fallback_cursor.fetch_add(1, Ordering::Relaxed);
+
+        Some(pending.swap_remove(cursor % pending.len()))
+    }
+
+    /// Pass response to the pending request.
+    ///
+    /// Returns `false` if there's no such request.
+    pub fn resolve(&self, id: u64, response: MessageInfo) -> bool {
+        let sender = self.pending.lock().ok()
+            .and_then(|mut pending| pending.remove(&id));
+
+        match sender {
+            Some((_, sender)) => sender.send(response).is_ok(),
+            None => false
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.pending.lock()
+            .map(|pending| pending.len())
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Try to become the request polling the server.
+    ///
+    /// Returns `None` if another request is polling now.
+    #[inline]
+    pub fn try_lock_poller(&self) -> Option<tokio::sync::MutexGuard<'_, ()>> {
+        self.poll_lock.try_lock().ok()
+    }
+}
+
+/// Request waiting for a response.
+#[derive(Debug)]
+pub struct PendingRequest {
+    id: u64,
+    channel: Channel,
+    receiver: oneshot::Receiver<MessageInfo>,
+    pending: Arc<PendingMap>
+}
+
+impl PendingRequest {
+    #[inline]
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Channel the response is expected on.
+    #[inline]
+    pub fn channel(&self) -> &Channel {
+        &self.channel
+    }
+
+    /// Take the response if it was received.
+    #[inline]
+    pub fn try_receive(&mut self) -> Option<MessageInfo> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Get mutable reference to the response receiver.
+    #[inline]
+    pub fn receiver(&mut self) -> &mut oneshot::Receiver<MessageInfo> {
+        &mut self.receiver
+    }
+}
+
+impl Drop for PendingRequest {
+    fn drop(&mut self) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(&self.id);
+        }
+    }
+}
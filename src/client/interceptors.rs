@@ -0,0 +1,90 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value as Json};
+
+use hyperborealib::rest_api::prelude::*;
+
+use super::ClientEndpoint;
+
+pub type InterceptorError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Interceptor called for every outgoing envelope
+/// before it is serialized and encrypted.
+#[async_trait::async_trait]
+pub trait SendInterceptor: Send + Sync {
+    async fn before_send(&self, envelope: &mut Json, endpoint: &ClientEndpoint) -> Result<(), InterceptorError>;
+}
+
+/// Interceptor called for every incoming envelope
+/// after it is decrypted and deserialized.
+#[async_trait::async_trait]
+pub trait ReceiveInterceptor: Send + Sync {
+    async fn after_receive(&self, envelope: &mut Json, info: &MessageInfo) -> Result<(), InterceptorError>;
+}
+
+impl std::fmt::Debug for dyn SendInterceptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SendInterceptor")
+    }
+}
+
+impl std::fmt::Debug for dyn ReceiveInterceptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ReceiveInterceptor")
+    }
+}
+
+/// Add `sent_at` field with the current unix timestamp
+/// to every outgoing envelope.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TimestampInterceptor;
+
+#[async_trait::async_trait]
+impl SendInterceptor for TimestampInterceptor {
+    async fn before_send(&self, envelope: &mut Json, _endpoint: &ClientEndpoint) -> Result<(), InterceptorError> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)?
+            .as_secs();
+
+        if let Some(envelope) = envelope.as_object_mut() {
+            envelope.insert(String::from("sent_at"), json!(timestamp));
+        }
+
+        Ok(())
+    }
+}
+
+/// Log every outgoing and incoming envelope
+/// using the `tracing` crate.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LoggingInterceptor;
+
+#[async_trait::async_trait]
+impl SendInterceptor for LoggingInterceptor {
+    #[allow(unused_variables)]
+    async fn before_send(&self, envelope: &mut Json, endpoint: &ClientEndpoint) -> Result<(), InterceptorError> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            receiver = %endpoint.client_public.to_base64(),
+            server = %endpoint.server_address,
+            "[client] Sending envelope: {envelope}"
+        );
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl ReceiveInterceptor for LoggingInterceptor {
+    #[allow(unused_variables)]
+    async fn after_receive(&self, envelope: &mut Json, info: &MessageInfo) -> Result<(), InterceptorError> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            sender = %info.sender.client.public_key.to_base64(),
+            server = %info.sender.server.address,
+            "[client] Received envelope: {envelope}"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,273 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value as Json};
+
+use sha2::{Digest, Sha256};
+
+use hyperborealib::crypto::prelude::*;
+
+use super::{Direction, RotatingFile, canonical_json, rotated_path};
+
+/// Record of the sent or received envelope.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    /// UTC timestamp of the record in seconds.
+    pub timestamp: u64,
+
+    pub direction: Direction,
+
+    /// Public key of the sender or the receiver.
+    pub peer: PublicKey,
+
+    pub channel: String,
+
+    /// Hex encoded SHA256 hash of the decrypted envelope.
+    pub hash: String,
+
+    /// Decrypted envelope if bodies recording
+    /// is enabled for its direction.
+    pub body: Option<Json>
+}
+
+impl JournalEntry {
+    /// Create new entry for the envelope sent or received now.
+    pub fn new(direction: Direction, peer: PublicKey, channel: impl ToString, envelope: &[u8], record_body: bool) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+
+            direction,
+            peer,
+            channel: channel.to_string(),
+            hash: format!("{:x}", Sha256::digest(envelope)),
+
+            body: record_body
+                .then(|| serde_json::from_slice::<Json>(envelope).ok())
+                .flatten()
+        }
+    }
+
+    pub fn to_json(&self) -> Json {
+        json!({
+            "timestamp": self.timestamp,
+            "direction": self.direction.to_string(),
+            "peer": self.peer.to_base64(),
+            "channel": self.channel,
+            "hash": self.hash,
+            "body": self.body
+        })
+    }
+
+    pub fn from_json(json: &Json) -> Option<Self> {
+        Some(Self {
+            timestamp: json.get("timestamp")?.as_u64()?,
+
+            direction: match json.get("direction")?.as_str()? {
+                "incoming" => Direction::Incoming,
+                "outgoing" => Direction::Outgoing,
+
+                _ => return None
+            },
+
+            peer: json.get("peer")?.as_str()
+                .and_then(|peer| PublicKey::from_base64(peer).ok())?,
+
+            channel: json.get("channel")?.as_str()?.to_string(),
+            hash: json.get("hash")?.as_str()?.to_string(),
+
+            body: json.get("body")
+                .filter(|body| !body.is_null())
+                .cloned()
+        })
+    }
+}
+
+/// Journal of the sent and received envelopes.
+///
+/// Journaling is best-effort: failed records are
+/// logged and never fail sending or receiving.
+pub trait MessageJournal: Send + Sync {
+    /// Append entry to the journal.
+    fn record(&self, entry: &JournalEntry) -> std::io::Result<()>;
+
+    /// Check if decrypted envelopes of the given
+    /// direction should be recorded.
+    ///
+    /// Returns `false` by default.
+    #[allow(unused_variables)]
+    fn record_body(&self, direction: Direction) -> bool {
+        false
+    }
+}
+
+impl std::fmt::Debug for dyn MessageJournal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MessageJournal")
+    }
+}
+
+/// JSONL message journal with rotation by size.
+///
+/// When the journal file exceeds `max_size` bytes it is renamed
+/// to `{path}.1`, previous `{path}.1` to `{path}.2` and so on,
+/// keeping at most `max_files` rotated files.
+///
+/// ```rust
+/// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+/// use hyperelm::client::*;
+///
+/// let path = std::env::temp_dir().join("hyperelm-journal-doctest.jsonl");
+/// let reader = JournalReader::new(&path, 8);
+///
+/// for i in 0..=8 {
+///     let _ = std::fs::remove_file(reader.rotated_path(i));
+/// }
+///
+/// let journal = FileMessageJournal::new(&path, 4096, 8)
+///     .with_bodies(Direction::Incoming);
+///
+/// assert!(journal.record_body(Direction::Incoming));
+/// assert!(!journal.record_body(Direction::Outgoing));
+///
+/// let peer = SecretKey::random().public();
+///
+/// for i in 0..300 {
+///     let envelope = format!("{{\"message\":{i}}}");
+///
+///     let entry = JournalEntry::new(Direction::Incoming, peer.clone(), "channel", envelope.as_bytes(), true);
+///
+///     journal.record(&entry).unwrap();
+/// }
+///
+/// assert!(reader.rotated_path(1).exists());
+///
+/// let entries = reader
+///     .iter_range(0, u64::MAX)
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+///
+/// // Oldest entries were removed by the rotation
+/// let first = entries[0].body.as_ref().unwrap()["message"].as_u64().unwrap();
+///
+/// assert_eq!(entries.len() as u64, 300 - first);
+///
+/// for (i, entry) in entries.iter().enumerate() {
+///     assert_eq!(entry.body.as_ref().unwrap()["message"].as_u64(), Some(first + i as u64));
+/// }
+/// ```
+#[derive(Debug)]
+pub struct FileMessageJournal {
+    file: RotatingFile,
+    max_files: usize,
+    record_incoming: bool,
+    record_outgoing: bool
+}
+
+impl FileMessageJournal {
+    pub fn new(path: impl Into<PathBuf>, max_size: u64, max_files: usize) -> Self {
+        Self {
+            file: RotatingFile::new(path, max_size, max_files),
+            max_files,
+            record_incoming: false,
+            record_outgoing: false
+        }
+    }
+
+    /// Record decrypted envelopes of the given direction.
+    pub fn with_bodies(mut self, direction: Direction) -> Self {
+        match direction {
+            Direction::Incoming => self.record_incoming = true,
+            Direction::Outgoing => self.record_outgoing = true
+        }
+
+        self
+    }
+
+    /// Get reader of the journal files.
+    #[inline]
+    pub fn reader(&self) -> JournalReader {
+        JournalReader::new(self.file.path(), self.max_files)
+    }
+}
+
+impl MessageJournal for FileMessageJournal {
+    fn record(&self, entry: &JournalEntry) -> std::io::Result<()> {
+        let mut line = canonical_json(&entry.to_json())?;
+
+        line.push(b'\n');
+
+        self.file.append(&line)
+    }
+
+    fn record_body(&self, direction: Direction) -> bool {
+        match direction {
+            Direction::Incoming => self.record_incoming,
+            Direction::Outgoing => self.record_outgoing
+        }
+    }
+}
+
+/// Reader of the `FileMessageJournal` files.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct JournalReader {
+    path: PathBuf,
+    max_files: usize
+}
+
+impl JournalReader {
+    pub fn new(path: impl Into<PathBuf>, max_files: usize) -> Self {
+        Self {
+            path: path.into(),
+            max_files
+        }
+    }
+
+    /// Get path to the journal file with given index.
+    ///
+    /// Index 0 is the current journal file.
+    pub fn rotated_path(&self, index: usize) -> PathBuf {
+        if index == 0 {
+            self.path.clone()
+        } else {
+            rotated_path(&self.path, index)
+        }
+    }
+
+    /// Iterate over entries recorded within the given
+    /// timestamps range (inclusive) from the oldest one.
+    ///
+    /// Lines which can't be parsed are skipped.
+    pub fn iter_range(&self, from: u64, to: u64) -> impl Iterator<Item = std::io::Result<JournalEntry>> {
+        let files = (0..=self.max_files).rev()
+            .map(|index| self.rotated_path(index))
+            .filter(|path| path.exists())
+            .collect::<Vec<_>>();
+
+        files.into_iter()
+            .flat_map(|path| {
+                let lines: Box<dyn Iterator<Item = std::io::Result<String>>> = match File::open(path) {
+                    Ok(file) => Box::new(BufReader::new(file).lines()),
+                    Err(err) => Box::new(std::iter::once(Err(err)))
+                };
+
+                lines
+            })
+            .filter_map(move |line| {
+                let entry = match line {
+                    Ok(line) => serde_json::from_str::<Json>(&line).ok()
+                        .as_ref()
+                        .and_then(JournalEntry::from_json)?,
+
+                    Err(err) => return Some(Err(err))
+                };
+
+                (from..=to).contains(&entry.timestamp)
+                    .then_some(Ok(entry))
+            })
+    }
+}
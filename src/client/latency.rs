@@ -0,0 +1,116 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Round trip time statistics of the connected server.
+///
+/// Keeps last `capacity` samples in a circular buffer.
+#[derive(Debug)]
+pub struct LatencyTracker {
+    samples: Mutex<VecDeque<Duration>>,
+    capacity: usize
+}
+
+impl Default for LatencyTracker {
+    #[inline]
+    fn default() -> Self {
+        Self::new(128)
+    }
+}
+
+impl LatencyTracker {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity: capacity.max(1)
+        }
+    }
+
+    /// Maximal amount of stored samples.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Add new round trip time sample.
+    pub fn record(&self, rtt: Duration) {
+        if let Ok(mut samples) = self.samples.lock() {
+            if samples.len() >= self.capacity {
+                samples.pop_front();
+            }
+
+            samples.push_back(rtt);
+        }
+    }
+
+    /// Get copy of the stored samples.
+    pub fn samples(&self) -> Vec<Duration> {
+        self.samples.lock()
+            .map(|samples| samples.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.samples.lock()
+            .map(|samples| samples.len())
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get percentile of the stored samples.
+    ///
+    /// `percentile` must be in range `[0.0, 1.0]`.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        let mut samples = self.samples();
+
+        if samples.is_empty() {
+            return None;
+        }
+
+        samples.sort();
+
+        let index = ((samples.len() - 1) as f64 * percentile.clamp(0.0, 1.0)).round() as usize;
+
+        samples.get(index).copied()
+    }
+
+    #[inline]
+    pub fn p50(&self) -> Option<Duration> {
+        self.percentile(0.5)
+    }
+
+    #[inline]
+    pub fn p95(&self) -> Option<Duration> {
+        self.percentile(0.95)
+    }
+
+    #[inline]
+    pub fn p99(&self) -> Option<Duration> {
+        self.percentile(0.99)
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        let samples = self.samples();
+
+        if samples.is_empty() {
+            return None;
+        }
+
+        Some(samples.iter().sum::<Duration>() / samples.len() as u32)
+    }
+
+    #[inline]
+    pub fn min(&self) -> Option<Duration> {
+        self.samples().into_iter().min()
+    }
+
+    #[inline]
+    pub fn max(&self) -> Option<Duration> {
+        self.samples().into_iter().max()
+    }
+}
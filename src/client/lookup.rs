@@ -0,0 +1,103 @@
+use serde_json::{json, Value as Json};
+
+use hyperborealib::rest_api::prelude::*;
+
+use super::ClientEndpoint;
+
+/// Full result of the client lookup.
+///
+/// ```rust
+/// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+/// use hyperelm::exports::hyperborealib::rest_api::prelude::*;
+///
+/// use hyperelm::client::{ClientEndpoint, LookupResult};
+///
+/// let client_secret = SecretKey::random();
+/// let server_public = SecretKey::random().public();
+///
+/// let certificate = ConnectionCertificate::new(&client_secret, server_public.clone());
+///
+/// let result = LookupResult {
+///     client: Client::new(client_secret.public(), certificate, ClientInfo::thin()),
+///     server: Server::new(server_public, "127.0.0.1:8001"),
+///     available: true
+/// };
+///
+/// let json = result.to_json().unwrap();
+///
+/// assert_eq!(LookupResult::from_json(&json).unwrap(), result);
+///
+/// assert_eq!(
+///     ClientEndpoint::from(&result),
+///     ClientEndpoint::new("127.0.0.1:8001", client_secret.public())
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupResult {
+    /// Found client with its connection certificate and info.
+    pub client: Client,
+
+    /// Server the client is connected to.
+    pub server: Server,
+
+    /// Whether the client is available on the server
+    /// according to the server's records.
+    pub available: bool
+}
+
+impl AsJson for LookupResult {
+    fn to_json(&self) -> Result<Json, AsJsonError> {
+        Ok(json!({
+            "client": self.client.to_json()?,
+            "server": self.server.to_json()?,
+            "available": self.available
+        }))
+    }
+
+    fn from_json(json: &Json) -> Result<Self, AsJsonError> where Self: Sized {
+        Ok(Self {
+            client: json.get("client")
+                .map(Client::from_json)
+                .ok_or_else(|| AsJsonError::FieldNotFound("client"))??,
+
+            server: json.get("server")
+                .map(Server::from_json)
+                .ok_or_else(|| AsJsonError::FieldNotFound("server"))??,
+
+            available: json.get("available")
+                .and_then(Json::as_bool)
+                .ok_or_else(|| AsJsonError::FieldNotFound("available"))?
+        })
+    }
+}
+
+impl From<(Client, Server, bool)> for LookupResult {
+    #[inline]
+    fn from((client, server, available): (Client, Server, bool)) -> Self {
+        Self {
+            client,
+            server,
+            available
+        }
+    }
+}
+
+impl From<&LookupResult> for ClientEndpoint {
+    #[inline]
+    fn from(result: &LookupResult) -> Self {
+        Self {
+            server_address: result.server.address.clone(),
+            client_public: result.client.public_key.clone()
+        }
+    }
+}
+
+impl From<LookupResult> for ClientEndpoint {
+    #[inline]
+    fn from(result: LookupResult) -> Self {
+        Self {
+            server_address: result.server.address,
+            client_public: result.client.public_key
+        }
+    }
+}
@@ -50,19 +50,44 @@
 ///         };
 /// 
 ///         messages: {
-///             InMsg::Msg(msg) => |_, info: MessageInfo| async move {
+///             InMsg::Msg(msg) => |_, ctx: MessageContext<Self>| async move {
 ///                 println!("Message: {msg}");
-///                 println!("Sender: {}", info.sender.client.public_key.to_base64());
+///                 println!("Sender: {}", ctx.info().sender.client.public_key.to_base64());
+///
+///                 // Echo the message back to its sender
+///                 ctx.reply(OutMsg::Msg(msg)).await?;
 /// 
 ///                 Ok(())
 ///             }
 ///         };
+/// 
+///         request_timeouts: {
+///             InReq::Ping => std::time::Duration::from_secs(5)
+///         };
+///
+///         init: |_state| async {
+///             println!("Client is starting");
+///
+///             Ok(())
+///         };
+///
+///         spawn: {
+///             "heartbeat" => |_state| async {
+///                 loop {
+///                     tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+///                 }
+///             }
+///         };
 ///     );
 /// 
 ///     fn get_params(&self) ->  &ClientAppParams {
 ///         todo!()
 ///     }
 ///
+///     fn get_runtime(&self) -> &ClientRuntime {
+///         todo!()
+///     }
+///
 ///     fn get_middlewire(&self) ->  &ClientMiddleware<Self::HttpClient>  {
 ///         todo!()
 ///     }
@@ -116,14 +141,22 @@ macro_rules! build_client {
             request: Self::InputRequest,
             info: $crate::exports::hyperborealib::rest_api::prelude::MessageInfo
         ) -> std::pin::Pin<Box<dyn std::future::Future<
-            Output = Result<Self::InputResponse, $crate::client::ClientAppError<Self::Error>>
+            Output = Result<$crate::client::Respond<Self::InputResponse>, $crate::client::ClientAppError<Self::Error>>
         > + Send + 'async_trait>>
         where
             'life0: 'async_trait,
             Self: 'async_trait
         {
             match request {
-                $( $request => Box::pin(($handler)(self.get_state(), info)), )*
+                // Handlers can return either the response or the `Respond` enum
+                $( $request => {
+                    let state = self.get_state();
+
+                    Box::pin(async move {
+                        ($handler)(state, info).await
+                            .map($crate::client::Respond::<Self::InputResponse>::from)
+                    })
+                } )*
 
                 #[allow(unreachable_patterns)]
                 _ => unimplemented!()
@@ -135,19 +168,20 @@ macro_rules! build_client {
 
     (messages: { $( $message:pat => $handler:expr )* }; $( $tail:tt )*) => {
         #[allow(unused_variables)]
-        fn handle_message<'life0, 'async_trait>(
+        fn handle_message<'life0, 'life1, 'async_trait>(
             &self,
             message: Self::InputMessage,
-            info: $crate::exports::hyperborealib::rest_api::prelude::MessageInfo
+            ctx: $crate::client::MessageContext<'life1, Self>
         ) -> std::pin::Pin<Box<dyn std::future::Future<
             Output = Result<(), $crate::client::ClientAppError<Self::Error>>
         > + Send + 'async_trait>>
         where
             'life0: 'async_trait,
+            'life1: 'async_trait,
             Self: 'async_trait
         {
             match message {
-                $( $message => Box::pin(($handler)(self.get_state(), info)), )*
+                $( $message => Box::pin(($handler)(self.get_state(), ctx)), )*
 
                 #[allow(unreachable_patterns)]
                 _ => unimplemented!()
@@ -157,5 +191,68 @@ macro_rules! build_client {
         build_client!( $( $tail )* );
     };
 
+    (request_timeouts: { $( $request:pat => $timeout:expr ),* $(,)? }; $( $tail:tt )*) => {
+        #[allow(unused_variables)]
+        fn request_timeout_for(&self, request: &Self::InputRequest) -> std::time::Duration {
+            match request {
+                $( $request => $timeout, )*
+
+                #[allow(unreachable_patterns)]
+                _ => std::time::Duration::MAX
+            }
+        }
+
+        build_client!( $( $tail )* );
+    };
+
+    // Called once before the first update, unlike the
+    // `warmup_window` param which retries server connections.
+    (init: $handler:expr; $( $tail:tt )*) => {
+        fn init<'life0, 'async_trait>(
+            &self
+        ) -> std::pin::Pin<Box<dyn std::future::Future<
+            Output = Result<(), $crate::client::ClientAppError<Self::Error>>
+        > + Send + 'async_trait>>
+        where
+            'life0: 'async_trait,
+            Self: 'async_trait
+        {
+            let state = self.get_state();
+
+            Box::pin(async move {
+                ($handler)(state).await
+                    .map_err($crate::client::ClientAppError::Custom)
+            })
+        }
+
+        build_client!( $( $tail )* );
+    };
+
+    // Tasks are spawned once after the `init` handler. Store
+    // a cancellation token in the state to stop them gracefully.
+    (spawn: { $( $name:literal => $handler:expr ),* $(,)? }; $( $tail:tt )*) => {
+        fn start_background_tasks<'life0, 'async_trait>(
+            &self
+        ) -> std::pin::Pin<Box<dyn std::future::Future<
+            Output = ()
+        > + Send + 'async_trait>>
+        where
+            'life0: 'async_trait,
+            Self: 'async_trait
+        {
+            $(
+                // Naming tokio tasks requires the `tokio_unstable` cfg
+                let _name: &str = $name;
+                let state = self.get_state();
+
+                $crate::exports::tokio::spawn(($handler)(state));
+            )*
+
+            Box::pin(std::future::ready(()))
+        }
+
+        build_client!( $( $tail )* );
+    };
+
     () => {}
 }
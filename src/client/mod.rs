@@ -1,16 +1,124 @@
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Instant;
 
-use hyperborealib::rest_api::middleware::Error;
+use hyperborealib::crypto::prelude::PublicKey;
 
+mod acl;
+mod channel;
+mod circuit;
+mod rate_limit;
+mod response_cache;
+mod polled_channels;
+mod poller;
+mod registry;
+mod pins;
+mod dedupe;
+mod nonce;
+mod envelope;
+mod journal;
+mod archive;
+mod schema;
+mod validation;
+mod queue;
+mod priority;
+mod scheduler;
+mod pagination;
+mod inflight;
+mod reconnect;
+mod warm_up;
+mod connection;
+mod respond;
+mod context;
+mod topics;
 mod params;
+mod runtime;
 mod endpoint;
+mod lookup;
+mod http;
+mod identity;
+mod latency;
+mod presence;
+mod gossip;
+mod remote_state;
+mod multi;
+mod compose;
+mod observer;
+mod state_store;
+mod transfer;
+mod interceptors;
 mod app;
 mod macros;
 
+pub use acl::*;
+pub use channel::*;
+pub use circuit::*;
+pub use rate_limit::*;
+pub use response_cache::*;
+pub use polled_channels::*;
+pub use poller::*;
+pub use registry::*;
+pub use pins::*;
+pub use dedupe::*;
+pub use nonce::*;
+pub use envelope::*;
+pub use journal::*;
+pub use archive::*;
+pub use schema::*;
+pub use validation::*;
+pub use queue::*;
+pub use priority::*;
+pub use scheduler::*;
+pub use pagination::*;
+pub use inflight::*;
+pub use reconnect::*;
+pub use warm_up::*;
+pub use connection::*;
+pub use respond::*;
+pub use context::*;
+pub use topics::*;
 pub use params::*;
+pub use runtime::*;
 pub use endpoint::*;
+pub use lookup::*;
+pub use http::*;
+pub use identity::*;
+pub use latency::*;
+pub use presence::*;
+pub use gossip::*;
+pub use remote_state::*;
+pub use multi::*;
+pub use compose::*;
+pub use observer::*;
+pub use state_store::*;
+pub use transfer::*;
+pub use interceptors::*;
 pub use app::*;
 
+#[cfg(feature = "opentelemetry")]
+mod telemetry;
+
+#[cfg(feature = "opentelemetry")]
+pub use telemetry::*;
+
+#[cfg(feature = "sqlite")]
+mod sqlite_archive;
+
+#[cfg(feature = "sqlite")]
+pub use sqlite_archive::*;
+
+#[cfg(feature = "group-encryption")]
+mod group;
+
+#[cfg(feature = "group-encryption")]
+mod channel_security;
+
+#[cfg(feature = "group-encryption")]
+pub use group::*;
+
+#[cfg(feature = "group-encryption")]
+pub use channel_security::*;
+
 /// Start given client application in tokio async thread,
 /// returning back an `Arc` containing original variant
 /// of the client to perform `send` and `request` calls.
@@ -19,31 +127,347 @@ pub use app::*;
 /// specified in the application's params and return error
 /// if this request fails.
 /// 
+/// Client's channel and polled channels are registered in the
+/// `ChannelRegistry` before starting, so this method fails if
+/// another client of the current process already uses them.
+///
+/// `ClientApp::init` is called before the first update,
+/// and its error is returned from this method. Background
+/// tasks of the application are started after it.
+///
+/// If the `ephemeral_keys` param is enabled, `get_middleware`
+/// must return middleware created with the session key of the
+/// params' identity, otherwise `SessionKeyMismatch` is returned.
+///
 /// This method doesn't freeze the caller's thread.
-pub async fn run<T>(app: T) -> Result<Arc<T>, Error>
+pub async fn run<T>(app: T) -> Result<Arc<T>, ClientAppError<T::Error>>
 where
     T: ClientApp + Send + Sync + 'static,
-    T::Error: std::fmt::Display
+    T::Error: std::fmt::Display + 'static
 {
+    check_session(&app).await?;
+    register_channels(&app)?;
+    load_nonces(&app).await;
+    load_scheduled(&app).await;
+
+    app.init().await?;
+    app.start_background_tasks().await;
+
+    let warm_up_failed = warm_up(&app).await;
+
     // Start background updates task
     let client = Arc::new(app);
 
-    {
-        let client = client.clone();
+    tokio::spawn(deliver_responses(client.clone()));
+    tokio::spawn(update_loop(client.clone(), warm_up_failed));
+
+    Ok(client)
+}
+
+/// Same as `run`, but processes incoming messages in the
+/// caller's task until the `shutdown` future is resolved.
+///
+/// Before returning this method drains messages waiting on the
+/// server for `drain_timeout` so messages sent just before the
+/// shutdown are not lost. Returns amount of drained messages.
+pub async fn run_with_shutdown<T>(client: Arc<T>, shutdown: impl Future<Output = ()> + Send) -> Result<u64, ClientAppError<T::Error>>
+where
+    T: ClientApp + Send + Sync + 'static,
+    T::Error: std::fmt::Display + 'static
+{
+    check_session(client.as_ref()).await?;
+    register_channels(client.as_ref())?;
+    load_nonces(client.as_ref()).await;
+    load_scheduled(client.as_ref()).await;
+
+    client.init().await?;
+    client.start_background_tasks().await;
+
+    let warm_up_failed = warm_up(client.as_ref()).await;
+
+    let params = client.get_params();
+    let runtime = client.get_runtime();
+
+    let responses = tokio::spawn(deliver_responses(client.clone()));
+
+    tokio::select! {
+        _ = update_loop(client.clone(), warm_up_failed) => (),
+        _ = shutdown => ()
+    }
+
+    responses.abort();
+
+    let drained = client.drain(params.drain_timeout).await;
+
+    save_nonces(client.as_ref()).await;
+    save_scheduled(client.as_ref()).await;
+
+    if runtime.connection.disconnect() {
+        if let Some(handler) = &params.event_handler {
+            handler.on_disconnected(&params.server_endpoint(), DisconnectReason::Shutdown).await;
+        }
+    }
+
+    drained
+}
+
+/// Verify that the client middleware is connected
+/// with the session key if ephemeral keys are enabled.
+///
+/// Session key is generated when the params are built, and
+/// messages are signed with it, so the middleware created with
+/// the long-term key would make the server drop them.
+async fn check_session<T: ClientApp>(app: &T) -> Result<(), ClientAppError<T::Error>> {
+    let params = app.get_params();
+
+    if !params.ephemeral_keys {
+        return Ok(());
+    }
+
+    let session = params.identity.public();
+
+    // Connected client is indexed by the server under its middleware's key
+    let found = app.get_connected_middleware().await?
+        .lookup(session.clone(), None).await?
+        .is_some_and(|(client, server, _)| {
+            client.public_key == session && server.public_key == params.server_public
+        });
+
+    if !found {
+        return Err(ClientAppError::SessionKeyMismatch {
+            session: session.to_base64()
+        });
+    }
+
+    #[cfg(feature = "tracing")]
+    tracing::info!("[client] Started session with ephemeral key {}", session.to_base64());
+
+    Ok(())
+}
+
+/// Register client's channel and polled channels.
+fn register_channels<T: ClientApp>(app: &T) -> Result<(), ClientAppError<T::Error>> {
+    let params = app.get_params();
+    let runtime = app.get_runtime();
+
+    app.register_channel(&params.channel)?;
+
+    for channel in runtime.polled_channels.list() {
+        app.register_channel(&channel)?;
+    }
+
+    Ok(())
+}
+
+/// Resolve peers listed in the `warm_up` param,
+/// returning the ones which were not resolved.
+async fn warm_up<T>(app: &T) -> Vec<PublicKey>
+where
+    T: ClientApp,
+    T::Error: std::fmt::Display
+{
+    let peers = &app.get_params().warm_up.peers;
+
+    if peers.is_empty() {
+        return vec![];
+    }
+
+    let report = app.warm_up(peers).await;
+
+    #[cfg(feature = "tracing")]
+    for (peer, status) in &report.peers {
+        tracing::debug!("[client] Warmed up peer {}: {status:?}", peer.to_base64());
+    }
+
+    report.failed()
+}
+
+/// Load seen nonces from the state store
+/// if replay protection is enabled.
+async fn load_nonces<T: ClientApp>(app: &T) {
+    let params = app.get_params();
+    let runtime = app.get_runtime();
+
+    if let (true, Some(store)) = (params.replay_protection, &params.state_store) {
+        if let Err(_err) = runtime.nonces.load(store.as_ref()).await {
+            #[cfg(feature = "tracing")]
+            tracing::error!("[client] Failed to load seen nonces: {_err}");
+        }
+    }
+}
+
+/// Save seen nonces to the state store
+/// if replay protection is enabled.
+async fn save_nonces<T: ClientApp>(app: &T) {
+    let params = app.get_params();
+    let runtime = app.get_runtime();
+
+    if let (true, Some(store)) = (params.replay_protection, &params.state_store) {
+        if let Err(_err) = runtime.nonces.save(store.as_ref()).await {
+            #[cfg(feature = "tracing")]
+            tracing::error!("[client] Failed to save seen nonces: {_err}");
+        }
+    }
+}
+
+/// Load scheduled messages from the state store.
+async fn load_scheduled<T: ClientApp>(app: &T) {
+    let params = app.get_params();
+    let runtime = app.get_runtime();
+
+    if let Some(store) = &params.state_store {
+        if let Err(_err) = runtime.scheduler.load(store.as_ref()).await {
+            #[cfg(feature = "tracing")]
+            tracing::error!("[client] Failed to load scheduled messages: {_err}");
+        }
+    }
+}
+
+/// Save scheduled messages to the state store.
+async fn save_scheduled<T: ClientApp>(app: &T) {
+    let params = app.get_params();
+    let runtime = app.get_runtime();
 
-        tokio::spawn(async move {
-            let params = client.get_params();
+    if let Some(store) = &params.state_store {
+        if let Err(_err) = runtime.scheduler.save(store.as_ref()).await {
+            #[cfg(feature = "tracing")]
+            tracing::error!("[client] Failed to save scheduled messages: {_err}");
+        }
+    }
+}
+
+/// Send responses of the bound `ResponseToken`s.
+///
+/// Responses are sent with `send_envelope`, so they're
+/// encrypted, journaled and passed to the interceptors
+/// the same way as the immediate ones.
+async fn deliver_responses<T>(client: Arc<T>)
+where
+    T: ClientApp + Send + Sync + 'static,
+    T::Error: std::fmt::Display + 'static
+{
+    // Responses are delivered by the first started client only
+    let Some(mut responses) = client.get_runtime().deferred_responses.take_receiver() else {
+        return;
+    };
+
+    while let Some(response) = responses.recv().await {
+        let result = match client.get_connected_middleware().await {
+            Ok(middleware) => client.send_envelope(
+                &middleware,
+                &response.endpoint,
+                &response.channel,
+                &response.envelope
+            ).await,
+
+            Err(err) => Err(err)
+        };
 
-            loop {
-                if let Err(_err) = client.update().await {
+        if let Err(_err) = &result {
+            #[cfg(feature = "tracing")]
+            tracing::error!("[client] Failed to send deferred response: {_err}");
+        }
+
+        response.complete(result.map_err(|err| err.to_string()));
+    }
+}
+
+/// Process incoming messages and maintain the client's state.
+///
+/// Lost connection to the server is restored using
+/// the `reconnect_policy` param, repeated after the
+/// policy's `max_delay` if all the attempts have failed.
+async fn update_loop<T>(client: Arc<T>, mut warm_up_failed: Vec<PublicKey>)
+where
+    T: ClientApp + Send + Sync + 'static,
+    T::Error: std::fmt::Display + 'static
+{
+    let params = client.get_params();
+    let runtime = client.get_runtime();
+
+    let mut last_periodic_task = Instant::now();
+    let mut last_gossip = Instant::now();
+    let mut last_warm_up = Instant::now();
+
+    loop {
+        match client.update_batch().await.map_err(ClientAppError::into_inner) {
+            Ok(_) => (),
+
+            // Reconnect to the server if the connection is lost
+            Err(ClientAppError::MiddlewareError(err)) => {
+                #[cfg(feature = "tracing")]
+                tracing::error!("[client] Update error: {err}");
+
+                client.notify_disconnected(&err).await;
+
+                runtime.restart_detector.force_check();
+
+                // Keep reconnecting until the client is stopped
+                while let Err(_err) = client.reconnect().await {
                     #[cfg(feature = "tracing")]
-                    tracing::error!("[client] Update error: {_err}");
+                    tracing::error!("[client] {_err}");
+
+                    tokio::time::sleep(params.reconnect_policy.max_delay).await;
                 }
+            }
 
-                tokio::time::sleep(params.delay).await;
+            Err(_err) => {
+                #[cfg(feature = "tracing")]
+                tracing::error!("[client] Update error: {_err}");
             }
-        });
-    }
+        }
 
-    Ok(client)
+        // Detect server restarts
+        if let Err(_err) = client.check_server_restart().await {
+            #[cfg(feature = "tracing")]
+            tracing::error!("[client] Failed to check server restart: {_err}");
+        }
+
+        // Probe presence of the watched peers
+        client.probe_presence().await;
+
+        // Renew subscriptions to the topics
+        client.renew_subscriptions().await;
+
+        // Persist nonces of the processed messages
+        save_nonces(client.as_ref()).await;
+
+        // Send due scheduled messages
+        client.send_scheduled().await;
+
+        save_scheduled(client.as_ref()).await;
+
+        // Run periodic maintenance task
+        if let Some(interval) = params.periodic_task_interval {
+            if last_periodic_task.elapsed() >= interval {
+                last_periodic_task = Instant::now();
+
+                if let Err(_err) = client.periodic_task().await {
+                    #[cfg(feature = "tracing")]
+                    tracing::error!("[client] Periodic task failed: {_err}");
+                }
+            }
+        }
+
+        // Exchange known peers with random ones
+        if last_gossip.elapsed() >= params.gossip_interval {
+            last_gossip = Instant::now();
+
+            if let Err(_err) = client.gossip_peers().await {
+                #[cfg(feature = "tracing")]
+                tracing::error!("[client] Failed to gossip peers: {_err}");
+            }
+        }
+
+        // Repeat warm up of the failed peers
+        if let Some(interval) = params.warm_up.retry_interval {
+            if !warm_up_failed.is_empty() && last_warm_up.elapsed() >= interval {
+                last_warm_up = Instant::now();
+
+                warm_up_failed = client.warm_up(&warm_up_failed).await.failed();
+            }
+        }
+
+        tokio::time::sleep(params.delay).await;
+    }
 }
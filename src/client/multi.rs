@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use serde_json::{json, Value as Json};
+
+use hyperborealib::exports::tokio;
+
+use hyperborealib::http::HttpClient;
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::drivers::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use super::{Channel, ClientEndpoint, canonical_json, monotonic_nonce};
+
+#[derive(Debug, thiserror::Error)]
+pub enum MultiClientError {
+    #[error(transparent)]
+    SerdeJsonError(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    MiddlewareError(#[from] MiddlewareError),
+
+    #[error(transparent)]
+    MessagesError(#[from] MessagesError),
+
+    #[error(transparent)]
+    AsJsonError(#[from] AsJsonError),
+
+    #[error("Identity is not registered")]
+    UnknownIdentity,
+
+    #[error("Handler error: {0}")]
+    Handler(Box<dyn std::error::Error + Send + Sync>)
+}
+
+/// Handler of the messages received by the identity
+/// registered in the `MultiClient`.
+#[async_trait::async_trait]
+pub trait IdentityHandler: Send + Sync {
+    /// Handle the `message` field of the incoming envelope.
+    async fn handle_message(&self, identity: &IdentityRef<'_>, message: Json, info: MessageInfo) -> Result<(), MultiClientError>;
+}
+
+impl std::fmt::Debug for dyn IdentityHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("IdentityHandler")
+    }
+}
+
+struct Identity<T: HttpClient> {
+    secret: SecretKey,
+    channel: Channel,
+    middleware: ClientMiddleware<T>,
+    connected: RwLock<Option<ConnectedClientMiddleware<T>>>,
+    handler: Arc<dyn IdentityHandler>
+}
+
+impl<T: HttpClient> Identity<T> {
+    #[inline]
+    fn is_connected(&self) -> bool {
+        self.connected.read()
+            .map(|connected| connected.is_some())
+            .unwrap_or(false)
+    }
+
+    #[inline]
+    fn disconnect(&self) {
+        if let Ok(mut connected) = self.connected.write() {
+            *connected = None;
+        }
+    }
+}
+
+/// Container of multiple client identities
+/// connected to the same server.
+///
+/// All the identities share one HTTP client and a single
+/// poll loop. Servers keep a separate inbox for every client,
+/// so the loop polls inbox of every identity and routes the
+/// polled messages to the identity's handler.
+///
+/// Identities are connected independently: registering a new
+/// identity or losing connection of one of them doesn't make
+/// the other ones reconnect.
+pub struct MultiClient<T: HttpClient> {
+    http: T,
+    server_address: String,
+    server_public: PublicKey,
+    encoding: MessageEncoding,
+    compression_level: CompressionLevel,
+    identities: RwLock<HashMap<PublicKey, Arc<Identity<T>>>>
+}
+
+impl<T: HttpClient> std::fmt::Debug for MultiClient<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MultiClient")
+            .field("server_address", &self.server_address)
+            .field("identities", &self.identities.read().map(|identities| identities.len()).unwrap_or_default())
+            .finish()
+    }
+}
+
+impl<T> MultiClient<T>
+where
+    T: HttpClient + Clone + Send + Sync + 'static
+{
+    pub fn new(http: T, server_public: PublicKey, server_address: impl ToString) -> Self {
+        Self {
+            http,
+            server_address: server_address.to_string(),
+            server_public,
+            encoding: MessageEncoding::default(),
+            compression_level: CompressionLevel::default(),
+            identities: RwLock::new(HashMap::new())
+        }
+    }
+
+    /// Register new identity.
+    ///
+    /// Messages sent to the identity on the given
+    /// channel are passed to the handler. The identity
+    /// is connected to the server on its first use.
+    pub fn register(
+        &self,
+        secret: SecretKey,
+        channel: Channel,
+        handler: impl IdentityHandler + 'static
+    ) {
+        let middleware = ClientMiddleware::new(
+            self.http.clone(),
+            ClientDriver::new(ClientInfo::thin(), secret.clone())
+        );
+
+        let identity = Identity {
+            secret,
+            channel,
+            middleware,
+            connected: RwLock::new(None),
+            handler: Arc::new(handler)
+        };
+
+        if let Ok(mut identities) = self.identities.write() {
+            identities.insert(identity.secret.public(), Arc::new(identity));
+        }
+    }
+
+    /// Remove identity with given public key.
+    pub fn unregister(&self, public_key: &PublicKey) {
+        if let Ok(mut identities) = self.identities.write() {
+            identities.remove(public_key);
+        }
+    }
+
+    /// Public keys of the registered identities.
+    pub fn identities(&self) -> Vec<PublicKey> {
+        self.identities.read()
+            .map(|identities| identities.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Check if all the identities are connected.
+    pub fn is_connected(&self) -> bool {
+        self.all_identities()
+            .iter()
+            .all(|identity| identity.is_connected())
+    }
+
+    /// Mark all the identities disconnected.
+    pub fn disconnect(&self) {
+        for identity in self.all_identities() {
+            identity.disconnect();
+        }
+    }
+
+    fn get_identity(&self, public_key: &PublicKey) -> Option<Arc<Identity<T>>> {
+        self.identities.read().ok()?
+            .get(public_key)
+            .cloned()
+    }
+
+    fn all_identities(&self) -> Vec<Arc<Identity<T>>> {
+        self.identities.read()
+            .map(|identities| identities.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Connect all the not connected identities to the server.
+    ///
+    /// Returns the first connection error, but
+    /// tries to connect all the identities anyway.
+    pub async fn connect(&self) -> Result<(), MultiClientError> {
+        let mut result = Ok(());
+
+        for identity in self.all_identities() {
+            if let Err(err) = self.connected(&identity).await {
+                if result.is_ok() {
+                    result = Err(err);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Get connected middleware of the identity,
+    /// connecting it if needed.
+    async fn connected(&self, identity: &Identity<T>) -> Result<ConnectedClientMiddleware<T>, MultiClientError> {
+        let connected = identity.connected.read().ok()
+            .and_then(|connected| connected.clone());
+
+        if let Some(middleware) = connected {
+            return Ok(middleware);
+        }
+
+        let middleware = identity.middleware.connect_to(
+            &self.server_address,
+            self.server_public.clone()
+        ).await?;
+
+        if let Ok(mut connected) = identity.connected.write() {
+            *connected = Some(middleware.clone());
+        }
+
+        Ok(middleware)
+    }
+
+    /// Use identity with given public key.
+    ///
+    /// Returns `None` if the identity is not registered.
+    pub fn as_identity(&self, public_key: &PublicKey) -> Option<IdentityRef<'_>> {
+        self.get_identity(public_key)?;
+
+        Some(IdentityRef {
+            client: self,
+            public_key: public_key.clone()
+        })
+    }
+
+    /// Send message from the identity with given public key.
+    async fn send_from(&self, public_key: &PublicKey, endpoint: &ClientEndpoint, channel: Option<&Channel>, message: Json) -> Result<(), MultiClientError> {
+        let identity = self.get_identity(public_key)
+            .ok_or(MultiClientError::UnknownIdentity)?;
+
+        let middleware = self.connected(&identity).await?;
+
+        let envelope = canonical_json(&json!({
+            "message": message,
+            "nonce": monotonic_nonce()
+        }))?;
+
+        let message = Message::create(
+            &identity.secret,
+            &endpoint.client_public,
+            envelope,
+            self.encoding,
+            self.compression_level
+        )?;
+
+        let result = middleware.send(
+            &endpoint.server_address,
+            endpoint.client_public.clone(),
+            channel.unwrap_or(&identity.channel),
+            message
+        ).await;
+
+        if let Err(err) = result {
+            identity.disconnect();
+
+            return Err(err.into());
+        }
+
+        Ok(())
+    }
+
+    /// Poll messages of the identity and pass them to its handler.
+    ///
+    /// Returns amount of handled messages.
+    async fn update_identity(&self, identity: &Identity<T>) -> Result<usize, MultiClientError> {
+        let middleware = self.connected(identity).await?;
+
+        let messages = match middleware.poll(&identity.channel, None).await {
+            Ok((messages, _)) => messages,
+
+            Err(err) => {
+                identity.disconnect();
+
+                return Err(err.into());
+            }
+        };
+
+        let identity_ref = IdentityRef {
+            client: self,
+            public_key: identity.secret.public()
+        };
+
+        let mut handled = 0;
+
+        for message in messages {
+            let content = message.message.read(
+                &identity.secret,
+                &message.sender.client.public_key
+            );
+
+            let content = match content {
+                Ok(content) => content,
+
+                Err(_err) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!("[client] Failed to decode incoming message: {_err}");
+
+                    continue;
+                }
+            };
+
+            let Ok(mut content) = serde_json::from_slice::<Json>(&content) else {
+                continue;
+            };
+
+            let Some(content) = content.get_mut("message").map(Json::take) else {
+                continue;
+            };
+
+            // Failed message doesn't prevent handling of the other ones
+            match identity.handler.handle_message(&identity_ref, content, message).await {
+                Ok(()) => handled += 1,
+
+                Err(_err) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::error!("[client] Identity {} failed to handle message: {_err}", identity_ref.public_key.to_base64());
+                }
+            }
+        }
+
+        Ok(handled)
+    }
+
+    /// Poll messages of all the identities once
+    /// and pass them to the identities handlers.
+    ///
+    /// Failures of one identity are logged and don't
+    /// prevent updating the other ones. Returns amount
+    /// of handled messages.
+    pub async fn update(&self) -> usize {
+        let mut handled = 0;
+
+        for identity in self.all_identities() {
+            match self.update_identity(&identity).await {
+                Ok(identity_handled) => handled += identity_handled,
+
+                Err(_err) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::error!("[client] Failed to update identity {}: {_err}", identity.secret.public().to_base64());
+                }
+            }
+        }
+
+        handled
+    }
+
+    /// Start the shared poll loop in the background.
+    pub fn spawn(self: Arc<Self>, delay: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                self.update().await;
+
+                tokio::time::sleep(delay).await;
+            }
+        })
+    }
+}
+
+/// Reference to the identity registered in the `MultiClient`.
+pub struct IdentityRef<'a> {
+    client: &'a (dyn IdentitySender + Send + Sync),
+    public_key: PublicKey
+}
+
+impl std::fmt::Debug for IdentityRef<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IdentityRef")
+            .field("public_key", &self.public_key.to_base64())
+            .finish()
+    }
+}
+
+/// Object-safe part of the `MultiClient` used by the identity references.
+#[async_trait::async_trait]
+trait IdentitySender {
+    async fn send_from(&self, public_key: &PublicKey, endpoint: &ClientEndpoint, channel: Option<&Channel>, message: Json) -> Result<(), MultiClientError>;
+}
+
+#[async_trait::async_trait]
+impl<T> IdentitySender for MultiClient<T>
+where
+    T: HttpClient + Clone + Send + Sync + 'static
+{
+    #[inline]
+    async fn send_from(&self, public_key: &PublicKey, endpoint: &ClientEndpoint, channel: Option<&Channel>, message: Json) -> Result<(), MultiClientError> {
+        MultiClient::send_from(self, public_key, endpoint, channel, message).await
+    }
+}
+
+impl IdentityRef<'_> {
+    #[inline]
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    /// Send message from the current identity
+    /// using the identity's channel.
+    pub async fn send(&self, endpoint: &ClientEndpoint, message: impl AsJson) -> Result<(), MultiClientError> {
+        let message = message.to_json()?;
+
+        self.client.send_from(&self.public_key, endpoint, None, message).await
+    }
+
+    /// Send message from the current identity using given channel.
+    pub async fn send_to_channel(&self, endpoint: &ClientEndpoint, channel: &Channel, message: impl AsJson) -> Result<(), MultiClientError> {
+        let message = message.to_json()?;
+
+        self.client.send_from(&self.public_key, endpoint, Some(channel), message).await
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hyperborealib::crypto::prelude::*;
+
+use super::{StateStore, StateStoreExt};
+
+/// Key of the state store value keeping seen nonces.
+pub const NONCES_STATE_KEY: &str = "hyperelm-nonces";
+
+static LAST_NONCE: AtomicU64 = AtomicU64::new(0);
+
+/// Get nonce of the outgoing envelope.
+///
+/// Nonces are based on the current time in microseconds
+/// and are strictly increasing within the process, so they
+/// keep increasing after the application restart.
+///
+/// ```rust
+/// use hyperelm::client::monotonic_nonce;
+///
+/// let first = monotonic_nonce();
+/// let second = monotonic_nonce();
+///
+/// assert!(second > first);
+/// ```
+pub fn monotonic_nonce() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros() as u64;
+
+    let last = LAST_NONCE.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+        Some(now.max(last + 1))
+    }).unwrap_or_default();
+
+    now.max(last + 1)
+}
+
+/// Tracker of the greatest nonces seen from every sender.
+///
+/// Messages with nonce not greater than the seen one
+/// are considered replayed.
+///
+/// ```rust
+/// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+/// use hyperelm::client::NonceTracker;
+///
+/// let sender = SecretKey::random().public();
+/// let nonces = NonceTracker::default();
+///
+/// assert!(nonces.check(&sender, 10));
+/// assert!(nonces.check(&sender, 11));
+///
+/// assert!(!nonces.check(&sender, 11));
+/// assert!(!nonces.check(&sender, 5));
+///
+/// assert_eq!(nonces.max_seen(&sender), Some(11));
+/// ```
+#[derive(Debug, Default)]
+pub struct NonceTracker {
+    seen: Mutex<HashMap<PublicKey, u64>>,
+    changed: AtomicBool
+}
+
+impl NonceTracker {
+    pub fn max_seen(&self, sender: &PublicKey) -> Option<u64> {
+        self.seen.lock().ok()?
+            .get(sender)
+            .copied()
+    }
+
+    /// Remember nonce of the incoming message.
+    ///
+    /// Returns `false` if the message is replayed.
+    pub fn check(&self, sender: &PublicKey, nonce: u64) -> bool {
+        let Ok(mut seen) = self.seen.lock() else {
+            return true;
+        };
+
+        match seen.get_mut(sender) {
+            Some(max_seen) if *max_seen >= nonce => return false,
+            Some(max_seen) => *max_seen = nonce,
+
+            None => {
+                seen.insert(sender.clone(), nonce);
+            }
+        }
+
+        self.changed.store(true, Ordering::Release);
+
+        true
+    }
+
+    /// Save seen nonces to the state store
+    /// if they were changed since the last save.
+    pub async fn save(&self, store: &dyn StateStore) -> std::io::Result<()> {
+        if !self.changed.swap(false, Ordering::AcqRel) {
+            return Ok(());
+        }
+
+        let seen = self.seen.lock()
+            .map(|seen| {
+                seen.iter()
+                    .map(|(sender, nonce)| (sender.to_base64(), *nonce))
+                    .collect::<HashMap<_, _>>()
+            })
+            .unwrap_or_default();
+
+        store.save_json(NONCES_STATE_KEY, &seen).await
+    }
+
+    /// Load seen nonces from the state store,
+    /// keeping the greatest ones.
+    pub async fn load(&self, store: &dyn StateStore) -> std::io::Result<()> {
+        let Some(loaded) = store.load_json::<HashMap<String, u64>>(NONCES_STATE_KEY).await? else {
+            return Ok(());
+        };
+
+        if let Ok(mut seen) = self.seen.lock() {
+            for (sender, nonce) in loaded {
+                let Ok(sender) = PublicKey::from_base64(sender) else {
+                    continue;
+                };
+
+                let max_seen = seen.entry(sender).or_default();
+
+                *max_seen = nonce.max(*max_seen);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use hyperborealib::http::HttpClient;
+
+use super::*;
+
+/// Read-only client which can query servers
+/// without connecting to them.
+///
+/// Observer doesn't need a server account, messaging
+/// channel or params, so it can be used by debugging
+/// tools to inspect the network.
+///
+/// ```rust,ignore
+/// let observer = ClientObserver::new(ClientMiddleware::new(http, driver));
+///
+/// let info = observer.server_info::<()>("127.0.0.1:8001").await?;
+///
+/// let endpoint = observer.lookup::<()>("127.0.0.1:8001", public_key, None).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClientObserver<T: HttpClient> {
+    middleware: ClientMiddleware<T>
+}
+
+impl<T: HttpClient> ClientObserver<T> {
+    #[inline]
+    pub fn new(middleware: ClientMiddleware<T>) -> Self {
+        Self {
+            middleware
+        }
+    }
+
+    #[inline]
+    pub fn middleware(&self) -> &ClientMiddleware<T> {
+        &self.middleware
+    }
+
+    /// Request info of the server with given address.
+    pub async fn server_info<E: Send + Sync>(&self, address: impl AsRef<str>) -> Result<InfoResponse, ClientAppError<E>> {
+        Ok(self.middleware.get_info(address.as_ref()).await?)
+    }
+
+    /// Search for the client in the network
+    /// starting from the server with given address.
+    pub async fn lookup<E: Send + Sync>(
+        &self,
+        address: impl AsRef<str>,
+        public_key: PublicKey,
+        client_type: Option<ClientType>
+    ) -> Result<Option<ClientEndpoint>, ClientAppError<E>> {
+        let endpoint = self.middleware.lookup(address.as_ref(), public_key, client_type).await?
+            .map(|(client, server, _)| {
+                ClientEndpoint {
+                    server_address: server.address,
+                    client_public: client.public_key
+                }
+            });
+
+        Ok(endpoint)
+    }
+
+    /// List servers known to the server with given address.
+    pub async fn list_known_servers<E: Send + Sync>(&self, address: impl AsRef<str>) -> Result<Vec<Server>, ClientAppError<E>> {
+        Ok(self.middleware.get_servers(address.as_ref()).await?)
+    }
+}
+
+impl<T: HttpClient> From<ClientMiddleware<T>> for ClientObserver<T> {
+    #[inline]
+    fn from(middleware: ClientMiddleware<T>) -> Self {
+        Self::new(middleware)
+    }
+}
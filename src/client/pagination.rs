@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::ValidationError;
+
+/// Default time during which cursors of the `Paginator` are valid.
+pub const DEFAULT_CURSOR_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Page of items returned in the response.
+///
+/// `{ "items": [...], "cursor": "...", "total": 250 }`
+///
+/// Requester should send the `cursor` back to receive
+/// the next page. The last page has no cursor.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+
+    /// Opaque cursor of the next page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+
+    /// Total amount of items, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>
+}
+
+impl<T> Paginated<T> {
+    /// Create the last page with given items.
+    #[inline]
+    pub fn last(items: Vec<T>) -> Self {
+        Self {
+            items,
+            cursor: None,
+            total: None
+        }
+    }
+
+    #[inline]
+    pub fn is_last(&self) -> bool {
+        self.cursor.is_none()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum PaginationError {
+    #[error("Unknown pagination cursor: {0}")]
+    InvalidCursor(String),
+
+    #[error("Pagination cursor has expired: {0}")]
+    ExpiredCursor(String)
+}
+
+impl From<PaginationError> for ValidationError {
+    #[inline]
+    fn from(error: PaginationError) -> Self {
+        ValidationError::field("cursor", error)
+    }
+}
+
+struct PaginatorCursor<T> {
+    items: Box<dyn Iterator<Item = T> + Send>,
+    page_size: usize,
+    total: Option<u64>,
+    expires_at: Instant
+}
+
+/// Responder-side helper which slices iterators
+/// into pages keyed by opaque cursors.
+///
+/// Remaining items are kept until the next page is requested
+/// or the cursor expires. Every cursor can be used only once.
+///
+/// ```rust
+/// use hyperelm::client::{Paginator, PaginationError};
+///
+/// let paginator = Paginator::default();
+///
+/// let mut page = paginator.paginate(0..250, 100);
+/// let mut items = page.items.clone();
+///
+/// assert_eq!(page.total, Some(250));
+///
+/// while let Some(cursor) = page.cursor {
+///     page = paginator.next_page(&cursor).unwrap();
+///
+///     assert!(page.items.len() <= 100);
+///
+///     items.extend(page.items.iter().copied());
+/// }
+///
+/// assert_eq!(items, (0..250).collect::<Vec<_>>());
+///
+/// // Unknown cursors are rejected
+/// assert_eq!(
+///     paginator.next_page("bogus").unwrap_err(),
+///     PaginationError::InvalidCursor(String::from("bogus"))
+/// );
+/// ```
+pub struct Paginator<T> {
+    cursors: Mutex<HashMap<String, PaginatorCursor<T>>>,
+    ttl: Duration
+}
+
+impl<T> std::fmt::Debug for Paginator<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Paginator")
+            .field("cursors", &self.len())
+            .field("ttl", &self.ttl)
+            .finish()
+    }
+}
+
+impl<T> Default for Paginator<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(DEFAULT_CURSOR_TTL)
+    }
+}
+
+impl<T> Paginator<T> {
+    /// Create paginator whose cursors expire after `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            cursors: Mutex::new(HashMap::new()),
+            ttl
+        }
+    }
+
+    #[inline]
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Amount of the active cursors.
+    pub fn len(&self) -> usize {
+        self.cursors.lock()
+            .map(|cursors| cursors.len())
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check that the cursor can be used to get the next page.
+    ///
+    /// Use it in the `validate_request` method to report
+    /// invalid cursors to the requester.
+    pub fn check_cursor(&self, cursor: &str) -> Result<(), PaginationError> {
+        let cursors = match self.cursors.lock() {
+            Ok(cursors) => cursors,
+            Err(err) => err.into_inner()
+        };
+
+        match cursors.get(cursor) {
+            Some(state) if state.expires_at > Instant::now() => Ok(()),
+            Some(_) => Err(PaginationError::ExpiredCursor(cursor.to_string())),
+            None => Err(PaginationError::InvalidCursor(cursor.to_string()))
+        }
+    }
+
+    /// Remove cursors with elapsed time to live.
+    pub fn prune(&self) {
+        if let Ok(mut cursors) = self.cursors.lock() {
+            let now = Instant::now();
+
+            cursors.retain(|_, cursor| cursor.expires_at > now);
+        }
+    }
+}
+
+impl<T: Send + 'static> Paginator<T> {
+    /// Get the first page of the items.
+    ///
+    /// Total amount of items is set if
+    /// the iterator knows its exact length.
+    pub fn paginate<I>(&self, items: I, page_size: usize) -> Paginated<T>
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: Send + 'static
+    {
+        self.prune();
+
+        let items = items.into_iter();
+
+        let total = match items.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(lower as u64),
+            _ => None
+        };
+
+        self.take_page(PaginatorCursor {
+            items: Box::new(items),
+            page_size: page_size.max(1),
+            total,
+            expires_at: Instant::now() + self.ttl
+        })
+    }
+
+    /// Get the next page of the items using the cursor
+    /// returned with the previous one.
+    pub fn next_page(&self, cursor: &str) -> Result<Paginated<T>, PaginationError> {
+        let state = self.cursors.lock()
+            .ok()
+            .and_then(|mut cursors| cursors.remove(cursor))
+            .ok_or_else(|| PaginationError::InvalidCursor(cursor.to_string()))?;
+
+        if state.expires_at <= Instant::now() {
+            return Err(PaginationError::ExpiredCursor(cursor.to_string()));
+        }
+
+        Ok(self.take_page(PaginatorCursor {
+            expires_at: Instant::now() + self.ttl,
+            ..state
+        }))
+    }
+
+    fn take_page(&self, mut state: PaginatorCursor<T>) -> Paginated<T> {
+        let items = state.items.by_ref()
+            .take(state.page_size)
+            .collect::<Vec<_>>();
+
+        let total = state.total;
+
+        // Don't issue the cursor if there are no more items
+        let mut remaining = state.items.peekable();
+
+        let cursor = if remaining.peek().is_none() {
+            None
+        } else {
+            let cursor = format!("{:016x}", rand::random::<u64>());
+
+            state.items = Box::new(remaining);
+
+            if let Ok(mut cursors) = self.cursors.lock() {
+                cursors.insert(cursor.clone(), state);
+            }
+
+            Some(cursor)
+        };
+
+        Paginated {
+            items,
+            cursor,
+            total
+        }
+    }
+}
@@ -1,12 +1,60 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
 use hyperborealib::crypto::prelude::*;
 use hyperborealib::rest_api::prelude::*;
 
-#[derive(Debug, Clone)]
+use super::*;
+
+/// Params of the client application.
+///
+/// Params are serialized as their builder, so runtime objects
+/// like interceptors or state store are not stored. Secret
+/// key is serialized as a base64 string and is never printed
+/// by the `Debug` and `Display` implementations.
+///
+/// ```rust
+/// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+/// use hyperelm::client::ClientAppParams;
+///
+/// let secret = SecretKey::random();
+///
+/// let params = ClientAppParams::builder()
+///     .client(secret.clone())
+///     .server(SecretKey::random().public(), "127.0.0.1:8001")
+///     .build()
+///     .unwrap();
+///
+/// // JSON round trip
+/// let json = serde_json::to_string(&params).unwrap();
+/// let restored = serde_json::from_str::<ClientAppParams>(&json).unwrap();
+///
+/// assert_eq!(restored.identity.secret(), secret);
+/// assert_eq!(restored.server_address, params.server_address);
+///
+/// // TOML round trip
+/// let toml = toml::to_string(&params).unwrap();
+/// let restored = toml::from_str::<ClientAppParams>(&toml).unwrap();
+///
+/// assert_eq!(restored.identity.secret(), secret);
+/// assert_eq!(restored.delay, params.delay);
+///
+/// // Secret key is never printed
+/// let debug = format!("{params:?} {params} {:?}", ClientAppParams::builder().client(secret.clone()));
+///
+/// assert!(!debug.contains(&secret.to_base64()));
+/// assert!(debug.contains(&secret.public().to_base64()));
+/// ```
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "ClientAppParamsBuilder", try_from = "ClientAppParamsBuilder"))]
 pub struct ClientAppParams {
-    /// Secret key of the current client.
-    pub client_secret: SecretKey,
+    /// Identity of the current client.
+    ///
+    /// Shared between all the clones of the params.
+    pub identity: Arc<ClientIdentity>,
 
     /// Public key of the server to connect to.
     pub server_public: PublicKey,
@@ -15,7 +63,7 @@ pub struct ClientAppParams {
     pub server_address: String,
 
     /// Messaging channel.
-    pub channel: String,
+    pub channel: Channel,
 
     /// Messages encoding format.
     pub encoding: MessageEncoding,
@@ -24,7 +72,264 @@ pub struct ClientAppParams {
     pub compression_level: CompressionLevel,
 
     /// Messages synchronization delay.
-    pub delay: Duration
+    pub delay: Duration,
+
+    /// Interval of calling the `ClientApp::periodic_task` method
+    /// by the background updates task. Disabled if not set.
+    pub periodic_task_interval: Option<Duration>,
+
+    /// Amount of random known peers asked for
+    /// their peers by every gossip exchange.
+    pub gossip_fanout: usize,
+
+    /// Maximal time to wait for the response
+    /// of every peer of the gossip exchange.
+    pub gossip_timeout: Duration,
+
+    /// Interval of the gossip exchanges performed
+    /// by the background updates task.
+    pub gossip_interval: Duration,
+
+    /// Maximal time to wait for the remote state value.
+    pub remote_state_timeout: Duration,
+
+    /// Maximal amount of concurrently processed
+    /// incoming messages. Default is 1.
+    pub handler_concurrency: usize,
+
+    /// Maximal amount of messages in the incoming queue
+    /// after which the background poller stops fetching
+    /// new messages. Default is 1024.
+    pub poller_buffer_size: usize,
+
+    /// Maximal time spent on processing messages waiting
+    /// on the server when the client is shutting down.
+    pub drain_timeout: Duration,
+
+    /// Tolerance of the clock skew between the sender and
+    /// the receiver applied to the envelopes' expiry time.
+    /// Default is 30 seconds.
+    pub expiry_grace: Duration,
+
+    /// Maximal amount of requests packed into one batch
+    /// by the `request_batch` method. Larger batches are
+    /// rejected by the receiver. Default is 64.
+    pub batch_max_items: usize,
+
+    /// Maximal size of the serialized requests packed
+    /// into one batch. Default is 256 KiB.
+    pub batch_max_bytes: usize,
+
+    /// Channel on which responses to the sent requests are received.
+    pub reply_channel_strategy: ReplyChannelStrategy,
+
+    /// Reject incoming messages and requests with nonce not greater
+    /// than the one previously seen from the same sender.
+    ///
+    /// Seen nonces are persisted in the state store if it's set.
+    /// Peers must use hyperelm versions sending monotonic nonces.
+    pub replay_protection: bool,
+
+    /// Generate fresh secret key when the params are built
+    /// so compromised long-term key can't decode messages of
+    /// the previous sessions.
+    ///
+    /// Client middleware must be created with the session key
+    /// returned by `identity.secret()`, otherwise the client
+    /// refuses to start.
+    ///
+    /// Other clients can't find the current one by its long-term
+    /// public key, so it must announce its session key to them
+    /// through some known channel before use.
+    pub ephemeral_keys: bool,
+
+    /// Send messages to clients of other servers through
+    /// the connected server instead of sending them directly.
+    ///
+    /// Connected server must have messages relaying enabled.
+    pub relay_through_home: bool,
+
+    /// Address on which the connected server serves its
+    /// `/multicast` endpoint, usually its admin address.
+    ///
+    /// Multicast messages are sent one by one if not set.
+    pub multicast_address: Option<String>,
+
+    /// Deliver messages and requests sent to the current
+    /// client in-process instead of sending them through
+    /// the server. Enabled by default.
+    pub loopback: bool,
+
+    /// Retry failed server connections with exponential
+    /// backoff within given time window.
+    ///
+    /// Useful when the client is started together
+    /// with the server it connects to.
+    pub warmup_window: Option<Duration>,
+
+    /// Policy of reconnecting to the server
+    /// after connection failures.
+    pub reconnect_policy: ReconnectPolicy,
+
+    /// Peers resolved in advance on startup.
+    pub warm_up: WarmUpPolicy,
+
+    /// Params of the HTTP client.
+    ///
+    /// Applied to HTTP clients built using
+    /// the `build_http_client` method.
+    pub http_config: HttpClientConfig,
+
+    /// HTTP headers included in every request
+    /// made by the client middleware.
+    ///
+    /// Applied to client middlewares and HTTP clients built
+    /// using the `build_middleware` and `build_http_client`
+    /// methods. Middlewares created manually from other
+    /// HTTP clients don't send them.
+    pub extra_request_headers: HashMap<String, String>,
+
+    /// Interceptors called for every outgoing envelope.
+    pub send_interceptors: Vec<Arc<dyn SendInterceptor>>,
+
+    /// Interceptors called for every incoming envelope.
+    pub receive_interceptors: Vec<Arc<dyn ReceiveInterceptor>>,
+
+    /// Access control lists of the messaging channels.
+    ///
+    /// Messages from not allowed senders are dropped.
+    pub channel_acl: HashMap<Channel, ChannelAcl>,
+
+    /// Encryption of the messages sent to the channels.
+    /// Channels not listed here use pairwise encryption.
+    ///
+    /// Shared keys are shared between all the clones of the params.
+    ///
+    /// Requires `group-encryption` feature.
+    #[cfg(feature = "group-encryption")]
+    pub channel_security: HashMap<Channel, ChannelSecurity>,
+
+    /// Schema of the incoming messages and requests.
+    ///
+    /// Not matching messages are rejected.
+    pub input_envelope_schema: Option<EnvelopeSchema>,
+
+    /// Messaging groups of the current client.
+    ///
+    /// Requires `group-encryption` feature.
+    #[cfg(feature = "group-encryption")]
+    pub groups: Vec<GroupChannel>,
+
+    /// Storage of the application state.
+    pub state_store: Option<Arc<dyn StateStore>>,
+
+    /// Journal of the sent and received envelopes.
+    pub journal: Option<Arc<dyn MessageJournal>>,
+
+    /// Archive of the sent and received messages.
+    pub archive: Option<Arc<dyn MessageArchive>>,
+
+    /// Handler of the connection lifecycle events.
+    pub event_handler: Option<Arc<dyn ConnectionEventHandler>>,
+
+    /// Storage of the incoming file transfers.
+    ///
+    /// Incoming files are rejected if not set.
+    pub file_transfers: Option<Arc<FileTransfers>>,
+
+    /// Time to live of the topic subscriptions.
+    pub topic_ttl: Duration,
+
+    /// Amount of failed deliveries in a row
+    /// after which topic subscriber is removed.
+    pub topic_max_failures: u32,
+
+    /// Maximal time to live of the subscriptions
+    /// requested by other clients.
+    pub topic_max_ttl: Duration,
+
+    /// Amount of round trip time samples
+    /// stored by the latency tracker.
+    pub latency_samples: usize,
+
+    /// Maximal time the `ping` method waits
+    /// for the message to return back.
+    pub ping_timeout: Duration,
+
+    /// Interval of probing peers from the presence watch-list.
+    ///
+    /// Peers are not probed if not set.
+    pub presence_probe_interval: Option<Duration>,
+
+    /// Maximal time to wait for the response
+    /// to the presence probe.
+    pub presence_probe_timeout: Duration,
+
+    /// Peers probed with the presence probes interval.
+    pub presence_watch_list: Vec<ClientEndpoint>,
+
+    /// Maximal amount of requests waiting for a response.
+    ///
+    /// Unlimited if not set.
+    pub max_inflight_requests: Option<usize>,
+
+    /// Behavior of the `request` method when
+    /// the in-flight requests limit is reached.
+    pub overload_behavior: OverloadBehavior,
+
+    /// Limits of the outgoing messages and requests.
+    ///
+    /// Unlimited if not set.
+    pub outbound_rate: Option<OutboundRate>,
+
+    /// Maximal amount of cached responses to the
+    /// requests marked cacheable by the `ClientApp::cache_policy`.
+    pub response_cache_capacity: usize,
+
+    /// Send scheduled messages whose time has passed while
+    /// the client was offline when they're loaded from the
+    /// state store. Otherwise they're dropped.
+    pub fire_missed_scheduled: bool,
+
+    /// Amount of the recently processed incoming messages
+    /// ids remembered to suppress duplicated deliveries.
+    pub seen_messages_capacity: usize,
+
+    /// Time during which processed message id is remembered.
+    pub seen_messages_ttl: Duration,
+
+    /// Path to the file storing processed messages ids
+    /// between restarts. Ids are stored in memory only if not set.
+    pub seen_messages_path: Option<PathBuf>,
+
+    /// Time during which responses to the outgoing requests
+    /// sent by the `request_with_id` method are remembered.
+    ///
+    /// Unlike `seen_messages_*` params it deduplicates requests
+    /// of the current client, not the incoming messages.
+    pub dedup_window: Duration,
+
+    /// Reaction on the changed server address of the pinned peer.
+    pub pin_policy: PinPolicy,
+
+    /// Path to the JSON file storing pinned server addresses
+    /// of the peers. Pins are stored in memory only if not set.
+    pub pins_path: Option<PathBuf>,
+
+    /// Additional channels polled by the `poll_channels` method.
+    pub polled_channels: Vec<Channel>,
+
+    /// Interval of checking if the connected server was restarted.
+    ///
+    /// Server is not checked if not set.
+    pub restart_check_interval: Option<Duration>,
+
+    /// Reconnect to the server when its restart is detected.
+    pub auto_reconnect: bool,
+
+    /// Params of the circuit breaker failing sends
+    /// to unavailable peers' servers fast.
+    pub circuit_breaker: CircuitBreakerConfig
 }
 
 impl ClientAppParams {
@@ -32,13 +337,168 @@ impl ClientAppParams {
     pub fn builder() -> ClientAppParamsBuilder {
         ClientAppParamsBuilder::default()
     }
+
+    /// Get endpoint of the connected server.
+    #[inline]
+    pub fn server_endpoint(&self) -> ServerEndpoint {
+        ServerEndpoint::new(&self.server_address, self.server_public.clone())
+    }
+}
+
+impl std::fmt::Debug for ClientAppParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("ClientAppParams");
+
+        debug.field("identity", &self.identity)
+            .field("server_public", &self.server_public.to_base64())
+            .field("server_address", &self.server_address)
+            .field("channel", &self.channel)
+            .field("encoding", &self.encoding)
+            .field("compression_level", &self.compression_level)
+            .field("delay", &self.delay)
+            .field("reply_channel_strategy", &self.reply_channel_strategy)
+            .field("warmup_window", &self.warmup_window)
+            .field("reconnect_policy", &self.reconnect_policy)
+            .field("warm_up", &self.warm_up)
+            .field("http_config", &self.http_config)
+            .field("extra_request_headers", &self.extra_request_headers.keys().collect::<Vec<_>>())
+            .field("send_interceptors", &self.send_interceptors.len())
+            .field("receive_interceptors", &self.receive_interceptors.len())
+            .field("channel_acl", &self.channel_acl);
+
+        #[cfg(feature = "group-encryption")]
+        debug.field("channel_security", &self.channel_security)
+            .field("groups", &self.groups);
+
+        debug.field("state_store", &self.state_store)
+            .field("journal", &self.journal)
+            .field("archive", &self.archive)
+            .field("event_handler", &self.event_handler)
+            .field("file_transfers", &self.file_transfers)
+            .field("topic_ttl", &self.topic_ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+impl std::fmt::Display for ClientAppParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "client {} on channel {} via {} ({})",
+            self.identity.public().to_base64(),
+            self.channel,
+            self.server_address,
+            self.server_public.to_base64()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+#[error("Required client params are not set")]
+pub struct IncompleteClientParams;
+
+impl TryFrom<ClientAppParamsBuilder> for ClientAppParams {
+    type Error = IncompleteClientParams;
+
+    #[inline]
+    fn try_from(builder: ClientAppParamsBuilder) -> Result<Self, Self::Error> {
+        builder.build().ok_or(IncompleteClientParams)
+    }
+}
+
+impl From<ClientAppParams> for ClientAppParamsBuilder {
+    fn from(params: ClientAppParams) -> Self {
+        Self {
+            client_secret: Some(params.identity.long_term()),
+            identity_grace_period: params.identity.grace_period(),
+            server_public: Some(params.server_public),
+            server_address: Some(params.server_address),
+            channel: params.channel,
+            encoding: params.encoding,
+            compression_level: params.compression_level,
+            delay: params.delay,
+            periodic_task_interval: params.periodic_task_interval,
+            gossip_fanout: params.gossip_fanout,
+            gossip_timeout: params.gossip_timeout,
+            gossip_interval: params.gossip_interval,
+            remote_state_timeout: params.remote_state_timeout,
+            handler_concurrency: params.handler_concurrency,
+            poller_buffer_size: params.poller_buffer_size,
+            drain_timeout: params.drain_timeout,
+            expiry_grace: params.expiry_grace,
+            batch_max_items: params.batch_max_items,
+            batch_max_bytes: params.batch_max_bytes,
+            reply_channel_strategy: params.reply_channel_strategy,
+            replay_protection: params.replay_protection,
+            ephemeral_keys: params.ephemeral_keys,
+            relay_through_home: params.relay_through_home,
+            multicast_address: params.multicast_address,
+            loopback: params.loopback,
+            polled_channels: params.polled_channels,
+            warmup_window: params.warmup_window,
+            reconnect_policy: params.reconnect_policy,
+            warm_up: params.warm_up,
+            restart_check_interval: params.restart_check_interval,
+            auto_reconnect: params.auto_reconnect,
+            circuit_breaker: params.circuit_breaker,
+            http_config: params.http_config,
+            extra_request_headers: params.extra_request_headers,
+            send_interceptors: params.send_interceptors,
+            receive_interceptors: params.receive_interceptors,
+            channel_acl: params.channel_acl,
+            #[cfg(feature = "group-encryption")]
+            channel_security: params.channel_security,
+            input_envelope_schema: params.input_envelope_schema,
+            #[cfg(feature = "group-encryption")]
+            groups: params.groups,
+            state_store: params.state_store,
+            journal: params.journal,
+            archive: params.archive,
+            event_handler: params.event_handler,
+            max_file_size: params.file_transfers.as_ref()
+                .map(|transfers| transfers.max_file_size())
+                .unwrap_or(DEFAULT_MAX_FILE_SIZE),
+
+            download_dir: params.file_transfers.map(|transfers| transfers.folder().to_path_buf()),
+            topic_ttl: params.topic_ttl,
+            topic_max_failures: params.topic_max_failures,
+            topic_max_ttl: params.topic_max_ttl,
+            latency_samples: params.latency_samples,
+            ping_timeout: params.ping_timeout,
+            presence_probe_interval: params.presence_probe_interval,
+            presence_probe_timeout: params.presence_probe_timeout,
+            presence_watch_list: params.presence_watch_list,
+            max_inflight_requests: params.max_inflight_requests,
+            outbound_rate: params.outbound_rate,
+            response_cache_capacity: params.response_cache_capacity,
+            fire_missed_scheduled: params.fire_missed_scheduled,
+            overload_behavior: params.overload_behavior,
+            seen_messages_capacity: params.seen_messages_capacity,
+            seen_messages_ttl: params.seen_messages_ttl,
+            seen_messages_path: params.seen_messages_path,
+            dedup_window: params.dedup_window,
+            pin_policy: params.pin_policy,
+            pins_path: params.pins_path
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+/// Builder of the client params.
+///
+/// Runtime fields like interceptors, access control lists,
+/// group and channel keys and state store are not serialized.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct ClientAppParamsBuilder {
     /// Secret key of the current client.
+    #[cfg_attr(feature = "serde", serde(with = "crate::scaffold::serde_secret_key_option"))]
     pub client_secret: Option<SecretKey>,
 
+    /// Time during which messages encrypted to the previous
+    /// secret key are accepted after identity rotation.
+    pub identity_grace_period: Duration,
+
     /// Public key of the server to connect to.
     pub server_public: Option<PublicKey>,
 
@@ -46,7 +506,7 @@ pub struct ClientAppParamsBuilder {
     pub server_address: Option<String>,
 
     /// Messaging channel.
-    pub channel: String,
+    pub channel: Channel,
 
     /// Messages encoding format.
     pub encoding: MessageEncoding,
@@ -55,19 +515,351 @@ pub struct ClientAppParamsBuilder {
     pub compression_level: CompressionLevel,
 
     /// Messages synchronization delay.
-    pub delay: Duration
+    pub delay: Duration,
+
+    /// Interval of calling the `ClientApp::periodic_task` method
+    /// by the background updates task. Disabled if not set.
+    pub periodic_task_interval: Option<Duration>,
+
+    /// Amount of random known peers asked for
+    /// their peers by every gossip exchange.
+    pub gossip_fanout: usize,
+
+    /// Maximal time to wait for the response
+    /// of every peer of the gossip exchange.
+    pub gossip_timeout: Duration,
+
+    /// Interval of the gossip exchanges performed
+    /// by the background updates task.
+    pub gossip_interval: Duration,
+
+    /// Maximal time to wait for the remote state value.
+    pub remote_state_timeout: Duration,
+
+    /// Maximal amount of concurrently processed
+    /// incoming messages. Default is 1.
+    pub handler_concurrency: usize,
+
+    /// Maximal amount of messages in the incoming queue
+    /// after which the background poller stops fetching
+    /// new messages. Default is 1024.
+    pub poller_buffer_size: usize,
+
+    /// Maximal time spent on processing messages waiting
+    /// on the server when the client is shutting down.
+    pub drain_timeout: Duration,
+
+    /// Tolerance of the clock skew between the sender and
+    /// the receiver applied to the envelopes' expiry time.
+    /// Default is 30 seconds.
+    pub expiry_grace: Duration,
+
+    /// Maximal amount of requests packed into one batch
+    /// by the `request_batch` method. Larger batches are
+    /// rejected by the receiver. Default is 64.
+    pub batch_max_items: usize,
+
+    /// Maximal size of the serialized requests packed
+    /// into one batch. Default is 256 KiB.
+    pub batch_max_bytes: usize,
+
+    /// Channel on which responses to the sent requests are received.
+    pub reply_channel_strategy: ReplyChannelStrategy,
+
+    /// Reject incoming messages and requests with nonce not greater
+    /// than the one previously seen from the same sender.
+    ///
+    /// Seen nonces are persisted in the state store if it's set.
+    /// Peers must use hyperelm versions sending monotonic nonces.
+    pub replay_protection: bool,
+
+    /// Generate fresh secret key when the params are built
+    /// so compromised long-term key can't decode messages of
+    /// the previous sessions.
+    ///
+    /// Client middleware must be created with the session key
+    /// returned by `identity.secret()`, otherwise the client
+    /// refuses to start.
+    ///
+    /// Other clients can't find the current one by its long-term
+    /// public key, so it must announce its session key to them
+    /// through some known channel before use.
+    pub ephemeral_keys: bool,
+
+    /// Send messages to clients of other servers through
+    /// the connected server instead of sending them directly.
+    ///
+    /// Connected server must have messages relaying enabled.
+    pub relay_through_home: bool,
+
+    /// Address on which the connected server serves its
+    /// `/multicast` endpoint, usually its admin address.
+    ///
+    /// Multicast messages are sent one by one if not set.
+    pub multicast_address: Option<String>,
+
+    /// Deliver messages and requests sent to the current
+    /// client in-process instead of sending them through
+    /// the server. Enabled by default.
+    pub loopback: bool,
+
+    /// Additional channels polled by the `poll_channels` method.
+    pub polled_channels: Vec<Channel>,
+
+    /// Retry failed server connections with exponential
+    /// backoff within given time window.
+    ///
+    /// Useful when the client is started together
+    /// with the server it connects to.
+    pub warmup_window: Option<Duration>,
+
+    /// Policy of reconnecting to the server
+    /// after connection failures.
+    pub reconnect_policy: ReconnectPolicy,
+
+    /// Peers resolved in advance on startup.
+    pub warm_up: WarmUpPolicy,
+
+    /// Interval of checking if the connected server was restarted.
+    ///
+    /// Server is not checked if not set.
+    pub restart_check_interval: Option<Duration>,
+
+    /// Reconnect to the server when its restart is detected.
+    pub auto_reconnect: bool,
+
+    /// Params of the circuit breaker failing sends
+    /// to unavailable peers' servers fast.
+    pub circuit_breaker: CircuitBreakerConfig,
+
+    /// Params of the HTTP client.
+    pub http_config: HttpClientConfig,
+
+    /// HTTP headers included in every request
+    /// made by the client middleware.
+    pub extra_request_headers: HashMap<String, String>,
+
+    /// Interceptors called for every outgoing envelope.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub send_interceptors: Vec<Arc<dyn SendInterceptor>>,
+
+    /// Interceptors called for every incoming envelope.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub receive_interceptors: Vec<Arc<dyn ReceiveInterceptor>>,
+
+    /// Access control lists of the messaging channels.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub channel_acl: HashMap<Channel, ChannelAcl>,
+
+    /// Encryption of the messages sent to the channels.
+    #[cfg(feature = "group-encryption")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub channel_security: HashMap<Channel, ChannelSecurity>,
+
+    /// Schema of the incoming messages and requests.
+    pub input_envelope_schema: Option<EnvelopeSchema>,
+
+    /// Messaging groups of the current client.
+    #[cfg(feature = "group-encryption")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub groups: Vec<GroupChannel>,
+
+    /// Storage of the application state.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub state_store: Option<Arc<dyn StateStore>>,
+
+    /// Journal of the sent and received envelopes.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub journal: Option<Arc<dyn MessageJournal>>,
+
+    /// Archive of the sent and received messages.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub archive: Option<Arc<dyn MessageArchive>>,
+
+    /// Handler of the connection lifecycle events.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub event_handler: Option<Arc<dyn ConnectionEventHandler>>,
+
+    /// Folder where received files are stored.
+    ///
+    /// Incoming files are rejected if not set.
+    pub download_dir: Option<PathBuf>,
+
+    /// Maximal size of the received file in bytes.
+    ///
+    /// Larger files are rejected without
+    /// calling `ClientApp::accept_file_offer`.
+    pub max_file_size: u64,
+
+    /// Time to live of the topic subscriptions.
+    pub topic_ttl: Duration,
+
+    /// Amount of failed deliveries in a row
+    /// after which topic subscriber is removed.
+    pub topic_max_failures: u32,
+
+    /// Maximal time to live of the subscriptions
+    /// requested by other clients.
+    pub topic_max_ttl: Duration,
+
+    /// Amount of round trip time samples
+    /// stored by the latency tracker.
+    pub latency_samples: usize,
+
+    /// Maximal time the `ping` method waits
+    /// for the message to return back.
+    pub ping_timeout: Duration,
+
+    /// Interval of probing peers from the presence watch-list.
+    ///
+    /// Peers are not probed if not set.
+    pub presence_probe_interval: Option<Duration>,
+
+    /// Maximal time to wait for the response
+    /// to the presence probe.
+    pub presence_probe_timeout: Duration,
+
+    /// Peers probed with the presence probes interval.
+    pub presence_watch_list: Vec<ClientEndpoint>,
+
+    /// Maximal amount of requests waiting for a response.
+    ///
+    /// Unlimited if not set.
+    pub max_inflight_requests: Option<usize>,
+
+    /// Behavior of the `request` method when
+    /// the in-flight requests limit is reached.
+    pub overload_behavior: OverloadBehavior,
+
+    /// Limits of the outgoing messages and requests.
+    ///
+    /// Unlimited if not set.
+    pub outbound_rate: Option<OutboundRate>,
+
+    /// Maximal amount of cached responses to the
+    /// requests marked cacheable by the `ClientApp::cache_policy`.
+    pub response_cache_capacity: usize,
+
+    /// Send scheduled messages whose time has passed while
+    /// the client was offline when they're loaded from the
+    /// state store. Otherwise they're dropped. Default is true.
+    pub fire_missed_scheduled: bool,
+
+    /// Amount of the recently processed incoming messages
+    /// ids remembered to suppress duplicated deliveries.
+    pub seen_messages_capacity: usize,
+
+    /// Time during which processed message id is remembered.
+    pub seen_messages_ttl: Duration,
+
+    /// Path to the file storing processed messages ids
+    /// between restarts. Ids are stored in memory only if not set.
+    pub seen_messages_path: Option<PathBuf>,
+
+    /// Time during which responses to the outgoing requests
+    /// sent by the `request_with_id` method are remembered.
+    ///
+    /// Unlike `seen_messages_*` params it deduplicates requests
+    /// of the current client, not the incoming messages.
+    pub dedup_window: Duration,
+
+    /// Reaction on the changed server address of the pinned peer.
+    pub pin_policy: PinPolicy,
+
+    /// Path to the JSON file storing pinned server addresses
+    /// of the peers. Pins are stored in memory only if not set.
+    pub pins_path: Option<PathBuf>
+}
+
+impl std::fmt::Debug for ClientAppParamsBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientAppParamsBuilder")
+            .field("client_public", &self.client_secret.as_ref().map(|secret| secret.public().to_base64()))
+            .field("server_public", &self.server_public.as_ref().map(PublicKey::to_base64))
+            .field("server_address", &self.server_address)
+            .field("channel", &self.channel)
+            .field("encoding", &self.encoding)
+            .field("compression_level", &self.compression_level)
+            .field("delay", &self.delay)
+            .field("reply_channel_strategy", &self.reply_channel_strategy)
+            .field("polled_channels", &self.polled_channels)
+            .field("http_config", &self.http_config)
+            .field("download_dir", &self.download_dir)
+            .field("max_file_size", &self.max_file_size)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Default for ClientAppParamsBuilder {
     fn default() -> Self {
         Self {
             client_secret: None,
+            identity_grace_period: Duration::from_secs(60 * 60),
             server_public: None,
             server_address: None,
-            channel: String::from("hyperelm"),
+            channel: Channel::default(),
             encoding: MessageEncoding::default(),
             compression_level: CompressionLevel::default(),
-            delay: Duration::from_secs(1)
+            delay: Duration::from_secs(1),
+            periodic_task_interval: None,
+            gossip_fanout: 3,
+            gossip_timeout: Duration::from_secs(5),
+            gossip_interval: Duration::from_secs(5 * 60),
+            remote_state_timeout: Duration::from_secs(10),
+            handler_concurrency: 1,
+            poller_buffer_size: 1024,
+            drain_timeout: Duration::from_secs(5),
+            expiry_grace: Duration::from_secs(30),
+            batch_max_items: 64,
+            batch_max_bytes: 256 * 1024,
+            reply_channel_strategy: ReplyChannelStrategy::default(),
+            replay_protection: false,
+            ephemeral_keys: false,
+            relay_through_home: false,
+            multicast_address: None,
+            loopback: true,
+            polled_channels: Vec::new(),
+            warmup_window: None,
+            reconnect_policy: ReconnectPolicy::default(),
+            warm_up: WarmUpPolicy::default(),
+            restart_check_interval: Some(Duration::from_secs(60)),
+            auto_reconnect: true,
+            circuit_breaker: CircuitBreakerConfig::default(),
+            http_config: HttpClientConfig::default(),
+            extra_request_headers: HashMap::new(),
+            send_interceptors: Vec::new(),
+            receive_interceptors: Vec::new(),
+            channel_acl: HashMap::new(),
+            #[cfg(feature = "group-encryption")]
+            channel_security: HashMap::new(),
+            input_envelope_schema: None,
+            #[cfg(feature = "group-encryption")]
+            groups: Vec::new(),
+            state_store: None,
+            journal: None,
+            archive: None,
+            event_handler: None,
+            download_dir: None,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            topic_ttl: Duration::from_secs(60 * 5),
+            topic_max_failures: 3,
+            topic_max_ttl: DEFAULT_TOPIC_MAX_TTL,
+            latency_samples: 128,
+            ping_timeout: Duration::from_secs(5),
+            presence_probe_interval: None,
+            presence_probe_timeout: Duration::from_secs(5),
+            presence_watch_list: Vec::new(),
+            max_inflight_requests: None,
+            outbound_rate: None,
+            response_cache_capacity: 256,
+            fire_missed_scheduled: true,
+            overload_behavior: OverloadBehavior::default(),
+            seen_messages_capacity: 4096,
+            seen_messages_ttl: Duration::from_secs(60 * 10),
+            seen_messages_path: None,
+            dedup_window: Duration::from_secs(60 * 5),
+            pin_policy: PinPolicy::default(),
+            pins_path: None
         }
     }
 }
@@ -79,6 +871,12 @@ impl ClientAppParamsBuilder {
         self
     }
 
+    pub fn identity_grace_period(mut self, grace_period: Duration) -> Self {
+        self.identity_grace_period = grace_period;
+
+        self
+    }
+
     pub fn server(mut self, public_key: PublicKey, address: impl ToString) -> Self {
         self.server_public = Some(public_key);
         self.server_address = Some(address.to_string());
@@ -86,8 +884,8 @@ impl ClientAppParamsBuilder {
         self
     }
 
-    pub fn channel(mut self, channel: impl ToString) -> Self {
-        self.channel = channel.to_string();
+    pub fn channel(mut self, channel: Channel) -> Self {
+        self.channel = channel;
 
         self
     }
@@ -110,15 +908,447 @@ impl ClientAppParamsBuilder {
         self
     }
 
+    pub fn periodic_task_interval(mut self, interval: Duration) -> Self {
+        self.periodic_task_interval = Some(interval);
+
+        self
+    }
+
+    pub fn gossip_fanout(mut self, fanout: usize) -> Self {
+        self.gossip_fanout = fanout;
+
+        self
+    }
+
+    pub fn gossip_timeout(mut self, timeout: Duration) -> Self {
+        self.gossip_timeout = timeout;
+
+        self
+    }
+
+    pub fn gossip_interval(mut self, interval: Duration) -> Self {
+        self.gossip_interval = interval;
+
+        self
+    }
+
+    pub fn remote_state_timeout(mut self, timeout: Duration) -> Self {
+        self.remote_state_timeout = timeout;
+
+        self
+    }
+
+    pub fn poller_buffer_size(mut self, size: usize) -> Self {
+        self.poller_buffer_size = size;
+
+        self
+    }
+
+    pub fn drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = timeout;
+
+        self
+    }
+
+    pub fn expiry_grace(mut self, grace: Duration) -> Self {
+        self.expiry_grace = grace;
+
+        self
+    }
+
+    pub fn batch_max_items(mut self, max: usize) -> Self {
+        self.batch_max_items = max.max(1);
+
+        self
+    }
+
+    pub fn batch_max_bytes(mut self, max: usize) -> Self {
+        self.batch_max_bytes = max;
+
+        self
+    }
+
+    pub fn relay_through_home(mut self, enabled: bool) -> Self {
+        self.relay_through_home = enabled;
+
+        self
+    }
+
+    pub fn multicast_address(mut self, address: impl ToString) -> Self {
+        self.multicast_address = Some(address.to_string());
+
+        self
+    }
+
+    pub fn loopback(mut self, enabled: bool) -> Self {
+        self.loopback = enabled;
+
+        self
+    }
+
+    pub fn handler_concurrency(mut self, concurrency: usize) -> Self {
+        self.handler_concurrency = concurrency;
+
+        self
+    }
+
+    pub fn reply_channel_strategy(mut self, strategy: ReplyChannelStrategy) -> Self {
+        self.reply_channel_strategy = strategy;
+
+        self
+    }
+
+    pub fn replay_protection(mut self, enabled: bool) -> Self {
+        self.replay_protection = enabled;
+
+        self
+    }
+
+    pub fn ephemeral_keys(mut self, enabled: bool) -> Self {
+        self.ephemeral_keys = enabled;
+
+        self
+    }
+
+    pub fn poll_channel(mut self, channel: Channel) -> Self {
+        self.polled_channels.push(channel);
+
+        self
+    }
+
+    pub fn warmup_window(mut self, window: Duration) -> Self {
+        self.warmup_window = Some(window);
+
+        self
+    }
+
+    pub fn reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect_policy = policy;
+
+        self
+    }
+
+    pub fn warm_up(mut self, policy: WarmUpPolicy) -> Self {
+        self.warm_up = policy;
+
+        self
+    }
+
+    pub fn restart_check_interval(mut self, interval: Option<Duration>) -> Self {
+        self.restart_check_interval = interval;
+
+        self
+    }
+
+    pub fn auto_reconnect(mut self, enabled: bool) -> Self {
+        self.auto_reconnect = enabled;
+
+        self
+    }
+
+    pub fn circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = config;
+
+        self
+    }
+
+    pub fn http_config(mut self, config: HttpClientConfig) -> Self {
+        self.http_config = config;
+
+        self
+    }
+
+    /// Accept invalid and self-signed TLS certificates
+    /// of the servers. Intended for development only.
+    pub fn accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.http_config.accept_invalid_certs = accept;
+
+        self
+    }
+
+    /// Connect to the servers over HTTPS.
+    pub fn https(mut self, https: bool) -> Self {
+        self.http_config.https = https;
+
+        self
+    }
+
+    pub fn header(mut self, name: impl ToString, value: impl ToString) -> Self {
+        self.extra_request_headers.insert(name.to_string(), value.to_string());
+
+        self
+    }
+
+    pub fn send_interceptor(mut self, interceptor: impl SendInterceptor + 'static) -> Self {
+        self.send_interceptors.push(Arc::new(interceptor));
+
+        self
+    }
+
+    pub fn receive_interceptor(mut self, interceptor: impl ReceiveInterceptor + 'static) -> Self {
+        self.receive_interceptors.push(Arc::new(interceptor));
+
+        self
+    }
+
+    pub fn channel_acl(mut self, channel: Channel, acl: ChannelAcl) -> Self {
+        self.channel_acl.insert(channel, acl);
+
+        self
+    }
+
+#[cfg(feature = "group-encryption")]
+    pub fn channel_security(mut self, channel: Channel, security: ChannelSecurity) -> Self {
+        self.channel_security.insert(channel, security);
+
+        self
+    }
+
+    pub fn input_envelope_schema(mut self, schema: EnvelopeSchema) -> Self {
+        self.input_envelope_schema = Some(schema);
+
+        self
+    }
+
+#[cfg(feature = "group-encryption")]
+    pub fn group(mut self, group: GroupChannel) -> Self {
+        self.groups.push(group);
+
+        self
+    }
+
+    pub fn state_store(mut self, store: impl StateStore + 'static) -> Self {
+        self.state_store = Some(Arc::new(store));
+
+        self
+    }
+
+    pub fn journal(mut self, journal: impl MessageJournal + 'static) -> Self {
+        self.journal = Some(Arc::new(journal));
+
+        self
+    }
+
+    pub fn archive(mut self, archive: impl MessageArchive + 'static) -> Self {
+        self.archive = Some(Arc::new(archive));
+
+        self
+    }
+
+    pub fn event_handler(mut self, handler: impl ConnectionEventHandler + 'static) -> Self {
+        self.event_handler = Some(Arc::new(handler));
+
+        self
+    }
+
+    pub fn download_dir(mut self, folder: impl Into<PathBuf>) -> Self {
+        self.download_dir = Some(folder.into());
+
+        self
+    }
+
+    pub fn max_file_size(mut self, size: u64) -> Self {
+        self.max_file_size = size;
+
+        self
+    }
+
+    pub fn topic_ttl(mut self, ttl: Duration) -> Self {
+        self.topic_ttl = ttl;
+
+        self
+    }
+
+    pub fn topic_max_failures(mut self, max_failures: u32) -> Self {
+        self.topic_max_failures = max_failures;
+
+        self
+    }
+
+    pub fn topic_max_ttl(mut self, ttl: Duration) -> Self {
+        self.topic_max_ttl = ttl;
+
+        self
+    }
+
+    pub fn latency_samples(mut self, samples: usize) -> Self {
+        self.latency_samples = samples;
+
+        self
+    }
+
+    pub fn ping_timeout(mut self, timeout: Duration) -> Self {
+        self.ping_timeout = timeout;
+
+        self
+    }
+
+    pub fn presence_probe_interval(mut self, interval: Duration) -> Self {
+        self.presence_probe_interval = Some(interval);
+
+        self
+    }
+
+    pub fn presence_probe_timeout(mut self, timeout: Duration) -> Self {
+        self.presence_probe_timeout = timeout;
+
+        self
+    }
+
+    pub fn watch_peer(mut self, endpoint: ClientEndpoint) -> Self {
+        self.presence_watch_list.push(endpoint);
+
+        self
+    }
+
+    pub fn max_inflight_requests(mut self, max: usize) -> Self {
+        self.max_inflight_requests = Some(max);
+
+        self
+    }
+
+    pub fn outbound_rate(mut self, rate: OutboundRate) -> Self {
+        self.outbound_rate = Some(rate);
+
+        self
+    }
+
+    pub fn response_cache_capacity(mut self, capacity: usize) -> Self {
+        self.response_cache_capacity = capacity;
+
+        self
+    }
+
+    pub fn fire_missed_scheduled(mut self, enabled: bool) -> Self {
+        self.fire_missed_scheduled = enabled;
+
+        self
+    }
+
+    pub fn overload_behavior(mut self, behavior: OverloadBehavior) -> Self {
+        self.overload_behavior = behavior;
+
+        self
+    }
+
+    pub fn seen_messages_capacity(mut self, capacity: usize) -> Self {
+        self.seen_messages_capacity = capacity;
+
+        self
+    }
+
+    pub fn seen_messages_ttl(mut self, ttl: Duration) -> Self {
+        self.seen_messages_ttl = ttl;
+
+        self
+    }
+
+    pub fn seen_messages_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.seen_messages_path = Some(path.into());
+
+        self
+    }
+
+    pub fn dedup_window(mut self, window: Duration) -> Self {
+        self.dedup_window = window;
+
+        self
+    }
+
+    pub fn pin_policy(mut self, policy: PinPolicy) -> Self {
+        self.pin_policy = policy;
+
+        self
+    }
+
+    pub fn pins_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.pins_path = Some(path.into());
+
+        self
+    }
+
+    /// Build client params.
+    ///
+    /// Returns `None` if required params are not set.
     pub fn build(self) -> Option<ClientAppParams> {
+        let identity = ClientIdentity::new(
+            self.client_secret?,
+            self.identity_grace_period
+        );
+
+        // Generate session key before the client middleware is built
+        if self.ephemeral_keys {
+            identity.start_session();
+        }
+
         Some(ClientAppParams {
-            client_secret: self.client_secret?,
+            identity: Arc::new(identity),
             server_public: self.server_public?,
             server_address: self.server_address?,
             channel: self.channel,
             encoding: self.encoding,
             compression_level: self.compression_level,
-            delay: self.delay
+            delay: self.delay,
+            periodic_task_interval: self.periodic_task_interval,
+            gossip_fanout: self.gossip_fanout,
+            gossip_timeout: self.gossip_timeout,
+            gossip_interval: self.gossip_interval,
+            remote_state_timeout: self.remote_state_timeout,
+            handler_concurrency: self.handler_concurrency,
+            poller_buffer_size: self.poller_buffer_size,
+            drain_timeout: self.drain_timeout,
+            expiry_grace: self.expiry_grace,
+            batch_max_items: self.batch_max_items,
+            batch_max_bytes: self.batch_max_bytes,
+            reply_channel_strategy: self.reply_channel_strategy,
+            replay_protection: self.replay_protection,
+            ephemeral_keys: self.ephemeral_keys,
+            relay_through_home: self.relay_through_home,
+            multicast_address: self.multicast_address,
+            loopback: self.loopback,
+            warmup_window: self.warmup_window,
+            reconnect_policy: self.reconnect_policy,
+            warm_up: self.warm_up,
+            http_config: self.http_config,
+            extra_request_headers: self.extra_request_headers,
+            send_interceptors: self.send_interceptors,
+            receive_interceptors: self.receive_interceptors,
+            channel_acl: self.channel_acl,
+            #[cfg(feature = "group-encryption")]
+            channel_security: self.channel_security,
+            input_envelope_schema: self.input_envelope_schema,
+            #[cfg(feature = "group-encryption")]
+            groups: self.groups,
+            state_store: self.state_store,
+            journal: self.journal,
+            archive: self.archive,
+            event_handler: self.event_handler,
+            file_transfers: self.download_dir.map(|folder| {
+                Arc::new(FileTransfers::new(folder).with_max_file_size(self.max_file_size))
+            }),
+            topic_ttl: self.topic_ttl,
+            topic_max_failures: self.topic_max_failures,
+            topic_max_ttl: self.topic_max_ttl,
+            latency_samples: self.latency_samples,
+            ping_timeout: self.ping_timeout,
+            presence_probe_interval: self.presence_probe_interval,
+            presence_probe_timeout: self.presence_probe_timeout,
+            presence_watch_list: self.presence_watch_list,
+            max_inflight_requests: self.max_inflight_requests,
+            overload_behavior: self.overload_behavior,
+            outbound_rate: self.outbound_rate,
+            response_cache_capacity: self.response_cache_capacity,
+            fire_missed_scheduled: self.fire_missed_scheduled,
+            seen_messages_capacity: self.seen_messages_capacity,
+            seen_messages_ttl: self.seen_messages_ttl,
+            seen_messages_path: self.seen_messages_path,
+            dedup_window: self.dedup_window,
+            pin_policy: self.pin_policy,
+            pins_path: self.pins_path,
+            polled_channels: self.polled_channels,
+            restart_check_interval: self.restart_check_interval,
+            auto_reconnect: self.auto_reconnect,
+            circuit_breaker: self.circuit_breaker
         })
     }
 }
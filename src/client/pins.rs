@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value as Json};
+
+use hyperborealib::crypto::prelude::*;
+
+/// Reaction on the changed server address of the pinned peer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PinPolicy {
+    /// Don't record or check peers' addresses.
+    Off,
+
+    /// Call the `on_endpoint_changed` hook
+    /// and use the new address.
+    #[default]
+    Warn,
+
+    /// Fail the lookup with `ClientAppError::EndpointChanged`.
+    Strict
+}
+
+/// Server address of the peer known from the first lookup.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PeerPin {
+    pub server_address: String,
+
+    /// UTC timestamp of the first time the peer was seen.
+    pub first_seen_at: u64
+}
+
+/// Result of checking the resolved peer address.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PinCheck {
+    /// Peer wasn't pinned before and is pinned now.
+    New,
+
+    /// Resolved address matches the pinned one.
+    Unchanged,
+
+    /// Resolved address differs from the pinned one.
+    Changed {
+        previous: String
+    }
+}
+
+/// Trust-on-first-use store of the peers' server addresses.
+///
+/// Address resolved by the first lookup of the peer is
+/// pinned, and following lookups are compared with it.
+/// When persistence is enabled pins are stored in a JSON file.
+///
+/// ```rust
+/// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+/// use hyperelm::client::{PeerPinStore, PinCheck};
+///
+/// let peer = SecretKey::random().public();
+/// let pins = PeerPinStore::new();
+///
+/// assert_eq!(pins.check(&peer, "127.0.0.1:8001"), PinCheck::New);
+/// assert_eq!(pins.check(&peer, "127.0.0.1:8001"), PinCheck::Unchanged);
+///
+/// assert_eq!(pins.check(&peer, "127.0.0.1:8002"), PinCheck::Changed {
+///     previous: String::from("127.0.0.1:8001")
+/// });
+///
+/// pins.pin(peer.clone(), "127.0.0.1:8002").unwrap();
+///
+/// assert_eq!(pins.check(&peer, "127.0.0.1:8002"), PinCheck::Unchanged);
+/// assert!(pins.unpin(&peer).unwrap());
+/// assert!(pins.list().is_empty());
+/// ```
+#[derive(Debug, Default)]
+pub struct PeerPinStore {
+    pins: RwLock<HashMap<PublicKey, PeerPin>>,
+    path: Option<PathBuf>
+}
+
+impl PeerPinStore {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store pins in the given JSON file.
+    ///
+    /// Pins are loaded from the file if it already exists.
+    pub fn with_persistence(mut self, path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+
+        if path.exists() {
+            let pins = serde_json::from_slice::<Json>(&std::fs::read(&path)?)?;
+
+            if let (Some(pins), Ok(mut loaded)) = (pins.as_object(), self.pins.write()) {
+                for (public_key, pin) in pins {
+                    let Ok(public_key) = PublicKey::from_base64(public_key) else {
+                        continue;
+                    };
+
+                    let (Some(server_address), Some(first_seen_at)) = (
+                        pin.get("server_address").and_then(Json::as_str),
+                        pin.get("first_seen_at").and_then(Json::as_u64)
+                    ) else {
+                        continue;
+                    };
+
+                    loaded.insert(public_key, PeerPin {
+                        server_address: server_address.to_string(),
+                        first_seen_at
+                    });
+                }
+            }
+        }
+
+        self.path = Some(path);
+
+        Ok(self)
+    }
+
+    #[inline]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn get(&self, public_key: &PublicKey) -> Option<PeerPin> {
+        self.pins.read().ok()?
+            .get(public_key)
+            .cloned()
+    }
+
+    /// Pin server address of the peer, replacing the previous one.
+    pub fn pin(&self, public_key: PublicKey, server_address: impl ToString) -> std::io::Result<()> {
+        if let Ok(mut pins) = self.pins.write() {
+            pins.insert(public_key, PeerPin {
+                server_address: server_address.to_string(),
+                first_seen_at: timestamp()
+            });
+        }
+
+        self.save()
+    }
+
+    /// Forget pinned address of the peer.
+    ///
+    /// Returns `false` if the peer wasn't pinned.
+    pub fn unpin(&self, public_key: &PublicKey) -> std::io::Result<bool> {
+        let removed = self.pins.write()
+            .map(|mut pins| pins.remove(public_key).is_some())
+            .unwrap_or(false);
+
+        if removed {
+            self.save()?;
+        }
+
+        Ok(removed)
+    }
+
+    /// List all the pinned peers.
+    pub fn list(&self) -> Vec<(PublicKey, PeerPin)> {
+        self.pins.read()
+            .map(|pins| {
+                pins.iter()
+                    .map(|(public_key, pin)| (public_key.clone(), pin.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Compare resolved server address of the peer
+    /// with the pinned one, pinning unknown peers.
+    ///
+    /// Changed addresses are not re-pinned.
+    pub fn check(&self, public_key: &PublicKey, server_address: &str) -> PinCheck {
+        match self.get(public_key) {
+            Some(pin) if pin.server_address == server_address => PinCheck::Unchanged,
+
+            Some(pin) => PinCheck::Changed {
+                previous: pin.server_address
+            },
+
+            None => {
+                if let Err(_err) = self.pin(public_key.clone(), server_address) {
+                    #[cfg(feature = "tracing")]
+                    tracing::error!("[client] Failed to save peer pins: {_err}");
+                }
+
+                PinCheck::New
+            }
+        }
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let pins = self.list().into_iter()
+            .map(|(public_key, pin)| {
+                (public_key.to_base64(), json!({
+                    "server_address": pin.server_address,
+                    "first_seen_at": pin.first_seen_at
+                }))
+            })
+            .collect::<serde_json::Map<_, _>>();
+
+        std::fs::write(path, serde_json::to_vec_pretty(&pins)?)
+    }
+}
+
+#[inline]
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -0,0 +1,83 @@
+use std::collections::BTreeSet;
+use std::sync::RwLock;
+
+use super::Channel;
+
+/// Set of the additional channels polled by the client.
+///
+/// Used by gateway-style clients which receive messages
+/// on many channels sharing the same prefix, e.g. `tenant/<id>`.
+/// Channels can be registered and unregistered at runtime
+/// from any thread.
+///
+/// ```rust
+/// use hyperelm::client::{Channel, PolledChannels};
+///
+/// let channels = PolledChannels::default();
+///
+/// channels.register(Channel::new("tenant/1").unwrap());
+/// channels.register(Channel::new("tenant/2").unwrap());
+/// channels.register(Channel::new("admin").unwrap());
+///
+/// assert_eq!(channels.matching("tenant/").len(), 2);
+///
+/// channels.unregister(&Channel::new("tenant/1").unwrap());
+///
+/// assert_eq!(channels.matching("tenant/"), vec![Channel::new("tenant/2").unwrap()]);
+/// ```
+#[derive(Debug, Default)]
+pub struct PolledChannels {
+    channels: RwLock<BTreeSet<Channel>>
+}
+
+impl PolledChannels {
+    pub fn new(channels: impl IntoIterator<Item = Channel>) -> Self {
+        Self {
+            channels: RwLock::new(channels.into_iter().collect())
+        }
+    }
+
+    /// Register new channel.
+    ///
+    /// Returns `false` if the channel was already registered.
+    pub fn register(&self, channel: Channel) -> bool {
+        self.channels.write()
+            .map(|mut channels| channels.insert(channel))
+            .unwrap_or_default()
+    }
+
+    /// Unregister the channel.
+    ///
+    /// Returns `false` if the channel wasn't registered.
+    pub fn unregister(&self, channel: &Channel) -> bool {
+        self.channels.write()
+            .map(|mut channels| channels.remove(channel))
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn contains(&self, channel: &Channel) -> bool {
+        self.channels.read()
+            .map(|channels| channels.contains(channel))
+            .unwrap_or_default()
+    }
+
+    /// Get sorted list of the registered channels
+    /// starting with the given prefix.
+    pub fn matching(&self, prefix: &str) -> Vec<Channel> {
+        self.channels.read()
+            .map(|channels| {
+                channels.iter()
+                    .filter(|channel| channel.as_str().starts_with(prefix))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get sorted list of all the registered channels.
+    #[inline]
+    pub fn list(&self) -> Vec<Channel> {
+        self.matching("")
+    }
+}
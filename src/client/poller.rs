@@ -0,0 +1,109 @@
+use std::sync::{Arc, Mutex};
+
+use hyperborealib::exports::tokio;
+
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+
+use hyperborealib::rest_api::prelude::*;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PollerState {
+    #[default]
+    Running,
+    Paused,
+    Stopped
+}
+
+/// Handle to the background poller task
+/// started by the `ClientApp::spawn_poller` method.
+#[derive(Debug)]
+pub struct PollerHandle {
+    state: watch::Sender<PollerState>,
+    subscribers: Arc<Mutex<Vec<mpsc::Sender<MessageInfo>>>>,
+    task: JoinHandle<()>
+}
+
+impl PollerHandle {
+    pub(crate) fn new(
+        state: watch::Sender<PollerState>,
+        subscribers: Arc<Mutex<Vec<mpsc::Sender<MessageInfo>>>>,
+        task: JoinHandle<()>
+    ) -> Self {
+        Self {
+            state,
+            subscribers,
+            task
+        }
+    }
+
+    #[inline]
+    pub fn state(&self) -> PollerState {
+        *self.state.borrow()
+    }
+
+    /// Stop fetching and processing messages
+    /// until the poller is resumed.
+    ///
+    /// Message which is being processed now is not interrupted.
+    pub fn pause(&self) {
+        self.state.send_if_modified(|state| {
+            let modified = *state == PollerState::Running;
+
+            if modified {
+                *state = PollerState::Paused;
+            }
+
+            modified
+        });
+    }
+
+    pub fn resume(&self) {
+        self.state.send_if_modified(|state| {
+            let modified = *state == PollerState::Paused;
+
+            if modified {
+                *state = PollerState::Running;
+            }
+
+            modified
+        });
+    }
+
+    /// Stop the poller task.
+    ///
+    /// Fetched but not yet processed messages
+    /// are kept in the incoming queue.
+    #[inline]
+    pub fn stop(&self) {
+        self.state.send_replace(PollerState::Stopped);
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Receive every incoming message before it's processed.
+    ///
+    /// Messages are dropped for the subscriber
+    /// if its buffer of given size is full.
+    pub fn subscribe(&self, buffer: usize) -> mpsc::Receiver<MessageInfo> {
+        let (sender, receiver) = mpsc::channel(buffer.max(1));
+
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(sender);
+        }
+
+        receiver
+    }
+}
+
+/// Pass message to the poller subscribers, forgetting closed ones.
+pub(crate) fn notify_subscribers(subscribers: &Mutex<Vec<mpsc::Sender<MessageInfo>>>, message: &MessageInfo) {
+    if let Ok(mut subscribers) = subscribers.lock() {
+        subscribers.retain(|subscriber| {
+            !matches!(subscriber.try_send(message.clone()), Err(mpsc::error::TrySendError::Closed(_)))
+        });
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use hyperborealib::exports::tokio;
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use super::*;
+
+/// Presence of the peer at the moment of taking the snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PeerPresence {
+    /// Base64 encoded public key of the peer.
+    pub public_key: String,
+
+    /// Seconds since the peer was seen last time.
+    ///
+    /// Not set if the peer was never seen.
+    pub last_seen_secs_ago: Option<u64>,
+
+    /// Whether the peer is in the probes watch-list.
+    pub watched: bool
+}
+
+/// Registry of the peers last seen time.
+///
+/// Peers are seen when a message is received from them
+/// or a request sent to them is completed. Peers from
+/// the watch-list are actively probed by the client's
+/// run loop if the probes interval is set.
+///
+/// ```rust
+/// use std::time::{Duration, Instant};
+///
+/// use hyperelm::client::PresenceTracker;
+///
+/// use hyperborealib::crypto::SecretKey;
+///
+/// let peer = SecretKey::random().public();
+/// let presence = PresenceTracker::default();
+///
+/// presence.seen(&peer);
+///
+/// assert!(presence.is_recently_seen(&peer, Duration::from_secs(5)));
+///
+/// // Simulate message received a minute ago
+/// presence.seen_at(&peer, Instant::now() - Duration::from_secs(60));
+///
+/// assert!(!presence.is_recently_seen(&peer, Duration::from_secs(5)));
+/// ```
+#[derive(Debug, Default)]
+pub struct PresenceTracker {
+    last_seen: Mutex<HashMap<PublicKey, Instant>>,
+    watch_list: Mutex<HashMap<PublicKey, ClientEndpoint>>,
+    probe_interval: Option<Duration>,
+    last_probe: Mutex<Option<Instant>>
+}
+
+impl PresenceTracker {
+    /// Create new tracker probing watched peers with given interval.
+    ///
+    /// Peers are not probed if the interval is not set.
+    pub fn new(probe_interval: Option<Duration>) -> Self {
+        Self {
+            probe_interval,
+            ..Self::default()
+        }
+    }
+
+    #[inline]
+    pub fn probe_interval(&self) -> Option<Duration> {
+        self.probe_interval
+    }
+
+    /// Mark peer as seen now.
+    #[inline]
+    pub fn seen(&self, public_key: &PublicKey) {
+        self.seen_at(public_key, Instant::now());
+    }
+
+    /// Mark peer as seen at given time.
+    pub fn seen_at(&self, public_key: &PublicKey, time: Instant) {
+        if let Ok(mut last_seen) = self.last_seen.lock() {
+            last_seen.insert(public_key.clone(), time);
+        }
+    }
+
+    /// Get time when the peer was seen last time.
+    pub fn last_seen(&self, public_key: &PublicKey) -> Option<Instant> {
+        self.last_seen.lock().ok()?
+            .get(public_key)
+            .copied()
+    }
+
+    /// Check if the peer was seen within given time.
+    pub fn is_recently_seen(&self, public_key: &PublicKey, within: Duration) -> bool {
+        self.last_seen(public_key)
+            .map(|time| time.elapsed() <= within)
+            .unwrap_or(false)
+    }
+
+    /// Forget peer's last seen time.
+    pub fn forget(&self, public_key: &PublicKey) {
+        if let Ok(mut last_seen) = self.last_seen.lock() {
+            last_seen.remove(public_key);
+        }
+    }
+
+    /// Add peer to the probes watch-list.
+    pub fn watch(&self, endpoint: ClientEndpoint) {
+        if let Ok(mut watch_list) = self.watch_list.lock() {
+            watch_list.insert(endpoint.client_public.clone(), endpoint);
+        }
+    }
+
+    /// Remove peer from the probes watch-list.
+    pub fn unwatch(&self, public_key: &PublicKey) {
+        if let Ok(mut watch_list) = self.watch_list.lock() {
+            watch_list.remove(public_key);
+        }
+    }
+
+    /// Get peers from the probes watch-list.
+    pub fn watch_list(&self) -> Vec<ClientEndpoint> {
+        self.watch_list.lock()
+            .map(|watch_list| watch_list.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Get watched peers if it's time to probe them.
+    ///
+    /// Returns empty list if the probes interval is not set
+    /// or not elapsed since the previous call.
+    pub fn due_probes(&self) -> Vec<ClientEndpoint> {
+        let Some(interval) = self.probe_interval else {
+            return vec![];
+        };
+
+        let Ok(mut last_probe) = self.last_probe.lock() else {
+            return vec![];
+        };
+
+        if last_probe.is_some_and(|time| time.elapsed() < interval) {
+            return vec![];
+        }
+
+        *last_probe = Some(Instant::now());
+
+        drop(last_probe);
+
+        self.watch_list()
+    }
+
+    /// Get presence of all the known peers.
+    pub fn snapshot(&self) -> Vec<PeerPresence> {
+        let last_seen = self.last_seen.lock()
+            .map(|last_seen| last_seen.clone())
+            .unwrap_or_default();
+
+        let watch_list = self.watch_list.lock()
+            .map(|watch_list| watch_list.keys().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        let mut snapshot = last_seen.iter()
+            .map(|(public_key, time)| PeerPresence {
+                public_key: public_key.to_base64(),
+                last_seen_secs_ago: Some(time.elapsed().as_secs()),
+                watched: watch_list.contains(public_key)
+            })
+            .collect::<Vec<_>>();
+
+        for public_key in watch_list {
+            if !last_seen.contains_key(&public_key) {
+                snapshot.push(PeerPresence {
+                    public_key: public_key.to_base64(),
+                    last_seen_secs_ago: None,
+                    watched: true
+                });
+            }
+        }
+
+        snapshot.sort_by(|a, b| a.public_key.cmp(&b.public_key));
+
+        snapshot
+    }
+}
+
+/// Send presence probe to the peer
+/// and wait for its response.
+pub(crate) async fn ping_peer<T: ClientApp + Sync + ?Sized>(client: &T, endpoint: &ClientEndpoint, timeout: Duration) -> Result<Option<Duration>, ClientAppError<T::Error>> {
+    let params = client.get_params();
+    let runtime = client.get_runtime();
+    let middleware = client.get_connected_middleware().await?;
+
+    let ping_id = safe_random_u64();
+    let session = runtime.inflight_requests.session();
+
+    let channel = params.channel.reply_to_session(ping_id, session);
+
+    let started_at = Instant::now();
+
+    client.send_envelope(&middleware, endpoint, &params.channel, &json!({
+        "id": ping_id,
+        "ping": true,
+        "session": session
+    })).await?;
+
+    while started_at.elapsed() < timeout {
+        let (messages, _) = middleware.poll(&channel, Some(1)).await?;
+
+        if !messages.is_empty() {
+            runtime.presence.seen(&endpoint.client_public);
+
+            return Ok(Some(started_at.elapsed()));
+        }
+
+        tokio::time::sleep(params.delay).await;
+    }
+
+    Ok(None)
+}
+
+/// Ping watched peers whose probes are due.
+pub(crate) async fn probe_presence<T: ClientApp + Sync + ?Sized>(client: &T) {
+    let params = client.get_params();
+    let runtime = client.get_runtime();
+
+    for endpoint in runtime.presence.due_probes() {
+        let _result = client.ping_peer(&endpoint, params.presence_probe_timeout).await;
+
+        #[cfg(feature = "tracing")]
+        if !matches!(_result, Ok(Some(_))) {
+            tracing::debug!("[client] Peer {} didn't respond to the presence probe", endpoint.client_public.to_base64());
+        }
+    }
+}
+
+/// Respond to the presence probe of the message sender.
+pub(crate) async fn answer_ping<T: ClientApp + Sync + ?Sized>(client: &T, message: MessageInfo, ping_id: u64, session: Option<u64>) -> Result<(), ClientAppError<T::Error>> {
+    let params = client.get_params();
+
+    let middleware = client.get_connected_middleware().await?;
+
+    let endpoint = ClientEndpoint::new(
+        &message.sender.server.address,
+        message.sender.client.public_key.clone()
+    );
+
+    client.send_envelope(
+        &middleware,
+        &endpoint,
+        &params.channel.reply_for(ping_id, session),
+        &json!({ "pong": true })
+    ).await?;
+
+    Ok(())
+}
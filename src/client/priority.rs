@@ -0,0 +1,212 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use hyperborealib::exports::tokio;
+
+use tokio::sync::{oneshot, Notify, Semaphore};
+use tokio::task::JoinHandle;
+
+use super::{ClientApp, ClientAppError, ClientEndpoint, MessageId};
+
+struct OutgoingMessage<A: ClientApp> {
+    priority: u8,
+    sequence: u64,
+    endpoint: ClientEndpoint,
+    message: A::OutputMessage,
+    sender: oneshot::Sender<Result<MessageId, ClientAppError<A::Error>>>
+}
+
+// Messages with higher priority go first,
+// equal ones are sent in FIFO order.
+
+impl<A: ClientApp> PartialEq for OutgoingMessage<A> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority && self.sequence == other.sequence
+    }
+}
+
+impl<A: ClientApp> Eq for OutgoingMessage<A> {}
+
+impl<A: ClientApp> PartialOrd for OutgoingMessage<A> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<A: ClientApp> Ord for OutgoingMessage<A> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority.cmp(&other.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+struct SenderQueue<A: ClientApp> {
+    heap: BinaryHeap<OutgoingMessage<A>>,
+    sequence: u64
+}
+
+/// Background sender of the outgoing messages
+/// in order of their priority.
+///
+/// Queued messages are sent by a background task with at most
+/// `max_concurrent` simultaneous sends. When a send slot is freed
+/// the message with the highest priority is taken, so urgent
+/// messages don't wait behind queued bulk ones.
+///
+/// ```rust,ignore
+/// let sender = PriorityMessageSender::new(client.clone(), 4);
+///
+/// // Don't wait until the bulk message is sent
+/// let bulk = sender.enqueue_message(endpoint.clone(), OutMsg::Chunk(data), 0);
+///
+/// sender.enqueue_message(endpoint, OutMsg::Shutdown, 255).await?;
+/// ```
+pub struct PriorityMessageSender<A: ClientApp> {
+    queue: Arc<Mutex<SenderQueue<A>>>,
+    notify: Arc<Notify>,
+    task: JoinHandle<()>
+}
+
+impl<A: ClientApp> std::fmt::Debug for PriorityMessageSender<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PriorityMessageSender")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl<A> PriorityMessageSender<A>
+where
+    A: ClientApp + Send + Sync + 'static,
+    A::OutputMessage: 'static,
+    A::Error: 'static
+{
+    /// Start background sender task.
+    pub fn new(client: Arc<A>, max_concurrent: usize) -> Self {
+        let queue = Arc::new(Mutex::new(SenderQueue {
+            heap: BinaryHeap::new(),
+            sequence: 0
+        }));
+
+        let notify = Arc::new(Notify::new());
+
+        let task = {
+            let queue = queue.clone();
+            let notify = notify.clone();
+
+            tokio::spawn(async move {
+                let slots = Arc::new(Semaphore::new(max_concurrent.max(1)));
+
+                loop {
+                    // Wait for a free slot before choosing the message
+                    let Ok(permit) = slots.clone().acquire_owned().await else {
+                        break;
+                    };
+
+                    let message = loop {
+                        let message = queue.lock().ok()
+                            .and_then(|mut queue| queue.heap.pop());
+
+                        match message {
+                            Some(message) => break message,
+                            None => notify.notified().await
+                        }
+                    };
+
+                    let client = client.clone();
+
+                    tokio::spawn(async move {
+                        let result = client.send_with_priority(
+                            message.endpoint,
+                            message.message,
+                            message.priority
+                        ).await;
+
+                        let _ = message.sender.send(result);
+
+                        drop(permit);
+                    });
+                }
+            })
+        };
+
+        Self {
+            queue,
+            notify,
+            task
+        }
+    }
+
+    /// Queue message to be sent with given priority.
+    ///
+    /// Returned future resolves with id of the
+    /// message when it is sent.
+    /// The message is queued even if the future is not awaited.
+    pub fn enqueue_message(
+        &self,
+        endpoint: ClientEndpoint,
+        message: A::OutputMessage,
+        priority: u8
+    ) -> impl Future<Output = Result<MessageId, ClientAppError<A::Error>>> + Send + 'static {
+        let (sender, receiver) = oneshot::channel();
+
+        if let Ok(mut queue) = self.queue.lock() {
+            let sequence = queue.sequence;
+
+            queue.sequence += 1;
+
+            queue.heap.push(OutgoingMessage {
+                priority,
+                sequence,
+                endpoint,
+                message,
+                sender
+            });
+        }
+
+        self.notify.notify_one();
+
+        async move {
+            receiver.await
+                .map_err(|_| ClientAppError::SenderStopped)?
+        }
+    }
+}
+
+impl<A: ClientApp> PriorityMessageSender<A> {
+    /// Amount of queued messages.
+    pub fn len(&self) -> usize {
+        self.queue.lock()
+            .map(|queue| queue.heap.len())
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Stop the background task.
+    ///
+    /// Queued messages are dropped and their
+    /// futures fail with `ClientAppError::SenderStopped`.
+    pub fn stop(&self) {
+        self.task.abort();
+
+        if let Ok(mut queue) = self.queue.lock() {
+            queue.heap.clear();
+        }
+    }
+}
+
+impl<A: ClientApp> Drop for PriorityMessageSender<A> {
+    #[inline]
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Default priority of the messages and requests.
+pub const DEFAULT_PRIORITY: u8 = 128;
+
+struct QueueEntry<T> {
+    priority: u8,
+    enqueued_at: u64,
+    value: T
+}
+
+struct QueueState<T> {
+    entries: VecDeque<QueueEntry<T>>,
+    ticks: u64
+}
+
+/// Thread-safe priority queue with aging.
+///
+/// Entries with higher priority are popped first. Each pop increases
+/// effective priority of all the waiting entries by one, so low priority
+/// entries can't be starved by a constant flow of high priority ones.
+/// Entries with equal effective priority are popped in FIFO order.
+pub struct PriorityQueue<T> {
+    state: Mutex<QueueState<T>>
+}
+
+impl<T> Default for PriorityQueue<T> {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                entries: VecDeque::new(),
+                ticks: 0
+            })
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for PriorityQueue<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PriorityQueue")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl<T> PriorityQueue<T> {
+    pub fn push(&self, priority: u8, value: T) {
+        if let Ok(mut state) = self.state.lock() {
+            let enqueued_at = state.ticks;
+
+            state.entries.push_back(QueueEntry {
+                priority,
+                enqueued_at,
+                value
+            });
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let mut state = self.state.lock().ok()?;
+
+        let ticks = state.ticks;
+
+        let (index, _) = state.entries.iter()
+            .enumerate()
+            .map(|(i, entry)| (i, entry.priority as u64 + ticks - entry.enqueued_at))
+            .fold(None, |best: Option<(usize, u64)>, (i, priority)| {
+                match best {
+                    Some((_, best_priority)) if best_priority >= priority => best,
+                    _ => Some((i, priority))
+                }
+            })?;
+
+        state.ticks += 1;
+
+        state.entries.remove(index)
+            .map(|entry| entry.value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock()
+            .map(|state| state.entries.len())
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
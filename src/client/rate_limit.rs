@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use hyperborealib::exports::tokio;
+
+use hyperborealib::crypto::asymmetric::PublicKey;
+
+/// Maximal amount of per-endpoint buckets kept before
+/// removing the ones which are full again.
+const MAX_BUCKETS: usize = 1024;
+
+/// Token bucket params.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RateLimit {
+    /// Amount of messages allowed per second.
+    pub per_second: f64,
+
+    /// Amount of messages which can be sent at once
+    /// after being idle.
+    pub burst: u32
+}
+
+/// Reaction on the exceeded outbound rate limit.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RateLimitMode {
+    /// Wait until the message can be sent.
+    #[default]
+    Wait,
+
+    /// Fail with `ClientAppError::RateLimited`.
+    FailFast
+}
+
+/// Limits of the outgoing messages and requests.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutboundRate {
+    /// Limit of messages sent to every endpoint.
+    pub per_endpoint: RateLimit,
+
+    /// Limit of messages sent to all the endpoints.
+    pub global: Option<RateLimit>,
+
+    pub mode: RateLimitMode
+}
+
+/// Counters of the outbound rate limiter.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RateLimiterStats {
+    /// Messages sent without waiting.
+    pub allowed: u64,
+
+    /// Messages sent after waiting for the limiter.
+    pub delayed: u64,
+
+    /// Messages rejected in the fail-fast mode.
+    pub rejected: u64
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant
+}
+
+impl Bucket {
+    #[inline]
+    fn new(limit: &RateLimit) -> Self {
+        Self {
+            tokens: limit.burst.max(1) as f64,
+            updated_at: Instant::now()
+        }
+    }
+
+    fn refill(&mut self, limit: &RateLimit) {
+        let now = Instant::now();
+
+        self.tokens = (self.tokens + now.duration_since(self.updated_at).as_secs_f64() * limit.per_second)
+            .min(limit.burst.max(1) as f64);
+
+        self.updated_at = now;
+    }
+
+    /// Time until the bucket has a token.
+    fn wait_time(&self, limit: &RateLimit) -> Duration {
+        if self.tokens >= 1.0 {
+            return Duration::ZERO;
+        }
+
+        if limit.per_second <= 0.0 {
+            return Duration::MAX;
+        }
+
+        Duration::from_secs_f64((1.0 - self.tokens) / limit.per_second)
+    }
+
+    #[inline]
+    fn is_full(&self, limit: &RateLimit) -> bool {
+        self.tokens >= limit.burst.max(1) as f64
+    }
+}
+
+/// Token bucket limiter of the outgoing messages
+/// keyed by the receiver's public key.
+///
+/// Buckets are refilled lazily when tokens are taken,
+/// so no background task is needed.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+/// use hyperelm::client::*;
+///
+/// let limiter = OutboundRateLimiter::new(Some(OutboundRate {
+///     per_endpoint: RateLimit {
+///         per_second: 10.0,
+///         burst: 1
+///     },
+///     global: None,
+///     mode: RateLimitMode::FailFast
+/// }));
+///
+/// let peer = SecretKey::random().public();
+///
+/// assert!(limiter.try_acquire(&peer).is_ok());
+///
+/// let failed = (0..100)
+///     .filter(|_| limiter.try_acquire(&peer).is_err())
+///     .count();
+///
+/// assert!(failed > 90);
+///
+/// // Other peers have their own buckets
+/// assert!(limiter.try_acquire(&SecretKey::random().public()).is_ok());
+///
+/// let wait = limiter.try_acquire(&peer).unwrap_err();
+///
+/// assert!(wait <= Duration::from_millis(100));
+/// ```
+#[derive(Debug, Default)]
+pub struct OutboundRateLimiter {
+    config: Option<OutboundRate>,
+    buckets: Mutex<HashMap<PublicKey, Bucket>>,
+    global: Mutex<Option<Bucket>>,
+    allowed: AtomicU64,
+    delayed: AtomicU64,
+    rejected: AtomicU64
+}
+
+impl OutboundRateLimiter {
+    /// Create new limiter. Nothing is limited if config is not set.
+    pub fn new(config: Option<OutboundRate>) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> Option<OutboundRate> {
+        self.config
+    }
+
+    pub fn stats(&self) -> RateLimiterStats {
+        RateLimiterStats {
+            allowed: self.allowed.load(Ordering::Relaxed),
+            delayed: self.delayed.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed)
+        }
+    }
+
+    /// Take a token for the message sent to the given peer.
+    ///
+    /// Returns time to wait before the next attempt if
+    /// there are no tokens in the peer's or the global bucket.
+    /// Doesn't update stats.
+    pub fn try_acquire(&self, peer: &PublicKey) -> Result<(), Duration> {
+        let Some(config) = &self.config else {
+            return Ok(());
+        };
+
+        let (Ok(mut buckets), Ok(mut global)) = (self.buckets.lock(), self.global.lock()) else {
+            return Ok(());
+        };
+
+        // Remove buckets of the idle peers
+        if buckets.len() >= MAX_BUCKETS {
+            buckets.retain(|_, bucket| {
+                bucket.refill(&config.per_endpoint);
+
+                !bucket.is_full(&config.per_endpoint)
+            });
+        }
+
+        let bucket = buckets.entry(peer.clone())
+            .or_insert_with(|| Bucket::new(&config.per_endpoint));
+
+        bucket.refill(&config.per_endpoint);
+
+        let mut wait = bucket.wait_time(&config.per_endpoint);
+
+        if let Some(limit) = &config.global {
+            let global = global.get_or_insert_with(|| Bucket::new(limit));
+
+            global.refill(limit);
+
+            wait = wait.max(global.wait_time(limit));
+
+            if wait.is_zero() {
+                global.tokens -= 1.0;
+            }
+        }
+
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+
+        bucket.tokens -= 1.0;
+
+        Ok(())
+    }
+
+    /// Take a token for the message sent to the given peer,
+    /// waiting for it or failing according to the limiter's mode.
+    ///
+    /// Returns time to wait before the next attempt
+    /// if the message was rejected.
+    pub async fn acquire(&self, peer: &PublicKey) -> Result<(), Duration> {
+        let mode = self.config
+            .map(|config| config.mode)
+            .unwrap_or_default();
+
+        let mut delayed = false;
+
+        loop {
+            match self.try_acquire(peer) {
+                Ok(()) => {
+                    if delayed {
+                        self.delayed.fetch_add(1, Ordering::Relaxed);
+                    } else {
+                        self.allowed.fetch_add(1, Ordering::Relaxed);
+                    }
+
+                    return Ok(());
+                }
+
+                Err(wait) if mode == RateLimitMode::FailFast || wait == Duration::MAX => {
+                    self.rejected.fetch_add(1, Ordering::Relaxed);
+
+                    return Err(wait);
+                }
+
+                Err(wait) => {
+                    delayed = true;
+
+                    tokio::time::sleep(wait).await;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+/// Policy of reconnecting to the server after connection failures.
+///
+/// Each failed attempt doubles the delay before the next one,
+/// up to the `max_delay`. The delay is randomly shifted by up to
+/// `jitter_factor` of its value so clients don't reconnect
+/// to the restarted server all at the same time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReconnectPolicy {
+    /// Delay before the first reconnection attempt.
+    pub base_delay: Duration,
+
+    /// Maximal delay between reconnection attempts.
+    pub max_delay: Duration,
+
+    /// Random delay deviation in range `[0.0, 1.0]`.
+    pub jitter_factor: f64,
+
+    /// Maximal amount of reconnection attempts.
+    ///
+    /// Unlimited if not set. Client started by the `run`
+    /// function makes new attempts after `max_delay`.
+    pub max_attempts: Option<u32>
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            jitter_factor: 0.25,
+            max_attempts: None
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Get delay before the reconnection attempt with given index,
+    /// starting from 0.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let delay = self.base_delay
+            .saturating_mul(2_u32.saturating_pow(attempt))
+            .min(self.max_delay);
+
+        let jitter_factor = self.jitter_factor.clamp(0.0, 1.0);
+
+        if jitter_factor == 0.0 {
+            return delay;
+        }
+
+        let jitter = rand::thread_rng().gen_range(-jitter_factor..jitter_factor);
+
+        delay.mul_f64(1.0 + jitter)
+    }
+
+    /// Check if another reconnection attempt is allowed
+    /// after given amount of failed ones.
+    #[inline]
+    pub fn can_retry(&self, attempts: u32) -> bool {
+        self.max_attempts.map(|max| attempts < max).unwrap_or(true)
+    }
+}
+
+/// Detector of the connected server restarts.
+///
+/// Keeps fingerprint of the server info reported at connection
+/// time and compares it with the current one every `interval`.
+/// Restarted server can lose its knowledge about connected
+/// clients, so the client has to connect again.
+#[derive(Debug)]
+pub struct RestartDetector {
+    interval: Option<Duration>,
+    auto_reconnect: bool,
+    fingerprint: Mutex<Option<u64>>,
+    last_check: Mutex<Option<Instant>>
+}
+
+impl Default for RestartDetector {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Some(Duration::from_secs(60)), true)
+    }
+}
+
+impl RestartDetector {
+    /// Create new detector.
+    ///
+    /// Server is never checked if `interval` is not set.
+    pub fn new(interval: Option<Duration>, auto_reconnect: bool) -> Self {
+        Self {
+            interval,
+            auto_reconnect,
+            fingerprint: Mutex::new(None),
+            last_check: Mutex::new(None)
+        }
+    }
+
+    #[inline]
+    pub fn interval(&self) -> Option<Duration> {
+        self.interval
+    }
+
+    /// Whether the client should reconnect to the restarted server.
+    #[inline]
+    pub fn auto_reconnect(&self) -> bool {
+        self.auto_reconnect
+    }
+
+    /// Check if the server should be checked now.
+    pub fn is_due(&self) -> bool {
+        let Some(interval) = self.interval else {
+            return false;
+        };
+
+        self.last_check.lock()
+            .map(|last_check| last_check.map(|time| time.elapsed() >= interval).unwrap_or(true))
+            .unwrap_or_default()
+    }
+
+    /// Check the server on the next `is_due` call,
+    /// e.g. after a failed request.
+    pub fn force_check(&self) {
+        if let Ok(mut last_check) = self.last_check.lock() {
+            *last_check = None;
+        }
+    }
+
+    /// Remember current server fingerprint.
+    ///
+    /// Returns `true` if it differs from the previous one.
+    pub fn observe(&self, fingerprint: u64) -> bool {
+        if let Ok(mut last_check) = self.last_check.lock() {
+            *last_check = Some(Instant::now());
+        }
+
+        self.fingerprint.lock()
+            .map(|mut known| known.replace(fingerprint).is_some_and(|known| known != fingerprint))
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use hyperborealib::crypto::prelude::*;
+
+use super::Channel;
+
+static CHANNELS: OnceLock<Mutex<HashMap<Channel, PublicKey>>> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("Channel {channel} is already registered by another client")]
+pub struct ChannelConflict {
+    pub channel: Channel,
+
+    /// Public key of the client owning the channel.
+    pub owner: PublicKey
+}
+
+/// Process-global registry of the channels used
+/// by the client applications.
+///
+/// Prevents independent libraries linked into the same
+/// binary from receiving each other's messages by using
+/// the same channel name.
+///
+/// ```rust
+/// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+/// use hyperelm::client::{Channel, ChannelRegistry};
+///
+/// let channel = Channel::new("registry-doctest").unwrap();
+///
+/// let first = SecretKey::random().public();
+/// let second = SecretKey::random().public();
+///
+/// assert!(ChannelRegistry::register(&channel, &first).is_ok());
+/// assert!(ChannelRegistry::register(&channel, &first).is_ok());
+///
+/// let conflict = ChannelRegistry::register(&channel, &second).unwrap_err();
+///
+/// assert_eq!(conflict.owner, first);
+///
+/// ChannelRegistry::unregister(&channel, &first);
+///
+/// assert!(ChannelRegistry::register(&channel, &second).is_ok());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ChannelRegistry;
+
+impl ChannelRegistry {
+    fn channels() -> &'static Mutex<HashMap<Channel, PublicKey>> {
+        CHANNELS.get_or_init(Mutex::default)
+    }
+
+    /// Register channel owned by the given client.
+    ///
+    /// Fails if the channel is already registered by another client.
+    pub fn register(channel: &Channel, owner: &PublicKey) -> Result<(), ChannelConflict> {
+        let mut channels = match Self::channels().lock() {
+            Ok(channels) => channels,
+            Err(err) => err.into_inner()
+        };
+
+        match channels.get(channel) {
+            Some(current) if current != owner => Err(ChannelConflict {
+                channel: channel.clone(),
+                owner: current.clone()
+            }),
+
+            Some(_) => Ok(()),
+
+            None => {
+                channels.insert(channel.clone(), owner.clone());
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Unregister channel if it's owned by the given client.
+    ///
+    /// Returns `false` if the channel wasn't unregistered.
+    pub fn unregister(channel: &Channel, owner: &PublicKey) -> bool {
+        let mut channels = match Self::channels().lock() {
+            Ok(channels) => channels,
+            Err(err) => err.into_inner()
+        };
+
+        if channels.get(channel) != Some(owner) {
+            return false;
+        }
+
+        channels.remove(channel);
+
+        true
+    }
+
+    /// Get owner of the registered channel.
+    pub fn owner(channel: &Channel) -> Option<PublicKey> {
+        Self::channels().lock().ok()?
+            .get(channel)
+            .cloned()
+    }
+}
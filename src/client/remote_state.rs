@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value as Json};
+
+use hyperborealib::exports::tokio;
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use super::*;
+
+/// Reserved envelope field of the remote state requests.
+///
+/// `{ "id": N, "__hyperelm_get_state": { "key": "..." }, "session": N }`
+pub const GET_STATE_FIELD: &str = "__hyperelm_get_state";
+
+type StateFuture = Pin<Box<dyn Future<Output = Json> + Send>>;
+
+type StateProvider = Arc<dyn Fn() -> StateFuture + Send + Sync>;
+
+/// Value of the remote state entry.
+///
+/// `{ "value": ..., "ttl": N }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteState {
+    pub value: Json,
+
+    /// Time the requester can cache the value for.
+    pub ttl: Option<Duration>
+}
+
+impl AsJson for RemoteState {
+    fn to_json(&self) -> Result<Json, AsJsonError> {
+        Ok(json!({
+            "value": self.value,
+            "ttl": self.ttl.map(|ttl| ttl.as_secs())
+        }))
+    }
+
+    fn from_json(json: &Json) -> Result<Self, AsJsonError> where Self: Sized {
+        Ok(Self {
+            value: json.get("value")
+                .cloned()
+                .ok_or_else(|| AsJsonError::FieldNotFound("value"))?,
+
+            ttl: json.get("ttl")
+                .and_then(Json::as_u64)
+                .map(Duration::from_secs)
+        })
+    }
+}
+
+#[derive(Clone)]
+struct RegisteredProvider {
+    provider: StateProvider,
+    ttl: Option<Duration>
+}
+
+/// Named pieces of state which can be
+/// requested by other clients.
+///
+/// Requests of unknown keys are answered with
+/// the `not_found` remote error.
+///
+/// ```rust,ignore
+/// client.state_providers().register("roster", || async {
+///     serde_json::json!(["alice", "bob"])
+/// });
+///
+/// let roster = other.get_remote_state(endpoint, "roster").await?;
+/// ```
+#[derive(Default)]
+pub struct StateProviderRegistry {
+    providers: RwLock<HashMap<String, RegisteredProvider>>
+}
+
+impl std::fmt::Debug for StateProviderRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StateProviderRegistry")
+            .field("keys", &self.keys())
+            .finish()
+    }
+}
+
+impl StateProviderRegistry {
+    /// Register provider of the state with given key,
+    /// replacing the previous one.
+    #[inline]
+    pub fn register<F, T>(&self, key: impl ToString, provider: F)
+    where
+        F: Fn() -> T + Send + Sync + 'static,
+        T: Future<Output = Json> + Send + 'static
+    {
+        self.insert(key.to_string(), provider, None);
+    }
+
+    /// Register provider of the state which can
+    /// be cached by the requesters for `ttl`.
+    #[inline]
+    pub fn register_cacheable<F, T>(&self, key: impl ToString, ttl: Duration, provider: F)
+    where
+        F: Fn() -> T + Send + Sync + 'static,
+        T: Future<Output = Json> + Send + 'static
+    {
+        self.insert(key.to_string(), provider, Some(ttl));
+    }
+
+    fn insert<F, T>(&self, key: String, provider: F, ttl: Option<Duration>)
+    where
+        F: Fn() -> T + Send + Sync + 'static,
+        T: Future<Output = Json> + Send + 'static
+    {
+        if let Ok(mut providers) = self.providers.write() {
+            providers.insert(key, RegisteredProvider {
+                provider: Arc::new(move || Box::pin(provider()) as StateFuture),
+                ttl
+            });
+        }
+    }
+
+    /// Remove provider of the state with given key.
+    pub fn unregister(&self, key: &str) -> bool {
+        self.providers.write()
+            .map(|mut providers| providers.remove(key).is_some())
+            .unwrap_or_default()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.providers.read()
+            .map(|providers| providers.contains_key(key))
+            .unwrap_or_default()
+    }
+
+    pub fn keys(&self) -> Vec<String> {
+        self.providers.read()
+            .map(|providers| providers.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Run provider of the state with given key.
+    ///
+    /// Returns `None` if the key is not registered.
+    pub async fn get(&self, key: &str) -> Option<RemoteState> {
+        let registered = self.providers.read().ok()?
+            .get(key)
+            .cloned()?;
+
+        Some(RemoteState {
+            value: (registered.provider)().await,
+            ttl: registered.ttl
+        })
+    }
+}
+
+/// Cache of the remote state values received
+/// from other clients with a time to live.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use serde_json::json;
+///
+/// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+/// use hyperelm::client::RemoteStateCache;
+///
+/// let cache = RemoteStateCache::default();
+/// let peer = SecretKey::random().public();
+///
+/// cache.insert(&peer, "config", json!({ "debug": true }), Duration::from_secs(60));
+/// cache.insert(&peer, "roster", json!([]), Duration::ZERO);
+///
+/// assert_eq!(cache.get(&peer, "config"), Some(json!({ "debug": true })));
+/// assert_eq!(cache.get(&peer, "roster"), None);
+/// assert_eq!(cache.get(&SecretKey::random().public(), "config"), None);
+/// ```
+#[derive(Debug, Default)]
+pub struct RemoteStateCache {
+    entries: RwLock<HashMap<(PublicKey, String), (Json, Instant)>>
+}
+
+impl RemoteStateCache {
+    /// Get cached value if it's not expired yet.
+    pub fn get(&self, peer: &PublicKey, key: &str) -> Option<Json> {
+        let entries = self.entries.read().ok()?;
+
+        let (value, expires_at) = entries.get(&(peer.clone(), key.to_string()))?;
+
+        (*expires_at > Instant::now()).then(|| value.clone())
+    }
+
+    /// Cache value for given time, removing expired entries.
+    pub fn insert(&self, peer: &PublicKey, key: impl ToString, value: Json, ttl: Duration) {
+        if let Ok(mut entries) = self.entries.write() {
+            let now = Instant::now();
+
+            entries.retain(|_, (_, expires_at)| *expires_at > now);
+
+            entries.insert((peer.clone(), key.to_string()), (value, now + ttl));
+        }
+    }
+
+    pub fn invalidate(&self, peer: &PublicKey, key: &str) -> bool {
+        self.entries.write()
+            .map(|mut entries| entries.remove(&(peer.clone(), key.to_string())).is_some())
+            .unwrap_or_default()
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.clear();
+        }
+    }
+}
+
+/// Request state of the endpoint unless it's
+/// stored in the remote state cache.
+pub(crate) async fn get_remote_state<T: ClientApp + Sync + ?Sized>(client: &T, endpoint: ClientEndpoint, key: &str) -> Result<Json, ClientAppError<T::Error>> {
+    let params = client.get_params();
+    let runtime = client.get_runtime();
+
+    if let Some(value) = runtime.remote_state_cache.get(&endpoint.client_public, key) {
+        return Ok(value);
+    }
+
+    // Reserve in-flight request slot
+    let _permit = runtime.inflight_requests.acquire().await
+        .map_err(|_| ClientAppError::Overloaded)?;
+
+    client.throttle(&endpoint).await?;
+
+    let middleware = client.get_connected_middleware().await?;
+
+    // Send request
+    let request_id = safe_random_u64();
+    let session = runtime.inflight_requests.session();
+
+    let pending = runtime.inflight_requests.register(
+        request_id,
+        params.channel.reply_to_session(request_id, session)
+    );
+
+    client.send_envelope(&middleware, &endpoint, &params.channel, &json!({
+        "id": request_id,
+        GET_STATE_FIELD: {
+            "key": key
+        },
+        "session": session
+    })).await?;
+
+    // Receive response
+    let response = tokio::time::timeout(
+        params.remote_state_timeout,
+        client.receive_response(&middleware, request_id, pending)
+    ).await
+        .unwrap_or(Err(ClientAppError::Timeout))
+        .with_peer(&endpoint.client_public)?;
+
+    if let Some(error) = response.get("remote_error") {
+        let error = serde_json::from_value::<RemoteError>(error.clone())?;
+
+        return Err(ClientAppError::Remote(error).with_peer(&endpoint.client_public));
+    }
+
+    let state = response.get("state")
+        .ok_or_else(|| AsJsonError::FieldNotFound("state"))?;
+
+    let state = RemoteState::from_json(state)?;
+
+    if let Some(ttl) = state.ttl {
+        runtime.remote_state_cache.insert(&endpoint.client_public, key, state.value.clone(), ttl);
+    }
+
+    runtime.presence.seen(&endpoint.client_public);
+
+    Ok(state.value)
+}
+
+/// Respond with the state of the registered provider,
+/// or with the `not_found` remote error.
+pub(crate) async fn answer_state_request<T: ClientApp + Sync + ?Sized>(client: &T, message: MessageInfo, request_id: u64, key: String, session: Option<u64>) -> Result<(), ClientAppError<T::Error>> {
+    let params = client.get_params();
+
+    let middleware = client.get_connected_middleware().await?;
+
+    let endpoint = ClientEndpoint::new(
+        &message.sender.server.address,
+        message.sender.client.public_key.clone()
+    );
+
+    let response = match client.state_providers().get(&key).await {
+        Some(state) => json!({ "state": state.to_json()? }),
+
+        None => json!({
+            "remote_error": RemoteError::new("not_found", format!("Unknown state key '{key}'"))
+        })
+    };
+
+    client.send_envelope(
+        &middleware,
+        &endpoint,
+        &params.channel.reply_for(request_id, session),
+        &response
+    ).await?;
+
+    Ok(())
+}
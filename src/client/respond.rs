@@ -0,0 +1,303 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde_json::{json, Value as Json};
+
+use tokio::sync::{mpsc, oneshot, watch};
+
+use hyperborealib::rest_api::prelude::*;
+
+use super::{Channel, ClientEndpoint};
+
+/// Result of the request handling.
+pub enum Respond<R> {
+    /// Send response immediately.
+    Now(R),
+
+    /// Send response later using the given token.
+    Later(ResponseToken<R>)
+}
+
+impl<R> From<R> for Respond<R> {
+    #[inline]
+    fn from(response: R) -> Self {
+        Self::Now(response)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ResponseError {
+    #[error(transparent)]
+    SerdeJsonError(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    AsJsonError(#[from] AsJsonError),
+
+    #[error("Failed to deliver response: {0}")]
+    Delivery(String),
+
+    #[error("Client is stopped, response can't be delivered")]
+    Stopped,
+
+    #[error("Response token was not returned from the request handler")]
+    Unbound,
+
+    #[error("Response was already sent")]
+    AlreadyResponded
+}
+
+/// Response waiting to be sent by the client's run loop.
+#[derive(Debug)]
+pub struct DeferredResponse {
+    pub endpoint: ClientEndpoint,
+    pub channel: Channel,
+    pub envelope: Json,
+
+    result: oneshot::Sender<Result<(), String>>
+}
+
+impl DeferredResponse {
+    /// Report delivery result to the responder.
+    #[inline]
+    pub fn complete(self, result: Result<(), String>) {
+        let _ = self.result.send(result);
+    }
+}
+
+/// Queue of the deferred responses.
+///
+/// Responses are sent by the client's run loop using
+/// the `send_envelope` method, like the immediate ones.
+#[derive(Debug)]
+pub struct DeferredResponses {
+    sender: mpsc::UnboundedSender<DeferredResponse>,
+    receiver: Mutex<Option<mpsc::UnboundedReceiver<DeferredResponse>>>
+}
+
+impl Default for DeferredResponses {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        Self {
+            sender,
+            receiver: Mutex::new(Some(receiver))
+        }
+    }
+}
+
+impl DeferredResponses {
+    /// Take receiver of the queued responses.
+    ///
+    /// Returns `None` if it was already taken.
+    pub fn take_receiver(&self) -> Option<mpsc::UnboundedReceiver<DeferredResponse>> {
+        self.receiver.lock().ok()?.take()
+    }
+
+    async fn send(&self, endpoint: ClientEndpoint, channel: Channel, envelope: Json) -> Result<(), ResponseError> {
+        let (result, receiver) = oneshot::channel();
+
+        self.sender.send(DeferredResponse {
+            endpoint,
+            channel,
+            envelope,
+            result
+        }).map_err(|_| ResponseError::Stopped)?;
+
+        receiver.await
+            .map_err(|_| ResponseError::Stopped)?
+            .map_err(ResponseError::Delivery)
+    }
+}
+
+/// Information needed to deliver the deferred response.
+pub struct ResponseBinding {
+    pub endpoint: ClientEndpoint,
+    pub channel: Channel,
+
+    /// Id of the request to wrap the response with
+    /// when it's sent to the shared replies channel.
+    pub reply_id: Option<u64>,
+
+    /// Session token of the requester echoed
+    /// in the shared replies channel.
+    pub reply_session: Option<u64>,
+
+    pub queue: Arc<DeferredResponses>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BindingState {
+    Pending,
+    Bound,
+    Unbound
+}
+
+struct ResponseTokenInner {
+    state: watch::Sender<BindingState>,
+    binding: Mutex<Option<ResponseBinding>>,
+
+    /// Amount of token clones which can still be bound.
+    holders: AtomicUsize
+}
+
+/// Token used to send deferred response to the request.
+///
+/// Return its clone from the request handler
+/// as `Respond::Later(token)` and call `respond`
+/// from any other task when the response is ready.
+///
+/// If all the other clones of the token are dropped
+/// without being bound to the request, for example
+/// because the handler failed, `respond` returns
+/// `ResponseError::Unbound`.
+///
+/// ```rust,ignore
+/// async fn handle_request(&self, request: InReq, info: MessageInfo) -> Result<Respond<InResp>, ClientAppError<()>> {
+///     let token = ResponseToken::new();
+///
+///     tokio::spawn({
+///         let token = token.clone();
+///
+///         async move {
+///             let response = render().await;
+///
+///             token.respond(response).await
+///         }
+///     });
+///
+///     Ok(Respond::Later(token))
+/// }
+/// ```
+pub struct ResponseToken<R> {
+    inner: Arc<ResponseTokenInner>,
+    _response: std::marker::PhantomData<fn(R)>
+}
+
+impl<R> Clone for ResponseToken<R> {
+    fn clone(&self) -> Self {
+        self.inner.holders.fetch_add(1, Ordering::AcqRel);
+
+        Self {
+            inner: self.inner.clone(),
+            _response: std::marker::PhantomData
+        }
+    }
+}
+
+impl<R> Drop for ResponseToken<R> {
+    fn drop(&mut self) {
+        // Last clone is dropped without binding
+        if self.inner.holders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.inner.state.send_if_modified(|state| {
+                if *state != BindingState::Pending {
+                    return false;
+                }
+
+                *state = BindingState::Unbound;
+
+                true
+            });
+        }
+    }
+}
+
+impl<R> Default for ResponseToken<R> {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(ResponseTokenInner {
+                state: watch::channel(BindingState::Pending).0,
+                binding: Mutex::new(None),
+                holders: AtomicUsize::new(1)
+            }),
+            _response: std::marker::PhantomData
+        }
+    }
+}
+
+impl<R> ResponseToken<R> {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bind the token to the request it should respond to.
+    ///
+    /// Called by the client application after
+    /// the request handler returned the token.
+    pub fn bind(&self, binding: ResponseBinding) {
+        if let Ok(mut current) = self.inner.binding.lock() {
+            if current.is_none() {
+                *current = Some(binding);
+            }
+        }
+
+        self.inner.state.send_if_modified(|state| {
+            if *state != BindingState::Pending {
+                return false;
+            }
+
+            *state = BindingState::Bound;
+
+            true
+        });
+    }
+}
+
+impl<R: AsJson> ResponseToken<R> {
+    /// Send response to the request.
+    ///
+    /// Waits until the token is bound to the request,
+    /// and then until the response is sent by the client.
+    ///
+    /// ```rust
+    /// use hyperelm::client::{ResponseToken, ResponseError};
+    ///
+    /// #[derive(serde::Serialize, serde::Deserialize)]
+    /// struct Pong;
+    ///
+    /// hyperborealib::impl_as_json!(Pong);
+    ///
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// let token = ResponseToken::<Pong>::new();
+    ///
+    /// let responder = tokio::spawn(token.clone().respond(Pong));
+    ///
+    /// // Request handler failed and dropped the token
+    /// drop(token);
+    ///
+    /// assert!(matches!(responder.await.unwrap(), Err(ResponseError::Unbound)));
+    /// # });
+    /// ```
+    pub async fn respond(self, response: R) -> Result<(), ResponseError> {
+        let mut state = self.inner.state.subscribe();
+        let inner = self.inner.clone();
+
+        // Current clone can't be bound anymore
+        drop(self);
+
+        let bound = state.wait_for(|state| *state != BindingState::Pending).await
+            .map(|state| *state == BindingState::Bound)
+            .unwrap_or(false);
+
+        if !bound {
+            return Err(ResponseError::Unbound);
+        }
+
+        let binding = inner.binding.lock()
+            .ok()
+            .and_then(|mut binding| binding.take())
+            .ok_or(ResponseError::AlreadyResponded)?;
+
+        let response = match binding.reply_id {
+            Some(id) => json!({
+                "id": id,
+                "session": binding.reply_session,
+                "response": response.to_json()?
+            }),
+
+            None => response.to_json()?
+        };
+
+        binding.queue.send(binding.endpoint, binding.channel, response).await
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use serde_json::Value as Json;
+
+use hyperborealib::crypto::asymmetric::PublicKey;
+
+#[derive(Debug)]
+struct CachedResponse {
+    response: Json,
+    expires_at: Instant,
+    last_used: u64
+}
+
+#[derive(Debug, Default)]
+struct ResponseCacheInner {
+    entries: HashMap<(PublicKey, Vec<u8>), CachedResponse>,
+
+    /// Counter used to order entries by their last use.
+    clock: u64
+}
+
+/// Bounded LRU cache of the responses to the
+/// idempotent requests sent by the client.
+///
+/// Entries are keyed by the receiver's public key and
+/// the canonically serialized request.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use serde_json::json;
+///
+/// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+/// use hyperelm::client::ResponseCache;
+///
+/// let cache = ResponseCache::new(2);
+/// let peer = SecretKey::random().public();
+///
+/// let ttl = Duration::from_secs(60);
+///
+/// cache.insert(&peer, b"profile".to_vec(), json!("alice"), ttl);
+/// cache.insert(&peer, b"page-1".to_vec(), json!([1, 2]), ttl);
+///
+/// assert_eq!(cache.get(&peer, b"profile"), Some(json!("alice")));
+///
+/// // Least recently used entry is evicted
+/// cache.insert(&peer, b"page-2".to_vec(), json!([3, 4]), ttl);
+///
+/// assert_eq!(cache.get(&peer, b"page-1"), None);
+/// assert_eq!(cache.len(), 2);
+///
+/// assert_eq!(cache.invalidate_peer(&peer), 2);
+/// assert!(cache.is_empty());
+/// ```
+#[derive(Debug, Default)]
+pub struct ResponseCache {
+    capacity: usize,
+    inner: Mutex<ResponseCacheInner>,
+    hits: AtomicU64,
+    misses: AtomicU64
+}
+
+impl ResponseCache {
+    /// Create new cache storing at most `capacity` responses.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ..Self::default()
+        }
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Amount of requests answered from the cache.
+    #[inline]
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Amount of cacheable requests sent to the network.
+    #[inline]
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Get not expired response to the request.
+    pub fn get(&self, peer: &PublicKey, request: &[u8]) -> Option<Json> {
+        let mut inner = self.inner.lock().ok()?;
+
+        inner.clock += 1;
+
+        let clock = inner.clock;
+        let key = (peer.clone(), request.to_vec());
+
+        let response = match inner.entries.get_mut(&key) {
+            Some(entry) if entry.expires_at > Instant::now() => {
+                entry.last_used = clock;
+
+                Some(entry.response.clone())
+            }
+
+            Some(_) => {
+                inner.entries.remove(&key);
+
+                None
+            }
+
+            None => None
+        };
+
+        match response {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed)
+        };
+
+        response
+    }
+
+    /// Cache response to the request for given time.
+    ///
+    /// Expired entries are removed first when the cache is
+    /// full, and then the least recently used one.
+    pub fn insert(&self, peer: &PublicKey, request: Vec<u8>, response: Json, ttl: Duration) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+
+        let key = (peer.clone(), request);
+
+        if inner.entries.len() >= self.capacity && !inner.entries.contains_key(&key) {
+            let now = Instant::now();
+
+            inner.entries.retain(|_, entry| entry.expires_at > now);
+
+            if inner.entries.len() >= self.capacity {
+                let oldest = inner.entries.iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(key, _)| key.clone());
+
+                if let Some(oldest) = oldest {
+                    inner.entries.remove(&oldest);
+                }
+            }
+        }
+
+        inner.clock += 1;
+
+        let last_used = inner.clock;
+
+        inner.entries.insert(key, CachedResponse {
+            response,
+            expires_at: Instant::now() + ttl,
+            last_used
+        });
+    }
+
+    /// Remove all the responses of the given peer.
+    ///
+    /// Returns amount of removed responses.
+    pub fn invalidate_peer(&self, peer: &PublicKey) -> usize {
+        let Ok(mut inner) = self.inner.lock() else {
+            return 0;
+        };
+
+        let len = inner.entries.len();
+
+        inner.entries.retain(|(key, _), _| key != peer);
+
+        len - inner.entries.len()
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.entries.clear();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock()
+            .map(|inner| inner.entries.len())
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
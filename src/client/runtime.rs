@@ -0,0 +1,192 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+
+use serde_json::Value as Json;
+
+use hyperborealib::rest_api::prelude::*;
+
+use super::*;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientRuntimeError {
+    #[error("Failed to load seen messages ids {path:?}: {source}")]
+    SeenMessages {
+        path: PathBuf,
+        source: std::io::Error
+    },
+
+    #[error("Failed to load peer pins {path:?}: {source}")]
+    PeerPins {
+        path: PathBuf,
+        source: std::io::Error
+    }
+}
+
+/// Runtime state of the client application.
+///
+/// Created from the client params once when the application
+/// is constructed and returned by the `ClientApp::get_runtime`
+/// method. Clones share the same state.
+///
+/// ```rust
+/// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+/// use hyperelm::client::{ClientAppParams, ClientRuntime};
+///
+/// let params = ClientAppParams::builder()
+///     .client(SecretKey::random())
+///     .server(SecretKey::random().public(), "127.0.0.1:8001")
+///     .latency_samples(16)
+///     .build()
+///     .unwrap();
+///
+/// let runtime = ClientRuntime::new(&params).unwrap();
+///
+/// assert_eq!(runtime.latency_tracker.capacity(), 16);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClientRuntime {
+    /// Connection to the server under the previous
+    /// identity, kept within its grace period.
+    pub previous_inbox: Arc<PreviousInbox>,
+
+    /// Registry of the pub/sub topics.
+    pub topics: Arc<TopicRegistry>,
+
+    /// Round trip time statistics of the connected server.
+    pub latency_tracker: Arc<LatencyTracker>,
+
+    /// Last seen time of the known peers.
+    pub presence: Arc<PresenceTracker>,
+
+    /// Peers learned from the gossip exchanges.
+    pub peer_cache: Arc<PeerCache>,
+
+    /// Providers of the state requested by other clients.
+    pub state_providers: Arc<StateProviderRegistry>,
+
+    /// Cached state values received from other clients.
+    pub remote_state_cache: Arc<RemoteStateCache>,
+
+    /// Ids of the recently processed messages
+    /// used to suppress duplicated deliveries.
+    pub seen_messages: Arc<SeenMessages>,
+
+    /// Greatest nonces seen from the senders.
+    pub nonces: Arc<NonceTracker>,
+
+    /// Server addresses of the looked up peers.
+    pub peer_pins: Arc<PeerPinStore>,
+
+    /// Requests waiting for a response.
+    pub inflight_requests: Arc<InflightRequests>,
+
+    /// Deferred responses waiting to be sent.
+    pub deferred_responses: Arc<DeferredResponses>,
+
+    /// Limiter of the outgoing messages and requests.
+    pub rate_limiter: Arc<OutboundRateLimiter>,
+
+    /// Responses to the cacheable requests.
+    pub response_cache: Arc<ResponseCache>,
+
+    /// Requests sent with caller-provided ids.
+    pub outgoing_requests: Arc<OutgoingRequestDedup>,
+
+    /// Messages scheduled to be sent later.
+    pub scheduler: Arc<Scheduler>,
+
+    /// Detector of the connected server restarts.
+    pub restart_detector: Arc<RestartDetector>,
+
+    /// State of the connection to the server.
+    pub connection: Arc<ConnectionTracker>,
+
+    /// Circuit breaker of the peers' servers.
+    pub circuit_breaker: Arc<CircuitBreaker>,
+
+    /// Additional channels polled by the `poll_channels` method.
+    pub polled_channels: Arc<PolledChannels>,
+
+    /// Amount of failed handlers of the concurrently
+    /// processed incoming messages.
+    pub handler_errors: Arc<AtomicU64>,
+
+    /// Amount of the incoming messages rejected
+    /// by the envelope schema.
+    pub invalid_message_stats: Arc<InvalidMessageStats>,
+
+    /// Amount of the incoming messages which
+    /// couldn't be decrypted or deserialized.
+    pub undecodable_messages: Arc<AtomicU64>,
+
+    /// Queue of received but not yet processed messages.
+    pub incoming_queue: Arc<PriorityQueue<(MessageInfo, Json)>>
+}
+
+impl ClientRuntime {
+    /// Create runtime state configured by the given params.
+    ///
+    /// Seen messages ids and peer pins are loaded from
+    /// the `seen_messages_path` and `pins_path` files if they're set.
+    pub fn new(params: &ClientAppParams) -> Result<Self, ClientRuntimeError> {
+        let mut seen_messages = SeenMessages::new(params.seen_messages_capacity, params.seen_messages_ttl);
+
+        if let Some(path) = &params.seen_messages_path {
+            seen_messages = seen_messages.with_persistence(path)
+                .map_err(|source| ClientRuntimeError::SeenMessages {
+                    path: path.clone(),
+                    source
+                })?;
+        }
+
+        let mut peer_pins = PeerPinStore::new();
+
+        if let Some(path) = &params.pins_path {
+            peer_pins = peer_pins.with_persistence(path)
+                .map_err(|source| ClientRuntimeError::PeerPins {
+                    path: path.clone(),
+                    source
+                })?;
+        }
+
+        let presence = PresenceTracker::new(params.presence_probe_interval);
+
+        for endpoint in &params.presence_watch_list {
+            presence.watch(endpoint.clone());
+        }
+
+        Ok(Self {
+            previous_inbox: Arc::default(),
+            topics: Arc::new(TopicRegistry::new(params.topic_max_failures, params.topic_max_ttl)),
+            latency_tracker: Arc::new(LatencyTracker::new(params.latency_samples)),
+            presence: Arc::new(presence),
+            peer_cache: Arc::default(),
+            state_providers: Arc::default(),
+            remote_state_cache: Arc::default(),
+            seen_messages: Arc::new(seen_messages),
+            nonces: Arc::default(),
+            peer_pins: Arc::new(peer_pins),
+            inflight_requests: Arc::new(InflightRequests::new(
+                params.max_inflight_requests,
+                params.overload_behavior
+            )),
+            deferred_responses: Arc::default(),
+            rate_limiter: Arc::new(OutboundRateLimiter::new(params.outbound_rate)),
+            response_cache: Arc::new(ResponseCache::new(params.response_cache_capacity)),
+            outgoing_requests: Arc::new(OutgoingRequestDedup::new(params.dedup_window)),
+            scheduler: Arc::new(Scheduler::new(params.fire_missed_scheduled)),
+            restart_detector: Arc::new(RestartDetector::new(
+                params.restart_check_interval,
+                params.auto_reconnect
+            )),
+            connection: Arc::default(),
+            circuit_breaker: Arc::new(CircuitBreaker::new(params.circuit_breaker)),
+            polled_channels: Arc::new(PolledChannels::new(params.polled_channels.clone())),
+            handler_errors: Arc::default(),
+            invalid_message_stats: Arc::default(),
+            undecodable_messages: Arc::default(),
+            incoming_queue: Arc::default()
+        })
+    }
+}
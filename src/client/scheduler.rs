@@ -0,0 +1,288 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value as Json};
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use super::{ClientEndpoint, StateStore, StateStoreExt};
+
+/// Key of the scheduled messages in the state store.
+pub const SCHEDULED_STATE_KEY: &str = "hyperelm-scheduled";
+
+/// Id of the scheduled message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ScheduleId(pub u64);
+
+impl std::fmt::Display for ScheduleId {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Message which should be sent at the given time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledMessage {
+    pub id: ScheduleId,
+    pub endpoint: ClientEndpoint,
+
+    /// Serialized output message.
+    pub message: Json,
+
+    pub at: SystemTime
+}
+
+impl AsJson for ScheduledMessage {
+    fn to_json(&self) -> Result<Json, AsJsonError> {
+        Ok(json!({
+            "id": self.id.0,
+            "server_address": self.endpoint.server_address,
+            "client_public": self.endpoint.client_public.to_base64(),
+            "message": self.message,
+            "at": self.at.duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64
+        }))
+    }
+
+    fn from_json(json: &Json) -> Result<Self, AsJsonError> where Self: Sized {
+        Ok(Self {
+            id: json.get("id")
+                .and_then(Json::as_u64)
+                .map(ScheduleId)
+                .ok_or_else(|| AsJsonError::FieldNotFound("id"))?,
+
+            endpoint: ClientEndpoint {
+                server_address: json.get("server_address")
+                    .and_then(Json::as_str)
+                    .map(String::from)
+                    .ok_or_else(|| AsJsonError::FieldNotFound("server_address"))?,
+
+                client_public: json.get("client_public")
+                    .and_then(Json::as_str)
+                    .and_then(|key| PublicKey::from_base64(key).ok())
+                    .ok_or_else(|| AsJsonError::FieldNotFound("client_public"))?
+            },
+
+            message: json.get("message")
+                .cloned()
+                .ok_or_else(|| AsJsonError::FieldNotFound("message"))?,
+
+            at: json.get("at")
+                .and_then(Json::as_u64)
+                .map(|at| UNIX_EPOCH + Duration::from_millis(at))
+                .ok_or_else(|| AsJsonError::FieldNotFound("at"))?
+        })
+    }
+}
+
+/// Timer of the messages scheduled to be sent later.
+///
+/// Due messages are taken by the client's background
+/// updates task and sent using the `ClientApp::send` method.
+/// Canceled messages are lazily removed from the timer.
+///
+/// ```rust
+/// use std::time::{Duration, SystemTime};
+///
+/// use serde_json::json;
+///
+/// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+/// use hyperelm::client::{ClientEndpoint, Scheduler};
+///
+/// let scheduler = Scheduler::new(true);
+/// let endpoint = ClientEndpoint::new("127.0.0.1:8001", SecretKey::random().public());
+///
+/// let now = SystemTime::now();
+///
+/// let third = scheduler.schedule(endpoint.clone(), json!("third"), now + Duration::from_secs(30));
+/// let first = scheduler.schedule(endpoint.clone(), json!("first"), now + Duration::from_secs(10));
+/// let second = scheduler.schedule(endpoint.clone(), json!("second"), now + Duration::from_secs(20));
+///
+/// assert!(scheduler.cancel(second));
+/// assert!(scheduler.take_due(now).is_empty());
+///
+/// let due = scheduler.take_due(now + Duration::from_secs(60));
+///
+/// assert_eq!(due.len(), 2);
+/// assert_eq!((due[0].id, &due[0].message), (first, &json!("first")));
+/// assert_eq!((due[1].id, &due[1].message), (third, &json!("third")));
+///
+/// assert!(scheduler.is_empty());
+/// ```
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    timer: Mutex<BinaryHeap<Reverse<(SystemTime, ScheduleId)>>>,
+    messages: Mutex<HashMap<ScheduleId, ScheduledMessage>>,
+    next_id: AtomicU64,
+    changed: AtomicBool,
+    fire_missed: bool
+}
+
+impl Scheduler {
+    /// Create new scheduler.
+    ///
+    /// If `fire_missed` is set, loaded messages whose time has
+    /// passed while the client was offline are sent immediately,
+    /// otherwise they're dropped.
+    pub fn new(fire_missed: bool) -> Self {
+        Self {
+            fire_missed,
+            ..Self::default()
+        }
+    }
+
+    #[inline]
+    pub fn fire_missed(&self) -> bool {
+        self.fire_missed
+    }
+
+    /// Schedule serialized message to be sent at the given time.
+    pub fn schedule(&self, endpoint: ClientEndpoint, message: Json, at: SystemTime) -> ScheduleId {
+        let id = ScheduleId(self.next_id.fetch_add(1, Ordering::Relaxed));
+
+        self.insert(ScheduledMessage {
+            id,
+            endpoint,
+            message,
+            at
+        });
+
+        id
+    }
+
+    fn insert(&self, message: ScheduledMessage) {
+        if let (Ok(mut timer), Ok(mut messages)) = (self.timer.lock(), self.messages.lock()) {
+            timer.push(Reverse((message.at, message.id)));
+            messages.insert(message.id, message);
+
+            self.changed.store(true, Ordering::Release);
+        }
+    }
+
+    /// Cancel scheduled message.
+    ///
+    /// Returns `false` if the message was already sent or canceled.
+    pub fn cancel(&self, id: ScheduleId) -> bool {
+        let removed = self.messages.lock()
+            .map(|mut messages| messages.remove(&id).is_some())
+            .unwrap_or_default();
+
+        if removed {
+            self.changed.store(true, Ordering::Release);
+        }
+
+        removed
+    }
+
+    /// Get scheduled message.
+    pub fn get(&self, id: ScheduleId) -> Option<ScheduledMessage> {
+        self.messages.lock().ok()?
+            .get(&id)
+            .cloned()
+    }
+
+    /// Time of the nearest scheduled message.
+    pub fn next_at(&self) -> Option<SystemTime> {
+        let messages = self.messages.lock().ok()?;
+
+        messages.values()
+            .map(|message| message.at)
+            .min()
+    }
+
+    /// Remove messages scheduled not later than `now`,
+    /// returning them in the order of their time.
+    pub fn take_due(&self, now: SystemTime) -> Vec<ScheduledMessage> {
+        let (Ok(mut timer), Ok(mut messages)) = (self.timer.lock(), self.messages.lock()) else {
+            return vec![];
+        };
+
+        let mut due = Vec::new();
+
+        while let Some(Reverse((at, id))) = timer.peek().copied() {
+            if at > now {
+                break;
+            }
+
+            timer.pop();
+
+            // Canceled messages are not in the map anymore
+            if let Some(message) = messages.remove(&id) {
+                due.push(message);
+            }
+        }
+
+        if !due.is_empty() {
+            self.changed.store(true, Ordering::Release);
+        }
+
+        due
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.lock()
+            .map(|messages| messages.len())
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Save scheduled messages to the state store
+    /// if they were changed since the last save.
+    pub async fn save(&self, store: &dyn StateStore) -> std::io::Result<()> {
+        if !self.changed.swap(false, Ordering::AcqRel) {
+            return Ok(());
+        }
+
+        let messages = self.messages.lock()
+            .map(|messages| {
+                messages.values()
+                    .map(ScheduledMessage::to_json)
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .unwrap_or_else(|_| Ok(vec![]))
+            .map_err(std::io::Error::other)?;
+
+        store.save_json(SCHEDULED_STATE_KEY, &messages).await
+    }
+
+    /// Load scheduled messages from the state store.
+    ///
+    /// Missed messages are dropped unless `fire_missed` is set.
+    pub async fn load(&self, store: &dyn StateStore) -> std::io::Result<()> {
+        let Some(loaded) = store.load_json::<Vec<Json>>(SCHEDULED_STATE_KEY).await? else {
+            return Ok(());
+        };
+
+        let now = SystemTime::now();
+
+        for message in loaded {
+            let Ok(message) = ScheduledMessage::from_json(&message) else {
+                continue;
+            };
+
+            // Don't reuse ids of the loaded messages
+            self.next_id.fetch_max(message.id.0 + 1, Ordering::Relaxed);
+
+            if message.at <= now && !self.fire_missed {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("[client] Dropped missed scheduled message {}", message.id);
+
+                continue;
+            }
+
+            self.insert(message);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde_json::Value as Json;
+
+/// Expected type of the envelope field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum FieldKind {
+    String,
+    U64,
+    I64,
+    F64,
+    Bool,
+    Object,
+    Array,
+    Any
+}
+
+impl FieldKind {
+    /// Check if the value has this type.
+    pub fn matches(&self, value: &Json) -> bool {
+        match self {
+            Self::String => value.is_string(),
+            Self::U64    => value.is_u64(),
+            Self::I64    => value.is_i64(),
+            Self::F64    => value.is_number(),
+            Self::Bool   => value.is_boolean(),
+            Self::Object => value.is_object(),
+            Self::Array  => value.is_array(),
+            Self::Any    => true
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::U64    => "u64",
+            Self::I64    => "i64",
+            Self::F64    => "f64",
+            Self::Bool   => "bool",
+            Self::Object => "object",
+            Self::Array  => "array",
+            Self::Any    => "any"
+        }
+    }
+}
+
+/// Get name of the value's type used in the schema violations.
+fn type_name(value: &Json) -> &'static str {
+    match value {
+        Json::Null      => "null",
+        Json::Bool(_)   => "bool",
+        Json::Number(_) => "number",
+        Json::String(_) => "string",
+        Json::Array(_)  => "array",
+        Json::Object(_) => "object"
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, thiserror::Error)]
+#[error("Field {field} must be {expected}, got {got}")]
+pub struct SchemaViolation {
+    pub field: String,
+    pub expected: String,
+    pub got: String
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct FieldRule {
+    kind: FieldKind,
+    required: bool,
+    nullable: bool
+}
+
+/// Schema of the incoming messages and requests.
+///
+/// Fields not listed in the schema are not validated.
+///
+/// ```rust
+/// use serde_json::json;
+///
+/// use hyperelm::client::EnvelopeSchema;
+///
+/// let schema = EnvelopeSchema::builder()
+///     .require_string("type")
+///     .require_u64("seq")
+///     .allow_null("metadata")
+///     .build();
+///
+/// assert!(schema.validate(&json!({ "type": "chat", "seq": 1 })).is_ok());
+/// assert!(schema.validate(&json!({ "type": "chat", "seq": 1, "metadata": null })).is_ok());
+///
+/// let violation = schema.validate(&json!({ "type": "chat", "seq": "1" })).unwrap_err();
+///
+/// assert_eq!(violation.field, "seq");
+/// assert_eq!(violation.expected, "u64");
+/// assert_eq!(violation.got, "string");
+///
+/// assert!(schema.validate(&json!({ "seq": 1 })).is_err());
+/// assert!(schema.validate(&json!([1, 2, 3])).is_err());
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnvelopeSchema {
+    fields: Vec<(String, FieldRule)>
+}
+
+impl EnvelopeSchema {
+    #[inline]
+    pub fn builder() -> EnvelopeSchemaBuilder {
+        EnvelopeSchemaBuilder::default()
+    }
+
+    /// Validate given value against the schema.
+    pub fn validate(&self, value: &Json) -> Result<(), SchemaViolation> {
+        let Some(object) = value.as_object() else {
+            return Err(SchemaViolation {
+                field: String::from("$"),
+                expected: String::from("object"),
+                got: type_name(value).to_string()
+            });
+        };
+
+        for (field, rule) in &self.fields {
+            match object.get(field) {
+                None if rule.required => {
+                    return Err(SchemaViolation {
+                        field: field.clone(),
+                        expected: rule.kind.name().to_string(),
+                        got: String::from("missing")
+                    });
+                }
+
+                Some(Json::Null) if rule.nullable => (),
+
+                Some(value) if !rule.kind.matches(value) => {
+                    return Err(SchemaViolation {
+                        field: field.clone(),
+                        expected: rule.kind.name().to_string(),
+                        got: type_name(value).to_string()
+                    });
+                }
+
+                _ => ()
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct EnvelopeSchemaBuilder {
+    fields: Vec<(String, FieldRule)>
+}
+
+impl EnvelopeSchemaBuilder {
+    fn rule(mut self, field: impl ToString, kind: FieldKind, required: bool) -> Self {
+        let field = field.to_string();
+
+        match self.fields.iter_mut().find(|(name, _)| name == &field) {
+            Some((_, rule)) => {
+                rule.kind = kind;
+                rule.required = required;
+            }
+
+            None => self.fields.push((field, FieldRule {
+                kind,
+                required,
+                nullable: false
+            }))
+        }
+
+        self
+    }
+
+    /// Require field of the given type.
+    #[inline]
+    pub fn require(self, field: impl ToString, kind: FieldKind) -> Self {
+        self.rule(field, kind, true)
+    }
+
+    /// Validate type of the field if it's present.
+    #[inline]
+    pub fn optional(self, field: impl ToString, kind: FieldKind) -> Self {
+        self.rule(field, kind, false)
+    }
+
+    #[inline]
+    pub fn require_string(self, field: impl ToString) -> Self {
+        self.require(field, FieldKind::String)
+    }
+
+    #[inline]
+    pub fn require_u64(self, field: impl ToString) -> Self {
+        self.require(field, FieldKind::U64)
+    }
+
+    #[inline]
+    pub fn require_i64(self, field: impl ToString) -> Self {
+        self.require(field, FieldKind::I64)
+    }
+
+    #[inline]
+    pub fn require_f64(self, field: impl ToString) -> Self {
+        self.require(field, FieldKind::F64)
+    }
+
+    #[inline]
+    pub fn require_bool(self, field: impl ToString) -> Self {
+        self.require(field, FieldKind::Bool)
+    }
+
+    #[inline]
+    pub fn require_object(self, field: impl ToString) -> Self {
+        self.require(field, FieldKind::Object)
+    }
+
+    #[inline]
+    pub fn require_array(self, field: impl ToString) -> Self {
+        self.require(field, FieldKind::Array)
+    }
+
+    /// Allow field to be null.
+    ///
+    /// Field which is not listed in the schema
+    /// becomes optional and can have any type.
+    pub fn allow_null(mut self, field: impl ToString) -> Self {
+        let field = field.to_string();
+
+        match self.fields.iter_mut().find(|(name, _)| name == &field) {
+            Some((_, rule)) => rule.nullable = true,
+
+            None => self.fields.push((field, FieldRule {
+                kind: FieldKind::Any,
+                required: false,
+                nullable: true
+            }))
+        }
+
+        self
+    }
+
+    #[inline]
+    pub fn build(self) -> EnvelopeSchema {
+        EnvelopeSchema {
+            fields: self.fields
+        }
+    }
+}
+
+/// Statistics of the incoming messages
+/// rejected by the envelope schema.
+#[derive(Debug, Default)]
+pub struct InvalidMessageStats {
+    total: AtomicU64,
+    fields: Mutex<HashMap<String, u64>>
+}
+
+impl InvalidMessageStats {
+    /// Count rejected message.
+    pub fn record(&self, violation: &SchemaViolation) {
+        self.total.fetch_add(1, Ordering::Relaxed);
+
+        if let Ok(mut fields) = self.fields.lock() {
+            *fields.entry(violation.field.clone()).or_default() += 1;
+        }
+    }
+
+    /// Total amount of rejected messages.
+    #[inline]
+    pub fn total(&self) -> u64 {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    /// Amount of messages rejected because of the given field.
+    pub fn field(&self, field: &str) -> u64 {
+        self.fields.lock()
+            .map(|fields| fields.get(field).copied().unwrap_or_default())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,150 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::Value as Json;
+
+use sqlx::{Connection, Row};
+use sqlx::sqlite::{SqliteConnection, SqliteConnectOptions};
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use super::{ClientEndpoint, Direction, MessageArchive, MessageId};
+
+const CREATE_TABLE: &str = "
+    CREATE TABLE IF NOT EXISTS messages (
+        id        INTEGER,
+        direction TEXT NOT NULL,
+        endpoint  TEXT NOT NULL,
+        channel   TEXT,
+        content   TEXT NOT NULL,
+        timestamp INTEGER NOT NULL
+    );
+
+    CREATE INDEX IF NOT EXISTS messages_endpoint
+        ON messages (endpoint, timestamp);
+";
+
+/// Message stored in the `SqliteMessageArchive`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchivedMessage {
+    /// Id of the sent message. Not known for received ones.
+    pub id: Option<MessageId>,
+
+    pub direction: Direction,
+
+    /// Public key of the sender or the receiver.
+    pub endpoint: PublicKey,
+
+    /// Channel of the received message.
+    pub channel: Option<String>,
+
+    pub content: Json,
+
+    /// UTC timestamp of the record in seconds.
+    pub timestamp: u64
+}
+
+/// Message archive stored in the SQLite database
+/// on the given path.
+///
+/// The database and its `messages` table are created
+/// on the first write. Failed records are logged.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SqliteMessageArchive(pub PathBuf);
+
+impl SqliteMessageArchive {
+    async fn connect(&self) -> Result<SqliteConnection, sqlx::Error> {
+        let options = SqliteConnectOptions::new()
+            .filename(&self.0)
+            .create_if_missing(true);
+
+        let mut connection = SqliteConnection::connect_with(&options).await?;
+
+        sqlx::raw_sql(CREATE_TABLE)
+            .execute(&mut connection).await?;
+
+        Ok(connection)
+    }
+
+    async fn insert(
+        &self,
+        id: Option<MessageId>,
+        direction: Direction,
+        endpoint: &PublicKey,
+        channel: Option<&str>,
+        content: &Json
+    ) -> Result<(), sqlx::Error> {
+        let mut connection = self.connect().await?;
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        sqlx::query("INSERT INTO messages (id, direction, endpoint, channel, content, timestamp) VALUES (?, ?, ?, ?, ?, ?)")
+            .bind(id.map(|id| id.0 as i64))
+            .bind(direction.to_string())
+            .bind(endpoint.to_base64())
+            .bind(channel)
+            .bind(content.to_string())
+            .bind(timestamp as i64)
+            .execute(&mut connection).await?;
+
+        Ok(())
+    }
+
+    /// Get messages received from the sender since
+    /// the given UTC timestamp in seconds, oldest first.
+    pub async fn query_by_sender(&self, sender: &PublicKey, since: u64, limit: u64) -> Result<Vec<ArchivedMessage>, sqlx::Error> {
+        let mut connection = self.connect().await?;
+
+        let rows = sqlx::query("SELECT id, endpoint, channel, content, timestamp FROM messages WHERE direction = ? AND endpoint = ? AND timestamp >= ? ORDER BY timestamp LIMIT ?")
+            .bind(Direction::Incoming.to_string())
+            .bind(sender.to_base64())
+            .bind(since as i64)
+            .bind(limit as i64)
+            .fetch_all(&mut connection).await?;
+
+        let messages = rows.into_iter()
+            .filter_map(|row| {
+                Some(ArchivedMessage {
+                    id: row.try_get::<Option<i64>, _>("id").ok()?
+                        .map(|id| MessageId(id as u64)),
+
+                    direction: Direction::Incoming,
+                    endpoint: sender.clone(),
+                    channel: row.try_get("channel").ok()?,
+
+                    content: row.try_get::<String, _>("content").ok()
+                        .and_then(|content| serde_json::from_str(&content).ok())?,
+
+                    timestamp: row.try_get::<i64, _>("timestamp").ok()? as u64
+                })
+            })
+            .collect();
+
+        Ok(messages)
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageArchive for SqliteMessageArchive {
+    async fn archive_sent(&self, endpoint: &ClientEndpoint, message: &Json, id: MessageId) {
+        let result = self.insert(Some(id), Direction::Outgoing, &endpoint.client_public, None, message).await;
+
+        if let Err(_err) = result {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("[client] Failed to archive sent message: {_err}");
+        }
+    }
+
+    async fn archive_received(&self, info: &MessageInfo, message: &Json) {
+        let result = self.insert(None, Direction::Incoming, &info.sender.client.public_key, Some(&info.channel), message).await;
+
+        if let Err(_err) = result {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("[client] Failed to archive received message: {_err}");
+        }
+    }
+}
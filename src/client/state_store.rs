@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use hyperborealib::exports::tokio;
+
+/// Storage of the client application state.
+#[async_trait::async_trait]
+pub trait StateStore: Send + Sync {
+    /// Store value under the given key.
+    async fn save(&self, key: &str, value: &[u8]) -> std::io::Result<()>;
+
+    /// Load value stored under the given key.
+    async fn load(&self, key: &str) -> std::io::Result<Option<Vec<u8>>>;
+
+    /// Remove value stored under the given key.
+    async fn delete(&self, key: &str) -> std::io::Result<()>;
+}
+
+impl std::fmt::Debug for dyn StateStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("StateStore")
+    }
+}
+
+/// JSON helpers implemented for all the state stores.
+#[async_trait::async_trait]
+pub trait StateStoreExt: StateStore {
+    /// Serialize value to JSON and store it under the given key.
+    async fn save_json<T>(&self, key: &str, value: &T) -> std::io::Result<()>
+    where
+        T: serde::Serialize + Sync
+    {
+        self.save(key, &serde_json::to_vec(value)?).await
+    }
+
+    /// Load JSON value stored under the given key and deserialize it.
+    async fn load_json<T>(&self, key: &str) -> std::io::Result<Option<T>>
+    where
+        T: serde::de::DeserializeOwned
+    {
+        match self.load(key).await? {
+            Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+            None => Ok(None)
+        }
+    }
+}
+
+impl<T: StateStore + ?Sized> StateStoreExt for T {}
+
+/// State store keeping values in memory.
+#[derive(Debug, Default)]
+pub struct MemoryStateStore {
+    values: Mutex<HashMap<String, Vec<u8>>>
+}
+
+#[async_trait::async_trait]
+impl StateStore for MemoryStateStore {
+    async fn save(&self, key: &str, value: &[u8]) -> std::io::Result<()> {
+        if let Ok(mut values) = self.values.lock() {
+            values.insert(key.to_string(), value.to_vec());
+        }
+
+        Ok(())
+    }
+
+    async fn load(&self, key: &str) -> std::io::Result<Option<Vec<u8>>> {
+        Ok(self.values.lock().ok()
+            .and_then(|values| values.get(key).cloned()))
+    }
+
+    async fn delete(&self, key: &str) -> std::io::Result<()> {
+        if let Ok(mut values) = self.values.lock() {
+            values.remove(key);
+        }
+
+        Ok(())
+    }
+}
+
+/// State store keeping every value in
+/// a separate file of the given folder.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FileStateStore(pub PathBuf);
+
+impl FileStateStore {
+    /// Get path to the file storing value with given key.
+    ///
+    /// Key characters which can't be safely used
+    /// in file names are percent-encoded.
+    pub fn path(&self, key: &str) -> PathBuf {
+        let mut name = String::with_capacity(key.len());
+
+        for byte in key.bytes() {
+            if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+                name.push(byte as char);
+            } else {
+                name.push_str(&format!("%{byte:02X}"));
+            }
+        }
+
+        self.0.join(name)
+    }
+}
+
+#[async_trait::async_trait]
+impl StateStore for FileStateStore {
+    async fn save(&self, key: &str, value: &[u8]) -> std::io::Result<()> {
+        let path = self.path(key);
+        let temp = path.with_extension("tmp");
+
+        tokio::fs::create_dir_all(&self.0).await?;
+
+        // Write to a temporary file first so the
+        // value is never left partially written
+        tokio::fs::write(&temp, value).await?;
+        tokio::fs::rename(temp, path).await
+    }
+
+    async fn load(&self, key: &str) -> std::io::Result<Option<Vec<u8>>> {
+        match tokio::fs::read(self.path(key)).await {
+            Ok(value) => Ok(Some(value)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err)
+        }
+    }
+
+    async fn delete(&self, key: &str) -> std::io::Result<()> {
+        match tokio::fs::remove_file(self.path(key)).await {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+            _ => Ok(())
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Value as Json};
+
+use opentelemetry::{global, Context, KeyValue};
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::{SpanKind, TraceContextExt, Tracer};
+
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+
+use hyperborealib::rest_api::prelude::*;
+
+/// Name of the envelope field storing the trace context.
+pub const TRACE_CONTEXT_FIELD: &str = "trace_context";
+
+/// Inject the current OpenTelemetry context into the envelope
+/// as a W3C TraceContext `traceparent` and `tracestate` fields.
+pub fn inject_trace_context(envelope: &mut Json) {
+    let mut fields = HashMap::<String, String>::new();
+
+    TraceContextPropagator::new().inject_context(&Context::current(), &mut fields);
+
+    // Nothing to inject if there's no active span
+    if fields.is_empty() {
+        return;
+    }
+
+    if let Some(envelope) = envelope.as_object_mut() {
+        envelope.insert(TRACE_CONTEXT_FIELD.to_string(), json!(fields));
+    }
+}
+
+/// Extract OpenTelemetry context from the envelope.
+///
+/// Returns empty context if the envelope doesn't have
+/// a valid `trace_context` field.
+pub fn extract_trace_context(envelope: &Json) -> Context {
+    let fields = envelope.get(TRACE_CONTEXT_FIELD)
+        .and_then(Json::as_object)
+        .map(|fields| {
+            fields.iter()
+                .filter_map(|(key, value)| {
+                    value.as_str().map(|value| (key.clone(), value.to_string()))
+                })
+                .collect::<HashMap<String, String>>()
+        })
+        .unwrap_or_default();
+
+    TraceContextPropagator::new().extract(&fields)
+}
+
+/// Create context with a child span of the current
+/// context, used to run the handlers.
+pub(crate) fn handler_context(name: &'static str, info: &MessageInfo) -> Context {
+    let parent = Context::current();
+    let tracer = global::tracer("hyperelm");
+
+    let span = tracer.span_builder(name)
+        .with_kind(SpanKind::Consumer)
+        .with_attributes([
+            KeyValue::new("hyperelm.sender", info.sender.client.public_key.to_base64()),
+            KeyValue::new("hyperelm.sender_server", info.sender.server.address.clone())
+        ])
+        .start_with_context(&tracer, &parent);
+
+    parent.with_span(span)
+}
@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use hyperborealib::rest_api::prelude::*;
+
+use super::*;
+
+/// Default maximal time to live of the topic subscribers.
+pub const DEFAULT_TOPIC_MAX_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Copy)]
+struct SubscriberState {
+    expires_at: Instant,
+    failures: u32
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SubscriptionState {
+    ttl: Duration,
+    renew_at: Instant
+}
+
+/// Registry of the pub/sub topics.
+///
+/// Stores subscribers of the topics published by the current
+/// client, and subscriptions of the current client to the
+/// topics of other clients.
+#[derive(Debug)]
+pub struct TopicRegistry {
+    subscribers: Mutex<HashMap<String, HashMap<ClientEndpoint, SubscriberState>>>,
+    subscriptions: Mutex<HashMap<(String, ClientEndpoint), SubscriptionState>>,
+    max_failures: u32,
+    max_ttl: Duration
+}
+
+impl Default for TopicRegistry {
+    #[inline]
+    fn default() -> Self {
+        Self::new(3, DEFAULT_TOPIC_MAX_TTL)
+    }
+}
+
+impl TopicRegistry {
+    /// Create new topics registry.
+    ///
+    /// Subscribers are removed after `max_failures`
+    /// failed deliveries in a row, and their TTL
+    /// is limited by `max_ttl`.
+    pub fn new(max_failures: u32, max_ttl: Duration) -> Self {
+        Self {
+            subscribers: Mutex::new(HashMap::new()),
+            subscriptions: Mutex::new(HashMap::new()),
+            max_failures,
+            max_ttl
+        }
+    }
+
+    #[inline]
+    pub fn max_failures(&self) -> u32 {
+        self.max_failures
+    }
+
+    #[inline]
+    pub fn max_ttl(&self) -> Duration {
+        self.max_ttl
+    }
+
+    /// Add subscriber to the topic or renew its subscription.
+    ///
+    /// TTL is requested by the subscriber, so it's
+    /// limited by the `max_ttl` of the registry.
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+    /// use hyperelm::client::{TopicRegistry, ClientEndpoint};
+    ///
+    /// let registry = TopicRegistry::new(3, Duration::from_secs(60));
+    /// let subscriber = ClientEndpoint::new("127.0.0.1:8001", SecretKey::random().public());
+    ///
+    /// registry.add_subscriber("news", subscriber.clone(), Duration::MAX);
+    ///
+    /// assert_eq!(registry.subscribers("news"), vec![subscriber]);
+    /// ```
+    pub fn add_subscriber(&self, topic: impl ToString, subscriber: ClientEndpoint, ttl: Duration) {
+        let now = Instant::now();
+
+        let expires_at = now.checked_add(ttl.min(self.max_ttl))
+            .unwrap_or(now);
+
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.entry(topic.to_string())
+                .or_default()
+                .insert(subscriber, SubscriberState {
+                    expires_at,
+                    failures: 0
+                });
+        }
+    }
+
+    pub fn remove_subscriber(&self, topic: &str, subscriber: &ClientEndpoint) {
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            if let Some(topic_subscribers) = subscribers.get_mut(topic) {
+                topic_subscribers.remove(subscriber);
+
+                if topic_subscribers.is_empty() {
+                    subscribers.remove(topic);
+                }
+            }
+        }
+    }
+
+    /// Get list of not expired subscribers of the topic.
+    pub fn subscribers(&self, topic: &str) -> Vec<ClientEndpoint> {
+        let Ok(mut subscribers) = self.subscribers.lock() else {
+            return vec![];
+        };
+
+        let Some(topic_subscribers) = subscribers.get_mut(topic) else {
+            return vec![];
+        };
+
+        let now = Instant::now();
+
+        topic_subscribers.retain(|_, state| state.expires_at > now);
+
+        topic_subscribers.keys().cloned().collect()
+    }
+
+    /// Report message delivery result to the subscriber.
+    ///
+    /// Returns `false` if the subscriber was removed
+    /// because of too many failed deliveries.
+    pub fn report_delivery(&self, topic: &str, subscriber: &ClientEndpoint, delivered: bool) -> bool {
+        let Ok(mut subscribers) = self.subscribers.lock() else {
+            return true;
+        };
+
+        let Some(topic_subscribers) = subscribers.get_mut(topic) else {
+            return false;
+        };
+
+        let Some(state) = topic_subscribers.get_mut(subscriber) else {
+            return false;
+        };
+
+        if delivered {
+            state.failures = 0;
+
+            return true;
+        }
+
+        state.failures += 1;
+
+        if state.failures >= self.max_failures {
+            topic_subscribers.remove(subscriber);
+
+            return false;
+        }
+
+        true
+    }
+
+    /// Remember subscription of the current client.
+    ///
+    /// It will be renewed after half of its TTL.
+    pub fn add_subscription(&self, topic: impl ToString, provider: ClientEndpoint, ttl: Duration) {
+        let now = Instant::now();
+
+        let renew_at = now.checked_add(ttl / 2)
+            .unwrap_or(now);
+
+        if let Ok(mut subscriptions) = self.subscriptions.lock() {
+            subscriptions.insert((topic.to_string(), provider), SubscriptionState {
+                ttl,
+                renew_at
+            });
+        }
+    }
+
+    pub fn remove_subscription(&self, topic: &str, provider: &ClientEndpoint) {
+        if let Ok(mut subscriptions) = self.subscriptions.lock() {
+            subscriptions.remove(&(topic.to_string(), provider.clone()));
+        }
+    }
+
+    /// List subscriptions of the current client.
+    pub fn subscriptions(&self) -> Vec<(String, ClientEndpoint)> {
+        self.subscriptions.lock()
+            .map(|subscriptions| subscriptions.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// List subscriptions which should be renewed now
+    /// with their TTLs.
+    pub fn expiring_subscriptions(&self) -> Vec<(String, ClientEndpoint, Duration)> {
+        let now = Instant::now();
+
+        self.subscriptions.lock()
+            .map(|subscriptions| {
+                subscriptions.iter()
+                    .filter(|(_, state)| state.renew_at <= now)
+                    .map(|((topic, provider), state)| (topic.clone(), provider.clone(), state.ttl))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Subscribe to the topic of the provider and remember
+/// the subscription to renew it before its TTL elapses.
+pub(crate) async fn subscribe<T: ClientApp + Sync + ?Sized>(client: &T, topic: &str, provider: ClientEndpoint) -> Result<(), ClientAppError<T::Error>> {
+    let params = client.get_params();
+    let runtime = client.get_runtime();
+    let middleware = client.get_connected_middleware().await?;
+
+    let envelope = json!({
+        "subscribe": {
+            "topic": topic,
+            "ttl": params.topic_ttl.as_secs()
+        }
+    });
+
+    client.send_envelope(&middleware, &provider, &params.channel, &envelope).await?;
+
+    runtime.topics.add_subscription(topic, provider, params.topic_ttl);
+
+    Ok(())
+}
+
+/// Forget the subscription and ask the provider
+/// to remove the client from the topic subscribers.
+pub(crate) async fn unsubscribe<T: ClientApp + Sync + ?Sized>(client: &T, topic: &str, provider: ClientEndpoint) -> Result<(), ClientAppError<T::Error>> {
+    let params = client.get_params();
+    let runtime = client.get_runtime();
+    let middleware = client.get_connected_middleware().await?;
+
+    runtime.topics.remove_subscription(topic, &provider);
+
+    let envelope = json!({
+        "unsubscribe": {
+            "topic": topic
+        }
+    });
+
+    client.send_envelope(&middleware, &provider, &params.channel, &envelope).await
+}
+
+/// Renew expiring subscriptions of the client.
+pub(crate) async fn renew_subscriptions<T: ClientApp + Sync + ?Sized>(client: &T) -> usize
+where
+    T::Error: std::fmt::Display
+{
+    let runtime = client.get_runtime();
+
+    let mut renewed = 0;
+
+    for (topic, provider, _) in runtime.topics.expiring_subscriptions() {
+        match client.subscribe(&topic, provider).await {
+            Ok(()) => renewed += 1,
+
+            Err(_err) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!("[client] Failed to renew subscription to the topic {topic}: {_err}");
+            }
+        }
+    }
+
+    renewed
+}
+
+/// Send message to the topic subscribers
+/// which don't exceed the rate limit.
+pub(crate) async fn publish<T: ClientApp + Sync + ?Sized>(client: &T, topic: &str, message: T::OutputMessage) -> Result<usize, ClientAppError<T::Error>> {
+    let params = client.get_params();
+    let runtime = client.get_runtime();
+    let middleware = client.get_connected_middleware().await?;
+
+    let envelope = json!({
+        "message": message.to_json()?,
+        "priority": DEFAULT_PRIORITY,
+        "topic": topic,
+        "nonce": monotonic_nonce()
+    });
+
+    let mut delivered = 0;
+
+    for subscriber in runtime.topics.subscribers(topic) {
+        // Skip subscribers exceeding the rate limit
+        // without counting it as a delivery failure
+        if let Err(_err) = client.throttle(&subscriber).await {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("[client] Skipped topic {topic} subscriber: {_err}");
+
+            continue;
+        }
+
+        let mut envelope = envelope.clone();
+
+        for interceptor in &params.send_interceptors {
+            interceptor.before_send(&mut envelope, &subscriber).await
+                .map_err(ClientAppError::Interceptor)?;
+        }
+
+        let result = client.send_envelope(&middleware, &subscriber, &params.channel, &envelope).await;
+
+        if result.is_ok() {
+            delivered += 1;
+        }
+
+        if !runtime.topics.report_delivery(topic, &subscriber, result.is_ok()) {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("[client] Removed dead subscriber of the topic {topic}");
+        }
+    }
+
+    Ok(delivered)
+}
@@ -0,0 +1,587 @@
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value as Json};
+use sha2::{Digest, Sha256};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+
+use hyperborealib::exports::tokio;
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use super::*;
+
+/// Default size of the file transfer chunk.
+pub const DEFAULT_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Default maximal size of the received file.
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Description of the transferred file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct FileManifest {
+    /// Id of the transfer.
+    ///
+    /// Derived from the file content, name and chunk size
+    /// so the same file gets the same id when it's sent again.
+    pub id: u64,
+
+    /// Name of the file.
+    pub name: String,
+
+    /// Size of the file in bytes.
+    pub size: u64,
+
+    /// Hex encoded sha256 hash of the file content.
+    pub sha256: String,
+
+    /// Size of the file chunks in bytes.
+    pub chunk_size: u64
+}
+
+impl FileManifest {
+    /// Read file and build its manifest.
+    pub fn from_file(path: impl AsRef<Path>, chunk_size: u64) -> std::io::Result<Self> {
+        let path = path.as_ref();
+
+        if chunk_size == 0 {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "chunk size can't be zero"));
+        }
+
+        let name = path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let mut file = File::open(path)?;
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0; 64 * 1024];
+        let mut size = 0;
+
+        loop {
+            let read = file.read(&mut buf)?;
+
+            if read == 0 {
+                break;
+            }
+
+            hasher.update(&buf[..read]);
+
+            size += read as u64;
+        }
+
+        let sha256 = hex(&hasher.finalize());
+
+        // Derive transfer id
+        let mut hasher = Sha256::new();
+
+        hasher.update(sha256.as_bytes());
+        hasher.update(name.as_bytes());
+        hasher.update(chunk_size.to_be_bytes());
+
+        let hash = hasher.finalize();
+
+        let mut id = [0; 8];
+
+        id.copy_from_slice(&hash[..8]);
+
+        Ok(Self {
+            id: u64::from_be_bytes(id),
+            name,
+            size,
+            sha256,
+            chunk_size
+        })
+    }
+
+    /// Amount of chunks in the file.
+    pub fn chunks(&self) -> u64 {
+        self.size.div_ceil(self.chunk_size.max(1))
+    }
+
+    /// Expected length of the chunk with given index.
+    ///
+    /// Returns `None` if the index is out of range.
+    pub fn chunk_len(&self, index: u64) -> Option<u64> {
+        if index >= self.chunks() {
+            return None;
+        }
+
+        Some(self.chunk_size.min(self.size - index * self.chunk_size))
+    }
+
+    /// Read chunk with given index from the file.
+    pub fn read_chunk(&self, path: impl AsRef<Path>, index: u64) -> std::io::Result<Vec<u8>> {
+        let Some(len) = self.chunk_len(index) else {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "chunk index is out of range"));
+        };
+
+        let mut file = File::open(path)?;
+        let mut chunk = vec![0; len as usize];
+
+        file.seek(SeekFrom::Start(index * self.chunk_size))?;
+        file.read_exact(&mut chunk)?;
+
+        Ok(chunk)
+    }
+}
+
+/// Options of the outgoing file transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TransferOptions {
+    /// Size of the file chunks in bytes.
+    pub chunk_size: u64,
+
+    /// Time to wait for the receiver to report
+    /// already received chunks.
+    ///
+    /// `send_file` fails with the `Timeout` error
+    /// if the receiver doesn't respond in time.
+    pub status_timeout: Duration
+}
+
+impl Default for TransferOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            status_timeout: Duration::from_secs(10)
+        }
+    }
+}
+
+/// Progress of the file transfer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferProgress {
+    /// Id of the transfer.
+    pub transfer: u64,
+
+    /// Public key of the other side of the transfer.
+    pub peer: PublicKey,
+
+    /// Whether the file is received or sent.
+    pub direction: Direction,
+
+    /// Amount of transferred chunks.
+    pub transferred_chunks: u64,
+
+    /// Total amount of chunks in the file.
+    pub total_chunks: u64
+}
+
+impl TransferProgress {
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.transferred_chunks >= self.total_chunks
+    }
+
+    /// Transferred part of the file, from 0.0 to 1.0.
+    pub fn ratio(&self) -> f64 {
+        if self.total_chunks == 0 {
+            return 1.0;
+        }
+
+        self.transferred_chunks as f64 / self.total_chunks as f64
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct TransferState {
+    manifest: FileManifest,
+    chunks: Vec<u64>
+}
+
+/// Storage of the incoming file transfers.
+///
+/// Chunks are written to the `.{key}.part` file in the download
+/// folder, and indexes of the received chunks are kept in
+/// the `.{key}.json` file, so interrupted transfers can be
+/// resumed even after the application restart.
+///
+/// ```rust
+/// use hyperelm::client::{FileManifest, FileTransfers};
+///
+/// use hyperborealib::crypto::prelude::*;
+///
+/// let folder = std::env::temp_dir().join("hyperelm-transfers-doctest");
+/// let source = std::env::temp_dir().join("hyperelm-transfers-doctest.bin");
+///
+/// let _ = std::fs::remove_dir_all(&folder);
+///
+/// std::fs::write(&source, (0..2 * 1024 * 1024).map(|i| i as u8).collect::<Vec<_>>()).unwrap();
+///
+/// let sender = SecretKey::random().public();
+/// let manifest = FileManifest::from_file(&source, 64 * 1024).unwrap();
+///
+/// // Receive 40% of the file
+/// let transfers = FileTransfers::new(&folder);
+///
+/// assert!(transfers.start(&sender, &manifest).unwrap().is_empty());
+///
+/// for i in 0..manifest.chunks() * 2 / 5 {
+///     let chunk = manifest.read_chunk(&source, i).unwrap();
+///
+///     transfers.write_chunk(&sender, manifest.id, i, &chunk).unwrap();
+/// }
+///
+/// // Resume the transfer after restart
+/// let transfers = FileTransfers::new(&folder);
+///
+/// let received = transfers.start(&sender, &manifest).unwrap();
+///
+/// assert_eq!(received.len() as u64, manifest.chunks() * 2 / 5);
+///
+/// for i in (0..manifest.chunks()).filter(|i| !received.contains(i)) {
+///     let chunk = manifest.read_chunk(&source, i).unwrap();
+///
+///     transfers.write_chunk(&sender, manifest.id, i, &chunk).unwrap();
+/// }
+///
+/// let (path, _) = transfers.finish(&sender, manifest.id).unwrap();
+///
+/// assert_eq!(std::fs::read(path).unwrap(), std::fs::read(&source).unwrap());
+/// ```
+#[derive(Debug)]
+pub struct FileTransfers {
+    folder: PathBuf,
+    max_file_size: u64,
+    lock: Mutex<()>
+}
+
+impl FileTransfers {
+    pub fn new(folder: impl Into<PathBuf>) -> Self {
+        Self {
+            folder: folder.into(),
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            lock: Mutex::new(())
+        }
+    }
+
+    /// Reject files larger than given size in bytes.
+    pub fn with_max_file_size(mut self, size: u64) -> Self {
+        self.max_file_size = size;
+
+        self
+    }
+
+    /// Folder where received files are stored.
+    #[inline]
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+
+    /// Maximal size of the received file in bytes.
+    #[inline]
+    pub fn max_file_size(&self) -> u64 {
+        self.max_file_size
+    }
+
+    #[inline]
+    fn key(sender: &PublicKey, transfer: u64) -> String {
+        format!("{:016x}", message_id(sender, b"file", transfer))
+    }
+
+    fn state_path(&self, sender: &PublicKey, transfer: u64) -> PathBuf {
+        self.folder.join(format!(".{}.json", Self::key(sender, transfer)))
+    }
+
+    fn part_path(&self, sender: &PublicKey, transfer: u64) -> PathBuf {
+        self.folder.join(format!(".{}.part", Self::key(sender, transfer)))
+    }
+
+    fn read_state(&self, sender: &PublicKey, transfer: u64) -> std::io::Result<Option<TransferState>> {
+        match std::fs::read(self.state_path(sender, transfer)) {
+            Ok(state) => Ok(Some(serde_json::from_slice(&state)?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err)
+        }
+    }
+
+    fn write_state(&self, sender: &PublicKey, state: &TransferState) -> std::io::Result<()> {
+        let path = self.state_path(sender, state.manifest.id);
+        let temp = path.with_extension("tmp");
+
+        std::fs::write(&temp, serde_json::to_vec(state)?)?;
+        std::fs::rename(temp, path)
+    }
+
+    /// Register incoming file transfer.
+    ///
+    /// Returns indexes of already received chunks
+    /// if the transfer was started before.
+    pub fn start(&self, sender: &PublicKey, manifest: &FileManifest) -> std::io::Result<Vec<u64>> {
+        if manifest.size > self.max_file_size {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "file is too large"));
+        }
+
+        let _lock = self.lock.lock()
+            .map_err(|_| std::io::Error::other("file transfers lock is poisoned"))?;
+
+        if let Some(state) = self.read_state(sender, manifest.id)? {
+            if &state.manifest == manifest {
+                return Ok(state.chunks);
+            }
+        }
+
+        std::fs::create_dir_all(&self.folder)?;
+
+        File::create(self.part_path(sender, manifest.id))?
+            .set_len(manifest.size)?;
+
+        self.write_state(sender, &TransferState {
+            manifest: manifest.clone(),
+            chunks: Vec::new()
+        })?;
+
+        Ok(Vec::new())
+    }
+
+    /// Get indexes of the received chunks of the transfer.
+    pub fn received_chunks(&self, sender: &PublicKey, transfer: u64) -> std::io::Result<Vec<u64>> {
+        let _lock = self.lock.lock()
+            .map_err(|_| std::io::Error::other("file transfers lock is poisoned"))?;
+
+        Ok(self.read_state(sender, transfer)?
+            .map(|state| state.chunks)
+            .unwrap_or_default())
+    }
+
+    /// Write received chunk of the transfer.
+    pub fn write_chunk(&self, sender: &PublicKey, transfer: u64, index: u64, chunk: &[u8]) -> std::io::Result<TransferProgress> {
+        let _lock = self.lock.lock()
+            .map_err(|_| std::io::Error::other("file transfers lock is poisoned"))?;
+
+        let Some(mut state) = self.read_state(sender, transfer)? else {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "unknown file transfer"));
+        };
+
+        if state.manifest.chunk_len(index) != Some(chunk.len() as u64) {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid file chunk"));
+        }
+
+        if !state.chunks.contains(&index) {
+            let mut file = OpenOptions::new()
+                .write(true)
+                .open(self.part_path(sender, transfer))?;
+
+            file.seek(SeekFrom::Start(index * state.manifest.chunk_size))?;
+            file.write_all(chunk)?;
+            file.flush()?;
+
+            state.chunks.push(index);
+
+            self.write_state(sender, &state)?;
+        }
+
+        Ok(TransferProgress {
+            transfer,
+            peer: sender.clone(),
+            direction: Direction::Incoming,
+            transferred_chunks: state.chunks.len() as u64,
+            total_chunks: state.manifest.chunks()
+        })
+    }
+
+    /// Verify hash of the completely received file and move
+    /// it to the download folder.
+    ///
+    /// Transfer is removed if the hash doesn't match.
+    pub fn finish(&self, sender: &PublicKey, transfer: u64) -> std::io::Result<(PathBuf, FileManifest)> {
+        let _lock = self.lock.lock()
+            .map_err(|_| std::io::Error::other("file transfers lock is poisoned"))?;
+
+        let Some(state) = self.read_state(sender, transfer)? else {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "unknown file transfer"));
+        };
+
+        if (state.chunks.len() as u64) < state.manifest.chunks() {
+            return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "file transfer is not completed"));
+        }
+
+        let part_path = self.part_path(sender, transfer);
+
+        // Verify file hash
+        let mut file = File::open(&part_path)?;
+        let mut hasher = Sha256::new();
+
+        std::io::copy(&mut file, &mut hasher)?;
+
+        if hex(&hasher.finalize()) != state.manifest.sha256 {
+            std::fs::remove_file(&part_path)?;
+            std::fs::remove_file(self.state_path(sender, transfer))?;
+
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "file hash mismatch"));
+        }
+
+        // Never let the sender choose the folder
+        let name = Path::new(&state.manifest.name)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .filter(|name| !name.starts_with('.'))
+            .unwrap_or_else(|| format!("{transfer:016x}"));
+
+        let mut path = self.folder.join(&name);
+
+        if path.exists() {
+            path = self.folder.join(format!("{transfer:016x}-{name}"));
+        }
+
+        std::fs::rename(part_path, &path)?;
+        std::fs::remove_file(self.state_path(sender, transfer))?;
+
+        Ok((path, state.manifest))
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Offer file to the endpoint and
+/// send chunks it doesn't have yet.
+pub(crate) async fn send_file<T: ClientApp + Sync + ?Sized>(client: &T, endpoint: ClientEndpoint, path: PathBuf, options: TransferOptions) -> Result<FileManifest, ClientAppError<T::Error>> {
+    let params = client.get_params();
+    let middleware = client.get_connected_middleware().await?;
+
+    let manifest = FileManifest::from_file(&path, options.chunk_size)
+        .map_err(ClientAppError::FileTransfer)?;
+
+    // Offer file to the receiver
+    let offer_id = safe_random_u64();
+    let session = client.get_runtime().inflight_requests.session();
+
+    let channel = params.channel.reply_to_session(offer_id, session);
+
+    client.send_envelope(&middleware, &endpoint, &params.channel, &json!({
+        "id": offer_id,
+        "file_offer": serde_json::to_value(&manifest)?,
+        "session": session
+    })).await?;
+
+    // Wait for already received chunks
+    let started_at = Instant::now();
+
+    let status = loop {
+        let (mut messages, _) = middleware.poll(&channel, Some(1)).await?;
+
+        if let Some(message) = messages.pop() {
+            break client.decode_incoming(&message).await?;
+        }
+
+        if started_at.elapsed() >= options.status_timeout {
+            return Err(ClientAppError::Timeout);
+        }
+
+        tokio::time::sleep(params.delay).await;
+    };
+
+    if status.get("file_rejected").and_then(Json::as_bool) == Some(true) {
+        return Err(ClientAppError::FileRejected);
+    }
+
+    let received = status.get("file_chunks")
+        .and_then(Json::as_array)
+        .map(|chunks| chunks.iter().filter_map(Json::as_u64).collect::<HashSet<_>>())
+        .unwrap_or_default();
+
+    // Send missing chunks
+    let mut progress = TransferProgress {
+        transfer: manifest.id,
+        peer: endpoint.client_public.clone(),
+        direction: Direction::Outgoing,
+        transferred_chunks: received.len() as u64,
+        total_chunks: manifest.chunks()
+    };
+
+    for index in (0..manifest.chunks()).filter(|index| !received.contains(index)) {
+        let chunk = manifest.read_chunk(&path, index)
+            .map_err(ClientAppError::FileTransfer)?;
+
+        client.send_envelope(&middleware, &endpoint, &params.channel, &json!({
+            "file_chunk": {
+                "transfer": manifest.id,
+                "index": index,
+                "data": BASE64.encode(chunk)
+            }
+        })).await?;
+
+        progress.transferred_chunks += 1;
+
+        client.on_transfer_progress(&progress).await;
+    }
+
+    Ok(manifest)
+}
+
+/// Respond to the file offer with indexes of the
+/// already received chunks, or reject the file.
+pub(crate) async fn answer_file_offer<T: ClientApp + Sync + ?Sized>(client: &T, message: MessageInfo, offer_id: u64, manifest: FileManifest, session: Option<u64>) -> Result<(), ClientAppError<T::Error>> {
+    let params = client.get_params();
+
+    let accepted = params.file_transfers.as_ref()
+        .filter(|transfers| manifest.size <= transfers.max_file_size())
+        .filter(|_| client.accept_file_offer(&manifest, &message));
+
+    let response = match accepted {
+        Some(transfers) => {
+            let chunks = transfers.start(&message.sender.client.public_key, &manifest)
+                .map_err(ClientAppError::FileTransfer)?;
+
+            json!({ "file_chunks": chunks })
+        }
+
+        None => {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("[client] Rejected file {} of {} bytes", manifest.name, manifest.size);
+
+            json!({ "file_rejected": true })
+        }
+    };
+
+    let middleware = client.get_connected_middleware().await?;
+
+    let endpoint = ClientEndpoint::new(
+        &message.sender.server.address,
+        message.sender.client.public_key.clone()
+    );
+
+    client.send_envelope(
+        &middleware,
+        &endpoint,
+        &params.channel.reply_for(offer_id, session),
+        &response
+    ).await?;
+
+    Ok(())
+}
+
+/// Store received chunk of the file and handle
+/// the file when all its chunks are received.
+pub(crate) async fn store_file_chunk<T: ClientApp + Sync + ?Sized>(client: &T, message: MessageInfo, transfer: u64, index: u64, data: Vec<u8>) -> Result<(), ClientAppError<T::Error>> {
+    let params = client.get_params();
+
+    if let Some(transfers) = &params.file_transfers {
+        let sender = &message.sender.client.public_key;
+
+        let progress = transfers.write_chunk(sender, transfer, index, &data)
+            .map_err(ClientAppError::FileTransfer)?;
+
+        client.on_transfer_progress(&progress).await;
+
+        if progress.is_complete() {
+            let (path, manifest) = transfers.finish(sender, transfer)
+                .map_err(ClientAppError::FileTransfer)?;
+
+            client.handle_file_received(path, manifest, message).await?;
+        }
+    }
+
+    Ok(())
+}
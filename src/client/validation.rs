@@ -0,0 +1,178 @@
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+
+/// Error of the incoming request or message validation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, thiserror::Error)]
+#[error("{message}")]
+pub struct ValidationError {
+    /// Name of the invalid field, if known.
+    pub field: Option<String>,
+
+    pub message: String
+}
+
+impl ValidationError {
+    #[inline]
+    pub fn new(message: impl ToString) -> Self {
+        Self {
+            field: None,
+            message: message.to_string()
+        }
+    }
+
+    #[inline]
+    pub fn field(field: impl ToString, message: impl ToString) -> Self {
+        Self {
+            field: Some(field.to_string()),
+            message: message.to_string()
+        }
+    }
+}
+
+/// Error reported by the remote client instead of the response.
+///
+/// Sent as `{ "remote_error": { "kind": "...", "message": "..." } }`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, thiserror::Error, serde::Serialize, serde::Deserialize)]
+#[error("Remote {kind} error: {message}")]
+pub struct RemoteError {
+    /// Kind of the error, e.g. `validation`.
+    pub kind: String,
+
+    pub message: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>
+}
+
+impl RemoteError {
+    #[inline]
+    pub fn new(kind: impl ToString, message: impl ToString) -> Self {
+        Self {
+            kind: kind.to_string(),
+            message: message.to_string(),
+            field: None
+        }
+    }
+}
+
+impl From<ValidationError> for RemoteError {
+    #[inline]
+    fn from(error: ValidationError) -> Self {
+        Self {
+            kind: String::from("validation"),
+            message: error.message,
+            field: error.field
+        }
+    }
+}
+
+/// Chain of simple validation rules.
+///
+/// First failed rule is returned by the `finish` method.
+///
+/// ```rust
+/// use hyperelm::client::Validator;
+///
+/// let validate = |name: &str, age: u8| {
+///     Validator::new()
+///         .non_empty("name", name)
+///         .max_len("name", name, 16)
+///         .in_range("age", age, 18..=150)
+///         .finish()
+/// };
+///
+/// assert!(validate("Amy", 20).is_ok());
+///
+/// assert_eq!(validate("", 20).unwrap_err().field.as_deref(), Some("name"));
+/// assert_eq!(validate("Amy", 12).unwrap_err().field.as_deref(), Some("age"));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Validator {
+    error: Option<ValidationError>
+}
+
+impl Validator {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply custom rule.
+    pub fn check(mut self, rule: impl FnOnce() -> Result<(), ValidationError>) -> Self {
+        if self.error.is_none() {
+            self.error = rule().err();
+        }
+
+        self
+    }
+
+    /// Require string to be non empty.
+    #[inline]
+    pub fn non_empty(self, field: &str, value: impl AsRef<str>) -> Self {
+        self.check(|| non_empty(field, value))
+    }
+
+    /// Require string to have at most `max` characters.
+    #[inline]
+    pub fn max_len(self, field: &str, value: impl AsRef<str>, max: usize) -> Self {
+        self.check(|| max_len(field, value, max))
+    }
+
+    /// Require number to be within the range.
+    #[inline]
+    pub fn in_range<T: PartialOrd + Display>(self, field: &str, value: T, range: RangeInclusive<T>) -> Self {
+        self.check(|| in_range(field, value, range))
+    }
+
+    /// Require list to have at most `max` items.
+    #[inline]
+    pub fn max_items<T>(self, field: &str, value: &[T], max: usize) -> Self {
+        self.check(|| max_items(field, value, max))
+    }
+
+    #[inline]
+    pub fn finish(self) -> Result<(), ValidationError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(())
+        }
+    }
+}
+
+/// Require string to be non empty.
+fn non_empty(field: &str, value: impl AsRef<str>) -> Result<(), ValidationError> {
+    if value.as_ref().trim().is_empty() {
+        return Err(ValidationError::field(field, format!("{field} must not be empty")));
+    }
+
+    Ok(())
+}
+
+/// Require string to have at most `max` characters.
+fn max_len(field: &str, value: impl AsRef<str>, max: usize) -> Result<(), ValidationError> {
+    let len = value.as_ref().chars().count();
+
+    if len > max {
+        return Err(ValidationError::field(field, format!("{field} must be at most {max} characters long, got {len}")));
+    }
+
+    Ok(())
+}
+
+/// Require number to be within the range.
+fn in_range<T: PartialOrd + Display>(field: &str, value: T, range: RangeInclusive<T>) -> Result<(), ValidationError> {
+    if !range.contains(&value) {
+        return Err(ValidationError::field(field, format!("{field} must be within {}..={}, got {value}", range.start(), range.end())));
+    }
+
+    Ok(())
+}
+
+/// Require list to have at most `max` items.
+fn max_items<T>(field: &str, value: &[T], max: usize) -> Result<(), ValidationError> {
+    if value.len() > max {
+        return Err(ValidationError::field(field, format!("{field} must have at most {max} items, got {}", value.len())));
+    }
+
+    Ok(())
+}
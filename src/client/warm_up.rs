@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use hyperborealib::crypto::prelude::*;
+
+use super::ClientEndpoint;
+
+/// Peers resolved by the client in advance so the first
+/// requests to them don't wait for the connection and lookups.
+///
+/// Warm up is performed by `run` after the `init` call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WarmUpPolicy {
+    /// Peers which should be resolved on startup.
+    pub peers: Vec<PublicKey>,
+
+    /// Maximal amount of peers resolved at the same time.
+    pub concurrency: usize,
+
+    /// Maximal time of the whole warm up.
+    pub timeout: Duration,
+
+    /// Ping resolved peers to open HTTP connections
+    /// to their servers.
+    pub ping: bool,
+
+    /// Repeat warm up of the failed peers with given
+    /// interval in the background updates task.
+    pub retry_interval: Option<Duration>
+}
+
+impl Default for WarmUpPolicy {
+    fn default() -> Self {
+        Self {
+            peers: vec![],
+            concurrency: 4,
+            timeout: Duration::from_secs(10),
+            ping: true,
+            retry_interval: None
+        }
+    }
+}
+
+/// Result of the peer warm up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WarmUpStatus {
+    /// Peer was found and responded to ping if it's enabled.
+    Resolved {
+        endpoint: ClientEndpoint,
+
+        /// Round trip time of the ping.
+        rtt: Option<Duration>
+    },
+
+    /// Peer was found but didn't respond to ping.
+    Unreachable {
+        endpoint: ClientEndpoint
+    },
+
+    /// Peer was not found in the network.
+    NotFound,
+
+    /// Peer was not resolved within the warm up timeout.
+    TimedOut,
+
+    /// Peer lookup failed.
+    Failed(String)
+}
+
+impl WarmUpStatus {
+    #[inline]
+    pub fn is_resolved(&self) -> bool {
+        matches!(self, Self::Resolved { .. })
+    }
+
+    /// Get endpoint of the found peer.
+    pub fn endpoint(&self) -> Option<&ClientEndpoint> {
+        match self {
+            Self::Resolved { endpoint, .. } |
+            Self::Unreachable { endpoint } => Some(endpoint),
+
+            _ => None
+        }
+    }
+}
+
+/// Results of the warm up of every listed peer.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WarmUpReport {
+    pub peers: Vec<(PublicKey, WarmUpStatus)>
+}
+
+impl WarmUpReport {
+    /// Get warm up result of the peer.
+    pub fn get(&self, peer: &PublicKey) -> Option<&WarmUpStatus> {
+        self.peers.iter()
+            .find(|(public_key, _)| public_key == peer)
+            .map(|(_, status)| status)
+    }
+
+    /// List peers which were not resolved.
+    pub fn failed(&self) -> Vec<PublicKey> {
+        self.peers.iter()
+            .filter(|(_, status)| !status.is_resolved())
+            .map(|(public_key, _)| public_key.clone())
+            .collect()
+    }
+
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.peers.iter().all(|(_, status)| status.is_resolved())
+    }
+}
@@ -0,0 +1,164 @@
+use std::future::Future;
+use std::net::SocketAddr;
+
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+/// DNS-SD service type of the hyperelm servers.
+pub const MDNS_SERVICE_TYPE: &str = "_hyperelm._tcp.local.";
+
+#[derive(Debug, thiserror::Error)]
+pub enum MdnsError {
+    #[error(transparent)]
+    Mdns(#[from] mdns_sd::Error),
+
+    #[error("Invalid server address: {0}")]
+    InvalidAddress(String),
+
+    #[error("Server has no remote addresses")]
+    NoAddresses
+}
+
+/// Zero-configuration discovery of the servers
+/// in the local network.
+///
+/// Announces `_hyperelm._tcp.local.` service with the server's
+/// addresses and public key, and reports servers announced by
+/// the others. The first address is used as the service's
+/// address, and all of them are listed in its `addresses`
+/// property.
+///
+/// ```rust,ignore
+/// let discovery = MdnsDiscovery::new(secret.public(), ["192.168.1.2:8001", "[fd00::2]:8001"])?;
+///
+/// discovery.start(|server| async move {
+///     router.index_server(server).await.ok();
+/// }).await?;
+/// ```
+pub struct MdnsDiscovery {
+    daemon: ServiceDaemon,
+    public_key: PublicKey,
+    address: SocketAddr,
+    addresses: Vec<String>
+}
+
+impl std::fmt::Debug for MdnsDiscovery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MdnsDiscovery")
+            .field("public_key", &self.public_key.to_base64())
+            .field("addresses", &self.addresses)
+            .finish()
+    }
+}
+
+impl MdnsDiscovery {
+    /// Create mDNS daemon announcing the server
+    /// with given public key and remote addresses.
+    pub fn new(public_key: PublicKey, addresses: impl IntoIterator<Item = impl AsRef<str>>) -> Result<Self, MdnsError> {
+        let addresses = addresses.into_iter()
+            .map(|address| address.as_ref().to_string())
+            .collect::<Vec<_>>();
+
+        let address = addresses.first()
+            .ok_or(MdnsError::NoAddresses)?;
+
+        Ok(Self {
+            address: address.parse()
+                .map_err(|_| MdnsError::InvalidAddress(address.clone()))?,
+            daemon: ServiceDaemon::new()?,
+            public_key,
+            addresses
+        })
+    }
+
+    /// Announce the server and call `on_discovered` for every
+    /// server announced by the others until the daemon stops.
+    ///
+    /// The daemon is stopped when the returned future is dropped.
+    pub async fn start<F, T>(self, on_discovered: F) -> Result<(), MdnsError>
+    where
+        F: Fn(Server) -> T,
+        T: Future<Output = ()>
+    {
+        let public_key = self.public_key.to_base64();
+
+        // Instance names are limited to 63 bytes
+        let instance = format!("hyperelm-{}", &public_key[..16.min(public_key.len())]);
+        let host = format!("{instance}.local.");
+
+        let addresses = self.addresses.join(",");
+
+        let properties = [
+            ("public_key", public_key.as_str()),
+            ("addresses", addresses.as_str())
+        ];
+
+        // Announce all the local addresses if
+        // the server is bound to all the interfaces
+        let unspecified = self.address.ip().is_unspecified();
+
+        let ip = if unspecified {
+            String::new()
+        } else {
+            self.address.ip().to_string()
+        };
+
+        let mut service = ServiceInfo::new(
+            MDNS_SERVICE_TYPE,
+            &instance,
+            &host,
+            ip.as_str(),
+            self.address.port(),
+            &properties[..]
+        )?;
+
+        if unspecified {
+            service = service.enable_addr_auto();
+        }
+
+        self.daemon.register(service)?;
+
+        let receiver = self.daemon.browse(MDNS_SERVICE_TYPE)?;
+
+        while let Ok(event) = receiver.recv_async().await {
+            let ServiceEvent::ServiceResolved(info) = event else {
+                continue;
+            };
+
+            let Some(server_public) = info.get_property_val_str("public_key") else {
+                continue;
+            };
+
+            // Skip own announcements
+            if server_public == public_key {
+                continue;
+            }
+
+            let Ok(server_public) = PublicKey::from_base64(server_public) else {
+                continue;
+            };
+
+            let Some(ip) = info.get_addresses().iter().next() else {
+                continue;
+            };
+
+            let address = SocketAddr::new(*ip, info.get_port());
+
+            #[cfg(feature = "tracing")]
+            tracing::debug!("[mdns] Discovered server {address}");
+
+            on_discovered(Server::new(server_public, address.to_string())).await;
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for MdnsDiscovery {
+    #[inline]
+    fn drop(&mut self) {
+        let _ = self.daemon.shutdown();
+    }
+}
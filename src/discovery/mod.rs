@@ -0,0 +1,5 @@
+//! Discovery of the servers without bootstrap addresses.
+
+mod mdns;
+
+pub use mdns::*;
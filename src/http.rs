@@ -0,0 +1,275 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde_json::Value as Json;
+
+use hyperborealib::http::HttpClient;
+use hyperborealib::rest_api::prelude::AsJson;
+
+#[derive(Debug, thiserror::Error)]
+pub enum HttpClientError {
+    #[error("Invalid HTTP header name: {0}")]
+    InvalidHeaderName(String),
+
+    #[error("Invalid value of HTTP header {0}")]
+    InvalidHeaderValue(String),
+
+    #[error("Invalid HTTP proxy URL {url}: {source}")]
+    InvalidProxy {
+        url: String,
+        source: reqwest::Error
+    },
+
+    #[error("Failed to read root certificate {path:?}: {source}")]
+    CertificateRead {
+        path: PathBuf,
+        source: std::io::Error
+    },
+
+    #[error("Invalid root certificate {path:?}: {source}")]
+    InvalidCertificate {
+        path: PathBuf,
+        source: reqwest::Error
+    },
+
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error)
+}
+
+/// Params of the HTTP client used by the applications.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HttpClientConfig {
+    /// Timeout of establishing connection to the remote host.
+    pub connect_timeout: Option<Duration>,
+
+    /// Timeout of the whole request, from connection
+    /// establishing to the end of the response body.
+    pub request_timeout: Option<Duration>,
+
+    /// Interval of the TCP keep-alive probes
+    /// sent over the open connections.
+    pub keep_alive_interval: Option<Duration>,
+
+    /// Maximal amount of idle connections
+    /// kept open for every remote host.
+    pub max_idle_per_host: Option<usize>,
+
+    /// Time after which idle pooled connections are closed.
+    pub idle_timeout: Option<Duration>,
+
+    /// URL of the proxy used for all the requests.
+    pub proxy: Option<String>,
+
+    /// Paths to the PEM encoded root certificates
+    /// trusted in addition to the system ones.
+    pub root_certificates: Vec<PathBuf>,
+
+    /// Value of the `User-Agent` header.
+    pub user_agent: Option<String>,
+
+    /// Accept invalid and self-signed TLS certificates.
+    ///
+    /// Intended for development only.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub accept_invalid_certs: bool,
+
+    /// Send requests over HTTPS.
+    ///
+    /// Hyperborealib builds `http://` URLs of the servers, so
+    /// their scheme is replaced. Required to connect to the
+    /// servers with the `tls` param set.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub https: bool
+}
+
+impl HttpClientConfig {
+    /// Create reqwest client builder using the current config.
+    pub fn reqwest_builder(&self) -> Result<reqwest::ClientBuilder, HttpClientError> {
+        let mut builder = reqwest::Client::builder();
+
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+
+        if let Some(timeout) = self.request_timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        if let Some(interval) = self.keep_alive_interval {
+            builder = builder.tcp_keepalive(interval);
+        }
+
+        if let Some(max_idle) = self.max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+
+        if let Some(timeout) = self.idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+
+        if let Some(url) = &self.proxy {
+            let proxy = reqwest::Proxy::all(url)
+                .map_err(|source| HttpClientError::InvalidProxy {
+                    url: url.clone(),
+                    source
+                })?;
+
+            builder = builder.proxy(proxy);
+        }
+
+        for path in &self.root_certificates {
+            let certificate = std::fs::read(path)
+                .map_err(|source| HttpClientError::CertificateRead {
+                    path: path.clone(),
+                    source
+                })?;
+
+            let certificate = reqwest::Certificate::from_pem(&certificate)
+                .map_err(|source| HttpClientError::InvalidCertificate {
+                    path: path.clone(),
+                    source
+                })?;
+
+            builder = builder.add_root_certificate(certificate);
+        }
+
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+
+        if self.accept_invalid_certs {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        Ok(builder)
+    }
+
+    /// Build HTTP client using the current config.
+    ///
+    /// Returns error if the config is invalid.
+    pub fn build(&self) -> Result<StatusHttpClient, HttpClientError> {
+        let client = self.reqwest_builder()?.build()?;
+
+        Ok(StatusHttpClient::new(client).with_https(self.https))
+    }
+}
+
+/// Response with non-success HTTP status.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("Server responded with HTTP status {status}")]
+pub struct HttpStatusError {
+    pub status: u16,
+
+    /// JSON body of the response, if any.
+    pub body: Option<Json>
+}
+
+impl HttpStatusError {
+    /// Find the status error in the sources chain of the given error.
+    ///
+    /// ```rust
+    /// use hyperelm::http::HttpStatusError;
+    ///
+    /// let error: Box<dyn std::error::Error + Send + Sync> = Box::new(HttpStatusError {
+    ///     status: 413,
+    ///     body: None
+    /// });
+    ///
+    /// assert_eq!(HttpStatusError::find(error.as_ref()).map(|err| err.status), Some(413));
+    /// ```
+    pub fn find<'a>(error: &'a (dyn std::error::Error + 'static)) -> Option<&'a Self> {
+        std::iter::successors(Some(error), |error| error.source())
+            .find_map(|error| error.downcast_ref::<Self>())
+    }
+}
+
+/// HTTP client reporting responses with non-success
+/// status as `HttpStatusError`.
+///
+/// Unlike the `ReqwestHttpClient` it doesn't try to decode
+/// error responses as the expected type, so applications
+/// can tell server errors from malformed responses.
+#[derive(Debug, Clone)]
+pub struct StatusHttpClient {
+    client: reqwest::Client,
+    https: bool
+}
+
+impl StatusHttpClient {
+    #[inline]
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            https: false
+        }
+    }
+
+    /// Replace `http://` scheme of the requested URLs by the `https://` one.
+    ///
+    /// ```rust
+    /// use hyperelm::http::StatusHttpClient;
+    ///
+    /// let client = StatusHttpClient::default().with_https(true);
+    ///
+    /// assert!(client.is_https());
+    /// ```
+    #[inline]
+    pub fn with_https(mut self, https: bool) -> Self {
+        self.https = https;
+
+        self
+    }
+
+    #[inline]
+    pub fn is_https(&self) -> bool {
+        self.https
+    }
+
+    fn url(&self, url: &str) -> String {
+        match url.strip_prefix("http://") {
+            Some(rest) if self.https => format!("https://{rest}"),
+            _ => url.to_string()
+        }
+    }
+
+    async fn read<T: AsJson>(response: reqwest::Response) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+        let status = response.status();
+
+        if !status.is_success() {
+            return Err(Box::new(HttpStatusError {
+                status: status.as_u16(),
+                body: response.json::<Json>().await.ok()
+            }));
+        }
+
+        let response = response.json::<Json>().await?;
+
+        Ok(T::from_json(&response)?)
+    }
+}
+
+impl Default for StatusHttpClient {
+    #[inline]
+    fn default() -> Self {
+        Self::new(reqwest::Client::new())
+    }
+}
+
+#[async_trait::async_trait]
+impl HttpClient for StatusHttpClient {
+    async fn get_request<T: AsJson>(&self, url: &str) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+        let response = self.client.get(self.url(url))
+            .send().await?;
+
+        Self::read(response).await
+    }
+
+    async fn post_request<T: AsJson + Send, F: AsJson>(&self, url: &str, request: T) -> Result<F, Box<dyn std::error::Error + Send + Sync>> {
+        let response = self.client.post(self.url(url))
+            .json(&request.to_json()?)
+            .send().await?;
+
+        Self::read(response).await
+    }
+}
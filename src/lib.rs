@@ -1,14 +1,27 @@
+pub mod http;
 pub mod client;
 pub mod server;
 
+#[cfg(feature = "mdns")]
+pub mod discovery;
+
+#[cfg(feature = "blocking")]
+pub mod blocking;
+
+#[cfg(feature = "serde")]
+pub mod scaffold;
+
 pub mod prelude {
     pub use hyperborealib;
 
     pub use super::client::{
         ClientAppParams,
+        ClientRuntime,
         ClientEndpoint,
+        Channel,
         ClientApp,
-        ClientAppError
+        ClientAppError,
+        MessageContext
     };
 
     pub use super::server::{
@@ -0,0 +1,226 @@
+//! Helpers to bootstrap new applications.
+//!
+//! Generated files are loaded by the `chat_server`
+//! and `chat_client` examples:
+//!
+//! ```text
+//! {dir}/server.key   - base64 encoded server's secret key
+//! {dir}/client.key   - base64 encoded client's secret key
+//! {dir}/server.json  - serialized `ServerAppParams`
+//! {dir}/client.json  - serialized `ClientAppParamsBuilder`
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use hyperborealib::crypto::asymmetric::SecretKey;
+
+use crate::client::ClientAppParamsBuilder;
+use crate::server::{ServerAppParams, CorruptionPolicy, DEFAULT_MAX_INCOMING_MESSAGE_BYTES, DEFAULT_RELAY_RATE_LIMIT};
+
+/// Serialize secret key as a base64 string.
+///
+/// Use with `#[serde(with = "hyperelm::scaffold::serde_secret_key")]`.
+pub mod serde_secret_key {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use hyperborealib::crypto::asymmetric::SecretKey;
+
+    pub fn serialize<S: Serializer>(key: &SecretKey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&key.to_base64())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SecretKey, D::Error> {
+        let key = String::deserialize(deserializer)?;
+
+        SecretKey::from_base64(&key)
+            .map_err(|err| serde::de::Error::custom(format!("invalid secret key: {err}")))
+    }
+}
+
+/// Same as `serde_secret_key`, but for optional keys.
+pub mod serde_secret_key_option {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use hyperborealib::crypto::asymmetric::SecretKey;
+
+    pub fn serialize<S: Serializer>(key: &Option<SecretKey>, serializer: S) -> Result<S::Ok, S::Error> {
+        match key {
+            Some(key) => serializer.serialize_some(&key.to_base64()),
+            None => serializer.serialize_none()
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<SecretKey>, D::Error> {
+        let Some(key) = Option::<String>::deserialize(deserializer)? else {
+            return Ok(None);
+        };
+
+        SecretKey::from_base64(&key)
+            .map(Some)
+            .map_err(|err| serde::de::Error::custom(format!("invalid secret key: {err}")))
+    }
+}
+
+/// Deserialize list of strings from either
+/// a single string or an array of them.
+///
+/// Use with `#[serde(deserialize_with = "hyperelm::scaffold::serde_one_or_many::deserialize")]`
+/// to keep accepting params from before a field became a list.
+pub mod serde_one_or_many {
+    use serde::{Deserialize, Deserializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+        Ok(match OneOrMany::deserialize(deserializer)? {
+            OneOrMany::One(value) => vec![value],
+            OneOrMany::Many(values) => values
+        })
+    }
+}
+
+fn invalid_data(err: impl std::fmt::Display) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, err.to_string())
+}
+
+/// Read base64 encoded secret key from the file.
+pub fn read_secret_key(path: impl AsRef<Path>) -> std::io::Result<SecretKey> {
+    let key = std::fs::read_to_string(path)?;
+
+    SecretKey::from_base64(key.trim())
+        .map_err(invalid_data)
+}
+
+/// Generate random secret key and store it in the `{name}.key`
+/// file of the given folder, and its public key in `{name}.pub`.
+pub fn generate_keypair_files(dir: impl AsRef<Path>, name: &str) -> std::io::Result<SecretKey> {
+    let dir = dir.as_ref();
+
+    std::fs::create_dir_all(dir)?;
+
+    let secret_key = SecretKey::random();
+
+    std::fs::write(dir.join(format!("{name}.key")), secret_key.to_base64())?;
+    std::fs::write(dir.join(format!("{name}.pub")), secret_key.public().to_base64())?;
+
+    Ok(secret_key)
+}
+
+/// Generate server and client keys and write default
+/// `server.json` and `client.json` params to the given folder.
+///
+/// Client is configured to connect to the generated
+/// server on `127.0.0.1:8001`.
+pub fn write_default_params(dir: impl AsRef<Path>) -> std::io::Result<()> {
+    let dir = dir.as_ref();
+
+    let server_secret = generate_keypair_files(dir, "server")?;
+    let client_secret = generate_keypair_files(dir, "client")?;
+
+    let server_params = ServerAppParams {
+        secret_key: server_secret.clone(),
+        local_addresses: vec![String::from("127.0.0.1:8001")],
+        remote_addresses: vec![String::from("127.0.0.1:8001")],
+        stun_server: None,
+        tls: None,
+        backend_folder: dir.join("server"),
+        on_corruption: CorruptionPolicy::Abort,
+        init_retries: 3,
+        init_retry_delay: Duration::from_secs(1),
+        bootstrap: vec![],
+        bootstrap_concurrency: 4,
+        bootstrap_timeout: Duration::from_secs(5),
+        contribute_peers_to_bootstrap: false,
+        open_ports: vec![],
+        announce: false,
+        enable_mdns: false,
+        relay_messages: false,
+        relay_rate_limit: DEFAULT_RELAY_RATE_LIMIT,
+        multicast: None,
+        sign_outbound_requests: false,
+        require_signed_inbound: false,
+        traverse_delay: Duration::from_secs(60 * 10),
+        adaptive_traversal: None,
+        peer_max_age: Duration::from_secs(24 * 60 * 60),
+        peer_sweep_interval: Duration::from_secs(60 * 60),
+        message_max_age: None,
+        dead_letter_channel: None,
+        max_incoming_message_bytes: DEFAULT_MAX_INCOMING_MESSAGE_BYTES,
+        blacklist_path: None,
+        traversal_workers: 1,
+        max_concurrent_outbound_connections: 16,
+        traversal_config: Default::default(),
+        http_config: Default::default(),
+        admin_address: None,
+        admin_token: None,
+        status_endpoint: None,
+        metrics_port: None,
+        metrics_bearer_token: None,
+        ip_filter: None,
+        plugins: vec![]
+    };
+
+    let client_params = ClientAppParamsBuilder::default()
+        .client(client_secret)
+        .server(server_secret.public(), "127.0.0.1:8001");
+
+    write_json(dir.join("server.json"), &server_params)?;
+    write_json(dir.join("client.json"), &client_params)?;
+
+    Ok(())
+}
+
+fn write_json(path: PathBuf, value: &impl serde::Serialize) -> std::io::Result<()> {
+    std::fs::write(path, serde_json::to_vec_pretty(value)?)
+}
+
+/// Load server params from the JSON file.
+///
+/// ```rust
+/// use hyperelm::scaffold;
+///
+/// let dir = std::env::temp_dir().join("hyperelm-scaffold-doctest");
+///
+/// scaffold::write_default_params(&dir).unwrap();
+///
+/// let params = scaffold::load_server_params(dir.join("server.json")).unwrap();
+///
+/// assert_eq!(params.secret_key, scaffold::read_secret_key(dir.join("server.key")).unwrap());
+///
+/// // Malformed keys are refused
+/// let malformed = std::fs::read_to_string(dir.join("server.json")).unwrap()
+///     .replace(&params.secret_key.to_base64(), "not a key");
+///
+/// std::fs::write(dir.join("malformed.json"), malformed).unwrap();
+///
+/// assert!(scaffold::load_server_params(dir.join("malformed.json")).is_err());
+/// ```
+pub fn load_server_params(path: impl AsRef<Path>) -> std::io::Result<ServerAppParams> {
+    serde_json::from_slice(&std::fs::read(path)?)
+        .map_err(invalid_data)
+}
+
+/// Load client params builder from the JSON file.
+///
+/// ```rust
+/// use hyperelm::scaffold;
+///
+/// let dir = std::env::temp_dir().join("hyperelm-scaffold-client-doctest");
+///
+/// scaffold::write_default_params(&dir).unwrap();
+///
+/// let params = scaffold::load_client_params(dir.join("client.json")).unwrap();
+///
+/// assert_eq!(params.client_secret, Some(scaffold::read_secret_key(dir.join("client.key")).unwrap()));
+/// assert!(params.build().is_some());
+/// ```
+pub fn load_client_params(path: impl AsRef<Path>) -> std::io::Result<ClientAppParamsBuilder> {
+    serde_json::from_slice(&std::fs::read(path)?)
+        .map_err(invalid_data)
+}
@@ -0,0 +1,220 @@
+use std::sync::Arc;
+
+use serde_json::json;
+
+use axum::Router;
+use axum::routing::{get, post, delete};
+use axum::extract::{State, Query, Path};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+
+use hyperborealib::rest_api::prelude::AsJson;
+
+use super::ServerApp;
+
+/// Default amount of peers returned by the `/peers` endpoint.
+pub const DEFAULT_PEERS_LIMIT: usize = 100;
+
+/// Maximal amount of peers returned by the `/peers` endpoint.
+pub const MAX_PEERS_LIMIT: usize = 1000;
+
+struct AdminState<T: ServerApp> {
+    app: Arc<T>,
+    token: Option<String>
+}
+
+impl<T: ServerApp> Clone for AdminState<T> {
+    fn clone(&self) -> Self {
+        Self {
+            app: self.app.clone(),
+            token: self.token.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct PeersQuery {
+    limit: Option<usize>,
+    offset: Option<usize>
+}
+
+/// Build administration REST API router.
+///
+/// | Method   | Path                       | Description                          |
+/// | -------- | -------------------------- | ------------------------------------ |
+/// | `GET`    | `/peers`                   | List known servers                   |
+/// | `GET`    | `/dead-letter`             | List dead letters                    |
+/// | `POST`   | `/dead-letter/:id/requeue` | Put dead letter back to the inbox    |
+/// | `DELETE` | `/dead-letter/:id`         | Discard dead letter                  |
+///
+/// Known servers are listed by the `list_known_servers`
+/// method of the application.
+///
+/// If `token` is given, all the requests must contain
+/// `Authorization: Bearer <token>` header.
+pub fn admin_router<T>(app: Arc<T>, token: Option<String>) -> Router
+where
+    T: ServerApp + Send + Sync + 'static,
+    T::Error: std::fmt::Debug
+{
+    Router::new()
+        .route("/peers", get(get_peers::<T>))
+        .route("/dead-letter", get(get_dead_letters::<T>))
+        .route("/dead-letter/:id", delete(discard_dead_letter::<T>))
+        .route("/dead-letter/:id/requeue", post(requeue_dead_letter::<T>))
+        .with_state(AdminState {
+            app,
+            token
+        })
+}
+
+/// Serve given router on given address.
+pub async fn serve_router(address: impl AsRef<str>, router: Router) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(address.as_ref()).await?;
+
+    axum::serve(listener, router).await
+}
+
+pub(crate) fn is_authorized(headers: &HeaderMap, token: Option<&str>) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+
+    headers.get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|value| value == token)
+        .unwrap_or(false)
+}
+
+async fn get_peers<T>(
+    State(state): State<AdminState<T>>,
+    Query(query): Query<PeersQuery>,
+    headers: HeaderMap
+) -> Response
+where
+    T: ServerApp + Send + Sync + 'static,
+    T::Error: std::fmt::Debug
+{
+    if !is_authorized(&headers, state.token.as_deref()) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    let limit = query.limit
+        .unwrap_or(DEFAULT_PEERS_LIMIT)
+        .min(MAX_PEERS_LIMIT);
+
+    let offset = query.offset.unwrap_or_default();
+
+    let result = tokio::try_join!(
+        state.app.list_known_servers(limit, offset),
+        state.app.count_known_servers()
+    );
+
+    match result {
+        Ok((peers, total)) => {
+            let peers = peers.iter()
+                .map(|server| server.to_json())
+                .collect::<Result<Vec<_>, _>>();
+
+            match peers {
+                Ok(peers) => axum::Json(json!({
+                    "peers": peers,
+                    "total": total
+                })).into_response(),
+
+                Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+            }
+        }
+
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{err:?}")).into_response()
+    }
+}
+
+async fn get_dead_letters<T>(
+    State(state): State<AdminState<T>>,
+    headers: HeaderMap
+) -> Response
+where
+    T: ServerApp + Send + Sync + 'static,
+    T::Error: std::fmt::Debug
+{
+    if !is_authorized(&headers, state.token.as_deref()) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    let Some(queue) = state.app.get_dead_letter_queue() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let letters = queue.list().await
+        .and_then(|letters| {
+            Ok(letters.iter()
+                .map(|letter| letter.to_json())
+                .collect::<Result<Vec<_>, _>>()?)
+        });
+
+    match letters {
+        Ok(letters) => axum::Json(json!({
+            "channel": queue.channel().as_str(),
+            "letters": letters
+        })).into_response(),
+
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+    }
+}
+
+async fn requeue_dead_letter<T>(
+    State(state): State<AdminState<T>>,
+    Path(id): Path<u64>,
+    headers: HeaderMap
+) -> Response
+where
+    T: ServerApp + Send + Sync + 'static,
+    T::Error: std::fmt::Debug
+{
+    if !is_authorized(&headers, state.token.as_deref()) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    let Some(queue) = state.app.get_dead_letter_queue() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let inbox = match state.app.get_messages_inbox().await {
+        Ok(inbox) => inbox,
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("{err:?}")).into_response()
+    };
+
+    match queue.requeue(id, &inbox).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => StatusCode::NOT_FOUND.into_response(),
+
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+    }
+}
+
+async fn discard_dead_letter<T>(
+    State(state): State<AdminState<T>>,
+    Path(id): Path<u64>,
+    headers: HeaderMap
+) -> Response
+where
+    T: ServerApp + Send + Sync + 'static,
+    T::Error: std::fmt::Debug
+{
+    if !is_authorized(&headers, state.token.as_deref()) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    let Some(queue) = state.app.get_dead_letter_queue() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    match queue.discard(id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => StatusCode::NOT_FOUND.into_response(),
+
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+    }
+}
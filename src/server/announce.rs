@@ -0,0 +1,160 @@
+use serde_json::{json, Value as Json};
+
+use hyperborealib::http::HttpClient;
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+/// Path of the announce endpoint of the servers.
+pub const ANNOUNCE_PATH: &str = "/api/v1/announce";
+
+/// Announcement of the current server sent to other ones.
+///
+/// `{ "server": { "public_key": "...", "address": "..." }, ...metadata }`
+///
+/// Fields of the metadata object are merged into the
+/// announcement, but can't replace the `server` field.
+///
+/// ```rust
+/// use serde_json::json;
+///
+/// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+/// use hyperelm::exports::hyperborealib::rest_api::prelude::*;
+///
+/// use hyperelm::server::ServerAnnouncement;
+///
+/// let server = Server::new(SecretKey::random().public(), "127.0.0.1:8001");
+///
+/// let announcement = ServerAnnouncement::new(server.clone(), json!({
+///     "region": "eu",
+///     "server": "ignored"
+/// }));
+///
+/// let json = announcement.to_json().unwrap();
+///
+/// assert_eq!(json["region"], "eu");
+/// assert_eq!(ServerAnnouncement::from_json(&json).unwrap(), announcement);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerAnnouncement {
+    pub server: Server,
+    pub metadata: Json
+}
+
+impl ServerAnnouncement {
+    #[inline]
+    pub fn new(server: Server, metadata: Json) -> Self {
+        Self {
+            server,
+            metadata
+        }
+    }
+}
+
+impl AsJson for ServerAnnouncement {
+    fn to_json(&self) -> Result<Json, AsJsonError> {
+        let mut announcement = json!({
+            "server": self.server.to_json()?
+        });
+
+        if let (Some(announcement), Some(metadata)) = (announcement.as_object_mut(), self.metadata.as_object()) {
+            for (key, value) in metadata {
+                if key != "server" {
+                    announcement.insert(key.clone(), value.clone());
+                }
+            }
+        }
+
+        Ok(announcement)
+    }
+
+    fn from_json(json: &Json) -> Result<Self, AsJsonError> where Self: Sized {
+        let server = json.get("server")
+            .map(Server::from_json)
+            .ok_or_else(|| AsJsonError::FieldNotFound("server"))??;
+
+        let mut metadata = json.clone();
+
+        if let Some(metadata) = metadata.as_object_mut() {
+            metadata.remove("server");
+        }
+
+        Ok(Self {
+            server,
+            metadata
+        })
+    }
+}
+
+/// Signed announce request of hyperborealib
+/// with the announcement metadata attached.
+///
+/// Metadata is sent in the `metadata` field, which is
+/// ignored by the servers not reading it.
+struct SignedAnnouncement {
+    request: AnnounceRequest,
+    metadata: Json
+}
+
+impl AsJson for SignedAnnouncement {
+    fn to_json(&self) -> Result<Json, AsJsonError> {
+        let mut request = self.request.to_json()?;
+
+        if let Some(request) = request.as_object_mut() {
+            request.insert(String::from("metadata"), self.metadata.clone());
+        }
+
+        Ok(request)
+    }
+
+    fn from_json(json: &Json) -> Result<Self, AsJsonError> where Self: Sized {
+        Ok(Self {
+            request: AnnounceRequest::from_json(json)?,
+            metadata: json.get("metadata")
+                .cloned()
+                .unwrap_or_else(|| json!({}))
+        })
+    }
+}
+
+/// Result of announcing the current server to another one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnounceOutcome {
+    pub server: Server,
+
+    /// Error message if the announce failed.
+    pub result: Result<(), String>
+}
+
+/// Results of the network traversal cycle.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TraversalReport {
+    /// Servers indexed during the cycle.
+    pub discovered: Vec<Server>,
+
+    /// Results of announcing the current server
+    /// to the known ones. Empty if announcing is disabled.
+    pub announced: Vec<AnnounceOutcome>
+}
+
+/// Send announcement to the server on given address.
+///
+/// Announcement is sent as the typed hyperborealib announce
+/// request signed by the `secret_key` of the current server,
+/// using the HTTP client of the traversal middleware.
+pub async fn announce_to<T: HttpClient>(
+    client: &ClientMiddleware<T>,
+    secret_key: &SecretKey,
+    address: &str,
+    announcement: &ServerAnnouncement
+) -> Result<(), String> {
+    let url = format!("http://{address}{ANNOUNCE_PATH}");
+
+    let request = SignedAnnouncement {
+        request: AnnounceRequest::server(secret_key, announcement.server.clone()),
+        metadata: announcement.metadata.clone()
+    };
+
+    client.http_client_ref().post_request::<_, AnnounceResponse>(&url, request).await
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
@@ -1,8 +1,20 @@
 use hyperborealib::http::*;
+use hyperborealib::crypto::prelude::*;
 use hyperborealib::rest_api::prelude::*;
 use hyperborealib::drivers::prelude::*;
 
-use super::ServerAppParams;
+use serde_json::{json, Value as Json};
+
+use super::{ServerAppParams, ServerRunError, RestApiRouter, Routes, DeadLetterQueue, TraversalReport, HttpLayer, BackendReport, CountingInbox};
+
+/// Driver of the server started by the `run` function.
+///
+/// Application's inbox is wrapped into the `CountingInbox`.
+pub type AppServerDriver<T> = ServerDriver<
+    <T as ServerApp>::Router,
+    <T as ServerApp>::Traversal,
+    CountingInbox<<T as ServerApp>::MessagesInbox>
+>;
 
 #[async_trait::async_trait]
 pub trait ServerApp {
@@ -11,9 +23,17 @@ pub trait ServerApp {
     type MessagesInbox: MessagesInbox + Send + Sync + 'static;
 
     type HttpClient: HttpClient + Send + Sync + 'static;
-    type HttpServer: HttpServer + Send + Sync + 'static;
+    /// HTTP server used by the hyperborealib middleware.
+    ///
+    /// The `run` function serves the router exposed by this
+    /// server together with the application's extra routes.
+    type HttpServer: HttpServer + RestApiRouter + Clone + Send + Sync + 'static;
 
-    type Error: Send;
+    /// Error of the application.
+    ///
+    /// Must be `Sync`, because the `on_error` method borrows
+    /// errors across await points of the spawned tasks.
+    type Error: Send + Sync;
 
     async fn get_router(&self) -> Result<Self::Router, Self::Error>;
     async fn get_traversal(&self) -> Result<Self::Traversal, Self::Error>;
@@ -22,7 +42,146 @@ pub trait ServerApp {
     async fn get_http_client(&self) -> Result<Self::HttpClient, Self::Error>;
     async fn get_http_server(&self) -> Result<Self::HttpServer, Self::Error>;
 
-    fn get_params(&self) -> ServerAppParams;
+    fn get_params(&self) -> &ServerAppParams;
+
+    /// Get secret key of the server.
+    #[inline]
+    fn get_secret_key(&self) -> &SecretKey {
+        &self.get_params().secret_key
+    }
+
+    /// Get public key of the server.
+    #[inline]
+    fn get_public_key(&self) -> PublicKey {
+        self.get_secret_key().public()
+    }
+
+    /// Application-specific REST API routes.
+    ///
+    /// Routes are merged into the public REST API served on
+    /// the `local_addresses` next to the hyperborealib routes
+    /// of the application's HTTP server. Server fails to start
+    /// if the routes overlap with each other or with the built-in
+    /// ones. Empty by default.
+    fn get_extra_routes(&self) -> Vec<Routes> {
+        vec![]
+    }
+
+    /// HTTP middleware applied to the public REST API,
+    /// administration API, status and metrics endpoints
+    /// in the given order.
+    ///
+    /// Hyperborealib routes are served by the public REST API,
+    /// so they're affected as well. Empty by default.
+    fn get_http_layers(&self) -> Vec<Box<dyn HttpLayer>> {
+        vec![]
+    }
+
+    /// List servers known by the application's router.
+    ///
+    /// Returns an empty list by default. `BasicServerApp`
+    /// lists servers indexed in the router folder of the backend.
+    #[allow(unused_variables)]
+    async fn list_known_servers(&self, limit: usize, offset: usize) -> Result<Vec<Server>, Self::Error> {
+        Ok(vec![])
+    }
+
+    /// Get amount of servers known by the application's router.
+    ///
+    /// Returns 0 by default.
+    async fn count_known_servers(&self) -> Result<usize, Self::Error> {
+        Ok(0)
+    }
+
+    /// Remove expired or banned server from the application's router.
+    ///
+    /// Routers of hyperborealib can't remove indexed servers,
+    /// so by default nothing is removed and `false` is returned.
+    /// `BasicServerApp` removes the server's record from the
+    /// router folder of the backend.
+    #[allow(unused_variables)]
+    async fn remove_known_server(&self, server: &Server) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+
+    /// Called with non-fatal errors of the running server,
+    /// like failed port forwarding or bootstrap server indexing.
+    ///
+    /// Does nothing by default.
+    #[allow(unused_variables)]
+    async fn on_error(&self, err: &ServerRunError<Self::Error>) {}
+
+    /// Extra fields merged into the announcement of the
+    /// current server, like its region or capacity.
+    ///
+    /// Returns an empty object by default.
+    fn announce_metadata(&self) -> Json {
+        json!({})
+    }
+
+    /// Check if the current server should be
+    /// announced to the given one.
+    ///
+    /// Returns `true` by default.
+    #[allow(unused_variables)]
+    async fn should_announce_to(&self, server: &Server) -> bool {
+        true
+    }
+
+    /// Called after every network traversal cycle
+    /// with discovered servers and announce results.
+    ///
+    /// Does nothing by default.
+    #[allow(unused_variables)]
+    async fn on_traversal_complete(&self, report: &TraversalReport) {}
+
+    /// Get dead-letter channel of the application.
+    ///
+    /// By default letters are stored in the `dead-letter.jsonl`
+    /// file of the backend folder if `dead_letter_channel`
+    /// param is set.
+    fn get_dead_letter_queue(&self) -> Option<DeadLetterQueue> {
+        let params = self.get_params();
+
+        params.dead_letter_channel.clone().map(|channel| {
+            DeadLetterQueue::new(channel, params.backend_folder.join("dead-letter.jsonl"))
+        })
+    }
+
+    /// Move expired messages of the inbox used by the
+    /// running server to the dead-letter channel.
+    ///
+    /// Called every `DEAD_LETTER_SWEEP_INTERVAL` if the
+    /// `message_max_age` param is set. Returns amount of
+    /// moved messages, does nothing by default.
+    #[allow(unused_variables)]
+    async fn sweep_inbox(&self, inbox: &Self::MessagesInbox) -> Result<u64, Self::Error> {
+        Ok(0)
+    }
+
+    /// Find corrupted files of the application's backend.
+    ///
+    /// Called before the server middleware is created. Found
+    /// issues are handled according to the `on_corruption` param.
+    /// Returns an empty report by default.
+    async fn check_backend(&self) -> Result<BackendReport, Self::Error> {
+        Ok(BackendReport::default())
+    }
+
+    /// Get amount of channels with messages
+    /// stored in the application's inbox.
+    ///
+    /// Returns 0 by default.
+    async fn count_inbox_channels(&self) -> Result<usize, Self::Error> {
+        Ok(0)
+    }
+
+    /// Get amount of messages stored in the application's inbox.
+    ///
+    /// Returns 0 by default.
+    async fn inbox_depth(&self) -> Result<usize, Self::Error> {
+        Ok(0)
+    }
 
     #[allow(clippy::type_complexity)]
     async fn get_driver(&self) -> Result<ServerDriver<
@@ -37,12 +196,16 @@ pub trait ServerApp {
             self.get_traversal().await?,
             self.get_messages_inbox().await?,
             ServerParams {
-                secret_key: params.secret_key.clone(),
-                address: params.remote_address.clone()
+                secret_key: self.get_secret_key().clone(),
+                address: params.remote_address().to_string()
             }
         ))
     }
 
+    /// Build server middleware from the application's parts.
+    ///
+    /// The `run` function doesn't use this method, because it
+    /// wraps the messages inbox into the `CountingInbox`.
     #[allow(clippy::type_complexity)]
     async fn get_middleware(&self) -> Result<ServerMiddleware<
         Self::HttpClient,
@@ -1,4 +1,7 @@
+use std::sync::Arc;
+
 use hyperborealib::drivers::prelude::*;
+use hyperborealib::rest_api::prelude::*;
 use hyperborealib::http::*;
 
 use super::*;
@@ -11,33 +14,89 @@ use super::*;
 /// 
 /// use hyperborealib::crypto::SecretKey;
 /// 
-/// struct MyServerApp;
+/// struct MyServerApp {
+///     params: ServerAppParams
+/// }
 /// 
 /// impl BasicServerApp for MyServerApp {
-///     fn get_params(&self) -> ServerAppParams {
-///         ServerAppParams {
-///             secret_key: SecretKey::random(),
-///             local_address: String::from("127.0.0.1:8001"),
-///             remote_address: String::from("127.0.0.1:8001"),
-///             bootstrap: vec![],
-///             announce: false,
-///             traverse_delay: std::time::Duration::from_secs(60 * 10)
-///         }
+///     fn get_params(&self) -> &ServerAppParams {
+///         &self.params
 ///     }
 /// }
+/// 
+/// let app = MyServerApp {
+///     params: ServerAppParams {
+///         secret_key: SecretKey::random(),
+///         local_addresses: vec![String::from("127.0.0.1:8001")],
+///         remote_addresses: vec![String::from("127.0.0.1:8001")],
+///         stun_server: None,
+///         tls: None,
+///         backend_folder: std::path::PathBuf::from("hyperelm"),
+///         on_corruption: hyperelm::server::CorruptionPolicy::Abort,
+///         init_retries: 3,
+///         init_retry_delay: std::time::Duration::from_secs(1),
+///         bootstrap: vec![],
+///         bootstrap_concurrency: 4,
+///         bootstrap_timeout: std::time::Duration::from_secs(5),
+///         contribute_peers_to_bootstrap: false,
+///         open_ports: vec![],
+///         announce: false,
+///         enable_mdns: false,
+///         relay_messages: false,
+///         relay_rate_limit: hyperelm::server::DEFAULT_RELAY_RATE_LIMIT,
+///         multicast: None,
+///         sign_outbound_requests: false,
+///         require_signed_inbound: false,
+///         traverse_delay: std::time::Duration::from_secs(60 * 10),
+///         adaptive_traversal: None,
+///         peer_max_age: std::time::Duration::from_secs(24 * 60 * 60),
+///         peer_sweep_interval: std::time::Duration::from_secs(60 * 60),
+///         blacklist_path: None,
+///         traversal_workers: 1,
+///         max_concurrent_outbound_connections: 16,
+///         traversal_config: Default::default(),
+///         message_max_age: None,
+///         dead_letter_channel: None,
+///         max_incoming_message_bytes: hyperelm::server::DEFAULT_MAX_INCOMING_MESSAGE_BYTES,
+///         http_config: Default::default(),
+///         admin_address: None,
+///         admin_token: None,
+///         status_endpoint: None,
+///         metrics_port: None,
+///         metrics_bearer_token: None,
+///         ip_filter: None,
+///         plugins: vec![]
+///     }
+/// };
+/// 
+/// assert_eq!(app.get_secret_key(), &app.params.secret_key);
 /// ```
 pub trait BasicServerApp {
-    fn get_params(&self) -> ServerAppParams;
+    fn get_params(&self) -> &ServerAppParams;
+
+    /// Application-specific REST API routes.
+    ///
+    /// Routes are served on the public REST API
+    /// together with the hyperborealib ones. Empty by default.
+    fn extra_routes(&self) -> Vec<Routes> {
+        vec![]
+    }
+
+    /// HTTP middleware like CORS or compression applied
+    /// to all the served routes. Empty by default.
+    fn http_layers(&self) -> Vec<Box<dyn HttpLayer>> {
+        vec![]
+    }
 }
 
 #[async_trait::async_trait]
 impl<T> ServerApp for T where T: BasicServerApp + Send + Sync {
     type Router = GlobalTableRouter;
     type Traversal = BfsRecursionTraversal;
-    type MessagesInbox = StoredQueueMessagesInbox;
+    type MessagesInbox = PluginInbox<RelayInbox<DeadLetterInbox<StoredQueueMessagesInbox>>>;
 
-    type HttpClient = ReqwestHttpClient;
-    type HttpServer = AxumHttpServer;
+    type HttpClient = SignedHttpClient;
+    type HttpServer = RestApiServer;
 
     type Error = std::io::Error;
 
@@ -60,21 +119,178 @@ impl<T> ServerApp for T where T: BasicServerApp + Send + Sync {
 
         let inbox = StoredQueueMessagesInbox::new(params.backend_folder.join("inbox")).await?;
 
-        Ok(inbox)
+        let inbox = DeadLetterInbox::new(
+            inbox,
+            params.message_max_age,
+            self.get_dead_letter_queue().map(Arc::new)
+        );
+
+        let relay = if params.relay_messages {
+            let relay = Relay::new(
+                self.get_http_client().await?,
+                params.secret_key.clone(),
+                params.remote_address()
+            );
+
+            Some(relay.spawn(params.relay_rate_limit))
+        } else {
+            None
+        };
+
+        let inbox = RelayInbox::new(inbox, relay);
+
+        Ok(PluginInbox::new(inbox, params.plugins.clone()))
     }
 
-    #[inline]
     async fn get_http_client(&self) -> Result<Self::HttpClient, Self::Error> {
-        Ok(ReqwestHttpClient::default())
+        let params = self.get_params();
+
+        let client = params.http_config.reqwest_builder()
+            .map_err(std::io::Error::other)?
+            .build()
+            .map_err(std::io::Error::other)?;
+
+        let secret_key = if params.sign_outbound_requests {
+            Some(params.secret_key.clone())
+        } else {
+            None
+        };
+
+        Ok(SignedHttpClient::new(client, secret_key, params.remote_address()))
     }
 
     #[inline]
     async fn get_http_server(&self) -> Result<Self::HttpServer, Self::Error> {
-        Ok(AxumHttpServer::default())
+        Ok(RestApiServer::default())
     }
 
     #[inline]
-    fn get_params(&self) -> ServerAppParams {
+    fn get_params(&self) -> &ServerAppParams {
         T::get_params(self)
     }
+
+    #[inline]
+    fn get_extra_routes(&self) -> Vec<Routes> {
+        T::extra_routes(self)
+    }
+
+    #[inline]
+    fn get_http_layers(&self) -> Vec<Box<dyn HttpLayer>> {
+        T::http_layers(self)
+    }
+
+    #[inline]
+    async fn sweep_inbox(&self, inbox: &Self::MessagesInbox) -> Result<u64, Self::Error> {
+        Ok(inbox.inner().inner().sweep_expired().await)
+    }
+
+    async fn check_backend(&self) -> Result<BackendReport, Self::Error> {
+        let maintenance = BackendMaintenance::new(&self.get_params().backend_folder)
+            .with_subfolder("router")
+            .with_subfolder("inbox");
+
+        Ok(maintenance.check())
+    }
+
+    async fn count_inbox_channels(&self) -> Result<usize, Self::Error> {
+        let mut channels = 0;
+        let mut folders = vec![self.get_params().backend_folder.join("inbox")];
+
+        // Count folders with messages stored by the inbox
+        while let Some(folder) = folders.pop() {
+            if !folder.exists() {
+                continue;
+            }
+
+            let mut entries = tokio::fs::read_dir(folder).await?;
+            let mut has_messages = false;
+
+            while let Some(entry) = entries.next_entry().await? {
+                if entry.file_type().await?.is_dir() {
+                    folders.push(entry.path());
+                } else {
+                    has_messages = true;
+                }
+            }
+
+            if has_messages {
+                channels += 1;
+            }
+        }
+
+        Ok(channels)
+    }
+
+    async fn inbox_depth(&self) -> Result<usize, Self::Error> {
+        let mut depth = 0;
+        let mut folders = vec![self.get_params().backend_folder.join("inbox")];
+
+        // Count files stored by the inbox
+        while let Some(folder) = folders.pop() {
+            if !folder.exists() {
+                continue;
+            }
+
+            let mut entries = tokio::fs::read_dir(folder).await?;
+
+            while let Some(entry) = entries.next_entry().await? {
+                if entry.file_type().await?.is_dir() {
+                    folders.push(entry.path());
+                } else {
+                    depth += 1;
+                }
+            }
+        }
+
+        Ok(depth)
+    }
+
+    async fn list_known_servers(&self, limit: usize, offset: usize) -> Result<Vec<Server>, Self::Error> {
+        let servers = self.get_router().await?
+            .servers().await?
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .collect();
+
+        Ok(servers)
+    }
+
+    async fn count_known_servers(&self) -> Result<usize, Self::Error> {
+        Ok(self.get_router().await?.servers().await?.len())
+    }
+
+    async fn remove_known_server(&self, server: &Server) -> Result<bool, Self::Error> {
+        let mut removed = false;
+        let mut folders = vec![self.get_params().backend_folder.join("router")];
+
+        // Router stores every indexed server in its own JSON file
+        while let Some(folder) = folders.pop() {
+            if !folder.exists() {
+                continue;
+            }
+
+            let mut entries = tokio::fs::read_dir(folder).await?;
+
+            while let Some(entry) = entries.next_entry().await? {
+                if entry.file_type().await?.is_dir() {
+                    folders.push(entry.path());
+
+                    continue;
+                }
+
+                let stored = tokio::fs::read(entry.path()).await.ok()
+                    .and_then(|record| serde_json::from_slice(&record).ok())
+                    .and_then(|record| Server::from_json(&record).ok());
+
+                if stored.as_ref() == Some(server) {
+                    tokio::fs::remove_file(entry.path()).await?;
+
+                    removed = true;
+                }
+            }
+        }
+
+        Ok(removed)
+    }
 }
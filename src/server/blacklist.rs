@@ -0,0 +1,181 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tokio::sync::Notify;
+
+/// Server banned from indexing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct BanEntry {
+    /// Address of the banned server.
+    pub address: String,
+
+    /// Reason of the ban.
+    pub reason: String,
+
+    /// Unix timestamp of the ban.
+    pub added_at: u64
+}
+
+#[derive(Debug, Default)]
+struct BlacklistInner {
+    path: Option<PathBuf>,
+    entries: Vec<BanEntry>
+}
+
+/// List of servers which shouldn't be indexed.
+///
+/// If the path is set, the list is stored
+/// in a JSON file and survives restarts.
+///
+/// ```rust
+/// use hyperelm::server::Blacklist;
+///
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let path = std::env::temp_dir().join("hyperelm-blacklist-doctest.json");
+///
+/// let _ = std::fs::remove_file(&path);
+///
+/// let blacklist = Blacklist::default();
+///
+/// blacklist.load(&path).await.unwrap();
+///
+/// assert!(blacklist.ban("127.0.0.1:8001", "spam").await.unwrap());
+/// assert!(!blacklist.ban("127.0.0.1:8001", "spam").await.unwrap());
+///
+/// // Bans survive restarts
+/// let blacklist = Blacklist::default();
+///
+/// blacklist.load(&path).await.unwrap();
+///
+/// assert!(blacklist.is_banned("127.0.0.1:8001"));
+/// assert!(blacklist.unban("127.0.0.1:8001").await.unwrap());
+/// assert!(blacklist.list().is_empty());
+/// # });
+/// ```
+#[derive(Debug, Default)]
+pub struct Blacklist {
+    inner: Mutex<BlacklistInner>,
+    save_lock: tokio::sync::Mutex<()>,
+    banned: Notify
+}
+
+impl Blacklist {
+    /// Load blacklist from the given file.
+    ///
+    /// Empty list is used if the file doesn't exist.
+    pub async fn load(&self, path: impl Into<PathBuf>) -> std::io::Result<()> {
+        let path = path.into();
+
+        let entries = match tokio::fs::read(&path).await {
+            Ok(entries) => serde_json::from_slice(&entries)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => vec![],
+            Err(err) => return Err(err)
+        };
+
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.path = Some(path);
+            inner.entries = entries;
+        }
+
+        Ok(())
+    }
+
+    /// Atomically write entries to the blacklist file.
+    ///
+    /// Writes are serialized, and every write stores
+    /// the entries current at its start.
+    async fn save(&self) -> std::io::Result<()> {
+        let _lock = self.save_lock.lock().await;
+
+        let snapshot = self.inner.lock().ok()
+            .and_then(|inner| Some((inner.path.clone()?, inner.entries.clone())));
+
+        let Some((path, entries)) = snapshot else {
+            return Ok(());
+        };
+
+        let temp = path.with_extension("tmp");
+
+        tokio::fs::write(&temp, serde_json::to_vec_pretty(&entries)?).await?;
+        tokio::fs::rename(temp, path).await
+    }
+
+    /// Ban server with given address.
+    ///
+    /// Returns `false` if the server was already banned.
+    pub async fn ban(&self, address: impl ToString, reason: impl ToString) -> std::io::Result<bool> {
+        let address = address.to_string();
+
+        {
+            let Ok(mut inner) = self.inner.lock() else {
+                return Ok(false);
+            };
+
+            if inner.entries.iter().any(|entry| entry.address == address) {
+                return Ok(false);
+            }
+
+            inner.entries.push(BanEntry {
+                address,
+                reason: reason.to_string(),
+                added_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+            });
+        }
+
+        self.banned.notify_one();
+
+        self.save().await?;
+
+        Ok(true)
+    }
+
+    /// Unban server with given address.
+    ///
+    /// Returns `false` if the server wasn't banned.
+    pub async fn unban(&self, address: impl AsRef<str>) -> std::io::Result<bool> {
+        let address = address.as_ref();
+
+        {
+            let Ok(mut inner) = self.inner.lock() else {
+                return Ok(false);
+            };
+
+            let len = inner.entries.len();
+
+            inner.entries.retain(|entry| entry.address != address);
+
+            if inner.entries.len() == len {
+                return Ok(false);
+            }
+        }
+
+        self.save().await?;
+
+        Ok(true)
+    }
+
+    /// Wait until a new server is banned.
+    pub async fn wait_banned(&self) {
+        self.banned.notified().await;
+    }
+
+    /// Check if the server with given address is banned.
+    pub fn is_banned(&self, address: impl AsRef<str>) -> bool {
+        let address = address.as_ref();
+
+        self.inner.lock()
+            .map(|inner| inner.entries.iter().any(|entry| entry.address == address))
+            .unwrap_or(false)
+    }
+
+    /// List banned servers.
+    pub fn list(&self) -> Vec<BanEntry> {
+        self.inner.lock()
+            .map(|inner| inner.entries.clone())
+            .unwrap_or_default()
+    }
+}
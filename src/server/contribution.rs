@@ -0,0 +1,243 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::{json, Value as Json};
+
+use axum::Router;
+use axum::routing::{get, post};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+
+use hyperborealib::http::HttpClient;
+use hyperborealib::rest_api::prelude::*;
+
+/// Path of the server capabilities endpoint.
+pub const CAPABILITIES_PATH: &str = "/capabilities";
+
+/// Path of the peer contribution endpoint.
+pub const PEER_CONTRIBUTION_PATH: &str = "/peer-contribution";
+
+/// Name of the peer contribution capability.
+pub const PEER_CONTRIBUTION_CAPABILITY: &str = "peer_contribution";
+
+/// Maximal amount of peers sent and accepted in one contribution.
+pub const PEER_CONTRIBUTION_MAX_PEERS: usize = 32;
+
+/// Time to wait for the info of a contributed peer.
+const PEER_VERIFY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Optional features supported by the server.
+///
+/// `{ "capabilities": ["peer_contribution"] }`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ServerCapabilities {
+    pub capabilities: Vec<String>
+}
+
+impl ServerCapabilities {
+    #[inline]
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|supported| supported == capability)
+    }
+}
+
+impl AsJson for ServerCapabilities {
+    fn to_json(&self) -> Result<Json, AsJsonError> {
+        Ok(json!({
+            "capabilities": self.capabilities
+        }))
+    }
+
+    fn from_json(json: &Json) -> Result<Self, AsJsonError> where Self: Sized {
+        Ok(Self {
+            capabilities: json.get("capabilities")
+                .and_then(Json::as_array)
+                .map(|capabilities| {
+                    capabilities.iter()
+                        .filter_map(Json::as_str)
+                        .map(String::from)
+                        .collect()
+                })
+                .ok_or_else(|| AsJsonError::FieldNotFound("capabilities"))?
+        })
+    }
+}
+
+/// Servers known by the sender, most recently seen first.
+///
+/// `{ "peers": [{ "public_key": "...", "address": "..." }] }`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PeerContribution {
+    pub peers: Vec<Server>
+}
+
+impl AsJson for PeerContribution {
+    fn to_json(&self) -> Result<Json, AsJsonError> {
+        Ok(json!({
+            "peers": self.peers.iter()
+                .map(Server::to_json)
+                .collect::<Result<Vec<_>, _>>()?
+        }))
+    }
+
+    fn from_json(json: &Json) -> Result<Self, AsJsonError> where Self: Sized {
+        Ok(Self {
+            peers: json.get("peers")
+                .and_then(Json::as_array)
+                .map(|peers| {
+                    peers.iter()
+                        .filter_map(|peer| Server::from_json(peer).ok())
+                        .collect()
+                })
+                .ok_or_else(|| AsJsonError::FieldNotFound("peers"))?
+        })
+    }
+}
+
+/// Response of the peer contribution endpoint.
+///
+/// `{ "accepted": N }`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PeerContributionResponse {
+    /// Amount of peers which will be verified and indexed.
+    pub accepted: usize
+}
+
+impl AsJson for PeerContributionResponse {
+    fn to_json(&self) -> Result<Json, AsJsonError> {
+        Ok(json!({
+            "accepted": self.accepted
+        }))
+    }
+
+    fn from_json(json: &Json) -> Result<Self, AsJsonError> where Self: Sized {
+        Ok(Self {
+            accepted: json.get("accepted")
+                .and_then(Json::as_u64)
+                .map(|accepted| accepted as usize)
+                .ok_or_else(|| AsJsonError::FieldNotFound("accepted"))?
+        })
+    }
+}
+
+/// Send known peers to the server on given address
+/// if it supports peer contributions.
+///
+/// Returns `false` if the server doesn't support them.
+pub async fn contribute_peers<T: HttpClient>(
+    http_client: &T,
+    address: &str,
+    contribution: &PeerContribution
+) -> Result<bool, String> {
+    let capabilities = http_client.get_request::<ServerCapabilities>(&format!("http://{address}{CAPABILITIES_PATH}")).await
+        .map_err(|err| err.to_string())?;
+
+    if !capabilities.supports(PEER_CONTRIBUTION_CAPABILITY) {
+        return Ok(false);
+    }
+
+    let url = format!("http://{address}{PEER_CONTRIBUTION_PATH}");
+
+    http_client.post_request::<_, PeerContributionResponse>(&url, contribution.clone()).await
+        .map(|_| true)
+        .map_err(|err| err.to_string())
+}
+
+struct ContributionState<T, F> {
+    client: Arc<ClientMiddleware<T>>,
+    index: F,
+    capabilities: ServerCapabilities
+}
+
+impl<T, F: Clone> Clone for ContributionState<T, F> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            index: self.index.clone(),
+            capabilities: self.capabilities.clone()
+        }
+    }
+}
+
+/// Build router of the `GET /capabilities`
+/// and `POST /peer-contribution` endpoints.
+///
+/// Contributed peers are indexed with the `index` callback
+/// in background, after they confirm their public keys.
+pub fn peer_contribution_router<T, F, R>(
+    client: Arc<ClientMiddleware<T>>,
+    capabilities: ServerCapabilities,
+    index: F
+) -> Router
+where
+    T: HttpClient + Send + Sync + 'static,
+    F: Fn(Server) -> R + Clone + Send + Sync + 'static,
+    R: Future<Output = bool> + Send + 'static
+{
+    Router::new()
+        .route(CAPABILITIES_PATH, get(get_capabilities::<T, F>))
+        .route(PEER_CONTRIBUTION_PATH, post(contribute::<T, F, R>))
+        .with_state(ContributionState {
+            client,
+            index,
+            capabilities
+        })
+}
+
+async fn get_capabilities<T, F>(State(state): State<ContributionState<T, F>>) -> Response
+where
+    T: HttpClient + Send + Sync + 'static,
+    F: Clone + Send + Sync + 'static
+{
+    match state.capabilities.to_json() {
+        Ok(capabilities) => axum::Json(capabilities).into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+    }
+}
+
+async fn contribute<T, F, R>(
+    State(state): State<ContributionState<T, F>>,
+    axum::Json(request): axum::Json<Json>
+) -> Response
+where
+    T: HttpClient + Send + Sync + 'static,
+    F: Fn(Server) -> R + Clone + Send + Sync + 'static,
+    R: Future<Output = bool> + Send + 'static
+{
+    let contribution = match PeerContribution::from_json(&request) {
+        Ok(contribution) => contribution,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response()
+    };
+
+    let peers = contribution.peers.into_iter()
+        .take(PEER_CONTRIBUTION_MAX_PEERS)
+        .collect::<Vec<_>>();
+
+    let accepted = peers.len();
+
+    // Contributed peers are not trusted, so index only
+    // the ones which respond with the same public key
+    tokio::spawn(async move {
+        for peer in peers {
+            let info = tokio::time::timeout(PEER_VERIFY_TIMEOUT, state.client.get_info(&peer.address)).await;
+
+            match info {
+                Ok(Ok(info)) if info.public_key == peer.public_key => {
+                    let _indexed = (state.index)(peer).await;
+                }
+
+                _ => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("[server] Skipped unverified contributed peer {}", peer.address);
+                }
+            }
+        }
+    });
+
+    match (PeerContributionResponse { accepted }).to_json() {
+        Ok(response) => axum::Json(response).into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+    }
+}
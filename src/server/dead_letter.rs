@@ -0,0 +1,432 @@
+use std::path::PathBuf;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value as Json};
+
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+use hyperborealib::drivers::prelude::*;
+
+use crate::client::Channel;
+
+/// Interval of moving expired messages to the dead-letter channel.
+pub const DEAD_LETTER_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+#[inline]
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DeadLetterError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    SerdeJson(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    AsJson(#[from] AsJsonError)
+}
+
+/// Message which wasn't delivered to its recipient.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadLetter {
+    /// Unique id of the dead letter.
+    pub id: u64,
+
+    /// Channel the message was sent to.
+    pub original_channel: String,
+
+    /// Public key of the message recipient.
+    pub original_recipient: PublicKey,
+
+    /// Reason why the message wasn't delivered.
+    pub failure_reason: String,
+
+    /// Unix timestamp of the delivery failure.
+    pub failed_at: u64,
+
+    /// Original message.
+    pub message: MessageInfo
+}
+
+impl AsJson for DeadLetter {
+    fn to_json(&self) -> Result<Json, AsJsonError> {
+        Ok(json!({
+            "id": self.id,
+            "original_channel": self.original_channel,
+            "original_recipient": self.original_recipient.to_base64(),
+            "failure_reason": self.failure_reason,
+            "failed_at": self.failed_at,
+            "message": self.message.to_json()?
+        }))
+    }
+
+    fn from_json(json: &Json) -> Result<Self, AsJsonError> where Self: Sized {
+        Ok(Self {
+            id: json.get("id")
+                .and_then(Json::as_u64)
+                .ok_or_else(|| AsJsonError::FieldNotFound("id"))?,
+
+            original_channel: json.get("original_channel")
+                .and_then(Json::as_str)
+                .map(String::from)
+                .ok_or_else(|| AsJsonError::FieldNotFound("original_channel"))?,
+
+            original_recipient: json.get("original_recipient")
+                .and_then(Json::as_str)
+                .and_then(|key| PublicKey::from_base64(key).ok())
+                .ok_or_else(|| AsJsonError::FieldNotFound("original_recipient"))?,
+
+            failure_reason: json.get("failure_reason")
+                .and_then(Json::as_str)
+                .map(String::from)
+                .ok_or_else(|| AsJsonError::FieldNotFound("failure_reason"))?,
+
+            failed_at: json.get("failed_at")
+                .and_then(Json::as_u64)
+                .ok_or_else(|| AsJsonError::FieldNotFound("failed_at"))?,
+
+            message: json.get("message")
+                .map(MessageInfo::from_json)
+                .ok_or_else(|| AsJsonError::FieldNotFound("message"))??
+        })
+    }
+}
+
+/// Dead-letter channel storing messages
+/// which weren't delivered to their recipients.
+///
+/// Letters are appended to a JSON lines file, and removed
+/// letters are marked by appending `{ "removed": id }` lines,
+/// so the file is never rewritten and multiple queues with the
+/// same path can be used from different places of the server.
+#[derive(Debug)]
+pub struct DeadLetterQueue {
+    channel: Channel,
+    path: PathBuf,
+    lock: Mutex<()>
+}
+
+impl DeadLetterQueue {
+    pub fn new(channel: Channel, path: impl Into<PathBuf>) -> Self {
+        Self {
+            channel,
+            path: path.into(),
+            lock: Mutex::new(())
+        }
+    }
+
+    /// Name of the dead-letter channel.
+    #[inline]
+    pub fn channel(&self) -> &Channel {
+        &self.channel
+    }
+
+    async fn read(&self) -> Result<Vec<DeadLetter>, DeadLetterError> {
+        let content = match tokio::fs::read(&self.path).await {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err.into())
+        };
+
+        let mut letters = Vec::new();
+
+        for line in content.split(|byte| *byte == b'\n') {
+            if line.is_empty() {
+                continue;
+            }
+
+            let record = serde_json::from_slice::<Json>(line)?;
+
+            match record.get("removed").and_then(Json::as_u64) {
+                Some(id) => letters.retain(|letter: &DeadLetter| letter.id != id),
+                None => letters.push(DeadLetter::from_json(&record)?)
+            }
+        }
+
+        Ok(letters)
+    }
+
+    async fn append(&self, record: &Json) -> Result<(), DeadLetterError> {
+        let mut line = serde_json::to_vec(record)?;
+
+        line.push(b'\n');
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path).await?;
+
+        file.write_all(&line).await?;
+        file.flush().await?;
+
+        Ok(())
+    }
+
+    /// Move message to the dead-letter channel.
+    pub async fn push(&self, message: MessageInfo, recipient: PublicKey, failure_reason: impl ToString) -> Result<DeadLetter, DeadLetterError> {
+        let letter = DeadLetter {
+            id: safe_random_u64(),
+            original_channel: message.channel.clone(),
+            original_recipient: recipient,
+            failure_reason: failure_reason.to_string(),
+            failed_at: timestamp(),
+            message
+        };
+
+        let _lock = self.lock.lock().await;
+
+        self.append(&letter.to_json()?).await?;
+
+        Ok(letter)
+    }
+
+    /// List stored dead letters.
+    pub async fn list(&self) -> Result<Vec<DeadLetter>, DeadLetterError> {
+        let _lock = self.lock.lock().await;
+
+        self.read().await
+    }
+
+    /// Remove dead letter from the channel, returning it.
+    pub async fn take(&self, id: u64) -> Result<Option<DeadLetter>, DeadLetterError> {
+        let _lock = self.lock.lock().await;
+
+        let letter = self.read().await?
+            .into_iter()
+            .find(|letter| letter.id == id);
+
+        if letter.is_some() {
+            self.append(&json!({ "removed": id })).await?;
+        }
+
+        Ok(letter)
+    }
+
+    /// Put dead letter back to the inbox of its recipient.
+    ///
+    /// Returns `false` if there's no letter with given id.
+    pub async fn requeue<T: MessagesInbox + Sync>(&self, id: u64, inbox: &T) -> Result<bool, DeadLetterError> {
+        let Some(letter) = self.take(id).await? else {
+            return Ok(false);
+        };
+
+        let result = inbox.add_message(
+            letter.message.sender.clone(),
+            letter.original_recipient.clone(),
+            letter.original_channel.clone(),
+            letter.message.message.clone()
+        ).await;
+
+        // Return the letter back if it can't be requeued
+        if let Err(err) = result {
+            let _lock = self.lock.lock().await;
+
+            self.append(&letter.to_json()?).await?;
+
+            return Err(DeadLetterError::Io(std::io::Error::other(err.to_string())));
+        }
+
+        Ok(true)
+    }
+
+    /// Remove dead letter from the channel.
+    ///
+    /// Returns `false` if there's no letter with given id.
+    #[inline]
+    pub async fn discard(&self, id: u64) -> Result<bool, DeadLetterError> {
+        Ok(self.take(id).await?.is_some())
+    }
+}
+
+/// Messages inbox wrapper which moves messages older
+/// than `max_age` to the dead-letter channel instead
+/// of delivering them to their recipients.
+///
+/// Expired messages are moved when their channel is polled
+/// or by the `sweep_expired` method, which is periodically
+/// called by the `run` function for the messages added
+/// since the server was started.
+pub struct DeadLetterInbox<T> {
+    inner: T,
+    max_age: Option<Duration>,
+    queue: Option<Arc<DeadLetterQueue>>,
+    added: std::sync::Mutex<HashMap<(PublicKey, String), VecDeque<u64>>>
+}
+
+impl<T> DeadLetterInbox<T> {
+    /// Wrap given inbox. Messages are delivered
+    /// as is if `max_age` or `queue` is not set.
+    pub fn new(inner: T, max_age: Option<Duration>, queue: Option<Arc<DeadLetterQueue>>) -> Self {
+        Self {
+            inner,
+            max_age,
+            queue,
+            added: std::sync::Mutex::new(HashMap::new())
+        }
+    }
+
+    #[inline]
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Forget timestamps of the polled messages.
+    fn forget_polled(&self, receiver: &PublicKey, channel: &str, polled: usize, remaining: u64) {
+        if let Ok(mut added) = self.added.lock() {
+            let key = (receiver.clone(), channel.to_string());
+
+            if let Some(timestamps) = added.get_mut(&key) {
+                timestamps.drain(..polled.min(timestamps.len()));
+
+                if timestamps.is_empty() || remaining == 0 {
+                    added.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// Move expired messages to the dead-letter
+    /// channel, returning the other ones.
+    async fn move_expired(&self, receiver: &PublicKey, messages: Vec<MessageInfo>) -> (Vec<MessageInfo>, u64) {
+        let (Some(max_age), Some(queue)) = (self.max_age, &self.queue) else {
+            return (messages, 0);
+        };
+
+        let now = timestamp();
+
+        let mut delivered = Vec::with_capacity(messages.len());
+        let mut moved = 0;
+
+        for message in messages {
+            if now.saturating_sub(message.received_at) <= max_age.as_secs() {
+                delivered.push(message);
+
+                continue;
+            }
+
+            // Move expired message to the dead-letter channel
+            match queue.push(message, receiver.clone(), "expired").await {
+                Ok(_) => moved += 1,
+
+                Err(_err) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::error!("[server] Failed to move message to the dead-letter channel: {_err}");
+                }
+            }
+        }
+
+        (delivered, moved)
+    }
+}
+
+impl<T> DeadLetterInbox<T>
+where
+    T: MessagesInbox + Send + Sync
+{
+    /// Move expired messages to the dead-letter channel
+    /// without waiting for their recipients to poll them.
+    ///
+    /// Only messages added through this inbox are tracked,
+    /// the older ones are moved when their channel is polled.
+    /// Returns amount of moved messages.
+    pub async fn sweep_expired(&self) -> u64 {
+        let (Some(max_age), Some(_)) = (self.max_age, &self.queue) else {
+            return 0;
+        };
+
+        let now = timestamp();
+
+        // Messages are polled in the order they were added,
+        // so the expired ones are at the front of their channels
+        let expired = self.added.lock()
+            .map(|added| {
+                added.iter()
+                    .map(|(key, timestamps)| {
+                        let expired = timestamps.iter()
+                            .take_while(|added_at| now.saturating_sub(**added_at) > max_age.as_secs())
+                            .count();
+
+                        (key.clone(), expired)
+                    })
+                    .filter(|(_, expired)| *expired > 0)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let mut swept = 0;
+
+        for ((receiver, channel), expired) in expired {
+            let Ok((messages, remaining)) = self.inner.poll_messages(receiver.clone(), channel.clone(), Some(expired as u64)).await else {
+                #[cfg(feature = "tracing")]
+                tracing::warn!("[server] Failed to poll expired messages of the {channel} channel");
+
+                continue;
+            };
+
+            self.forget_polled(&receiver, &channel, messages.len(), remaining);
+
+            let (fresh, moved) = self.move_expired(&receiver, messages).await;
+
+            swept += moved;
+
+            // Return messages which weren't expired yet, e.g. if
+            // the recipient has polled the channel in the meantime
+            for message in fresh {
+                let _result = self.add_message(message.sender, receiver.clone(), channel.clone(), message.message).await;
+
+                #[cfg(feature = "tracing")]
+                if _result.is_err() {
+                    tracing::error!("[server] Failed to return message to the {channel} channel");
+                }
+            }
+        }
+
+        swept
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> MessagesInbox for DeadLetterInbox<T>
+where
+    T: MessagesInbox + Send + Sync
+{
+    type Error = T::Error;
+
+    async fn add_message(&self, sender: Sender, receiver: PublicKey, channel: String, message: Message) -> Result<(), Self::Error> {
+        let key = (receiver.clone(), channel.clone());
+
+        self.inner.add_message(sender, receiver, channel, message).await?;
+
+        // Remember when the message was added to sweep it once expired
+        if self.max_age.is_some() && self.queue.is_some() {
+            if let Ok(mut added) = self.added.lock() {
+                added.entry(key)
+                    .or_default()
+                    .push_back(timestamp());
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn poll_messages(&self, receiver: PublicKey, channel: String, limit: Option<u64>) -> Result<(Vec<MessageInfo>, u64), Self::Error> {
+        let (messages, remaining) = self.inner.poll_messages(receiver.clone(), channel.clone(), limit).await?;
+
+        self.forget_polled(&receiver, &channel, messages.len(), remaining);
+
+        let (delivered, _) = self.move_expired(&receiver, messages).await;
+
+        Ok((delivered, remaining))
+    }
+}
@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+
+use super::BackendReport;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ServerRunError<E> {
+    #[error("Failed to initialize server middleware: {0:?}")]
+    MiddlewareInit(E),
+
+    #[error("Failed to initialize HTTP client: {0:?}")]
+    HttpClientInit(E),
+
+    #[error("Failed to bind address: {0}")]
+    Bind(std::io::Error),
+
+    #[error("Failed to load servers blacklist: {0}")]
+    Blacklist(std::io::Error),
+
+    #[error("Failed to load TLS certificate: {0}")]
+    Tls(std::io::Error),
+
+    #[error("Failed to forward port {port}: {reason}")]
+    PortForward {
+        port: u16,
+        reason: String
+    },
+
+    #[error("Failed to index bootstrap server {address}: {reason}")]
+    BootstrapIndex {
+        address: String,
+        reason: String
+    },
+
+    #[error("Server public key mismatch: configured {configured}, actual {actual}")]
+    IdentityMismatch {
+        configured: String,
+        actual: String
+    },
+
+    #[error("REST API routes overlap: {0}")]
+    RoutesOverlap(String),
+
+    #[error("Network traversal task panicked")]
+    TraversalPanic,
+
+    #[error("Failed to check backend folder: {0:?}")]
+    BackendCheck(E),
+
+    #[error("Backend folder is corrupted: {0}")]
+    BackendCorrupted(BackendReport),
+
+    #[error("Failed to quarantine corrupted backend files: {0}")]
+    BackendQuarantine(std::io::Error),
+
+    #[error("Corrupted backend files were moved to {}: {report}", quarantine.display())]
+    BackendQuarantined {
+        quarantine: PathBuf,
+        report: BackendReport
+    }
+}
+
+impl<E> ServerRunError<E> {
+    /// Check if the server can't continue running after this error.
+    ///
+    /// Non-fatal errors are passed to the `ServerApp::on_error` hook,
+    /// and fatal ones are returned from the `run` function.
+    pub fn is_fatal(&self) -> bool {
+        !matches!(self, Self::PortForward { .. } | Self::BootstrapIndex { .. } | Self::BackendQuarantined { .. })
+    }
+}
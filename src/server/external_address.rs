@@ -0,0 +1,101 @@
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use tokio::net::UdpSocket;
+
+use ::stun::message::{Message, Getter, BINDING_REQUEST};
+use ::stun::agent::TransactionId;
+use ::stun::xoraddr::XorMappedAddress;
+
+/// Timeout of the STUN binding request.
+pub const STUN_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, thiserror::Error)]
+pub enum StunError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid STUN message: {0}")]
+    Message(#[from] ::stun::Error),
+
+    #[error("STUN server address can't be resolved")]
+    UnresolvedServer,
+
+    #[error("STUN server didn't respond in time")]
+    Timeout,
+
+    #[error("STUN server responded to another transaction")]
+    TransactionMismatch
+}
+
+/// Discover external IP address of the current
+/// machine using the given STUN server.
+pub async fn discover_external_ip(stun_server: impl AsRef<str>) -> Result<IpAddr, StunError> {
+    let server = tokio::net::lookup_host(stun_server.as_ref()).await?
+        .next()
+        .ok_or(StunError::UnresolvedServer)?;
+
+    let local_address = match server {
+        SocketAddr::V4(_) => "0.0.0.0:0",
+        SocketAddr::V6(_) => "[::]:0"
+    };
+
+    let socket = UdpSocket::bind(local_address).await?;
+
+    socket.connect(server).await?;
+
+    // Send binding request
+    let mut request = Message::new();
+
+    request.build(&[
+        Box::new(TransactionId::new()),
+        Box::new(BINDING_REQUEST)
+    ])?;
+
+    socket.send(&request.raw).await?;
+
+    // Read binding response
+    let mut buf = [0; 1024];
+
+    let len = tokio::time::timeout(STUN_TIMEOUT, socket.recv(&mut buf)).await
+        .map_err(|_| StunError::Timeout)??;
+
+    let mut response = Message::new();
+
+    response.unmarshal_binary(&buf[..len])?;
+
+    if response.transaction_id != request.transaction_id {
+        return Err(StunError::TransactionMismatch);
+    }
+
+    let mut address = XorMappedAddress::default();
+
+    address.get_from(&response)?;
+
+    Ok(address.ip)
+}
+
+/// Replace IP of the given `remote_address` by the `external_ip`
+/// keeping its port.
+///
+/// Returns `None` if the address is already the same, or if it's
+/// not an IP address (e.g. a domain name which shouldn't be replaced).
+///
+/// ```rust
+/// use hyperelm::server::replace_address_ip;
+///
+/// let external = "203.0.113.7".parse().unwrap();
+///
+/// assert_eq!(replace_address_ip("127.0.0.1:8001", external).as_deref(), Some("203.0.113.7:8001"));
+/// assert_eq!(replace_address_ip("203.0.113.7:8001", external), None);
+/// assert_eq!(replace_address_ip("example.com:8001", external), None);
+/// ```
+pub fn replace_address_ip(remote_address: &str, external_ip: IpAddr) -> Option<String> {
+    let address = remote_address.parse::<SocketAddr>().ok()?;
+
+    if address.ip() == external_ip {
+        return None;
+    }
+
+    Some(SocketAddr::new(external_ip, address.port()).to_string())
+}
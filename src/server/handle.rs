@@ -0,0 +1,182 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+use super::{ServerStats, Blacklist, BanEntry, PeerAges};
+
+/// Reason why the server didn't pass its local self-check.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ServerNotReady {
+    #[error("Server is not reachable by its local address {0}")]
+    Unreachable(String),
+
+    #[error("Server was stopped before passing its self-check")]
+    Stopped
+}
+
+#[derive(Debug)]
+struct ServerHandleInner {
+    stats: ServerStats,
+    blacklist: Blacklist,
+    peer_ages: PeerAges,
+    peers_swept: AtomicU64,
+    traverse_delay_ms: AtomicU64,
+    startup_token: AtomicU64,
+    ready: watch::Sender<Option<Result<(), ServerNotReady>>>,
+    shutdown: watch::Sender<bool>
+}
+
+/// Handle to the running server application.
+///
+/// Can be cloned and shared between threads.
+#[derive(Debug, Clone)]
+pub struct ServerHandle {
+    inner: Arc<ServerHandleInner>
+}
+
+impl Default for ServerHandle {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(ServerHandleInner {
+                stats: ServerStats::default(),
+                blacklist: Blacklist::default(),
+                peer_ages: PeerAges::default(),
+                peers_swept: AtomicU64::new(0),
+                traverse_delay_ms: AtomicU64::new(0),
+                startup_token: AtomicU64::new(0),
+                ready: watch::channel(None).0,
+                shutdown: watch::channel(false).0
+            })
+        }
+    }
+}
+
+impl ServerHandle {
+    #[inline]
+    pub fn stats(&self) -> &ServerStats {
+        &self.inner.stats
+    }
+
+    #[inline]
+    pub fn blacklist(&self) -> &Blacklist {
+        &self.inner.blacklist
+    }
+
+    /// Ban server with given address so it's not indexed
+    /// by the network traversal anymore.
+    ///
+    /// Banned server is removed from the router using the
+    /// `ServerApp::remove_known_server` method. Ban is stored
+    /// in the `blacklist_path` file if it's set.
+    #[inline]
+    pub async fn ban_server(&self, address: &str, reason: &str) -> std::io::Result<bool> {
+        self.inner.blacklist.ban(address, reason).await
+    }
+
+    /// Remove server with given address from the blacklist.
+    #[inline]
+    pub async fn unban_server(&self, address: &str) -> std::io::Result<bool> {
+        self.inner.blacklist.unban(address).await
+    }
+
+    /// List banned servers.
+    #[inline]
+    pub fn list_banned(&self) -> Vec<BanEntry> {
+        self.inner.blacklist.list()
+    }
+
+    #[inline]
+    pub fn peer_ages(&self) -> &PeerAges {
+        &self.inner.peer_ages
+    }
+
+    /// Get amount of expired servers removed from
+    /// the routing table by the last peers sweep.
+    #[inline]
+    pub fn peers_swept_last_cycle(&self) -> u64 {
+        self.inner.peers_swept.load(Ordering::Relaxed)
+    }
+
+    /// Get current delay between network traversals.
+    ///
+    /// Changes over time if the adaptive traversal is enabled.
+    #[inline]
+    pub fn current_traverse_delay(&self) -> Duration {
+        Duration::from_millis(self.inner.traverse_delay_ms.load(Ordering::Relaxed))
+    }
+
+    /// Get random token generated when the server was started.
+    ///
+    /// Token is changed on every run, so clients can detect
+    /// server restarts by comparing it with the known one.
+    #[inline]
+    pub fn startup_token(&self) -> u64 {
+        self.inner.startup_token.load(Ordering::Relaxed)
+    }
+
+    /// Check if the server has passed its local self-check.
+    #[inline]
+    pub fn is_ready(&self) -> bool {
+        matches!(*self.inner.ready.borrow(), Some(Ok(())))
+    }
+
+    /// Wait until the server finishes its local self-check.
+    ///
+    /// Returns error if the server is not reachable
+    /// for clients or was stopped before the check.
+    pub async fn ready(&self) -> Result<(), ServerNotReady> {
+        let mut ready = self.inner.ready.subscribe();
+
+        match ready.wait_for(Option::is_some).await {
+            Ok(result) => (*result).clone().unwrap_or(Err(ServerNotReady::Stopped)),
+            Err(_) => Err(ServerNotReady::Stopped)
+        }
+    }
+
+    /// Ask the server to stop.
+    #[inline]
+    pub fn shutdown(&self) {
+        self.inner.shutdown.send_replace(true);
+    }
+
+    #[inline]
+    pub fn is_shutdown(&self) -> bool {
+        *self.inner.shutdown.borrow()
+    }
+
+    /// Wait until the server is asked to stop.
+    pub async fn wait_shutdown(&self) {
+        let mut shutdown = self.inner.shutdown.subscribe();
+
+        let _ = shutdown.wait_for(|shutdown| *shutdown).await;
+    }
+
+    /// Report result of the self-check.
+    ///
+    /// Only the first reported result is kept.
+    pub(crate) fn set_ready(&self, result: Result<(), ServerNotReady>) {
+        self.inner.ready.send_if_modified(|ready| {
+            if ready.is_some() {
+                return false;
+            }
+
+            *ready = Some(result);
+
+            true
+        });
+    }
+
+    pub(crate) fn set_startup_token(&self, token: u64) {
+        self.inner.startup_token.store(token, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_peers_swept(&self, swept: u64) {
+        self.inner.peers_swept.store(swept, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_traverse_delay(&self, delay: Duration) {
+        self.inner.traverse_delay_ms.store(delay.as_millis() as u64, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,163 @@
+use std::net::{IpAddr, SocketAddr};
+
+use serde_json::json;
+
+use ipnet::IpNet;
+
+use axum::Router;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::middleware::{from_fn_with_state, Next};
+use axum::response::{IntoResponse, Response};
+
+/// Header containing addresses of the client and the proxies.
+pub const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// Filter of the HTTP requests by the client IP address.
+///
+/// Client address is taken from the `X-Forwarded-For` header
+/// if the request was sent by one of the trusted proxies,
+/// and from the socket address otherwise.
+///
+/// ```rust
+/// use hyperelm::server::IpFilter;
+///
+/// let filter = IpFilter {
+///     allowlist: Some(vec!["10.0.0.0/8".parse().unwrap()]),
+///     blocklist: vec!["10.0.0.13/32".parse().unwrap()],
+///     trusted_proxies: vec!["127.0.0.1/32".parse().unwrap()]
+/// };
+///
+/// assert!(filter.is_allowed("10.1.2.3".parse().unwrap()));
+/// assert!(!filter.is_allowed("10.0.0.13".parse().unwrap()));
+/// assert!(!filter.is_allowed("192.168.1.1".parse().unwrap()));
+///
+/// // Forwarded address is used only for the trusted proxies
+/// assert_eq!(
+///     filter.client_ip("127.0.0.1".parse().unwrap(), Some("192.168.1.1, 10.1.2.3")),
+///     "10.1.2.3".parse::<std::net::IpAddr>().unwrap()
+/// );
+///
+/// assert_eq!(
+///     filter.client_ip("10.0.0.13".parse().unwrap(), Some("10.1.2.3")),
+///     "10.0.0.13".parse::<std::net::IpAddr>().unwrap()
+/// );
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IpFilter {
+    /// Networks allowed to send requests.
+    /// All the networks are allowed if not set.
+    pub allowlist: Option<Vec<IpNet>>,
+
+    /// Networks not allowed to send requests.
+    ///
+    /// Applied before the allowlist.
+    pub blocklist: Vec<IpNet>,
+
+    /// Networks of the reverse proxies whose
+    /// `X-Forwarded-For` header is trusted.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub trusted_proxies: Vec<IpNet>
+}
+
+impl IpFilter {
+    /// Check if requests from the address are allowed.
+    pub fn is_allowed(&self, ip: IpAddr) -> bool {
+        if self.blocklist.iter().any(|net| net.contains(&ip)) {
+            return false;
+        }
+
+        match &self.allowlist {
+            Some(allowlist) => allowlist.iter().any(|net| net.contains(&ip)),
+            None => true
+        }
+    }
+
+    #[inline]
+    fn is_trusted_proxy(&self, ip: &IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(ip))
+    }
+
+    /// Get address of the client which sent the request
+    /// from the given socket address.
+    ///
+    /// The `X-Forwarded-For` header is read from right to left,
+    /// skipping trusted proxies, so clients can't spoof their
+    /// address by sending the header themselves.
+    pub fn client_ip(&self, socket_ip: IpAddr, forwarded_for: Option<&str>) -> IpAddr {
+        if !self.is_trusted_proxy(&socket_ip) {
+            return socket_ip;
+        }
+
+        let Some(forwarded_for) = forwarded_for else {
+            return socket_ip;
+        };
+
+        let mut client_ip = socket_ip;
+
+        for ip in forwarded_for.rsplit(',') {
+            let Ok(ip) = ip.trim().parse::<IpAddr>() else {
+                break;
+            };
+
+            client_ip = ip;
+
+            if !self.is_trusted_proxy(&ip) {
+                break;
+            }
+        }
+
+        client_ip
+    }
+
+    fn request_ip(&self, socket: Option<SocketAddr>, headers: &HeaderMap) -> Option<IpAddr> {
+        let forwarded_for = headers.get(FORWARDED_FOR_HEADER)
+            .and_then(|value| value.to_str().ok());
+
+        socket.map(|socket| self.client_ip(socket.ip(), forwarded_for))
+    }
+}
+
+/// Build response returned for requests from not allowed addresses.
+///
+/// Response has `403 Forbidden` status
+/// and `{ "error": "forbidden" }` body.
+pub fn ip_forbidden() -> Response {
+    (StatusCode::FORBIDDEN, axum::Json(json!({
+        "error": "forbidden"
+    }))).into_response()
+}
+
+async fn check_ip(State(filter): State<IpFilter>, request: Request, next: Next) -> Response {
+    let socket = request.extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(socket)| *socket);
+
+    let ip = filter.request_ip(socket, request.headers());
+
+    // Reject requests with unknown address if the allowlist is set
+    let allowed = match ip {
+        Some(ip) => filter.is_allowed(ip),
+        None => filter.allowlist.is_none()
+    };
+
+    if !allowed {
+        #[cfg(feature = "tracing")]
+        tracing::info!("[server] Blocked request from {ip:?} to {}", request.uri().path());
+
+        return ip_forbidden();
+    }
+
+    next.run(request).await
+}
+
+/// Reject requests from the addresses not allowed
+/// by the filter with `403 Forbidden` response.
+///
+/// The router must be served with the socket address connect
+/// info, otherwise client addresses are unknown and all the
+/// requests are rejected if the allowlist is set.
+pub fn filter_ips(router: Router, filter: IpFilter) -> Router {
+    router.layer(from_fn_with_state(filter, check_ip))
+}
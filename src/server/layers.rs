@@ -0,0 +1,120 @@
+use axum::Router;
+use axum::extract::Request;
+use axum::http::{HeaderName, HeaderValue, Method};
+use axum::middleware::{from_fn, Next};
+use axum::response::Response;
+
+/// Header containing id of the HTTP request.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// HTTP middleware applied to the routers
+/// served by the server application.
+///
+/// ```rust,ignore
+/// impl BasicServerApp for MyServerApp {
+///     fn http_layers(&self) -> Vec<Box<dyn HttpLayer>> {
+///         vec![
+///             Box::new(RequestIdLayer),
+///             Box::new(CorsLayer::any()),
+///             Box::new(CompressionLayer)
+///         ]
+///     }
+/// }
+/// ```
+pub trait HttpLayer: Send + Sync {
+    /// Wrap all the routes of the router.
+    fn apply(&self, router: Router) -> Router;
+}
+
+/// Apply layers to the router in the given order.
+pub fn apply_layers(router: Router, layers: &[Box<dyn HttpLayer>]) -> Router {
+    layers.iter().fold(router, |router, layer| layer.apply(router))
+}
+
+/// Allow cross-origin requests from the browsers.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CorsLayer {
+    /// Allowed origins. Any origin is allowed if not set.
+    pub allowed_origins: Option<Vec<String>>
+}
+
+impl CorsLayer {
+    /// Allow requests from any origin.
+    #[inline]
+    pub fn any() -> Self {
+        Self::default()
+    }
+
+    /// Allow requests from the listed origins only.
+    pub fn origins(origins: impl IntoIterator<Item = impl ToString>) -> Self {
+        Self {
+            allowed_origins: Some(origins.into_iter().map(|origin| origin.to_string()).collect())
+        }
+    }
+}
+
+impl HttpLayer for CorsLayer {
+    fn apply(&self, router: Router) -> Router {
+        let cors = tower_http::cors::CorsLayer::new()
+            .allow_methods([Method::GET, Method::POST, Method::DELETE])
+            .allow_headers(tower_http::cors::Any);
+
+        let cors = match &self.allowed_origins {
+            Some(origins) => cors.allow_origin(origins.iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok())
+                .collect::<Vec<_>>()),
+
+            None => cors.allow_origin(tower_http::cors::Any)
+        };
+
+        router.layer(cors)
+    }
+}
+
+/// Compress responses using the encodings
+/// accepted by the client.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CompressionLayer;
+
+impl HttpLayer for CompressionLayer {
+    #[inline]
+    fn apply(&self, router: Router) -> Router {
+        router.layer(tower_http::compression::CompressionLayer::new())
+    }
+}
+
+/// Add `X-Request-Id` header to every request and response.
+///
+/// Id sent by the client is kept, otherwise a random one is generated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RequestIdLayer;
+
+impl HttpLayer for RequestIdLayer {
+    #[inline]
+    fn apply(&self, router: Router) -> Router {
+        router.layer(from_fn(inject_request_id))
+    }
+}
+
+async fn inject_request_id(mut request: Request, next: Next) -> Response {
+    let header = HeaderName::from_static(REQUEST_ID_HEADER);
+
+    let id = match request.headers().get(&header) {
+        Some(id) => id.clone(),
+
+        None => {
+            let id = HeaderValue::from_str(&format!("{:032x}", rand::random::<u128>()))
+                .expect("hex string is a valid header value");
+
+            request.headers_mut().insert(header.clone(), id.clone());
+
+            id
+        }
+    };
+
+    let mut response = next.run(request).await;
+
+    response.headers_mut().insert(header, id);
+
+    response
+}
@@ -0,0 +1,49 @@
+use serde_json::json;
+
+use axum::Router;
+use axum::body::{to_bytes, Body};
+use axum::extract::{Request, State};
+use axum::http::{StatusCode, header};
+use axum::middleware::{from_fn_with_state, Next};
+use axum::response::{IntoResponse, Response};
+
+/// Default maximal size of the incoming request body.
+pub const DEFAULT_MAX_INCOMING_MESSAGE_BYTES: usize = 4 * 1024 * 1024;
+
+/// Build response returned for requests with too large body.
+///
+/// Response has `413 Payload Too Large` status and
+/// `{ "error": "payload_too_large", "limit": N }` body.
+pub fn payload_too_large(limit: usize) -> Response {
+    (StatusCode::PAYLOAD_TOO_LARGE, axum::Json(json!({
+        "error": "payload_too_large",
+        "limit": limit
+    }))).into_response()
+}
+
+async fn limit_body_size(State(limit): State<usize>, request: Request, next: Next) -> Response {
+    // Reject request early if its declared size is too large
+    let content_length = request.headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+
+    if content_length.is_some_and(|length| length > limit) {
+        return payload_too_large(limit);
+    }
+
+    // Read the body otherwise, failing if it exceeds the limit
+    let (parts, body) = request.into_parts();
+
+    let Ok(body) = to_bytes(body, limit).await else {
+        return payload_too_large(limit);
+    };
+
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
+
+/// Reject requests with body larger than `limit` bytes
+/// with `413 Payload Too Large` response.
+pub fn limit_payload_size(router: Router, limit: usize) -> Router {
+    router.layer(from_fn_with_state(limit, limit_body_size))
+}
@@ -0,0 +1,223 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Name of the backend subfolder storing quarantined files.
+pub const QUARANTINE_FOLDER: &str = "quarantine";
+
+/// Action performed when the backend folder is corrupted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum CorruptionPolicy {
+    /// Stop the server with the `BackendCorrupted` error.
+    #[default]
+    Abort,
+
+    /// Move corrupted files to the `quarantine/<timestamp>`
+    /// folder of the backend and continue without them.
+    Quarantine
+}
+
+/// Corrupted file or folder of the backend.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BackendIssue {
+    pub path: PathBuf,
+    pub reason: String
+}
+
+/// Result of the backend folder integrity check.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BackendReport {
+    pub issues: Vec<BackendIssue>
+}
+
+impl BackendReport {
+    #[inline]
+    pub fn is_healthy(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl std::fmt::Display for BackendReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.issues.is_empty() {
+            return f.write_str("no issues");
+        }
+
+        let issues = self.issues.iter()
+            .map(|issue| format!("{} ({})", issue.path.display(), issue.reason))
+            .collect::<Vec<_>>();
+
+        f.write_str(&issues.join(", "))
+    }
+}
+
+/// Integrity checks of the server backend folder.
+///
+/// Listed subfolders must be readable folders, and files
+/// stored in them must not be empty or truncated JSON.
+/// Missing subfolders are not reported because they're
+/// created by the backend on the first start.
+///
+/// ```rust
+/// use hyperelm::server::BackendMaintenance;
+///
+/// let folder = std::env::temp_dir().join("hyperelm-maintenance-doctest");
+///
+/// let _ = std::fs::remove_dir_all(&folder);
+///
+/// std::fs::create_dir_all(folder.join("inbox/channel")).unwrap();
+/// std::fs::write(folder.join("inbox/channel/1"), "{\"message\":1}").unwrap();
+/// std::fs::write(folder.join("inbox/channel/2"), "{\"mess").unwrap();
+///
+/// let maintenance = BackendMaintenance::new(&folder)
+///     .with_subfolder("router")
+///     .with_subfolder("inbox");
+///
+/// let report = maintenance.check();
+///
+/// assert_eq!(report.issues.len(), 1);
+/// assert_eq!(report.issues[0].path, folder.join("inbox/channel/2"));
+///
+/// let quarantine = maintenance.quarantine(&report).unwrap();
+///
+/// assert!(quarantine.join("inbox/channel/2").exists());
+/// assert!(folder.join("inbox/channel/1").exists());
+/// assert!(maintenance.check().is_healthy());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BackendMaintenance {
+    folder: PathBuf,
+    subfolders: Vec<String>
+}
+
+impl BackendMaintenance {
+    pub fn new(folder: impl Into<PathBuf>) -> Self {
+        Self {
+            folder: folder.into(),
+            subfolders: vec![]
+        }
+    }
+
+    /// Check given subfolder of the backend folder.
+    pub fn with_subfolder(mut self, name: impl ToString) -> Self {
+        self.subfolders.push(name.to_string());
+
+        self
+    }
+
+    #[inline]
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+
+    /// Find corrupted files and folders of the backend.
+    pub fn check(&self) -> BackendReport {
+        let mut report = BackendReport::default();
+
+        for name in &self.subfolders {
+            let subfolder = self.folder.join(name);
+
+            if !subfolder.exists() {
+                continue;
+            }
+
+            if !subfolder.is_dir() {
+                report.issues.push(BackendIssue {
+                    path: subfolder,
+                    reason: String::from("not a folder")
+                });
+
+                continue;
+            }
+
+            check_folder(&subfolder, &mut report);
+        }
+
+        report
+    }
+
+    /// Move files and folders listed in the report to
+    /// the `quarantine/<timestamp>` folder of the backend,
+    /// keeping their relative paths.
+    ///
+    /// Returns path to the created quarantine folder.
+    pub fn quarantine(&self, report: &BackendReport) -> std::io::Result<PathBuf> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let quarantine = self.folder
+            .join(QUARANTINE_FOLDER)
+            .join(timestamp.to_string());
+
+        for issue in &report.issues {
+            let relative = issue.path.strip_prefix(&self.folder)
+                .unwrap_or(&issue.path);
+
+            let target = quarantine.join(relative);
+
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            std::fs::rename(&issue.path, target)?;
+        }
+
+        Ok(quarantine)
+    }
+}
+
+fn check_folder(folder: &Path, report: &mut BackendReport) {
+    let entries = match std::fs::read_dir(folder) {
+        Ok(entries) => entries,
+
+        Err(err) => {
+            report.issues.push(BackendIssue {
+                path: folder.to_path_buf(),
+                reason: format!("unreadable folder: {err}")
+            });
+
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            check_folder(&path, report);
+        }
+
+        else if let Some(reason) = check_file(&path) {
+            report.issues.push(BackendIssue {
+                path,
+                reason
+            });
+        }
+    }
+}
+
+fn check_file(path: &Path) -> Option<String> {
+    let content = match std::fs::read(path) {
+        Ok(content) => content,
+        Err(err) => return Some(format!("unreadable file: {err}"))
+    };
+
+    if content.is_empty() {
+        return Some(String::from("empty file"));
+    }
+
+    // Files of the hyperborealib backends are stored as JSON,
+    // so partially written ones fail to deserialize
+    let is_json = content.iter()
+        .find(|byte| !byte.is_ascii_whitespace())
+        .is_some_and(|byte| *byte == b'{' || *byte == b'[');
+
+    if is_json && serde_json::from_slice::<serde_json::Value>(&content).is_err() {
+        return Some(String::from("truncated JSON"));
+    }
+
+    None
+}
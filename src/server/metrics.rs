@@ -0,0 +1,222 @@
+use std::sync::Arc;
+
+use axum::Router;
+use axum::routing::get;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+
+use hyperborealib::drivers::prelude::Router as _;
+
+use prometheus::{Encoder, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder};
+
+use crate::client::Direction;
+
+use super::{ServerApp, ServerHandle, AppServerDriver, is_authorized};
+
+struct MetricsState<T: ServerApp> {
+    app: Arc<T>,
+    driver: Arc<AppServerDriver<T>>,
+    handle: ServerHandle,
+    token: Option<String>,
+    metrics: Arc<ServerMetrics>
+}
+
+impl<T: ServerApp> Clone for MetricsState<T> {
+    fn clone(&self) -> Self {
+        Self {
+            app: self.app.clone(),
+            driver: self.driver.clone(),
+            handle: self.handle.clone(),
+            token: self.token.clone(),
+            metrics: self.metrics.clone()
+        }
+    }
+}
+
+/// Prometheus metrics of the server application.
+///
+/// Values are refreshed from the server stats
+/// every time the metrics are gathered.
+#[derive(Debug, Clone)]
+pub struct ServerMetrics {
+    registry: Registry,
+    known_peers: IntGauge,
+    traversal_cycles: IntCounter,
+    inbox_depth: IntGauge,
+    messages_processed: IntCounterVec,
+    bootstrap_indexed: IntCounterVec,
+    announced: IntCounterVec
+}
+
+impl ServerMetrics {
+    pub fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new();
+
+        let known_peers = IntGauge::new(
+            "hyperelm_known_peers_total",
+            "Amount of servers known by the router"
+        )?;
+
+        let traversal_cycles = IntCounter::new(
+            "hyperelm_traversal_cycles_total",
+            "Amount of completed network traversal cycles"
+        )?;
+
+        let inbox_depth = IntGauge::new(
+            "hyperelm_inbox_depth",
+            "Amount of messages stored in the inbox"
+        )?;
+
+        let messages_processed = IntCounterVec::new(
+            Opts::new(
+                "hyperelm_messages_processed_total",
+                "Amount of processed messages"
+            ),
+            &["direction"]
+        )?;
+
+        let bootstrap_indexed = IntCounterVec::new(
+            Opts::new(
+                "hyperelm_bootstrap_indexed_total",
+                "Amount of bootstrap address indexing attempts"
+            ),
+            &["address", "result"]
+        )?;
+
+        let announced = IntCounterVec::new(
+            Opts::new(
+                "hyperelm_announce_total",
+                "Amount of attempts to announce the server to other ones"
+            ),
+            &["address", "result"]
+        )?;
+
+        registry.register(Box::new(known_peers.clone()))?;
+        registry.register(Box::new(traversal_cycles.clone()))?;
+        registry.register(Box::new(inbox_depth.clone()))?;
+        registry.register(Box::new(messages_processed.clone()))?;
+        registry.register(Box::new(bootstrap_indexed.clone()))?;
+        registry.register(Box::new(announced.clone()))?;
+
+        Ok(Self {
+            registry,
+            known_peers,
+            traversal_cycles,
+            inbox_depth,
+            messages_processed,
+            bootstrap_indexed,
+            announced
+        })
+    }
+
+    /// Refresh metrics values and encode them
+    /// in the Prometheus text exposition format.
+    ///
+    /// Known peers are counted by the given router.
+    /// Returns formatted error of the router or the app.
+    pub async fn gather<T>(&self, app: &T, router: &T::Router, handle: &ServerHandle) -> Result<String, String>
+    where
+        T: ServerApp + Send + Sync,
+        T::Error: std::fmt::Debug
+    {
+        let stats = handle.stats();
+
+        let (known_peers, inbox_depth) = tokio::try_join!(
+            async {
+                router.servers().await
+                    .map(|servers| servers.len())
+                    .map_err(|err| err.to_string())
+            },
+            async {
+                app.inbox_depth().await
+                    .map_err(|err| format!("{err:?}"))
+            }
+        )?;
+
+        self.known_peers.set(known_peers as i64);
+        self.inbox_depth.set(inbox_depth as i64);
+
+        self.traversal_cycles.reset();
+        self.traversal_cycles.inc_by(stats.traversal_cycles());
+
+        for direction in [Direction::Incoming, Direction::Outgoing] {
+            let counter = self.messages_processed.with_label_values(&[match direction {
+                Direction::Incoming => "in",
+                Direction::Outgoing => "out"
+            }]);
+
+            counter.reset();
+            counter.inc_by(stats.messages_processed(direction));
+        }
+
+        for (address, results) in stats.bootstrap_results() {
+            for (result, value) in [("success", results.succeeded), ("failure", results.failed)] {
+                let counter = self.bootstrap_indexed.with_label_values(&[&address, result]);
+
+                counter.reset();
+                counter.inc_by(value);
+            }
+        }
+
+        for (address, results) in stats.announce_results() {
+            for (result, value) in [("success", results.succeeded), ("failure", results.failed)] {
+                let counter = self.announced.with_label_values(&[&address, result]);
+
+                counter.reset();
+                counter.inc_by(value);
+            }
+        }
+
+        let mut buffer = Vec::new();
+
+        // Encoding to a vector can't fail
+        let _ = TextEncoder::new().encode(&self.registry.gather(), &mut buffer);
+
+        Ok(String::from_utf8_lossy(&buffer).to_string())
+    }
+}
+
+/// Build Prometheus metrics router serving `/metrics` endpoint.
+///
+/// Known peers are counted by the router of the given driver.
+///
+/// If `token` is given, all the requests must contain
+/// `Authorization: Bearer <token>` header.
+pub fn metrics_router<T>(app: Arc<T>, driver: Arc<AppServerDriver<T>>, handle: ServerHandle, token: Option<String>) -> prometheus::Result<Router>
+where
+    T: ServerApp + Send + Sync + 'static,
+    T::Error: std::fmt::Debug
+{
+    Ok(Router::new()
+        .route("/metrics", get(get_metrics::<T>))
+        .with_state(MetricsState {
+            app,
+            driver,
+            handle,
+            token,
+            metrics: Arc::new(ServerMetrics::new()?)
+        }))
+}
+
+async fn get_metrics<T>(
+    State(state): State<MetricsState<T>>,
+    headers: HeaderMap
+) -> Response
+where
+    T: ServerApp + Send + Sync + 'static,
+    T::Error: std::fmt::Debug
+{
+    if !is_authorized(&headers, state.token.as_deref()) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    match state.metrics.gather(state.app.as_ref(), state.driver.router(), &state.handle).await {
+        Ok(metrics) => (
+            [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+            metrics
+        ).into_response(),
+
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err).into_response()
+    }
+}
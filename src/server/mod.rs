@@ -1,11 +1,65 @@
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use hyperborealib::crypto::prelude::*;
+use futures::stream::{FuturesUnordered, StreamExt};
+
+use hyperborealib::http::HttpClient;
 use hyperborealib::drivers::prelude::*;
 use hyperborealib::rest_api::prelude::*;
 use hyperborealib::port_forward::*;
 
+use crate::http::{HttpClientConfig, StatusHttpClient};
+
 mod params;
+mod error;
+mod stats;
+mod blacklist;
+mod peers;
+mod handle;
+mod dead_letter;
+mod plugins;
+mod relay;
+mod announce;
+mod multicast;
+mod contribution;
+mod layers;
+mod signing;
+mod tls;
+mod traversal;
+mod admin;
+mod limits;
+mod rest_api;
+mod maintenance;
+mod ip_filter;
+mod status;
 mod app;
 
 pub use params::*;
+pub use error::*;
+pub use stats::*;
+pub use blacklist::*;
+pub use peers::*;
+pub use handle::*;
+pub use dead_letter::*;
+pub use plugins::*;
+pub use relay::*;
+pub use announce::*;
+pub use multicast::*;
+pub use contribution::*;
+pub use layers::*;
+pub use signing::*;
+pub use tls::*;
+pub use traversal::*;
+pub use admin::*;
+pub use limits::*;
+pub use rest_api::*;
+pub use maintenance::*;
+pub use ip_filter::*;
+pub use status::*;
 pub use app::*;
 
 #[cfg(feature = "server-basic-app")]
@@ -14,92 +68,1268 @@ mod basic_app;
 #[cfg(feature = "server-basic-app")]
 pub use basic_app::*;
 
+#[cfg(feature = "stun")]
+mod external_address;
+
+#[cfg(feature = "stun")]
+pub use external_address::*;
+
+#[cfg(feature = "metrics")]
+mod metrics;
+
+#[cfg(feature = "metrics")]
+pub use metrics::*;
+
+/// Wait until the server on given address responds
+/// to the info request or the timeout is elapsed.
+///
+/// Returns `true` if the server is reachable.
+pub async fn wait_ready(address: impl AsRef<str>, timeout: Duration) -> bool {
+    let client = ClientMiddleware::new(
+        StatusHttpClient::default(),
+        ClientDriver::new(ClientInfo::thin(), SecretKey::random())
+    );
+
+    wait_ready_with(&client, address, timeout).await
+}
+
+/// Same as `wait_ready`, but uses given client middleware.
+async fn wait_ready_with<T: HttpClient>(
+    client: &ClientMiddleware<T>,
+    address: impl AsRef<str>,
+    timeout: Duration
+) -> bool {
+    let address = address.as_ref();
+    let started_at = Instant::now();
+
+    loop {
+        if client.get_info(address).await.is_ok() {
+            return true;
+        }
+
+        if started_at.elapsed() >= timeout {
+            return false;
+        }
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+/// Wait until the server on given address responds to the
+/// info request and compare its public key with the expected one.
+///
+/// Unreachable servers are not reported as mismatched.
+async fn verify_identity<T: HttpClient, E>(
+    client: &ClientMiddleware<T>,
+    address: &str,
+    expected: &PublicKey,
+    timeout: Duration
+) -> Result<(), ServerRunError<E>> {
+    if !wait_ready_with(client, address, timeout).await {
+        return Ok(());
+    }
+
+    match client.get_info(address).await {
+        Ok(info) if &info.public_key != expected => Err(ServerRunError::IdentityMismatch {
+            configured: expected.to_base64(),
+            actual: info.public_key.to_base64()
+        }),
+
+        _ => Ok(())
+    }
+}
+
+/// Index bootstrap servers, requesting info
+/// of at most `concurrency` addresses at a time.
+///
+/// Every address is given its own `timeout`, so unresponsive
+/// ones don't delay indexing of the others. Results are recorded
+/// to the server stats.
+///
+/// Returns failed addresses with the failure reasons.
+async fn index_bootstrap<'a, T, R>(
+    client: &ClientMiddleware<T>,
+    router: &R,
+    handle: &ServerHandle,
+    addresses: impl IntoIterator<Item = &'a String>,
+    concurrency: usize,
+    timeout: Duration
+) -> Vec<(String, String)>
+where
+    T: HttpClient,
+    R: Router
+{
+    let index = move |address: &'a String| async move {
+        let result = match tokio::time::timeout(timeout, client.get_info(address)).await {
+            Ok(Ok(server)) => {
+                let server = Server::new(server.public_key, address);
+
+                handle.peer_ages().confirm(&server);
+
+                router.index_server(server).await
+                    .map_err(|err| err.to_string())
+            }
+
+            Ok(Err(err)) => Err(err.to_string()),
+            Err(_) => Err(format!("server didn't respond within {timeout:?}"))
+        };
+
+        (address, result)
+    };
+
+    let mut addresses = addresses.into_iter();
+
+    let mut pending = addresses.by_ref()
+        .take(concurrency.max(1))
+        .map(&index)
+        .collect::<FuturesUnordered<_>>();
+
+    let mut failed = Vec::new();
+
+    while let Some((address, result)) = pending.next().await {
+        handle.stats().bootstrap_indexed(address, result.is_ok());
+
+        if let Err(reason) = result {
+            failed.push((address.clone(), reason));
+        }
+
+        if let Some(address) = addresses.next() {
+            pending.push(index(address));
+        }
+    }
+
+    failed
+}
+
+/// Remove servers not confirmed active within `max_age`
+/// from the application's router.
+///
+/// Expired servers are asked for their info first, at most
+/// `concurrency` at a time and within `PEER_PROBE_TIMEOUT`
+/// each, and kept if they respond.
+///
+/// Returns amount of removed servers.
+async fn sweep_expired_peers<T, R>(
+    app: &T,
+    client: &ClientMiddleware<T::HttpClient>,
+    router: &R,
+    ages: &PeerAges,
+    max_age: Duration,
+    concurrency: usize
+) -> u64
+where
+    T: ServerApp + Send + Sync,
+    R: Router + Send + Sync
+{
+    let Ok(servers) = router.servers().await else {
+        return 0;
+    };
+
+    ages.observe(&servers);
+
+    let probe = |server: &Server| {
+        let server = server.clone();
+
+        async move {
+            let responded = tokio::time::timeout(PEER_PROBE_TIMEOUT, client.get_info(&server.address)).await
+                .is_ok_and(|result| result.is_ok());
+
+            (server, responded)
+        }
+    };
+
+    let probed = futures::stream::iter(ages.expired(&servers, max_age))
+        .map(probe)
+        .buffer_unordered(concurrency.max(1))
+        .collect::<Vec<_>>().await;
+
+    let mut swept = 0;
+
+    for (server, responded) in &probed {
+        if *responded {
+            ages.confirm(server);
+
+            continue;
+        }
+
+        match app.remove_known_server(server).await {
+            Ok(true) => {
+                ages.forget(server);
+
+                swept += 1;
+            }
+
+            Ok(false) => (),
+
+            Err(_) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!("[server] Failed to remove expired server {}", server.address);
+            }
+        }
+    }
+
+    swept
+}
+
+/// Remove banned servers from the application's router.
+///
+/// Returns amount of removed servers.
+async fn evict_banned<T, R>(app: &T, router: &R, handle: &ServerHandle) -> u64
+where
+    T: ServerApp + Send + Sync,
+    R: Router + Send + Sync
+{
+    let Ok(servers) = router.servers().await else {
+        return 0;
+    };
+
+    let mut evicted = 0;
+
+    for server in servers.iter().filter(|server| handle.blacklist().is_banned(&server.address)) {
+        match app.remove_known_server(server).await {
+            Ok(true) => {
+                handle.peer_ages().forget(server);
+
+                evicted += 1;
+            }
+
+            Ok(false) => (),
+
+            Err(_) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!("[server] Failed to remove banned server {}", server.address);
+            }
+        }
+    }
+
+    evicted
+}
+
 /// Start given server application in tokio async thread,
-/// returning back an `Arc` containing original variant
-/// of the server.
-/// 
+/// returning back a handle to it.
+///
+/// Use `ServerHandle::ready` to wait until the server
+/// becomes reachable, and `ServerHandle::shutdown` to stop it.
+pub fn spawn<T>(app: T) -> ServerHandle
+where
+    T: ServerApp + Send + Sync + 'static,
+    T::Error: std::fmt::Debug
+{
+    let handle = ServerHandle::default();
+
+    {
+        let handle = handle.clone();
+
+        tokio::spawn(async move {
+            let shutdown = handle.clone();
+
+            if let Err(_err) = run_with_handle(app, handle, async move { shutdown.wait_shutdown().await }).await {
+                #[cfg(feature = "tracing")]
+                tracing::error!("[server] Server stopped with error: {_err:?}");
+            }
+        });
+    }
+
+    handle
+}
+
+/// Run given server application.
+///
 /// This method will freeze caller's thread while server app is running.
-pub async fn run<T>(app: T) -> Result<(), T::Error>
+pub async fn run<T>(app: T) -> Result<(), ServerRunError<T::Error>>
+where
+    T: ServerApp + Send + Sync + 'static,
+    T::Error: std::fmt::Debug
+{
+    run_with_shutdown(app, std::future::pending()).await
+}
+
+/// Same as `run`, but stops the server when
+/// the `shutdown` future is resolved.
+///
+/// Before returning this method will log the server state
+/// and revoke all the ports opened by the UPnP forwarder.
+#[inline]
+pub async fn run_with_shutdown<T>(app: T, shutdown: impl Future<Output = ()> + Send) -> Result<(), ServerRunError<T::Error>>
+where
+    T: ServerApp + Send + Sync + 'static,
+    T::Error: std::fmt::Debug
+{
+    run_with_handle(app, ServerHandle::default(), shutdown).await
+}
+
+/// Report non-fatal error to the application.
+async fn report_error<T>(app: &T, err: ServerRunError<T::Error>)
+where
+    T: ServerApp + Send + Sync,
+    T::Error: std::fmt::Debug
+{
+    #[cfg(feature = "tracing")]
+    tracing::error!("[server] {err}");
+
+    app.on_error(&err).await;
+}
+
+/// Bind TCP listener for the router served by the application.
+async fn bind<E>(address: impl AsRef<str>) -> Result<tokio::net::TcpListener, ServerRunError<E>> {
+    // Don't accept IPv4 connections on the IPv6 address
+    // so `0.0.0.0` and `[::]` can share the same port
+    #[cfg(feature = "dual-stack")]
+    if let Some(address) = address.as_ref().parse::<SocketAddr>().ok().filter(SocketAddr::is_ipv6) {
+        return bind_ipv6_only(address)
+            .map_err(ServerRunError::Bind);
+    }
+
+    tokio::net::TcpListener::bind(address.as_ref()).await
+        .map_err(ServerRunError::Bind)
+}
+
+#[cfg(feature = "dual-stack")]
+fn bind_ipv6_only(address: SocketAddr) -> std::io::Result<tokio::net::TcpListener> {
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    let socket = Socket::new(Domain::IPV6, Type::STREAM, Some(Protocol::TCP))?;
+
+    socket.set_only_v6(true)?;
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+
+    socket.bind(&address.into())?;
+    socket.listen(1024)?;
+
+    tokio::net::TcpListener::from_std(socket.into())
+}
+
+/// Get pattern of the route path which doesn't
+/// depend on the names of its parameters.
+///
+/// Paths with the same pattern overlap.
+fn route_pattern(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            if segment.starts_with(':') {
+                ":"
+            } else if segment.starts_with('*') {
+                "*"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Merge named routes into the `base` ones.
+///
+/// Paths of all the routes are compared before merging,
+/// and the name of the routes overlapping with already
+/// merged ones is returned.
+fn merge_routes(base: Routes, routes: Vec<(String, Routes)>) -> Result<axum::Router, String> {
+    let mut owners = HashMap::new();
+
+    for path in base.paths() {
+        owners.insert(route_pattern(path), String::from("hyperborealib routes"));
+    }
+
+    for (name, routes) in &routes {
+        for path in routes.paths() {
+            if let Some(owner) = owners.insert(route_pattern(path), name.clone()) {
+                return Err(format!("{name}: path {path} is already served by {owner}"));
+            }
+        }
+    }
+
+    let merged = routes.into_iter()
+        .fold(base.into_router(), |merged, (_, routes)| merged.merge(routes.into_router()));
+
+    Ok(merged)
+}
+
+/// Spawn task serving given router using given listener.
+///
+/// Router is served over HTTPS if TLS config is given.
+fn spawn_router(
+    listener: tokio::net::TcpListener,
+    router: axum::Router,
+    tls: Option<RustlsConfig>,
+    _name: &'static str
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let result = match tls {
+            #[cfg(feature = "tls")]
+            Some(tls) => match listener.into_std() {
+                Ok(listener) => axum_server::from_tcp_rustls(listener, tls)
+                    .serve(router.into_make_service_with_connect_info::<SocketAddr>()).await,
+
+                Err(err) => Err(err)
+            }
+
+            #[cfg(not(feature = "tls"))]
+            Some(tls) => match tls {},
+
+            None => axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>()).await
+        };
+
+        if let Err(_err) = result {
+            #[cfg(feature = "tracing")]
+            tracing::error!("[server] Failed to serve {_name}: {_err}");
+        }
+    })
+}
+
+/// Apply IP filter to the router if it's set.
+fn apply_ip_filter(router: axum::Router, filter: &Option<IpFilter>) -> axum::Router {
+    match filter {
+        Some(filter) => filter_ips(router, filter.clone()),
+        None => router
+    }
+}
+
+/// Call server initialization step, retrying it
+/// up to `retries` times if it fails.
+async fn init_with_retries<R, E, F, Fut>(_name: &str, retries: u32, delay: Duration, mut init: F) -> Result<R, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<R, E>>,
+    E: std::fmt::Debug
+{
+    let mut attempt = 0;
+
+    loop {
+        match init().await {
+            Ok(result) => return Ok(result),
+
+            Err(_err) if attempt < retries => {
+                attempt += 1;
+
+                #[cfg(feature = "tracing")]
+                tracing::warn!("[server] Failed to initialize {_name}, retrying (attempt {attempt} of {retries}): {_err:?}");
+
+                tokio::time::sleep(delay).await;
+            }
+
+            Err(err) => return Err(err)
+        }
+    }
+}
+
+/// Same as `run_with_shutdown`, but reports server
+/// state to the given handle.
+///
+/// Pending `ServerHandle::ready` futures are resolved
+/// with `ServerNotReady::Stopped` when the server stops
+/// before passing its self-check.
+pub async fn run_with_handle<T>(app: T, handle: ServerHandle, shutdown: impl Future<Output = ()> + Send) -> Result<(), ServerRunError<T::Error>>
+where
+    T: ServerApp + Send + Sync + 'static,
+    T::Error: std::fmt::Debug
+{
+    let result = run_server(app, handle.clone(), shutdown).await;
+
+    handle.set_ready(Err(ServerNotReady::Stopped));
+
+    result
+}
+
+async fn run_server<T>(app: T, handle: ServerHandle, shutdown: impl Future<Output = ()> + Send) -> Result<(), ServerRunError<T::Error>>
 where
     T: ServerApp + Send + Sync + 'static,
     T::Error: std::fmt::Debug
 {
-    let params = app.get_params();
+    let app = Arc::new(app);
+
+    let mut params = app.get_params().clone();
+    let stats = handle.stats();
+
+    // Let clients detect restarts of the server
+    handle.set_startup_token(rand::random());
+
+    // Load banned servers
+    if let Some(path) = &params.blacklist_path {
+        handle.blacklist().load(path).await
+            .map_err(ServerRunError::Blacklist)?;
+    }
+
+    // Verify integrity of the backend folder
+    let report = app.check_backend().await
+        .map_err(ServerRunError::BackendCheck)?;
+
+    if !report.is_healthy() {
+        match params.on_corruption {
+            CorruptionPolicy::Abort => {
+                #[cfg(feature = "tracing")]
+                tracing::error!("[server] Backend folder is corrupted, aborting: {report}");
+
+                return Err(ServerRunError::BackendCorrupted(report));
+            }
+
+            CorruptionPolicy::Quarantine => {
+                let quarantine = BackendMaintenance::new(&params.backend_folder)
+                    .quarantine(&report)
+                    .map_err(ServerRunError::BackendQuarantine)?;
+
+                report_error(app.as_ref(), ServerRunError::BackendQuarantined {
+                    quarantine,
+                    report
+                }).await;
+            }
+        }
+    }
+
+    // Discover external address
+    #[cfg(feature = "stun")]
+    if let Some(stun_server) = &params.stun_server {
+        match discover_external_ip(stun_server).await {
+            Ok(external_ip) => {
+                for remote_address in &mut params.remote_addresses {
+                    // Don't replace IPv4 addresses by IPv6 one and vice versa
+                    let same_family = remote_address.parse::<std::net::SocketAddr>()
+                        .is_ok_and(|address| address.is_ipv4() == external_ip.is_ipv4());
+
+                    if !same_family {
+                        continue;
+                    }
+
+                    if let Some(address) = replace_address_ip(remote_address, external_ip) {
+                        #[cfg(feature = "tracing")]
+                        tracing::info!("[server] Remote address changed from {remote_address} to {address} using STUN");
+
+                        *remote_address = address;
+                    }
+                }
+            }
+
+            Err(_err) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!("[server] Failed to discover external address using {stun_server}: {_err}");
+            }
+        }
+    }
+
+    #[cfg(all(not(feature = "stun"), feature = "tracing"))]
+    if params.stun_server.is_some() {
+        tracing::warn!("[server] STUN server is set but the stun feature is disabled");
+    }
 
     // Resolve server middleware and driver
-    let middleware = app.get_middleware().await
-        .expect("Failed to obtain application middleware");
+    let app_ref = app.as_ref();
+    let remote_address = params.remote_address();
+    let inbox_handle = handle.clone();
+
+    let (middleware, http_server, inbox) = init_with_retries("server middleware", params.init_retries, params.init_retry_delay, move || {
+        let inbox_handle = inbox_handle.clone();
+
+        async move {
+            let inbox = Arc::new(app_ref.get_messages_inbox().await?);
+            let http_server = app_ref.get_http_server().await?;
+
+            // Build middleware manually to announce the discovered
+            // address and count messages processed by the inbox
+            let driver = ServerDriver::new(
+                app_ref.get_router().await?,
+                app_ref.get_traversal().await?,
+                CountingInbox::new(inbox.clone(), inbox_handle),
+                ServerParams {
+                    secret_key: app_ref.get_secret_key().clone(),
+                    address: remote_address.to_string()
+                }
+            );
+
+            // Keep a clone of the HTTP server to serve
+            // the routes registered by the middleware
+            let middleware = ServerMiddleware::new(
+                app_ref.get_http_client().await?,
+                http_server.clone(),
+                driver
+            ).await;
+
+            Ok((middleware, http_server, inbox))
+        }
+    }).await.map_err(ServerRunError::MiddlewareInit)?;
 
     let driver = middleware.driver();
 
     // Create client middleware for traversal thread
-    let traversal_client = ClientMiddleware::new(
-        app.get_http_client().await?,
+    let http_client = init_with_retries("HTTP client", params.init_retries, params.init_retry_delay, || app_ref.get_http_client()).await
+        .map_err(ServerRunError::HttpClientInit)?;
+
+    let traversal_client = Arc::new(ClientMiddleware::new(
+        http_client,
         driver.as_client()
-    );
+    ));
+
+    // Load TLS certificate of the served routers
+    let tls = match &params.tls {
+        Some(tls) => Some(tls.rustls_config().await.map_err(ServerRunError::Tls)?),
+        None => None
+    };
+
+    // Create client middleware checking the server itself,
+    // which must use HTTPS if the REST API is served with TLS
+    let probe_http_client = HttpClientConfig {
+        https: tls.is_some(),
+        accept_invalid_certs: tls.is_some(),
+        ..HttpClientConfig::default()
+    }.build().map_err(|err| ServerRunError::Tls(std::io::Error::other(err)))?;
+
+    let probe_client = Arc::new(ClientMiddleware::new(
+        probe_http_client,
+        driver.as_client()
+    ));
+
+    // Resolve public keys of the servers signing requests
+    let known_server_key = {
+        let driver = driver.clone();
+        let public_key = params.secret_key.public();
+
+        let own_addresses = params.remote_addresses.iter()
+            .chain(app.get_params().remote_addresses.iter())
+            .cloned()
+            .collect::<HashSet<_>>();
+
+        move |address: String| {
+            let driver = driver.clone();
+            let public_key = public_key.clone();
+            let is_own = own_addresses.contains(&address);
+
+            async move {
+                // Requests of the current server, e.g. sent by the relay
+                if is_own {
+                    return Some(public_key);
+                }
+
+                driver.router().servers().await.ok()?
+                    .into_iter()
+                    .find(|server| server.address == address)
+                    .map(|server| server.public_key)
+            }
+        }
+    };
+
+    // Build the public REST API
+    let extra_routes = app.get_extra_routes();
+    let http_layers = app.get_http_layers();
+
+    let mut public_routes = vec![
+        (String::from("startup"), Routes::with_paths(startup_router(handle.clone()), [STARTUP_PATH]))
+    ];
+
+    for (i, routes) in extra_routes.into_iter().enumerate() {
+        let routes = if params.require_signed_inbound {
+            routes.map(|router| require_signed_requests(router, params.max_incoming_message_bytes, known_server_key.clone()))
+        } else {
+            routes
+        };
+
+        public_routes.push((format!("extra routes #{i}"), routes));
+    }
+
+    // Multicast requests are sent by clients, so they're not signed
+    if let Some(policy) = &params.multicast {
+        public_routes.push((
+            String::from("multicast"),
+            Routes::with_paths(multicast_router(app.clone(), policy.clone()), [MULTICAST_PATH])
+        ));
+    }
+
+    // Index servers contributed by other ones
+    let capabilities = ServerCapabilities {
+        capabilities: vec![String::from(PEER_CONTRIBUTION_CAPABILITY)]
+    };
+
+    let index = {
+        let driver = driver.clone();
+        let handle = handle.clone();
+
+        move |server: Server| {
+            let driver = driver.clone();
+            let handle = handle.clone();
+
+            async move {
+                handle.peer_ages().confirm(&server);
+
+                driver.router().index_server(server).await.is_ok()
+            }
+        }
+    };
+
+    public_routes.push((
+        String::from("peer contribution"),
+        Routes::with_paths(
+            peer_contribution_router(traversal_client.clone(), capabilities, index),
+            [CAPABILITIES_PATH, PEER_CONTRIBUTION_PATH]
+        )
+    ));
+
+    let rest_api = merge_routes(http_server.routes(), public_routes)
+        .map_err(ServerRunError::RoutesOverlap)?;
+
+    let rest_api = if params.require_signed_inbound {
+        require_signed_paths(rest_api, SERVER_TO_SERVER_PATHS, params.max_incoming_message_bytes, known_server_key.clone())
+    } else {
+        rest_api
+    };
+
+    let rest_api = apply_layers(rest_api, &http_layers);
+    let rest_api = limit_payload_size(rest_api, params.max_incoming_message_bytes);
+    let rest_api = apply_ip_filter(rest_api, &params.ip_filter);
+
+    // Bind all the addresses before starting any background task
+    let mut public_listeners = Vec::with_capacity(params.local_addresses.len());
+
+    for address in &params.local_addresses {
+        public_listeners.push(bind(address).await?);
+    }
+
+    let admin_listener = match &params.admin_address {
+        Some(address) => Some(bind(address).await?),
+        None => None
+    };
+
+    let status_listener = match &params.status_endpoint {
+        Some(address) => Some(bind(address).await?),
+        None => None
+    };
+
+    #[cfg(feature = "metrics")]
+    let metrics_listener = match params.metrics_port {
+        Some(port) => Some(bind(format!("127.0.0.1:{port}")).await?),
+        None => None
+    };
 
     // Open ports if given
-    if !params.open_ports.is_empty() {
-        tokio::spawn(async move {
-            let duration = std::time::Duration::from_secs(3600);
+    let upnp = Arc::new(UpnpPortForwarder::new());
+
+    let upnp_task = if !params.open_ports.is_empty() {
+        let app = app.clone();
+        let upnp = upnp.clone();
+        let handle = handle.clone();
 
-            let upnp = UpnpPortForwarder::new();
+        // Forward ports of all the local addresses as well
+        let mut open_ports = params.open_ports.clone();
+
+        for address in &params.local_addresses {
+            if let Ok(address) = address.parse::<std::net::SocketAddr>() {
+                if !open_ports.contains(&address.port()) {
+                    open_ports.push(address.port());
+                }
+            }
+        }
+
+        Some(tokio::spawn(async move {
+            let duration = std::time::Duration::from_secs(3600);
 
             loop {
-                for port in params.open_ports.iter().copied() {
-                    if let Err(_err) = upnp.open(port, Protocol::TCP, duration).await {
-                        #[cfg(feature = "tracing")]
-                        tracing::error!("[server] Failed to open port {port} using UPnP forwarder: {_err}");
+                for port in open_ports.iter().copied() {
+                    match upnp.open(port, Protocol::TCP, duration).await {
+                        Ok(_) => handle.stats().port_opened(port),
+
+                        Err(err) => report_error(app.as_ref(), ServerRunError::PortForward {
+                            port,
+                            reason: err.to_string()
+                        }).await
                     }
                 }
 
                 tokio::time::sleep(duration).await;
             }
-        });
+        }))
+    } else {
+        None
+    };
+
+    // Start the REST API on all the local addresses
+    let rest_api_tasks = public_listeners.into_iter()
+        .map(|listener| spawn_router(listener, rest_api.clone(), tls.clone(), "REST API"))
+        .collect::<Vec<_>>();
+
+    // Start the administration API
+    let admin_task = admin_listener.map(|listener| {
+        let router = apply_layers(admin_router(app.clone(), params.admin_token.clone()), &http_layers);
+        let router = limit_payload_size(router, params.max_incoming_message_bytes);
+        let router = apply_ip_filter(router, &params.ip_filter);
+
+        spawn_router(listener, router, tls.clone(), "administration API")
+    });
+
+    // Start the status endpoint
+    let status_task = status_listener.map(|listener| {
+        let router = limit_payload_size(
+            apply_layers(status_router(app.clone(), driver.clone(), handle.clone()), &http_layers),
+            params.max_incoming_message_bytes
+        );
+
+        let router = apply_ip_filter(router, &params.ip_filter);
+
+        spawn_router(listener, router, tls.clone(), "status endpoint")
+    });
+
+    // Start the metrics endpoint
+    #[cfg(feature = "metrics")]
+    let metrics_task = match metrics_listener {
+        Some(listener) => match metrics_router(app.clone(), driver.clone(), handle.clone(), params.metrics_bearer_token.clone()) {
+            Ok(router) => {
+                let router = apply_layers(router, &http_layers);
+                let router = limit_payload_size(router, params.max_incoming_message_bytes);
+                let router = apply_ip_filter(router, &params.ip_filter);
+
+                Some(spawn_router(listener, router, tls.clone(), "metrics endpoint"))
+            }
+
+            Err(_err) => {
+                #[cfg(feature = "tracing")]
+                tracing::error!("[server] Failed to register metrics: {_err}");
+
+                None
+            }
+        }
+
+        None => None
+    };
+
+    #[cfg(all(not(feature = "metrics"), feature = "tracing"))]
+    if params.metrics_port.is_some() {
+        tracing::warn!("[server] Metrics port is set but the metrics feature is disabled");
     }
 
-    // Start the server
-    tokio::spawn(async move {
-        if let Err(_err) = middleware.serve(&params.local_address).await {
-            #[cfg(feature = "tracing")]
-            tracing::error!("[server] {_err}");
+    // Discover servers in the local network
+    #[cfg(feature = "mdns")]
+    let mdns_task = if params.enable_mdns {
+        let client = traversal_client.clone();
+        let driver = driver.clone();
+        let handle = handle.clone();
+
+        let discovery = crate::discovery::MdnsDiscovery::new(
+            params.secret_key.public(),
+            &params.remote_addresses
+        );
+
+        match discovery {
+            Ok(discovery) => Some(tokio::spawn(async move {
+                // Index announced servers as bootstrap ones
+                let result = discovery.start(|server| {
+                    let client = client.clone();
+                    let driver = driver.clone();
+                    let handle = handle.clone();
+
+                    async move {
+                        if let Ok(info) = client.get_info(&server.address).await {
+                            let server = Server::new(info.public_key, &server.address);
+
+                            handle.peer_ages().confirm(&server);
+
+                            if let Err(_err) = driver.router().index_server(server).await {
+                                #[cfg(feature = "tracing")]
+                                tracing::warn!("[server] Failed to index server discovered by mDNS: {_err}");
+                            }
+                        }
+                    }
+                }).await;
+
+                if let Err(_err) = result {
+                    #[cfg(feature = "tracing")]
+                    tracing::error!("[server] mDNS discovery failed: {_err}");
+                }
+            })),
+
+            Err(_err) => {
+                #[cfg(feature = "tracing")]
+                tracing::error!("[server] Failed to start mDNS discovery: {_err}");
+
+                None
+            }
         }
+    } else {
+        None
+    };
+
+    #[cfg(all(not(feature = "mdns"), feature = "tracing"))]
+    if params.enable_mdns {
+        tracing::warn!("[server] mDNS discovery is enabled but the mdns feature is disabled");
+    }
+
+    // Verify that the started server uses configured secret key
+    let identity_client = probe_client.clone();
+
+    let identity_check = verify_identity(
+        &identity_client,
+        params.local_address(),
+        &params.secret_key.public(),
+        Duration::from_secs(30)
+    );
+
+    // Evict servers from the router when they're banned
+    let eviction_task = {
+        let app = app.clone();
+        let handle = handle.clone();
+        let driver = driver.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let _evicted = evict_banned(app.as_ref(), driver.router(), &handle).await;
+
+                #[cfg(feature = "tracing")]
+                if _evicted > 0 {
+                    tracing::info!("[server] Removed {_evicted} banned servers from the router");
+                }
+
+                handle.blacklist().wait_banned().await;
+            }
+        })
+    };
+
+    // Move expired messages to the dead-letter channel
+    let sweep_task = params.message_max_age.map(|_| {
+        let app = app.clone();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(DEAD_LETTER_SWEEP_INTERVAL).await;
+
+                match app.sweep_inbox(&inbox).await {
+                    Ok(_swept) => {
+                        #[cfg(feature = "tracing")]
+                        if _swept > 0 {
+                            tracing::info!("[server] Moved {_swept} expired messages to the dead-letter channel");
+                        }
+                    }
+
+                    Err(_err) => {
+                        #[cfg(feature = "tracing")]
+                        tracing::error!("[server] Failed to sweep expired messages: {_err:?}");
+                    }
+                }
+            }
+        })
     });
 
-    loop {
-        // Index bootstrap servers
-        #[cfg(feature = "tracing")]
-        tracing::debug!("[server] Indexing bootstrap addresses");
+    // Start network traversal
+    let mut traversal_task = {
+        let app = app.clone();
+        let handle = handle.clone();
+        let params = params.clone();
+        let driver = driver.clone();
+
+        tokio::spawn(async move {
+            let stats = handle.stats();
+
+            // Wait until the server is reachable
+            if wait_ready_with(&probe_client, params.local_address(), Duration::from_secs(30)).await {
+                handle.set_ready(Ok(()));
+            }
+
+            else {
+                #[cfg(feature = "tracing")]
+                tracing::warn!("[server] Server is not reachable by its local address");
+
+                handle.set_ready(Err(ServerNotReady::Unreachable(params.local_address().to_string())));
+            }
+
+            let mut traverse_delay = match params.adaptive_traversal {
+                Some(adaptive) => adaptive.clamp(params.traverse_delay),
+                None => params.traverse_delay
+            };
+
+            handle.set_traverse_delay(traverse_delay);
+
+            let mut last_sweep = Instant::now();
+
+            loop {
+                // Remember known servers to find discovered ones
+                let known_servers = match driver.router().servers().await {
+                    Ok(servers) => servers.into_iter()
+                        .map(|server| server.public_key.to_base64())
+                        .collect::<HashSet<_>>(),
+
+                    Err(_) => HashSet::new()
+                };
+
+                // Index bootstrap servers
+                #[cfg(feature = "tracing")]
+                tracing::debug!("[server] Indexing bootstrap addresses");
+
+                let failed = index_bootstrap(
+                    &traversal_client,
+                    driver.router(),
+                    &handle,
+                    params.bootstrap.iter().filter(|address| !handle.blacklist().is_banned(address)),
+                    params.bootstrap_concurrency,
+                    params.bootstrap_timeout
+                ).await;
 
-        for address in &params.bootstrap {
-            if let Ok(server) = traversal_client.get_info(&address).await {
-                let _result = driver.router().index_server(Server::new(
-                    server.public_key,
-                    address
-                )).await;
+                // Share known servers with the indexed bootstrap ones
+                if params.contribute_peers_to_bootstrap {
+                    let servers = driver.router().servers().await
+                        .unwrap_or_default();
 
+                    let indexed = params.bootstrap.iter()
+                        .filter(|address| !handle.blacklist().is_banned(address))
+                        .filter(|address| !failed.iter().any(|(failed, _)| failed == *address));
+
+                    for address in indexed {
+                        let peers = servers.iter()
+                            .filter(|server| server.address != *address);
+
+                        let contribution = PeerContribution {
+                            peers: handle.peer_ages().most_recent(peers, PEER_CONTRIBUTION_MAX_PEERS)
+                                .into_iter()
+                                .cloned()
+                                .collect()
+                        };
+
+                        if contribution.peers.is_empty() {
+                            continue;
+                        }
+
+                        let _result = contribute_peers(
+                            traversal_client.http_client_ref(),
+                            address,
+                            &contribution
+                        ).await;
+
+                        #[cfg(feature = "tracing")]
+                        match _result {
+                            Ok(true) => tracing::debug!("[server] Contributed {} peers to {address}", contribution.peers.len()),
+                            Ok(false) => tracing::debug!("[server] Bootstrap server {address} doesn't accept peer contributions"),
+                            Err(err) => tracing::debug!("[server] Failed to contribute peers to {address}: {err}")
+                        }
+                    }
+                }
+
+                for (address, reason) in failed {
+                    report_error(app.as_ref(), ServerRunError::BootstrapIndex {
+                        address,
+                        reason
+                    }).await;
+                }
+
+                // Traverse network
                 #[cfg(feature = "tracing")]
-                if let Err(err) = _result {
-                    tracing::error!("[server] Failed to index bootstrap server: {err}");
+                tracing::debug!("[server] Traversing network");
+
+                if params.traversal_workers > 1 {
+                    let _result = traverse_parallel(
+                        traversal_client.clone(),
+                        driver.router(),
+                        params.traversal_workers,
+                        params.max_concurrent_outbound_connections,
+                        |server| !handle.blacklist().is_banned(&server.address)
+                    ).await;
+
+                    #[cfg(feature = "tracing")]
+                    match _result {
+                        Ok(indexed) => tracing::debug!("[server] Indexed {indexed} new servers"),
+                        Err(err) => tracing::error!("[server] Failed to traverse network: {err}")
+                    }
+                }
+
+                else if params.traversal_config.is_limited() {
+                    let _result = traverse_bfs(
+                        &traversal_client,
+                        driver.router(),
+                        params.traversal_config,
+                        |server| !handle.blacklist().is_banned(&server.address)
+                    ).await;
+
+                    #[cfg(feature = "tracing")]
+                    match _result {
+                        Ok(indexed) => tracing::debug!("[server] Indexed {indexed} new servers"),
+                        Err(err) => tracing::error!("[server] Failed to traverse network: {err}")
+                    }
+                }
+
+                // Default traversal can't filter servers,
+                // so the banned ones are evicted after it
+                else {
+                    driver.traversal().traverse(
+                        traversal_client.http_client_ref().clone(),
+                        &driver
+                    ).await;
+
+                    evict_banned(app.as_ref(), driver.router(), &handle).await;
+                }
+
+                stats.traversal_completed();
+
+                let discovered = driver.router().servers().await
+                    .map(|servers| {
+                        servers.into_iter()
+                            .filter(|server| !known_servers.contains(&server.public_key.to_base64()))
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+
+                // Notify plugins about discovered servers
+                for server in &discovered {
+                    handle.peer_ages().confirm(server);
+
+                    for plugin in &params.plugins {
+                        plugin.on_peer_discovered(server).await;
+                    }
                 }
+
+                // Adapt traversing delay to the network activity
+                if let Some(adaptive) = params.adaptive_traversal {
+                    traverse_delay = adaptive.next_interval(traverse_delay, discovered.len());
+
+                    handle.set_traverse_delay(traverse_delay);
+
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("[server] Discovered {} servers, next traversal in {traverse_delay:?}", discovered.len());
+                }
+
+                // Remove servers which weren't active for too long
+                if last_sweep.elapsed() >= params.peer_sweep_interval {
+                    let swept = sweep_expired_peers(
+                        app.as_ref(),
+                        &traversal_client,
+                        driver.router(),
+                        handle.peer_ages(),
+                        params.peer_max_age,
+                        params.max_concurrent_outbound_connections
+                    ).await;
+
+                    handle.set_peers_swept(swept);
+
+                    last_sweep = Instant::now();
+
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("[server] Swept {swept} expired servers");
+                }
+
+                // Announce servers about ourselves
+                let announced = if params.announce {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("[server] Announcing the server");
+
+                    let announcement = ServerAnnouncement::new(
+                        Server::new(params.secret_key.public(), params.remote_address()),
+                        app.announce_metadata()
+                    );
+
+                    let servers = driver.router().servers().await
+                        .unwrap_or_default();
+
+                    let mut announced = Vec::new();
+
+                    for server in servers {
+                        if handle.blacklist().is_banned(&server.address) || !app.should_announce_to(&server).await {
+                            continue;
+                        }
+
+                        let result = announce_to(
+                            &traversal_client,
+                            &params.secret_key,
+                            &server.address,
+                            &announcement
+                        ).await;
+
+                        stats.announced(&server.address, result.is_ok());
+
+                        #[cfg(feature = "tracing")]
+                        if let Err(err) = &result {
+                            tracing::debug!("[server] Failed to announce the server to {}: {err}", server.address);
+                        }
+
+                        announced.push(AnnounceOutcome {
+                            server,
+                            result
+                        });
+                    }
+
+                    announced
+                } else {
+                    vec![]
+                };
+
+                app.on_traversal_complete(&TraversalReport {
+                    discovered,
+                    announced
+                }).await;
+
+                // Wait before repeating
+                tokio::time::sleep(traverse_delay).await;
             }
-        }
+        })
+    };
 
-        // Traverse network
-        #[cfg(feature = "tracing")]
-        tracing::debug!("[server] Traversing network");
+    let result = tokio::select! {
+        Err(err) = identity_check => Err(err),
 
-        driver.traversal().traverse(
-            traversal_client.http_client_ref().clone(),
-            &driver
-        ).await;
+        result = &mut traversal_task => match result {
+            Err(err) if err.is_panic() => Err(ServerRunError::TraversalPanic),
+            _ => Ok(())
+        },
 
-        // Announce servers about ourselves
-        if params.announce {
-            // TODO
-        }
+        _ = shutdown => Ok(())
+    };
 
-        // Wait before repeating
-        tokio::time::sleep(params.traverse_delay).await;
+    // Stop background tasks
+    traversal_task.abort();
+    eviction_task.abort();
+
+    if let Some(task) = sweep_task {
+        task.abort();
+    }
+
+    for task in rest_api_tasks {
+        task.abort();
+    }
+
+    if let Some(task) = upnp_task {
+        task.abort();
+    }
+
+    if let Some(task) = admin_task {
+        task.abort();
+    }
+
+    if let Some(task) = status_task {
+        task.abort();
+    }
+
+    #[cfg(feature = "metrics")]
+    if let Some(task) = metrics_task {
+        task.abort();
+    }
+
+    #[cfg(feature = "mdns")]
+    if let Some(task) = mdns_task {
+        task.abort();
     }
+
+    #[cfg(feature = "tracing")]
+    {
+        let known_peers = driver.router().servers().await
+            .map(|servers| servers.len())
+            .unwrap_or_default();
+
+        tracing::info!(
+            known_peers,
+            active_upnp_ports = ?stats.open_ports(),
+            traversal_cycles_completed = stats.traversal_cycles(),
+            uptime_secs = stats.uptime().as_secs(),
+            bootstrap_server_count = params.bootstrap.len(),
+            "[server] Shutting down"
+        );
+    }
+
+    // Revoke opened ports
+    for port in stats.open_ports() {
+        match upnp.close(port, Protocol::TCP).await {
+            Ok(_) => {
+                stats.port_closed(port);
+
+                #[cfg(feature = "tracing")]
+                tracing::info!("[server] Revoked port {port} from UPnP forwarder");
+            }
+
+            Err(_err) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!("[server] Failed to revoke port {port} from UPnP forwarder: {_err}");
+            }
+        }
+    }
+
+    result
 }
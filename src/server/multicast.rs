@@ -0,0 +1,272 @@
+use std::sync::Arc;
+
+use serde_json::{json, Value as Json};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+
+use axum::Router;
+use axum::routing::post;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::drivers::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use super::ServerApp;
+
+/// Path of the multicast endpoint of the servers.
+pub const MULTICAST_PATH: &str = "/multicast";
+
+/// Default maximal amount of recipients of the multicast message.
+pub const DEFAULT_MULTICAST_MAX_RECIPIENTS: usize = 256;
+
+/// Rules of the multicast messages accepted by the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MulticastPolicy {
+    /// Maximal amount of recipients of one message.
+    pub max_recipients: usize,
+
+    /// Clients allowed to multicast messages.
+    ///
+    /// All the clients are allowed if not set.
+    pub allowed_senders: Option<Vec<PublicKey>>
+}
+
+impl Default for MulticastPolicy {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            max_recipients: DEFAULT_MULTICAST_MAX_RECIPIENTS,
+            allowed_senders: None
+        }
+    }
+}
+
+impl MulticastPolicy {
+    /// Check if the sender can multicast message
+    /// to the given amount of recipients.
+    pub fn allows(&self, sender: &PublicKey, recipients: usize) -> bool {
+        if recipients > self.max_recipients {
+            return false;
+        }
+
+        match &self.allowed_senders {
+            Some(senders) => senders.contains(sender),
+            None => true
+        }
+    }
+}
+
+/// Request of the `/multicast` endpoint.
+///
+/// The message is encrypted for the server, which decrypts it
+/// and stores a copy for every recipient on the given channel.
+/// Content of the message is signed by the sender, so recipients
+/// don't need to trust the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MulticastEnvelope {
+    pub sender: Sender,
+    pub recipients: Vec<PublicKey>,
+    pub channel: String,
+    pub message: Message,
+
+    /// Sender's signature of the message content.
+    pub signature: Vec<u8>
+}
+
+impl AsJson for MulticastEnvelope {
+    fn to_json(&self) -> Result<Json, AsJsonError> {
+        Ok(json!({
+            "sender": self.sender.to_json()?,
+            "recipients": self.recipients.iter()
+                .map(PublicKey::to_base64)
+                .collect::<Vec<_>>(),
+            "channel": self.channel,
+            "message": self.message.to_json()?,
+            "signature": BASE64.encode(&self.signature)
+        }))
+    }
+
+    fn from_json(json: &Json) -> Result<Self, AsJsonError> where Self: Sized {
+        Ok(Self {
+            sender: json.get("sender")
+                .map(Sender::from_json)
+                .ok_or_else(|| AsJsonError::FieldNotFound("sender"))??,
+
+            recipients: json.get("recipients")
+                .and_then(Json::as_array)
+                .and_then(|recipients| {
+                    recipients.iter()
+                        .map(|key| key.as_str().and_then(|key| PublicKey::from_base64(key).ok()))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or_else(|| AsJsonError::FieldNotFound("recipients"))?,
+
+            channel: json.get("channel")
+                .and_then(Json::as_str)
+                .map(String::from)
+                .ok_or_else(|| AsJsonError::FieldNotFound("channel"))?,
+
+            message: json.get("message")
+                .map(Message::from_json)
+                .ok_or_else(|| AsJsonError::FieldNotFound("message"))??,
+
+            signature: json.get("signature")
+                .and_then(Json::as_str)
+                .and_then(|signature| BASE64.decode(signature).ok())
+                .ok_or_else(|| AsJsonError::FieldNotFound("signature"))?
+        })
+    }
+}
+
+/// Response of the `/multicast` endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MulticastResponse {
+    /// Amount of recipients the message was stored for.
+    pub delivered: usize
+}
+
+impl AsJson for MulticastResponse {
+    fn to_json(&self) -> Result<Json, AsJsonError> {
+        Ok(json!({
+            "delivered": self.delivered
+        }))
+    }
+
+    fn from_json(json: &Json) -> Result<Self, AsJsonError> where Self: Sized {
+        Ok(Self {
+            delivered: json.get("delivered")
+                .and_then(Json::as_u64)
+                .map(|delivered| delivered as usize)
+                .ok_or_else(|| AsJsonError::FieldNotFound("delivered"))?
+        })
+    }
+}
+
+struct MulticastState<T> {
+    app: Arc<T>,
+    policy: MulticastPolicy
+}
+
+impl<T> Clone for MulticastState<T> {
+    fn clone(&self) -> Self {
+        Self {
+            app: self.app.clone(),
+            policy: self.policy.clone()
+        }
+    }
+}
+
+/// Build router of the `POST /multicast` endpoint.
+///
+/// Forwarded copies are sent on behalf of the server's own
+/// client in the `multicast` envelope:
+///
+/// `{ "multicast": { "sender": "...", "server_address": "...", "server_public": "...", "content": "...", "signature": "..." } }`
+pub fn multicast_router<T>(app: Arc<T>, policy: MulticastPolicy) -> Router
+where
+    T: ServerApp + Send + Sync + 'static,
+    T::Error: std::fmt::Debug
+{
+    Router::new()
+        .route(MULTICAST_PATH, post(multicast::<T>))
+        .with_state(MulticastState {
+            app,
+            policy
+        })
+}
+
+async fn multicast<T>(
+    State(state): State<MulticastState<T>>,
+    axum::Json(request): axum::Json<Json>
+) -> Response
+where
+    T: ServerApp + Send + Sync + 'static,
+    T::Error: std::fmt::Debug
+{
+    let request = match MulticastEnvelope::from_json(&request) {
+        Ok(request) => request,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response()
+    };
+
+    let sender = &request.sender.client.public_key;
+
+    if !state.policy.allows(sender, request.recipients.len()) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    let params = state.app.get_params();
+
+    // Only the sender can encrypt the message for the server
+    let content = match request.message.read(&params.secret_key, sender) {
+        Ok(content) => content,
+        Err(_) => return StatusCode::FORBIDDEN.into_response()
+    };
+
+    let envelope = json!({
+        "multicast": {
+            "sender": sender.to_base64(),
+            "server_address": request.sender.server.address,
+            "server_public": request.sender.server.public_key.to_base64(),
+            "content": BASE64.encode(&content),
+            "signature": BASE64.encode(&request.signature)
+        }
+    });
+
+    let envelope = match serde_json::to_vec(&envelope) {
+        Ok(envelope) => envelope,
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+    };
+
+    let inbox = match state.app.get_messages_inbox().await {
+        Ok(inbox) => inbox,
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("{err:?}")).into_response()
+    };
+
+    // Store message for every recipient on behalf of the server's own client
+    let server_public = params.secret_key.public();
+
+    let server_sender = Sender::new(
+        Client::new(
+            server_public.clone(),
+            ConnectionCertificate::new(&params.secret_key, server_public.clone()),
+            ClientInfo::thin()
+        ),
+        Server::new(server_public, params.remote_address())
+    );
+
+    let mut delivered = 0;
+
+    for recipient in request.recipients {
+        let message = Message::create(
+            &params.secret_key,
+            &recipient,
+            envelope.clone(),
+            MessageEncoding::default(),
+            CompressionLevel::default()
+        );
+
+        let message = match message {
+            Ok(message) => message,
+            Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+        };
+
+        if let Err(err) = inbox.add_message(server_sender.clone(), recipient, request.channel.clone(), message).await {
+            return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response();
+        }
+
+        delivered += 1;
+    }
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!("[server] Multicasted message of {} to {delivered} recipients", sender.to_base64());
+
+    match (MulticastResponse { delivered }).to_json() {
+        Ok(response) => axum::Json(response).into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+    }
+}
@@ -1,32 +1,184 @@
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
 use hyperborealib::crypto::asymmetric::SecretKey;
+use hyperborealib::crypto::prelude::*;
 
-#[derive(Debug, Clone)]
+use crate::http::HttpClientConfig;
+use crate::client::{Channel, RateLimit};
+
+use super::{TraversalConfig, AdaptiveTraversal, ServerPlugin, TlsConfig, IpFilter, CorruptionPolicy};
+
+/// Maximal amount of bootstrap addresses printed
+/// by the `Debug` implementation of the server params.
+const DEBUG_BOOTSTRAP_LIMIT: usize = 3;
+
+/// Params of the server application.
+///
+/// Secret key is serialized as a base64 string and is never
+/// printed by the `Debug` and `Display` implementations.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+/// use hyperelm::server::*;
+///
+/// let params = ServerAppParams {
+///     secret_key: SecretKey::random(),
+///     local_addresses: vec![String::from("0.0.0.0:8001"), String::from("[::]:8001")],
+///     remote_addresses: vec![String::from("127.0.0.1:8001")],
+///     stun_server: None,
+///     tls: None,
+///     backend_folder: std::env::temp_dir().join("hyperelm-params-doctest"),
+///     on_corruption: CorruptionPolicy::Abort,
+///     init_retries: 0,
+///     init_retry_delay: Duration::from_secs(1),
+///     bootstrap: (0..10).map(|i| format!("10.0.0.{i}:8001")).collect(),
+///     bootstrap_concurrency: 4,
+///     bootstrap_timeout: Duration::from_secs(5),
+///     contribute_peers_to_bootstrap: false,
+///     open_ports: vec![],
+///     announce: false,
+///     enable_mdns: false,
+///     relay_messages: false,
+///     relay_rate_limit: DEFAULT_RELAY_RATE_LIMIT,
+///     multicast: None,
+///     sign_outbound_requests: false,
+///     require_signed_inbound: false,
+///     traverse_delay: Duration::from_secs(600),
+///     adaptive_traversal: None,
+///     peer_max_age: Duration::from_secs(24 * 60 * 60),
+///     peer_sweep_interval: Duration::from_secs(60 * 60),
+///     blacklist_path: None,
+///     traversal_workers: 1,
+///     max_concurrent_outbound_connections: 16,
+///     traversal_config: Default::default(),
+///     message_max_age: None,
+///     dead_letter_channel: None,
+///     max_incoming_message_bytes: DEFAULT_MAX_INCOMING_MESSAGE_BYTES,
+///     http_config: Default::default(),
+///     admin_address: None,
+///     admin_token: Some(String::from("admin-token")),
+///     status_endpoint: None,
+///     metrics_port: None,
+///     metrics_bearer_token: None,
+///     ip_filter: None,
+///     plugins: vec![]
+/// };
+///
+/// // JSON round trip
+/// let json = serde_json::to_string(&params).unwrap();
+/// let restored = serde_json::from_str::<ServerAppParams>(&json).unwrap();
+///
+/// assert_eq!(restored.secret_key, params.secret_key);
+/// assert_eq!(restored.bootstrap, params.bootstrap);
+///
+/// // TOML round trip
+/// let toml = toml::to_string(&params).unwrap();
+/// let restored = toml::from_str::<ServerAppParams>(&toml).unwrap();
+///
+/// assert_eq!(restored.secret_key, params.secret_key);
+/// assert_eq!(restored.traverse_delay, params.traverse_delay);
+///
+/// // Secrets are never printed
+/// let debug = format!("{params:?} {params}");
+///
+/// assert!(!debug.contains(&params.secret_key.to_base64()));
+/// assert!(!debug.contains("admin-token"));
+/// assert!(debug.contains(&params.secret_key.public().to_base64()));
+/// assert!(debug.contains("7 more"));
+/// ```
+#[derive(Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ServerAppParams {
     /// Current server's secret key.
+    #[cfg_attr(feature = "serde", serde(with = "crate::scaffold::serde_secret_key"))]
     pub secret_key: SecretKey,
 
-    /// Local server address on which we should run
-    /// the HTTP server.
-    pub local_address: String,
+    /// Local server addresses on which we should run
+    /// the HTTP server, e.g. `["0.0.0.0:8001", "[::]:8001"]`
+    /// for the IPv4 and IPv6 dual-stack server.
+    ///
+    /// With `dual-stack` feature IPv6 addresses are bound with
+    /// the `IPV6_V6ONLY` option, so IPv4 and IPv6 addresses
+    /// with the same port don't conflict with each other.
+    ///
+    /// The first one is used to reach the server itself.
+    #[cfg_attr(feature = "serde", serde(
+        alias = "local_address",
+        deserialize_with = "crate::scaffold::serde_one_or_many::deserialize"
+    ))]
+    pub local_addresses: Vec<String>,
 
-    /// Address by which other clients can access
+    /// Addresses by which other clients can access
     /// current server through the Internet.
-    pub remote_address: String,
+    ///
+    /// The first one is announced to other servers.
+    #[cfg_attr(feature = "serde", serde(
+        alias = "remote_address",
+        deserialize_with = "crate::scaffold::serde_one_or_many::deserialize"
+    ))]
+    pub remote_addresses: Vec<String>,
+
+    /// STUN server used to discover external IP of the current
+    /// server, e.g. `stun.l.google.com:19302`.
+    ///
+    /// If set, the IPs of the `remote_addresses` are replaced
+    /// by the discovered one on the server startup.
+    /// Domain names are never replaced.
+    ///
+    /// Requires `stun` feature.
+    pub stun_server: Option<String>,
+
+    /// TLS certificate used to serve the public REST API,
+    /// administration API, status and metrics endpoints over HTTPS.
+    ///
+    /// Hyperborealib clients and servers connect using plain
+    /// addresses, so they must have the `https` option of their
+    /// HTTP config enabled to reach such server.
+    ///
+    /// Requires `tls` feature, otherwise the server won't start.
+    pub tls: Option<TlsConfig>,
 
     /// Path to the folder where the server middleware
     /// saves its state.
     pub backend_folder: PathBuf,
 
+    /// Action performed when corrupted files are
+    /// found in the backend folder on startup.
+    pub on_corruption: CorruptionPolicy,
+
+    /// Amount of retries of the server middleware
+    /// and HTTP client initialization on startup.
+    ///
+    /// Useful when the application's backend can be
+    /// temporarily unavailable, e.g. when a database is
+    /// still locked by a previous server instance.
+    pub init_retries: u32,
+
+    /// Delay between initialization retries.
+    pub init_retry_delay: Duration,
+
     /// Bootstrap addresses used to gather
     /// initial information about the network.
     /// 
     /// Usually some static server addresses.
     pub bootstrap: Vec<String>,
 
+    /// Maximal amount of bootstrap addresses
+    /// indexed at the same time.
+    pub bootstrap_concurrency: usize,
+
+    /// Maximal time to wait for the info
+    /// of a single bootstrap server.
+    pub bootstrap_timeout: Duration,
+
+    /// Send most recently seen known servers to the
+    /// bootstrap servers which support peer contributions.
+    pub contribute_peers_to_bootstrap: bool,
+
     /// Open listed ports using available mechanisms.
     pub open_ports: Vec<u16>,
 
@@ -39,6 +191,49 @@ pub struct ServerAppParams {
     /// your server can't be accessed through the internet.
     pub announce: bool,
 
+    /// Announce current server in the local network
+    /// using mDNS and index servers announced by others.
+    ///
+    /// Requires `mdns` feature.
+    pub enable_mdns: bool,
+
+    /// Forward messages which clients of the current server
+    /// send to the relay channels to their target servers.
+    ///
+    /// Useful for clients which can't reach other
+    /// servers directly. Relayed messages stay end-to-end
+    /// encrypted.
+    ///
+    /// Messages are relayed only to the servers known
+    /// to the current one.
+    pub relay_messages: bool,
+
+    /// Limit of messages relayed for every client.
+    ///
+    /// Default is `DEFAULT_RELAY_RATE_LIMIT`.
+    pub relay_rate_limit: RateLimit,
+
+    /// Accept messages which clients of the current server
+    /// send to multiple recipients at once using the
+    /// `/multicast` endpoint. Disabled if not set.
+    ///
+    /// The endpoint is served on the public REST API.
+    pub multicast: Option<MulticastPolicy>,
+
+    /// Sign every HTTP request sent to other servers
+    /// with the current server's secret key.
+    ///
+    /// Signature is sent in the `X-Hyperelm-Signature` header.
+    pub sign_outbound_requests: bool,
+
+    /// Reject HTTP requests to the application's extra routes
+    /// and to the `SERVER_TO_SERVER_PATHS` of the REST API
+    /// which are not signed by a server known by the router.
+    ///
+    /// Servers must be indexed by the current one (e.g. via
+    /// bootstrap or announcements) before they can traverse it.
+    pub require_signed_inbound: bool,
+
     /// Network traversing delay.
     /// 
     /// Traversing is performed to gather information
@@ -46,5 +241,215 @@ pub struct ServerAppParams {
     /// 
     /// You don't need to perform this too often
     /// because this is a heavy operation.
-    pub traverse_delay: Duration
+    pub traverse_delay: Duration,
+
+    /// Adapt the traversing delay to the amount of
+    /// servers discovered during the last traversal.
+    ///
+    /// Initial delay is `traverse_delay`. Delay is fixed if not set.
+    pub adaptive_traversal: Option<AdaptiveTraversal>,
+
+    /// Remove servers not confirmed active within this time
+    /// from the routing table.
+    pub peer_max_age: Duration,
+
+    /// Minimal delay between sweeps of the expired servers.
+    ///
+    /// Sweep is performed at the end of the traversal cycle.
+    pub peer_sweep_interval: Duration,
+
+    /// Maximal age of the messages stored in the inbox.
+    ///
+    /// Older messages are moved to the dead-letter channel
+    /// if it's set. Messages never expire if not set.
+    pub message_max_age: Option<Duration>,
+
+    /// Channel storing messages which weren't delivered
+    /// to their recipients before `message_max_age`.
+    ///
+    /// Dead letters can be inspected, requeued or discarded
+    /// using the administration REST API.
+    pub dead_letter_channel: Option<Channel>,
+
+    /// Maximal size of the incoming HTTP request body.
+    ///
+    /// Larger requests are rejected with `413 Payload Too Large`
+    /// status. Default is `DEFAULT_MAX_INCOMING_MESSAGE_BYTES`.
+    pub max_incoming_message_bytes: usize,
+
+    /// Path to the JSON file storing banned servers.
+    ///
+    /// Bans are kept in memory only if not set.
+    pub blacklist_path: Option<PathBuf>,
+
+    /// Amount of concurrent network traversal workers.
+    ///
+    /// Known servers are split between workers equally.
+    /// Traversal implementation of the application's driver
+    /// is used if there's only one worker.
+    pub traversal_workers: usize,
+
+    /// Maximal amount of concurrent HTTP requests
+    /// made by all the traversal workers.
+    pub max_concurrent_outbound_connections: usize,
+
+    /// Limits of the network traversal.
+    ///
+    /// Limited traversal is performed by hyperelm itself
+    /// instead of the application's driver when there's
+    /// only one traversal worker.
+    pub traversal_config: TraversalConfig,
+
+    /// Params of the HTTP client used
+    /// to communicate with other servers.
+    pub http_config: HttpClientConfig,
+
+    /// Address on which the administration REST API
+    /// should be served. Disabled if not set.
+    ///
+    /// It's recommended to keep this API on localhost.
+    pub admin_address: Option<String>,
+
+    /// Bearer token required to access
+    /// the administration REST API.
+    pub admin_token: Option<String>,
+
+    /// Address on which the node status JSON document
+    /// should be served. Disabled if not set.
+    ///
+    /// It's recommended to keep this endpoint on localhost.
+    pub status_endpoint: Option<String>,
+
+    /// Port on which the Prometheus metrics endpoint
+    /// should be served on localhost. Disabled if not set.
+    ///
+    /// Requires `metrics` feature.
+    pub metrics_port: Option<u16>,
+
+    /// Bearer token required to access the metrics endpoint.
+    pub metrics_bearer_token: Option<String>,
+
+    /// Filter of the requests to the public REST API, administration
+    /// API, status and metrics endpoints by the client IP address.
+    ///
+    /// Requests from not allowed addresses are rejected
+    /// with `403 Forbidden` status. Disabled if not set.
+    pub ip_filter: Option<IpFilter>,
+
+    /// Plugins extending the server behavior.
+    ///
+    /// Applied in order to every incoming message
+    /// and every discovered server.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub plugins: Vec<Arc<dyn ServerPlugin>>
+}
+
+struct Redacted<'a>(&'a Option<String>);
+
+impl std::fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(_) => f.write_str("Some(<redacted>)"),
+            None => f.write_str("None")
+        }
+    }
+}
+
+struct Truncated<'a>(&'a [String]);
+
+impl std::fmt::Debug for Truncated<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut list = f.debug_list();
+
+        list.entries(self.0.iter().take(DEBUG_BOOTSTRAP_LIMIT));
+
+        if self.0.len() > DEBUG_BOOTSTRAP_LIMIT {
+            list.entry(&format_args!("... {} more", self.0.len() - DEBUG_BOOTSTRAP_LIMIT));
+        }
+
+        list.finish()
+    }
+}
+
+impl ServerAppParams {
+    /// Local address used to reach the server itself.
+    ///
+    /// Returns the first of the `local_addresses`.
+    #[inline]
+    pub fn local_address(&self) -> &str {
+        self.local_addresses.first()
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+
+    /// Remote address announced to other servers.
+    ///
+    /// Returns the first of the `remote_addresses`.
+    #[inline]
+    pub fn remote_address(&self) -> &str {
+        self.remote_addresses.first()
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+}
+
+impl std::fmt::Debug for ServerAppParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServerAppParams")
+            .field("public_key", &self.secret_key.public().to_base64())
+            .field("local_addresses", &self.local_addresses)
+            .field("remote_addresses", &self.remote_addresses)
+            .field("stun_server", &self.stun_server)
+            .field("tls", &self.tls)
+            .field("backend_folder", &self.backend_folder)
+            .field("on_corruption", &self.on_corruption)
+            .field("init_retries", &self.init_retries)
+            .field("init_retry_delay", &self.init_retry_delay)
+            .field("bootstrap", &Truncated(&self.bootstrap))
+            .field("bootstrap_concurrency", &self.bootstrap_concurrency)
+            .field("bootstrap_timeout", &self.bootstrap_timeout)
+            .field("contribute_peers_to_bootstrap", &self.contribute_peers_to_bootstrap)
+            .field("open_ports", &self.open_ports)
+            .field("announce", &self.announce)
+            .field("enable_mdns", &self.enable_mdns)
+            .field("relay_messages", &self.relay_messages)
+            .field("relay_rate_limit", &self.relay_rate_limit)
+            .field("multicast", &self.multicast)
+            .field("sign_outbound_requests", &self.sign_outbound_requests)
+            .field("require_signed_inbound", &self.require_signed_inbound)
+            .field("traverse_delay", &self.traverse_delay)
+            .field("adaptive_traversal", &self.adaptive_traversal)
+            .field("peer_max_age", &self.peer_max_age)
+            .field("peer_sweep_interval", &self.peer_sweep_interval)
+            .field("message_max_age", &self.message_max_age)
+            .field("dead_letter_channel", &self.dead_letter_channel)
+            .field("max_incoming_message_bytes", &self.max_incoming_message_bytes)
+            .field("blacklist_path", &self.blacklist_path)
+            .field("traversal_workers", &self.traversal_workers)
+            .field("max_concurrent_outbound_connections", &self.max_concurrent_outbound_connections)
+            .field("traversal_config", &self.traversal_config)
+            .field("http_config", &self.http_config)
+            .field("admin_address", &self.admin_address)
+            .field("admin_token", &Redacted(&self.admin_token))
+            .field("status_endpoint", &self.status_endpoint)
+            .field("metrics_port", &self.metrics_port)
+            .field("metrics_bearer_token", &Redacted(&self.metrics_bearer_token))
+            .field("ip_filter", &self.ip_filter)
+            .field("plugins", &self.plugins)
+            .finish()
+    }
+}
+
+impl std::fmt::Display for ServerAppParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "server {} on {} (remote {}, {} bootstrap servers, announce: {})",
+            self.secret_key.public().to_base64(),
+            self.local_addresses.join(", "),
+            self.remote_addresses.join(", "),
+            self.bootstrap.len(),
+            self.announce
+        )
+    }
 }
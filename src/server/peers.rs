@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hyperborealib::rest_api::prelude::*;
+
+/// Time to wait for the expired server
+/// to respond during the peers sweep.
+pub const PEER_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Timestamps of the last confirmation of the known servers.
+///
+/// Servers are confirmed when they're indexed from the bootstrap
+/// list, discovered by the network traversal or respond to the
+/// info request during the peers sweep.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+/// use hyperelm::exports::hyperborealib::rest_api::prelude::*;
+/// use hyperelm::server::PeerAges;
+///
+/// let server = Server::new(SecretKey::random().public(), "127.0.0.1:8001");
+/// let ages = PeerAges::default();
+///
+/// assert!(ages.last_seen(&server).is_none());
+///
+/// ages.observe([&server]);
+///
+/// assert!(ages.last_seen(&server).is_some());
+/// assert!(ages.expired([&server], Duration::from_secs(60)).is_empty());
+///
+/// ages.forget(&server);
+///
+/// assert!(ages.last_seen(&server).is_none());
+/// ```
+#[derive(Debug, Default)]
+pub struct PeerAges {
+    last_seen: RwLock<HashMap<String, u64>>
+}
+
+impl PeerAges {
+    /// Get UTC timestamp of the last server confirmation.
+    pub fn last_seen(&self, server: &Server) -> Option<u64> {
+        self.last_seen.read().ok()?
+            .get(&server.public_key.to_base64())
+            .copied()
+    }
+
+    /// Mark server as active now.
+    pub fn confirm(&self, server: &Server) {
+        if let Ok(mut last_seen) = self.last_seen.write() {
+            last_seen.insert(server.public_key.to_base64(), timestamp());
+        }
+    }
+
+    /// Start tracking given servers if they're not tracked yet.
+    ///
+    /// Servers indexed before the tracking started
+    /// are treated as confirmed now.
+    pub fn observe<'a>(&self, servers: impl IntoIterator<Item = &'a Server>) {
+        let now = timestamp();
+
+        if let Ok(mut last_seen) = self.last_seen.write() {
+            for server in servers {
+                last_seen.entry(server.public_key.to_base64())
+                    .or_insert(now);
+            }
+        }
+    }
+
+    /// Stop tracking given server.
+    pub fn forget(&self, server: &Server) {
+        if let Ok(mut last_seen) = self.last_seen.write() {
+            last_seen.remove(&server.public_key.to_base64());
+        }
+    }
+
+    /// Choose up to `amount` servers confirmed most recently.
+    ///
+    /// Servers which are not tracked go last.
+    pub fn most_recent<'a>(&self, servers: impl IntoIterator<Item = &'a Server>, amount: usize) -> Vec<&'a Server> {
+        let mut servers = servers.into_iter()
+            .map(|server| (self.last_seen(server).unwrap_or_default(), server))
+            .collect::<Vec<_>>();
+
+        servers.sort_by(|a, b| b.0.cmp(&a.0));
+
+        servers.into_iter()
+            .take(amount)
+            .map(|(_, server)| server)
+            .collect()
+    }
+
+    /// Filter servers not confirmed within the given time.
+    pub fn expired<'a>(&self, servers: impl IntoIterator<Item = &'a Server>, max_age: Duration) -> Vec<&'a Server> {
+        let now = timestamp();
+
+        let Ok(last_seen) = self.last_seen.read() else {
+            return vec![];
+        };
+
+        servers.into_iter()
+            .filter(|server| {
+                last_seen.get(&server.public_key.to_base64())
+                    .is_some_and(|last_seen| now.saturating_sub(*last_seen) > max_age.as_secs())
+            })
+            .collect()
+    }
+}
+
+#[inline]
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -0,0 +1,245 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+use hyperborealib::drivers::prelude::*;
+
+/// Decision of the plugin about the incoming message.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PluginDecision {
+    Accept,
+    Reject(String)
+}
+
+/// Extension of the server behavior.
+///
+/// Plugins are applied in order of the `plugins` param.
+#[async_trait::async_trait]
+pub trait ServerPlugin: Send + Sync {
+    /// Name of the plugin used in logs and errors.
+    fn name(&self) -> &str;
+
+    /// Called before the message is stored in the inbox.
+    ///
+    /// Rejected messages are not stored and the
+    /// following plugins are not called.
+    #[allow(unused_variables)]
+    async fn on_message(&self, info: &MessageInfo) -> PluginDecision {
+        PluginDecision::Accept
+    }
+
+    /// Called for every new server indexed
+    /// during the network traversal.
+    #[allow(unused_variables)]
+    async fn on_peer_discovered(&self, server: &Server) {}
+}
+
+impl std::fmt::Debug for dyn ServerPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ServerPlugin")
+            .field(&self.name())
+            .finish()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PluginInboxError<E> {
+    #[error(transparent)]
+    Inbox(E),
+
+    #[error("Message was rejected by the {plugin} plugin: {reason}")]
+    Rejected {
+        plugin: String,
+        reason: String
+    }
+}
+
+/// Messages inbox wrapper which passes incoming
+/// messages through the server plugins.
+pub struct PluginInbox<T> {
+    inner: T,
+    plugins: Vec<Arc<dyn ServerPlugin>>
+}
+
+impl<T> PluginInbox<T> {
+    #[inline]
+    pub fn new(inner: T, plugins: Vec<Arc<dyn ServerPlugin>>) -> Self {
+        Self {
+            inner,
+            plugins
+        }
+    }
+
+    #[inline]
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> MessagesInbox for PluginInbox<T>
+where
+    T: MessagesInbox + Send + Sync,
+    T::Error: std::error::Error + Send + Sync
+{
+    type Error = PluginInboxError<T::Error>;
+
+    async fn add_message(&self, sender: Sender, receiver: PublicKey, channel: String, message: Message) -> Result<(), Self::Error> {
+        if self.plugins.is_empty() {
+            return self.inner.add_message(sender, receiver, channel, message).await
+                .map_err(PluginInboxError::Inbox);
+        }
+
+        let info = MessageInfo {
+            sender,
+            channel,
+            message,
+            received_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        };
+
+        for plugin in &self.plugins {
+            if let PluginDecision::Reject(reason) = plugin.on_message(&info).await {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("[server] Message was rejected by the {} plugin: {reason}", plugin.name());
+
+                return Err(PluginInboxError::Rejected {
+                    plugin: plugin.name().to_string(),
+                    reason
+                });
+            }
+        }
+
+        self.inner.add_message(info.sender, receiver, info.channel, info.message).await
+            .map_err(PluginInboxError::Inbox)
+    }
+
+    #[inline]
+    async fn poll_messages(&self, receiver: PublicKey, channel: String, limit: Option<u64>) -> Result<(Vec<MessageInfo>, u64), Self::Error> {
+        self.inner.poll_messages(receiver, channel, limit).await
+            .map_err(PluginInboxError::Inbox)
+    }
+}
+
+/// Limit amount of messages sent by every client
+/// within the given time window.
+#[derive(Debug)]
+pub struct RateLimitPlugin {
+    max_messages: u32,
+    window: Duration,
+    counters: Mutex<HashMap<String, (Instant, u32)>>
+}
+
+impl RateLimitPlugin {
+    pub fn new(max_messages: u32, window: Duration) -> Self {
+        Self {
+            max_messages,
+            window,
+            counters: Mutex::new(HashMap::new())
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ServerPlugin for RateLimitPlugin {
+    #[inline]
+    fn name(&self) -> &str {
+        "rate-limit"
+    }
+
+    async fn on_message(&self, info: &MessageInfo) -> PluginDecision {
+        let Ok(mut counters) = self.counters.lock() else {
+            return PluginDecision::Accept;
+        };
+
+        // Forget clients with elapsed windows
+        counters.retain(|_, (started_at, _)| started_at.elapsed() < self.window);
+
+        let (_, count) = counters.entry(info.sender.client.public_key.to_base64())
+            .or_insert_with(|| (Instant::now(), 0));
+
+        if *count >= self.max_messages {
+            return PluginDecision::Reject(format!("more than {} messages per {:?}", self.max_messages, self.window));
+        }
+
+        *count += 1;
+
+        PluginDecision::Accept
+    }
+}
+
+/// Log incoming messages and discovered servers.
+///
+/// Does nothing if the `tracing` feature is disabled.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LoggingPlugin;
+
+#[async_trait::async_trait]
+impl ServerPlugin for LoggingPlugin {
+    #[inline]
+    fn name(&self) -> &str {
+        "logging"
+    }
+
+    async fn on_message(&self, _info: &MessageInfo) -> PluginDecision {
+        #[cfg(feature = "tracing")]
+        tracing::info!(
+            sender = _info.sender.client.public_key.to_base64(),
+            channel = _info.channel,
+            "[server] Incoming message"
+        );
+
+        PluginDecision::Accept
+    }
+
+    async fn on_peer_discovered(&self, _server: &Server) {
+        #[cfg(feature = "tracing")]
+        tracing::info!(
+            address = _server.address,
+            public_key = _server.public_key.to_base64(),
+            "[server] Discovered server"
+        );
+    }
+}
+
+/// Accept messages only from the listed clients.
+#[derive(Debug, Default, Clone)]
+pub struct AllowListPlugin {
+    allowed: HashSet<String>
+}
+
+impl AllowListPlugin {
+    pub fn new(clients: impl IntoIterator<Item = PublicKey>) -> Self {
+        Self {
+            allowed: clients.into_iter()
+                .map(|client| client.to_base64())
+                .collect()
+        }
+    }
+
+    /// Allow messages from the given client.
+    #[inline]
+    pub fn allow(&mut self, client: &PublicKey) {
+        self.allowed.insert(client.to_base64());
+    }
+}
+
+#[async_trait::async_trait]
+impl ServerPlugin for AllowListPlugin {
+    #[inline]
+    fn name(&self) -> &str {
+        "allow-list"
+    }
+
+    async fn on_message(&self, info: &MessageInfo) -> PluginDecision {
+        if self.allowed.contains(&info.sender.client.public_key.to_base64()) {
+            PluginDecision::Accept
+        } else {
+            PluginDecision::Reject(String::from("sender is not allowed"))
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use tokio::sync::{mpsc, Mutex, OnceCell};
+
+use hyperborealib::http::HttpClient;
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::drivers::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use crate::client::{OutboundRate, OutboundRateLimiter, RateLimit, RateLimitMode};
+
+/// Default limit of messages relayed for every client.
+pub const DEFAULT_RELAY_RATE_LIMIT: RateLimit = RateLimit {
+    per_second: 10.0,
+    burst: 20
+};
+
+/// Maximal amount of messages waiting to be relayed.
+pub const RELAY_QUEUE_SIZE: usize = 1024;
+
+/// Time after which servers known to the current
+/// one are requested again by the relay.
+const KNOWN_SERVERS_TTL: Duration = Duration::from_secs(60);
+
+/// Prefix of the channels used by clients to ask their
+/// home server to relay a message to another server.
+///
+/// Relay channel has `hyperelm-relay/{server_address}/{channel}` format.
+pub const RELAY_CHANNEL_PREFIX: &str = "hyperelm-relay/";
+
+/// Build channel asking the home server to relay message
+/// to the `channel` of the client on the `target_server`.
+#[inline]
+pub fn relay_channel(target_server: &str, channel: &str) -> String {
+    format!("{RELAY_CHANNEL_PREFIX}{target_server}/{channel}")
+}
+
+/// Split relay channel to the target server address
+/// and the original channel.
+///
+/// ```rust
+/// use hyperelm::server::{relay_channel, parse_relay_channel};
+///
+/// let channel = relay_channel("127.0.0.1:8002", "hyperelm");
+///
+/// assert_eq!(parse_relay_channel(&channel), Some(("127.0.0.1:8002", "hyperelm")));
+/// assert_eq!(parse_relay_channel("hyperelm"), None);
+/// ```
+pub fn parse_relay_channel(channel: &str) -> Option<(&str, &str)> {
+    channel.strip_prefix(RELAY_CHANNEL_PREFIX)?
+        .split_once('/')
+        .filter(|(server, channel)| !server.is_empty() && !channel.is_empty())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RelayInboxError<E> {
+    #[error(transparent)]
+    Inbox(E),
+
+    #[error("Messages relaying is disabled")]
+    Disabled,
+
+    #[error("Only clients of the current server can relay messages")]
+    NotLocalClient,
+
+    #[error("Relay rate limit exceeded, retry after {retry_after:?}")]
+    RateLimited {
+        retry_after: Duration
+    },
+
+    #[error("Relay queue is full")]
+    QueueFull
+}
+
+/// Message waiting to be forwarded by the relay.
+#[derive(Debug, Clone)]
+pub struct RelayedMessage {
+    pub sender: Sender,
+    pub receiver: PublicKey,
+    pub target_server: String,
+    pub channel: String,
+    pub message: Message
+}
+
+/// Forwarder of the relayed messages.
+///
+/// Relayed message stays encrypted for its recipient. It's
+/// wrapped into the `relayed` envelope containing the original
+/// sender and sent to the target server on behalf of the current
+/// server's own client.
+///
+/// Messages are forwarded only to the servers known to the
+/// current one, and only if their sender is connected to it.
+pub struct Relay<T: HttpClient> {
+    middleware: ClientMiddleware<T>,
+    secret_key: SecretKey,
+    server_address: String,
+    connected: OnceCell<ConnectedClientMiddleware<T>>,
+    known_servers: Mutex<Option<(Instant, HashSet<String>)>>
+}
+
+impl<T: HttpClient + Send + Sync> Relay<T> {
+    /// Create new relay sending messages through the
+    /// current server available on the given address.
+    pub fn new(http_client: T, secret_key: SecretKey, server_address: impl ToString) -> Self {
+        let driver = ClientDriver::new(ClientInfo::thin(), secret_key.clone());
+
+        Self {
+            middleware: ClientMiddleware::new(http_client, driver),
+            secret_key,
+            server_address: server_address.to_string(),
+            connected: OnceCell::new(),
+            known_servers: Mutex::new(None)
+        }
+    }
+
+    /// Check if the server with given address is
+    /// indexed by the router of the current server.
+    ///
+    /// Known servers are requested from the current
+    /// server at most once per `KNOWN_SERVERS_TTL`.
+    async fn is_known_server(&self, address: &str) -> Result<bool, String> {
+        let mut known_servers = self.known_servers.lock().await;
+
+        let expired = match &*known_servers {
+            Some((updated_at, _)) => updated_at.elapsed() >= KNOWN_SERVERS_TTL,
+            None => true
+        };
+
+        if expired {
+            let servers = self.middleware.get_servers(&self.server_address).await
+                .map_err(|err| err.to_string())?
+                .into_iter()
+                .map(|server| server.address)
+                .collect();
+
+            *known_servers = Some((Instant::now(), servers));
+        }
+
+        Ok(known_servers.as_ref().is_some_and(|(_, servers)| servers.contains(address)))
+    }
+
+    /// Check if the client is connected to the current server.
+    async fn is_local_client(&self, client: &PublicKey) -> Result<bool, String> {
+        let result = self.middleware.lookup(&self.server_address, client.clone(), None).await
+            .map_err(|err| err.to_string())?;
+
+        Ok(result.is_some_and(|(_, server, _)| server.public_key == self.secret_key.public()))
+    }
+
+    /// Forward message to the client of the target server.
+    pub async fn forward(
+        &self,
+        sender: &Sender,
+        receiver: PublicKey,
+        target_server: &str,
+        channel: &str,
+        message: Message
+    ) -> Result<(), String> {
+        if !self.is_local_client(&sender.client.public_key).await? {
+            return Err(String::from("sender is not connected to the current server"));
+        }
+
+        if !self.is_known_server(target_server).await? {
+            return Err(format!("target server {target_server} is not known"));
+        }
+
+        let middleware = self.connected.get_or_try_init(|| async {
+            self.middleware.connect_to(&self.server_address, self.secret_key.public()).await
+        }).await.map_err(|err| err.to_string())?;
+
+        let envelope = json!({
+            "relayed": {
+                "sender": sender.client.public_key.to_base64(),
+                "server_address": sender.server.address,
+                "server_public": sender.server.public_key.to_base64(),
+                "message": message.to_json().map_err(|err| err.to_string())?
+            }
+        });
+
+        let envelope = serde_json::to_vec(&envelope)
+            .map_err(|err| err.to_string())?;
+
+        let message = Message::create(
+            &self.secret_key,
+            &receiver,
+            envelope,
+            MessageEncoding::default(),
+            CompressionLevel::default()
+        ).map_err(|err| err.to_string())?;
+
+        middleware.send(target_server, receiver, channel, message).await
+            .map_err(|err| err.to_string())?;
+
+        Ok(())
+    }
+}
+
+impl<T: HttpClient + Send + Sync + 'static> Relay<T> {
+    /// Forward queued messages in a background task.
+    ///
+    /// Every client can queue at most `rate_limit` messages.
+    /// The task is stopped when the returned queue is dropped.
+    pub fn spawn(self, rate_limit: RateLimit) -> RelayQueue {
+        let (sender, mut receiver) = mpsc::channel::<RelayedMessage>(RELAY_QUEUE_SIZE);
+
+        let server_public = self.secret_key.public();
+
+        tokio::spawn(async move {
+            while let Some(relayed) = receiver.recv().await {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("[server] Relaying message to {}", relayed.target_server);
+
+                let result = self.forward(
+                    &relayed.sender,
+                    relayed.receiver,
+                    &relayed.target_server,
+                    &relayed.channel,
+                    relayed.message
+                ).await;
+
+                if let Err(_err) = result {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!("[server] Failed to relay message to {}: {_err}", relayed.target_server);
+                }
+            }
+        });
+
+        RelayQueue {
+            sender,
+            server_public,
+            limiter: Arc::new(OutboundRateLimiter::new(Some(OutboundRate {
+                per_endpoint: rate_limit,
+                global: None,
+                mode: RateLimitMode::FailFast
+            })))
+        }
+    }
+}
+
+/// Queue of the messages forwarded by the relay task.
+#[derive(Debug, Clone)]
+pub struct RelayQueue {
+    sender: mpsc::Sender<RelayedMessage>,
+    server_public: PublicKey,
+    limiter: Arc<OutboundRateLimiter>
+}
+
+impl RelayQueue {
+    /// Queue message to be relayed.
+    ///
+    /// Messages of the clients which don't claim to be connected
+    /// to the current server are rejected immediately. The claim
+    /// itself is verified by the relay task.
+    pub fn push<E>(&self, relayed: RelayedMessage) -> Result<(), RelayInboxError<E>> {
+        if relayed.sender.server.public_key != self.server_public {
+            return Err(RelayInboxError::NotLocalClient);
+        }
+
+        self.limiter.try_acquire(&relayed.sender.client.public_key)
+            .map_err(|retry_after| RelayInboxError::RateLimited { retry_after })?;
+
+        self.sender.try_send(relayed)
+            .map_err(|_| RelayInboxError::QueueFull)
+    }
+}
+
+/// Messages inbox wrapper forwarding messages sent
+/// to the relay channels to their target servers.
+pub struct RelayInbox<T> {
+    inner: T,
+    relay: Option<RelayQueue>
+}
+
+impl<T> RelayInbox<T> {
+    /// Wrap inbox. Relay channels are rejected if `relay` is not set.
+    #[inline]
+    pub fn new(inner: T, relay: Option<RelayQueue>) -> Self {
+        Self {
+            inner,
+            relay
+        }
+    }
+
+    #[inline]
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> MessagesInbox for RelayInbox<T>
+where
+    T: MessagesInbox + Send + Sync,
+    T::Error: std::error::Error + Send + Sync
+{
+    type Error = RelayInboxError<T::Error>;
+
+    async fn add_message(&self, sender: Sender, receiver: PublicKey, channel: String, message: Message) -> Result<(), Self::Error> {
+        let Some((target_server, target_channel)) = parse_relay_channel(&channel) else {
+            return self.inner.add_message(sender, receiver, channel, message).await
+                .map_err(RelayInboxError::Inbox);
+        };
+
+        let Some(relay) = &self.relay else {
+            return Err(RelayInboxError::Disabled);
+        };
+
+        // Forwarding is done by the relay task, so slow
+        // target servers don't block the inbound request
+        relay.push(RelayedMessage {
+            sender,
+            receiver,
+            target_server: target_server.to_string(),
+            channel: target_channel.to_string(),
+            message
+        })
+    }
+
+    #[inline]
+    async fn poll_messages(&self, receiver: PublicKey, channel: String, limit: Option<u64>) -> Result<(Vec<MessageInfo>, u64), Self::Error> {
+        self.inner.poll_messages(receiver, channel, limit).await
+            .map_err(RelayInboxError::Inbox)
+    }
+}
@@ -0,0 +1,178 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use axum::Router;
+use axum::http::StatusCode;
+use axum::routing::MethodRouter;
+use axum::response::{IntoResponse, Response};
+
+use serde_json::{json, Value as Json};
+
+use hyperborealib::http::HttpServer;
+use hyperborealib::rest_api::prelude::*;
+
+/// Router of the REST API with the list of its paths,
+/// so overlapping routes can be found before merging.
+///
+/// ```rust
+/// use axum::routing::get;
+///
+/// use hyperelm::server::Routes;
+///
+/// let routes = Routes::new()
+///     .route("/hello", get(|| async { "hello" }));
+///
+/// assert_eq!(routes.paths(), ["/hello"]);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Routes {
+    paths: Vec<String>,
+    router: Router
+}
+
+impl Routes {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use router serving given paths.
+    ///
+    /// Paths must list all the routes of the router,
+    /// otherwise their overlaps can't be found.
+    pub fn with_paths(router: Router, paths: impl IntoIterator<Item = impl ToString>) -> Self {
+        Self {
+            paths: paths.into_iter()
+                .map(|path| path.to_string())
+                .collect(),
+            router
+        }
+    }
+
+    /// Add route to the router.
+    pub fn route(mut self, path: &str, method_router: MethodRouter) -> Self {
+        if !self.paths.iter().any(|known| known == path) {
+            self.paths.push(path.to_string());
+        }
+
+        self.router = self.router.route(path, method_router);
+
+        self
+    }
+
+    /// Update the router keeping its paths,
+    /// e.g. to apply some layer to it.
+    pub fn map(mut self, map: impl FnOnce(Router) -> Router) -> Self {
+        self.router = map(self.router);
+
+        self
+    }
+
+    #[inline]
+    pub fn paths(&self) -> &[String] {
+        &self.paths
+    }
+
+    #[inline]
+    pub fn into_router(self) -> Router {
+        self.router
+    }
+}
+
+/// HTTP server which can expose routes registered
+/// by the hyperborealib middleware.
+///
+/// The `run` function serves these routes itself, so the
+/// payload size limit, IP filter, signatures verification
+/// and HTTP layers cover the hyperborealib endpoints.
+pub trait RestApiRouter {
+    /// Get all the registered routes.
+    fn routes(&self) -> Routes;
+}
+
+/// HTTP server of the hyperborealib middleware
+/// which collects registered routes.
+///
+/// Clones share the same routes, so routes registered
+/// by the middleware are available to all of them.
+///
+/// ```rust
+/// use hyperelm::server::{RestApiServer, RestApiRouter};
+///
+/// let server = RestApiServer::default();
+///
+/// // Routes can be served by the application
+/// assert!(server.routes().paths().is_empty());
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct RestApiServer {
+    routes: Arc<Mutex<Routes>>
+}
+
+impl RestApiServer {
+    fn route(&self, path: &str, method_router: MethodRouter) {
+        let mut routes = self.routes.lock()
+            .expect("REST API routes lock is poisoned");
+
+        *routes = std::mem::take(&mut *routes).route(path, method_router);
+    }
+}
+
+impl RestApiRouter for RestApiServer {
+    fn routes(&self) -> Routes {
+        self.routes.lock()
+            .expect("REST API routes lock is poisoned")
+            .clone()
+    }
+}
+
+/// Convert value returned by the route handler to the response.
+fn json_response<T: AsJson>(value: T) -> Response {
+    match value.to_json() {
+        Ok(json) => axum::Json(json).into_response(),
+
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, axum::Json(json!({
+            "error": err.to_string()
+        }))).into_response()
+    }
+}
+
+#[async_trait::async_trait]
+impl HttpServer for RestApiServer {
+    async fn get<T, F, Fut>(&mut self, path: &str, callback: F)
+    where
+        T: AsJson + Send,
+        F: FnOnce() -> Fut + Clone + Send + Sync + 'static,
+        Fut: Future<Output = T> + Send
+    {
+        self.route(path, axum::routing::get(move || async move {
+            json_response(callback().await)
+        }));
+    }
+
+    async fn post<T, R, F, Fut>(&mut self, path: &str, callback: F)
+    where
+        T: AsJson + Send,
+        R: AsJson + Send,
+        F: FnOnce(T) -> Fut + Clone + Send + Sync + 'static,
+        Fut: Future<Output = R> + Send
+    {
+        self.route(path, axum::routing::post(move |axum::Json(request): axum::Json<Json>| async move {
+            match T::from_json(&request) {
+                Ok(request) => json_response(callback(request).await),
+
+                Err(err) => (StatusCode::BAD_REQUEST, axum::Json(json!({
+                    "error": err.to_string()
+                }))).into_response()
+            }
+        }));
+    }
+
+    async fn serve(self, address: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let listener = tokio::net::TcpListener::bind(address).await?;
+
+        axum::serve(listener, self.routes().into_router()).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::Value as Json;
+
+use sha2::{Digest, Sha256};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+
+use axum::Router;
+use axum::body::{to_bytes, Body};
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::middleware::{from_fn_with_state, Next};
+use axum::response::{IntoResponse, Response};
+
+use hyperborealib::http::HttpClient;
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+/// Base64 encoded signature of the request.
+pub const SIGNATURE_HEADER: &str = "X-Hyperelm-Signature";
+
+/// UTC timestamp of the request in seconds.
+pub const TIMESTAMP_HEADER: &str = "X-Hyperelm-Timestamp";
+
+/// Address of the server which signed the request.
+///
+/// Signature is verified with the public key the router
+/// knows for this address, so it can't be forged by sending
+/// another key with the request.
+pub const ADDRESS_HEADER: &str = "X-Hyperelm-Address";
+
+/// Paths of the REST API used by the servers to traverse
+/// the network. Requests to them must be signed by a known
+/// server if the `require_signed_inbound` param is enabled.
+pub const SERVER_TO_SERVER_PATHS: &[&str] = &[
+    "/api/v1/clients",
+    "/api/v1/servers"
+];
+
+/// Maximal difference between the signed request
+/// timestamp and the current time.
+pub const SIGNATURE_MAX_AGE: Duration = Duration::from_secs(5 * 60);
+
+/// Build hash of the signed request.
+///
+/// Hash is `SHA256(method || path || timestamp || body)`.
+pub fn signature_payload(method: &str, path: &str, timestamp: u64, body: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+
+    hasher.update(method.as_bytes());
+    hasher.update(path.as_bytes());
+    hasher.update(timestamp.to_string().as_bytes());
+    hasher.update(body);
+
+    hasher.finalize().to_vec()
+}
+
+/// Sign HTTP request, returning values of the
+/// signature and timestamp headers.
+///
+/// ```rust
+/// use hyperelm::exports::hyperborealib::crypto::prelude::*;
+/// use hyperelm::server::{sign_request, verify_request};
+///
+/// let secret = SecretKey::random();
+///
+/// let (signature, timestamp) = sign_request(&secret, "POST", "/api/v1/send", b"{}");
+///
+/// assert!(verify_request(&secret.public(), &signature, timestamp, "POST", "/api/v1/send", b"{}"));
+/// assert!(!verify_request(&secret.public(), &signature, timestamp, "POST", "/api/v1/send", b"[]"));
+/// assert!(!verify_request(&secret.public(), &signature, timestamp - 3600, "POST", "/api/v1/send", b"{}"));
+/// ```
+pub fn sign_request(secret_key: &SecretKey, method: &str, path: &str, body: &[u8]) -> (String, u64) {
+    let timestamp = unix_timestamp();
+
+    let signature = secret_key.create_signature(signature_payload(method, path, timestamp, body));
+
+    (BASE64.encode(signature), timestamp)
+}
+
+/// Verify signature of the HTTP request.
+///
+/// Requests with timestamp differing from the current
+/// time by more than `SIGNATURE_MAX_AGE` are rejected.
+pub fn verify_request(
+    public_key: &PublicKey,
+    signature: &str,
+    timestamp: u64,
+    method: &str,
+    path: &str,
+    body: &[u8]
+) -> bool {
+    if unix_timestamp().abs_diff(timestamp) > SIGNATURE_MAX_AGE.as_secs() {
+        return false;
+    }
+
+    let Ok(signature) = BASE64.decode(signature) else {
+        return false;
+    };
+
+    public_key.verify_signature(signature_payload(method, path, timestamp, body), signature)
+        .unwrap_or(false)
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// HTTP client signing every request with the server's secret key.
+///
+/// Requests are sent unsigned if the secret key is not set.
+/// The `address` is sent in the `X-Hyperelm-Address` header
+/// so receivers can find the server in their routers.
+#[derive(Debug, Clone)]
+pub struct SignedHttpClient {
+    client: reqwest::Client,
+    secret_key: Option<SecretKey>,
+    address: String
+}
+
+impl SignedHttpClient {
+    #[inline]
+    pub fn new(client: reqwest::Client, secret_key: Option<SecretKey>, address: impl ToString) -> Self {
+        Self {
+            client,
+            secret_key,
+            address: address.to_string()
+        }
+    }
+
+    #[inline]
+    pub fn is_signing(&self) -> bool {
+        self.secret_key.is_some()
+    }
+
+    fn sign(&self, request: reqwest::RequestBuilder, method: &str, url: &str, body: &[u8]) -> reqwest::RequestBuilder {
+        let Some(secret_key) = &self.secret_key else {
+            return request;
+        };
+
+        let path = reqwest::Url::parse(url)
+            .map(|url| url.path().to_string())
+            .unwrap_or_else(|_| String::from("/"));
+
+        let (signature, timestamp) = sign_request(secret_key, method, &path, body);
+
+        request.header(SIGNATURE_HEADER, signature)
+            .header(TIMESTAMP_HEADER, timestamp)
+            .header(ADDRESS_HEADER, &self.address)
+    }
+}
+
+#[async_trait::async_trait]
+impl HttpClient for SignedHttpClient {
+    async fn get_request<T: AsJson>(&self, url: &str) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+        let request = self.sign(self.client.get(url), "GET", url, &[]);
+
+        let response = request.send().await?
+            .json::<Json>().await?;
+
+        Ok(T::from_json(&response)?)
+    }
+
+    async fn post_request<T: AsJson + Send, F: AsJson>(&self, url: &str, request: T) -> Result<F, Box<dyn std::error::Error + Send + Sync>> {
+        let body = serde_json::to_vec(&request.to_json()?)?;
+
+        let request = self.sign(self.client.post(url), "POST", url, &body)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body);
+
+        let response = request.send().await?
+            .json::<Json>().await?;
+
+        Ok(F::from_json(&response)?)
+    }
+}
+
+/// Signature headers of the request.
+struct SignatureHeaders {
+    signature: String,
+    timestamp: u64,
+    address: String
+}
+
+impl SignatureHeaders {
+    fn parse(headers: &HeaderMap) -> Option<Self> {
+        let header = |name: &str| headers.get(name)
+            .and_then(|value| value.to_str().ok());
+
+        Some(Self {
+            signature: header(SIGNATURE_HEADER)?.to_string(),
+            timestamp: header(TIMESTAMP_HEADER)?.parse().ok()?,
+            address: header(ADDRESS_HEADER)?.to_string()
+        })
+    }
+}
+
+#[derive(Clone)]
+struct VerifierState<F> {
+    limit: usize,
+    paths: Option<Arc<[String]>>,
+    resolve: F
+}
+
+async fn verify_signature<F, R>(State(state): State<VerifierState<F>>, request: Request, next: Next) -> Response
+where
+    F: Fn(String) -> R + Clone + Send + Sync + 'static,
+    R: Future<Output = Option<PublicKey>> + Send + 'static
+{
+    if let Some(paths) = &state.paths {
+        if !paths.iter().any(|path| path == request.uri().path()) {
+            return next.run(request).await;
+        }
+    }
+
+    let (parts, body) = request.into_parts();
+
+    let Ok(body) = to_bytes(body, state.limit).await else {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    };
+
+    let verified = match SignatureHeaders::parse(&parts.headers) {
+        Some(headers) => match (state.resolve)(headers.address).await {
+            Some(public_key) => verify_request(
+                &public_key,
+                &headers.signature,
+                headers.timestamp,
+                parts.method.as_str(),
+                parts.uri.path(),
+                &body
+            ),
+
+            None => false
+        },
+
+        None => false
+    };
+
+    if !verified {
+        #[cfg(feature = "tracing")]
+        tracing::warn!("[server] Rejected unsigned request to {}", parts.uri.path());
+
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
+
+/// Reject requests which are not signed by a known
+/// server with `401 Unauthorized` status.
+///
+/// The `resolve` callback returns public key of the server
+/// with the address from the `X-Hyperelm-Address` header,
+/// or `None` if the server is not known.
+///
+/// Bodies larger than `limit` bytes are rejected
+/// with `413 Payload Too Large` status.
+pub fn require_signed_requests<F, R>(router: Router, limit: usize, resolve: F) -> Router
+where
+    F: Fn(String) -> R + Clone + Send + Sync + 'static,
+    R: Future<Output = Option<PublicKey>> + Send + 'static
+{
+    router.layer(from_fn_with_state(VerifierState {
+        limit,
+        paths: None,
+        resolve
+    }, verify_signature::<F, R>))
+}
+
+/// Same as `require_signed_requests`, but verifies
+/// only requests to the given paths.
+pub fn require_signed_paths<F, R>(router: Router, paths: &[&str], limit: usize, resolve: F) -> Router
+where
+    F: Fn(String) -> R + Clone + Send + Sync + 'static,
+    R: Future<Output = Option<PublicKey>> + Send + 'static
+{
+    let paths = paths.iter()
+        .map(|path| path.to_string())
+        .collect::<Vec<_>>();
+
+    router.layer(from_fn_with_state(VerifierState {
+        limit,
+        paths: Some(paths.into()),
+        resolve
+    }, verify_signature::<F, R>))
+}
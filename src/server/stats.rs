@@ -0,0 +1,220 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+use hyperborealib::drivers::prelude::*;
+
+use crate::client::Direction;
+
+use super::ServerHandle;
+
+/// Results of indexing a bootstrap address.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BootstrapResults {
+    pub succeeded: u64,
+    pub failed: u64
+}
+
+/// Results of announcing the current server to another one.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AnnounceResults {
+    pub succeeded: u64,
+    pub failed: u64
+}
+
+/// Runtime statistics of the running server application.
+#[derive(Debug)]
+pub struct ServerStats {
+    started_at: Instant,
+    traversal_cycles: AtomicU64,
+    last_traversal: Mutex<Option<SystemTime>>,
+    messages_received: AtomicU64,
+    messages_sent: AtomicU64,
+    open_ports: Mutex<HashSet<u16>>,
+    bootstrap_results: Mutex<HashMap<String, BootstrapResults>>,
+    announce_results: Mutex<HashMap<String, AnnounceResults>>
+}
+
+impl Default for ServerStats {
+    fn default() -> Self {
+        Self {
+            started_at: Instant::now(),
+            traversal_cycles: AtomicU64::new(0),
+            last_traversal: Mutex::new(None),
+            messages_received: AtomicU64::new(0),
+            messages_sent: AtomicU64::new(0),
+            open_ports: Mutex::new(HashSet::new()),
+            bootstrap_results: Mutex::new(HashMap::new()),
+            announce_results: Mutex::new(HashMap::new())
+        }
+    }
+}
+
+impl ServerStats {
+    #[inline]
+    pub fn uptime(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    #[inline]
+    pub fn traversal_cycles(&self) -> u64 {
+        self.traversal_cycles.load(Ordering::Relaxed)
+    }
+
+    /// Time when the last network traversal was completed.
+    pub fn last_traversal(&self) -> Option<SystemTime> {
+        self.last_traversal.lock()
+            .map(|time| *time)
+            .unwrap_or_default()
+    }
+
+    /// Amount of messages processed by the server
+    /// in the given direction.
+    pub fn messages_processed(&self, direction: Direction) -> u64 {
+        match direction {
+            Direction::Incoming => self.messages_received.load(Ordering::Relaxed),
+            Direction::Outgoing => self.messages_sent.load(Ordering::Relaxed)
+        }
+    }
+
+    /// Count processed message.
+    ///
+    /// Incoming messages are the ones sent to the server's
+    /// inbox, and outgoing are the ones polled from it.
+    #[inline]
+    pub fn message_processed(&self, direction: Direction) {
+        self.messages_processed_by(direction, 1);
+    }
+
+    pub(crate) fn messages_processed_by(&self, direction: Direction, amount: u64) {
+        match direction {
+            Direction::Incoming => self.messages_received.fetch_add(amount, Ordering::Relaxed),
+            Direction::Outgoing => self.messages_sent.fetch_add(amount, Ordering::Relaxed)
+        };
+    }
+
+    /// List of ports currently opened by the UPnP forwarder.
+    pub fn open_ports(&self) -> Vec<u16> {
+        let mut ports = self.open_ports.lock()
+            .map(|ports| ports.iter().copied().collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        ports.sort();
+
+        ports
+    }
+
+    /// Results of indexing the bootstrap addresses.
+    pub fn bootstrap_results(&self) -> HashMap<String, BootstrapResults> {
+        self.bootstrap_results.lock()
+            .map(|results| results.clone())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn bootstrap_indexed(&self, address: &str, success: bool) {
+        if let Ok(mut results) = self.bootstrap_results.lock() {
+            let results = results.entry(address.to_string())
+                .or_default();
+
+            if success {
+                results.succeeded += 1;
+            } else {
+                results.failed += 1;
+            }
+        }
+    }
+
+    /// Results of announcing the current server to other ones.
+    pub fn announce_results(&self) -> HashMap<String, AnnounceResults> {
+        self.announce_results.lock()
+            .map(|results| results.clone())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn announced(&self, address: &str, success: bool) {
+        if let Ok(mut results) = self.announce_results.lock() {
+            let results = results.entry(address.to_string())
+                .or_default();
+
+            if success {
+                results.succeeded += 1;
+            } else {
+                results.failed += 1;
+            }
+        }
+    }
+
+    pub(crate) fn traversal_completed(&self) {
+        self.traversal_cycles.fetch_add(1, Ordering::Relaxed);
+
+        if let Ok(mut time) = self.last_traversal.lock() {
+            *time = Some(SystemTime::now());
+        }
+    }
+
+    pub(crate) fn port_opened(&self, port: u16) {
+        if let Ok(mut ports) = self.open_ports.lock() {
+            ports.insert(port);
+        }
+    }
+
+    pub(crate) fn port_closed(&self, port: u16) {
+        if let Ok(mut ports) = self.open_ports.lock() {
+            ports.remove(&port);
+        }
+    }
+}
+
+/// Messages inbox wrapper counting processed
+/// messages in the server's stats.
+///
+/// Used by the `run` function to wrap the application's inbox.
+///
+/// Wrapped inbox is shared, so the server can
+/// use it directly, e.g. to sweep expired messages.
+pub struct CountingInbox<T> {
+    inner: Arc<T>,
+    handle: ServerHandle
+}
+
+impl<T> CountingInbox<T> {
+    #[inline]
+    pub fn new(inner: Arc<T>, handle: ServerHandle) -> Self {
+        Self {
+            inner,
+            handle
+        }
+    }
+
+    #[inline]
+    pub fn inner(&self) -> &Arc<T> {
+        &self.inner
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> MessagesInbox for CountingInbox<T>
+where
+    T: MessagesInbox + Send + Sync
+{
+    type Error = T::Error;
+
+    async fn add_message(&self, sender: Sender, receiver: PublicKey, channel: String, message: Message) -> Result<(), Self::Error> {
+        self.inner.add_message(sender, receiver, channel, message).await?;
+
+        self.handle.stats().message_processed(Direction::Incoming);
+
+        Ok(())
+    }
+
+    async fn poll_messages(&self, receiver: PublicKey, channel: String, limit: Option<u64>) -> Result<(Vec<MessageInfo>, u64), Self::Error> {
+        let (messages, remaining) = self.inner.poll_messages(receiver, channel, limit).await?;
+
+        self.handle.stats().messages_processed_by(Direction::Outgoing, messages.len() as u64);
+
+        Ok((messages, remaining))
+    }
+}
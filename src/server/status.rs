@@ -0,0 +1,166 @@
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use serde_json::{json, Value as Json};
+
+use axum::Router;
+use axum::routing::get;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+
+use hyperborealib::http::HttpClient;
+use hyperborealib::rest_api::prelude::*;
+use hyperborealib::drivers::prelude::Router as _;
+
+use super::{ServerApp, ServerHandle, AppServerDriver};
+
+/// Path of the server startup token endpoint.
+pub const STARTUP_PATH: &str = "/startup";
+
+/// Random token generated on every server start.
+///
+/// `{ "startup_token": 1234567890 }`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StartupInfo {
+    pub startup_token: u64
+}
+
+impl AsJson for StartupInfo {
+    fn to_json(&self) -> Result<Json, AsJsonError> {
+        Ok(json!({
+            "startup_token": self.startup_token
+        }))
+    }
+
+    fn from_json(json: &Json) -> Result<Self, AsJsonError> where Self: Sized {
+        Ok(Self {
+            startup_token: json.get("startup_token")
+                .and_then(Json::as_u64)
+                .ok_or_else(|| AsJsonError::FieldNotFound("startup_token"))?
+        })
+    }
+}
+
+/// Get startup token of the hyperelm server on given address.
+///
+/// Fails if the server is not running a hyperelm application.
+pub async fn fetch_startup_token<T: HttpClient>(http_client: &T, address: &str) -> Result<u64, String> {
+    http_client.get_request::<StartupInfo>(&format!("http://{address}{STARTUP_PATH}")).await
+        .map(|info| info.startup_token)
+        .map_err(|err| err.to_string())
+}
+
+/// Build router of the `GET /startup` endpoint
+/// reporting the server startup token.
+///
+/// Served on the public REST API so clients can
+/// detect restarts of their server.
+pub fn startup_router(handle: ServerHandle) -> Router {
+    Router::new()
+        .route(STARTUP_PATH, get(get_startup))
+        .with_state(handle)
+}
+
+async fn get_startup(State(handle): State<ServerHandle>) -> Response {
+    let info = StartupInfo {
+        startup_token: handle.startup_token()
+    };
+
+    match info.to_json() {
+        Ok(info) => axum::Json(info).into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+    }
+}
+
+struct StatusState<T: ServerApp> {
+    app: Arc<T>,
+    driver: Arc<AppServerDriver<T>>,
+    handle: ServerHandle
+}
+
+impl<T: ServerApp> Clone for StatusState<T> {
+    fn clone(&self) -> Self {
+        Self {
+            app: self.app.clone(),
+            driver: self.driver.clone(),
+            handle: self.handle.clone()
+        }
+    }
+}
+
+/// Build router serving the node status
+/// JSON document on the `/status` endpoint.
+///
+/// ```json
+/// {
+///     "public_key": "...",
+///     "summary": "server ... on 0.0.0.0:8001 (...)",
+///     "version": "0.1.0",
+///     "uptime_secs": 120,
+///     "startup_token": 1234567890,
+///     "ready": true,
+///     "known_peers": 10,
+///     "inbox_channels": 2,
+///     "traversal_cycles": 1,
+///     "last_traversal": 1700000000
+/// }
+/// ```
+///
+/// Known peers are counted by the router of the given driver.
+pub fn status_router<T>(app: Arc<T>, driver: Arc<AppServerDriver<T>>, handle: ServerHandle) -> Router
+where
+    T: ServerApp + Send + Sync + 'static,
+    T::Error: std::fmt::Debug
+{
+    Router::new()
+        .route("/status", get(get_status::<T>))
+        .with_state(StatusState {
+            app,
+            driver,
+            handle
+        })
+}
+
+async fn get_status<T>(State(state): State<StatusState<T>>) -> Response
+where
+    T: ServerApp + Send + Sync + 'static,
+    T::Error: std::fmt::Debug
+{
+    let stats = state.handle.stats();
+
+    let result = tokio::try_join!(
+        async {
+            state.driver.router().servers().await
+                .map(|servers| servers.len())
+                .map_err(|err| err.to_string())
+        },
+        async {
+            state.app.count_inbox_channels().await
+                .map_err(|err| format!("{err:?}"))
+        }
+    );
+
+    match result {
+        Ok((known_peers, inbox_channels)) => {
+            let last_traversal = stats.last_traversal()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|time| time.as_secs());
+
+            axum::Json(json!({
+                "public_key": state.app.get_public_key().to_base64(),
+                "summary": state.app.get_params().to_string(),
+                "version": env!("CARGO_PKG_VERSION"),
+                "uptime_secs": stats.uptime().as_secs(),
+                "startup_token": state.handle.startup_token(),
+                "ready": state.handle.is_ready(),
+                "known_peers": known_peers,
+                "inbox_channels": inbox_channels,
+                "traversal_cycles": stats.traversal_cycles(),
+                "last_traversal": last_traversal
+            })).into_response()
+        }
+
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err).into_response()
+    }
+}
@@ -0,0 +1,64 @@
+use std::path::PathBuf;
+
+#[cfg(feature = "tls-self-signed")]
+use std::path::Path;
+
+#[cfg(feature = "tls")]
+pub(crate) use axum_server::tls_rustls::RustlsConfig;
+
+/// TLS config can't be loaded without the `tls`
+/// feature, so there's no value of this type.
+#[cfg(not(feature = "tls"))]
+pub(crate) type RustlsConfig = std::convert::Infallible;
+
+/// Paths to the PEM encoded TLS certificate and its private key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TlsConfig {
+    pub cert_pem: PathBuf,
+    pub key_pem: PathBuf
+}
+
+impl TlsConfig {
+    /// Load certificate and private key files.
+    #[cfg(feature = "tls")]
+    pub async fn rustls_config(&self) -> std::io::Result<RustlsConfig> {
+        RustlsConfig::from_pem_file(&self.cert_pem, &self.key_pem).await
+    }
+
+    /// Routers can't be served over HTTPS
+    /// without the `tls` feature.
+    #[cfg(not(feature = "tls"))]
+    pub(crate) async fn rustls_config(&self) -> std::io::Result<RustlsConfig> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "TLS is configured but the tls feature is disabled"))
+    }
+}
+
+/// Generate self-signed certificate for `localhost`
+/// and save it to the given folder.
+///
+/// Intended for development only. Clients must have
+/// `accept_invalid_certs` enabled in their HTTP config
+/// to connect to servers using such certificates.
+#[cfg(feature = "tls-self-signed")]
+pub fn generate_self_signed_tls_cert(path: &Path) -> std::io::Result<TlsConfig> {
+    let names = vec![
+        String::from("localhost"),
+        String::from("127.0.0.1")
+    ];
+
+    let certificate = rcgen::generate_simple_self_signed(names)
+        .map_err(std::io::Error::other)?;
+
+    std::fs::create_dir_all(path)?;
+
+    let config = TlsConfig {
+        cert_pem: path.join("cert.pem"),
+        key_pem: path.join("key.pem")
+    };
+
+    std::fs::write(&config.cert_pem, certificate.cert.pem())?;
+    std::fs::write(&config.key_pem, certificate.key_pair.serialize_pem())?;
+
+    Ok(config)
+}
@@ -0,0 +1,257 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use hyperborealib::http::HttpClient;
+use hyperborealib::drivers::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+/// Limits of the network traversal.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TraversalConfig {
+    /// Maximal amount of explored BFS levels.
+    ///
+    /// First level are the servers already known by the router.
+    pub max_depth: Option<usize>,
+
+    /// Maximal amount of servers requested during one traversal.
+    pub max_nodes: Option<usize>
+}
+
+impl TraversalConfig {
+    /// Check if the traversal is limited by this config.
+    #[inline]
+    pub fn is_limited(&self) -> bool {
+        self.max_depth.is_some() || self.max_nodes.is_some()
+    }
+}
+
+/// Params of the network traversal interval
+/// adapting to the peers discovery rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdaptiveTraversal {
+    /// Minimal delay between traversals.
+    pub min_interval: Duration,
+
+    /// Maximal delay between traversals.
+    pub max_interval: Duration,
+
+    /// Halve the delay if more servers
+    /// were discovered during the traversal.
+    pub scale_up_threshold: usize,
+
+    /// Double the delay if this or less servers
+    /// were discovered during the traversal.
+    pub scale_down_threshold: usize
+}
+
+impl Default for AdaptiveTraversal {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            min_interval: Duration::from_secs(60),
+            max_interval: Duration::from_secs(60 * 60),
+            scale_up_threshold: 10,
+            scale_down_threshold: 0
+        }
+    }
+}
+
+impl AdaptiveTraversal {
+    /// Get delay before the next traversal.
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// use hyperelm::server::AdaptiveTraversal;
+    ///
+    /// let adaptive = AdaptiveTraversal {
+    ///     min_interval: Duration::from_secs(60),
+    ///     max_interval: Duration::from_secs(600),
+    ///     scale_up_threshold: 10,
+    ///     scale_down_threshold: 0
+    /// };
+    ///
+    /// let interval = Duration::from_secs(400);
+    ///
+    /// assert_eq!(adaptive.next_interval(interval, 0), Duration::from_secs(600));
+    /// assert_eq!(adaptive.next_interval(interval, 5), interval);
+    /// assert_eq!(adaptive.next_interval(interval, 20), Duration::from_secs(200));
+    /// assert_eq!(adaptive.next_interval(Duration::from_secs(100), 20), Duration::from_secs(60));
+    /// ```
+    pub fn next_interval(&self, current: Duration, discovered: usize) -> Duration {
+        let next = if discovered > self.scale_up_threshold {
+            current / 2
+        } else if discovered <= self.scale_down_threshold {
+            current.saturating_mul(2)
+        } else {
+            current
+        };
+
+        self.clamp(next)
+    }
+
+    /// Limit given interval by the min and max intervals.
+    #[inline]
+    pub fn clamp(&self, interval: Duration) -> Duration {
+        interval.clamp(self.min_interval, self.max_interval.max(self.min_interval))
+    }
+}
+
+/// Traverse the network in breadth-first order
+/// within the limits of the given config.
+///
+/// Servers known by the router form the first level. Every next
+/// level consists of the new servers reported by the previous one.
+/// Discovered servers are indexed by the router if they pass
+/// the `filter`.
+///
+/// Returns amount of newly indexed servers.
+pub async fn traverse_bfs<T, R>(
+    client: &ClientMiddleware<T>,
+    router: &R,
+    config: TraversalConfig,
+    filter: impl Fn(&Server) -> bool
+) -> Result<usize, R::Error>
+where
+    T: HttpClient + Send + Sync,
+    R: Router + Send + Sync
+{
+    let mut level = router.servers().await?;
+
+    let mut seen = level.iter()
+        .map(|server| server.public_key.to_base64())
+        .collect::<HashSet<_>>();
+
+    let mut depth = 0;
+    let mut requested = 0;
+    let mut indexed = 0;
+
+    while !level.is_empty() {
+        if config.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+            break;
+        }
+
+        let mut next_level = Vec::new();
+
+        for server in level {
+            if config.max_nodes.is_some_and(|max_nodes| requested >= max_nodes) {
+                return Ok(indexed);
+            }
+
+            requested += 1;
+
+            match client.get_servers(&server.address).await {
+                Ok(servers) => {
+                    for server in servers {
+                        if filter(&server) && seen.insert(server.public_key.to_base64()) {
+                            router.index_server(server.clone()).await?;
+
+                            next_level.push(server);
+
+                            indexed += 1;
+                        }
+                    }
+                }
+
+                Err(_err) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("[server] Failed to request servers from {}: {_err}", server.address);
+                }
+            }
+        }
+
+        level = next_level;
+        depth += 1;
+    }
+
+    Ok(indexed)
+}
+
+/// Traverse the network using multiple concurrent workers.
+///
+/// Known servers are split into `workers` equal shards and every
+/// worker asks servers of its shard about the servers they know.
+/// Amount of concurrent HTTP requests of all the workers is limited
+/// by `max_connections`. Discovered servers are deduplicated and
+/// indexed by the router if they pass the `filter`.
+///
+/// Returns amount of newly indexed servers.
+pub async fn traverse_parallel<T, R>(
+    client: Arc<ClientMiddleware<T>>,
+    router: &R,
+    workers: usize,
+    max_connections: usize,
+    filter: impl Fn(&Server) -> bool
+) -> Result<usize, R::Error>
+where
+    T: HttpClient + Send + Sync + 'static,
+    R: Router + Send + Sync
+{
+    let known = router.servers().await?;
+
+    if known.is_empty() {
+        return Ok(0);
+    }
+
+    let semaphore = Arc::new(Semaphore::new(max_connections.max(1)));
+    let shard_size = known.len().div_ceil(workers.max(1));
+
+    // Spawn workers
+    let mut tasks = JoinSet::new();
+
+    for shard in known.chunks(shard_size) {
+        let client = client.clone();
+        let semaphore = semaphore.clone();
+        let shard = shard.to_vec();
+
+        tasks.spawn(async move {
+            let mut discovered = Vec::new();
+
+            for server in shard {
+                let Ok(_permit) = semaphore.acquire().await else {
+                    break;
+                };
+
+                match client.get_servers(&server.address).await {
+                    Ok(servers) => discovered.extend(servers),
+
+                    Err(_err) => {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!("[server] Failed to request servers from {}: {_err}", server.address);
+                    }
+                }
+            }
+
+            discovered
+        });
+    }
+
+    // Merge discovered servers
+    let mut seen = known.iter()
+        .map(|server| server.public_key.to_base64())
+        .collect::<HashSet<_>>();
+
+    let mut indexed = 0;
+
+    while let Some(result) = tasks.join_next().await {
+        let Ok(servers) = result else {
+            continue;
+        };
+
+        for server in servers {
+            if filter(&server) && seen.insert(server.public_key.to_base64()) {
+                router.index_server(server).await?;
+
+                indexed += 1;
+            }
+        }
+    }
+
+    Ok(indexed)
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::{json, Value as Json};
+
+use hyperelm::prelude::*;
+use hyperelm::server::TraversalReport;
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use common::*;
+
+/// Server announcing itself only to the allowed peer.
+struct FilteredServer {
+    server: TestServer,
+    allowed: PublicKey,
+
+    /// Servers the filter was consulted about.
+    checked: Arc<Mutex<Vec<Server>>>,
+
+    reports: Arc<Mutex<Vec<TraversalReport>>>
+}
+
+#[async_trait::async_trait]
+impl ServerApp for FilteredServer {
+    type Router = <TestServer as ServerApp>::Router;
+    type Traversal = <TestServer as ServerApp>::Traversal;
+    type MessagesInbox = <TestServer as ServerApp>::MessagesInbox;
+
+    type HttpClient = <TestServer as ServerApp>::HttpClient;
+    type HttpServer = <TestServer as ServerApp>::HttpServer;
+
+    type Error = <TestServer as ServerApp>::Error;
+
+    async fn get_router(&self) -> Result<Self::Router, Self::Error> {
+        self.server.get_router().await
+    }
+
+    async fn get_traversal(&self) -> Result<Self::Traversal, Self::Error> {
+        self.server.get_traversal().await
+    }
+
+    async fn get_messages_inbox(&self) -> Result<Self::MessagesInbox, Self::Error> {
+        self.server.get_messages_inbox().await
+    }
+
+    async fn get_http_client(&self) -> Result<Self::HttpClient, Self::Error> {
+        self.server.get_http_client().await
+    }
+
+    async fn get_http_server(&self) -> Result<Self::HttpServer, Self::Error> {
+        self.server.get_http_server().await
+    }
+
+    fn get_params(&self) -> &ServerAppParams {
+        ServerApp::get_params(&self.server)
+    }
+
+    fn announce_metadata(&self) -> Json {
+        json!({
+            "region": "eu",
+            "capacity": 100
+        })
+    }
+
+    async fn should_announce_to(&self, server: &Server) -> bool {
+        self.checked.lock().unwrap().push(server.clone());
+
+        server.public_key == self.allowed
+    }
+
+    async fn on_traversal_complete(&self, report: &TraversalReport) {
+        self.reports.lock().unwrap().push(report.clone());
+    }
+}
+
+#[tokio::test]
+async fn announce_is_sent_only_to_allowed_servers() {
+    let mut peers = Vec::new();
+    let mut handles = Vec::new();
+
+    for i in 0..3 {
+        let params = server_params(&format!("announce-peer-{i}"));
+
+        handles.push(start_server(params.clone()).await);
+        peers.push(params);
+    }
+
+    let allowed = peers[1].secret_key.public();
+
+    let mut params = server_params("announce");
+
+    params.announce = true;
+    params.traverse_delay = Duration::from_secs(60);
+
+    params.bootstrap = peers.iter()
+        .map(|peer| peer.local_address().to_string())
+        .collect();
+
+    let checked = Arc::new(Mutex::new(Vec::new()));
+    let reports = Arc::new(Mutex::new(Vec::new()));
+
+    let handle = hyperelm::server::spawn(FilteredServer {
+        server: TestServer(params),
+        allowed: allowed.clone(),
+        checked: checked.clone(),
+        reports: reports.clone()
+    });
+
+    handle.ready().await.unwrap();
+
+    // Wait for the first traversal cycle
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while reports.lock().unwrap().is_empty() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }).await.unwrap();
+
+    let report = reports.lock().unwrap()[0].clone();
+
+    // Filter is consulted about every known server
+    let mut checked = checked.lock().unwrap()
+        .iter()
+        .map(|server| server.public_key.to_base64())
+        .collect::<Vec<_>>();
+
+    checked.sort();
+    checked.dedup();
+
+    assert_eq!(checked.len(), 3);
+
+    // But only one of them is announced to
+    assert_eq!(report.announced.len(), 1);
+    assert_eq!(report.announced[0].server.public_key, allowed);
+    assert_eq!(report.announced[0].result, Ok(()));
+
+    let results = handle.stats().announce_results();
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[peers[1].local_address()].succeeded, 1);
+    assert_eq!(results[peers[1].local_address()].failed, 0);
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use hyperelm::prelude::*;
+use hyperelm::server::{CorruptionPolicy, ServerRunError, QUARANTINE_FOLDER};
+
+use common::*;
+
+/// Plant truncated message file into the backend inbox.
+fn plant_corrupted_file(backend: &Path) -> PathBuf {
+    let folder = backend.join("inbox/corrupted-channel");
+
+    std::fs::create_dir_all(&folder).unwrap();
+
+    let path = folder.join("message");
+
+    std::fs::write(&path, "{\"mess").unwrap();
+
+    path
+}
+
+#[tokio::test]
+async fn abort_policy_reports_corrupted_files() {
+    let mut params = server_params("backend-abort");
+
+    params.on_corruption = CorruptionPolicy::Abort;
+
+    let corrupted = plant_corrupted_file(&params.backend_folder);
+
+    let result = tokio::time::timeout(
+        Duration::from_secs(10),
+        hyperelm::server::run(TestServer(params))
+    ).await.unwrap();
+
+    let Err(ServerRunError::BackendCorrupted(report)) = result else {
+        panic!("server must abort with the corruption report");
+    };
+
+    assert_eq!(report.issues.len(), 1);
+    assert_eq!(report.issues[0].path, corrupted);
+
+    // Nothing is moved
+    assert!(corrupted.exists());
+}
+
+#[tokio::test]
+async fn quarantine_policy_moves_corrupted_files() {
+    let mut params = server_params("backend-quarantine");
+
+    params.on_corruption = CorruptionPolicy::Quarantine;
+
+    let backend = params.backend_folder.clone();
+    let corrupted = plant_corrupted_file(&backend);
+
+    // Server starts with fresh state
+    let _handle = start_server(params).await;
+
+    assert!(!corrupted.exists());
+
+    let quarantines = std::fs::read_dir(backend.join(QUARANTINE_FOLDER)).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+
+    assert_eq!(quarantines.len(), 1);
+
+    let quarantined = quarantines[0].join(corrupted.strip_prefix(&backend).unwrap());
+
+    assert_eq!(std::fs::read_to_string(quarantined).unwrap(), "{\"mess");
+}
@@ -0,0 +1,66 @@
+use hyperelm::client::{Channel, ChannelError, CHANNEL_MAX_LENGTH};
+
+#[test]
+fn invalid_channel_names_are_rejected() {
+    assert_eq!(Channel::new(""), Err(ChannelError::Empty));
+    assert_eq!(Channel::new("hyper elm"), Err(ChannelError::InvalidCharacter(' ')));
+    assert_eq!(Channel::new("hyper@elm"), Err(ChannelError::InvalidCharacter('@')));
+
+    assert_eq!(Channel::new("a".repeat(CHANNEL_MAX_LENGTH + 1)), Err(ChannelError::TooLong {
+        length: CHANNEL_MAX_LENGTH + 1,
+        max: CHANNEL_MAX_LENGTH
+    }));
+
+    assert!(Channel::new("a".repeat(CHANNEL_MAX_LENGTH)).is_ok());
+    assert!(Channel::new("chat/room-1:v2_beta.3").is_ok());
+}
+
+#[test]
+fn derived_channels_keep_their_base() {
+    let channel = Channel::new("hyperelm").unwrap();
+
+    for derived in [channel.reply_to(123), channel.reply_to_session(123, 42), channel.ack(123), channel.replies(), channel.ping(123)] {
+        assert!(derived.is_derived());
+        assert_eq!(derived.base(), channel.as_str());
+
+        // Channels derived from derived ones use the same base
+        assert_eq!(derived.reply_to(7), channel.reply_to(7));
+    }
+
+    assert!(!channel.is_derived());
+    assert_eq!(channel.suffix(), None);
+}
+
+#[test]
+fn reply_channels_carry_their_ids() {
+    let channel = Channel::new("hyperelm").unwrap();
+
+    assert_eq!(channel.reply_to(123).reply_id(), Some(123));
+    assert_eq!(channel.reply_to(123).ack_id(), None);
+
+    assert_eq!(channel.ack(123).ack_id(), Some(123));
+    assert_eq!(channel.ack(123).reply_id(), None);
+
+    assert_eq!(channel.reply_for(123, None), channel.reply_to(123));
+    assert_eq!(channel.reply_for(123, Some(42)), channel.reply_to_session(123, 42));
+    assert_eq!(channel.reply_to_session(123, 42).to_string(), "hyperelm@000000000000002a-123");
+
+    assert!(channel.ping(123).is_ping());
+    assert!(!channel.reply_to(123).is_ping());
+}
+
+#[test]
+fn channel_names_round_trip_through_strings() {
+    let channel = Channel::new("hyperelm").unwrap();
+
+    assert_eq!(String::from(channel.clone()).parse::<Channel>(), Ok(channel.clone()));
+    assert_eq!(Channel::try_from(channel.to_string()), Ok(channel.clone()));
+
+    let serialized = serde_json::to_string(&channel).unwrap();
+
+    assert_eq!(serialized, "\"hyperelm\"");
+    assert_eq!(serde_json::from_str::<Channel>(&serialized).unwrap(), channel);
+
+    // Names are validated when deserialized
+    assert!(serde_json::from_str::<Channel>("\"hyper elm\"").is_err());
+}
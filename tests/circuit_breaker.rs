@@ -0,0 +1,108 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use hyperelm::prelude::*;
+use hyperelm::client::{CircuitBreakerConfig, CircuitState};
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+const FAILURE_THRESHOLD: u32 = 3;
+const COOLDOWN: Duration = Duration::from_millis(500);
+
+#[tokio::test]
+async fn circuit_opens_and_closes_after_probe() {
+    let home = server_params("circuit-home");
+
+    let _home_handle = start_server(home.clone()).await;
+
+    // Server of the receiver isn't started yet
+    let peer_server = server_params("circuit-peer");
+
+    let receiver = TestClient::new(&peer_server);
+    let endpoint = receiver.endpoint();
+
+    let sender = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(home.secret_key.public(), home.local_address())
+        .circuit_breaker(CircuitBreakerConfig {
+            failure_threshold: FAILURE_THRESHOLD,
+            window: Duration::from_secs(30),
+            cooldown: COOLDOWN
+        }));
+
+    sender.get_connected_middleware().await.unwrap();
+
+    // Trip the breaker
+    for _ in 0..FAILURE_THRESHOLD {
+        let result = sender.send(endpoint.clone(), TestMessage::Text(String::from("lost"))).await
+            .map_err(ClientAppError::into_inner);
+
+        assert!(result.is_err());
+        assert!(!matches!(result, Err(ClientAppError::CircuitOpen { .. })));
+    }
+
+    assert_eq!(sender.circuit_state(&endpoint), CircuitState::Open);
+
+    // Sends and requests fail fast
+    let started_at = Instant::now();
+
+    let result = sender.send(endpoint.clone(), TestMessage::Text(String::from("fast"))).await
+        .map_err(ClientAppError::into_inner);
+
+    assert!(matches!(result, Err(ClientAppError::CircuitOpen { .. })));
+
+    let result = sender.request(endpoint.clone(), TestRequest::Echo(String::from("fast"))).await
+        .map_err(ClientAppError::into_inner);
+
+    assert!(matches!(result, Err(ClientAppError::CircuitOpen { .. })));
+    assert!(started_at.elapsed() < Duration::from_millis(100));
+
+    // Bring the server back
+    let _peer_handle = start_server(peer_server).await;
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    tokio::time::sleep(COOLDOWN).await;
+
+    assert_eq!(sender.circuit_state(&endpoint), CircuitState::HalfOpen);
+
+    // Successful probe closes the circuit
+    sender.send(endpoint.clone(), TestMessage::Text(String::from("probe"))).await
+        .unwrap();
+
+    assert_eq!(sender.circuit_state(&endpoint), CircuitState::Closed);
+
+    receiver.update_batch().await.unwrap();
+
+    assert_eq!(*receiver.state.received.lock().unwrap(), ["probe"]);
+}
+
+#[tokio::test]
+async fn reset_closes_open_circuit() {
+    let home = server_params("circuit-reset");
+
+    let _home_handle = start_server(home.clone()).await;
+
+    let sender = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(home.secret_key.public(), home.local_address())
+        .circuit_breaker(CircuitBreakerConfig {
+            failure_threshold: 1,
+            window: Duration::from_secs(30),
+            cooldown: Duration::from_secs(60)
+        }));
+
+    sender.get_connected_middleware().await.unwrap();
+
+    let endpoint = ClientEndpoint::new(free_address(), SecretKey::random().public());
+
+    assert!(sender.send(endpoint.clone(), TestMessage::Text(String::from("lost"))).await.is_err());
+    assert_eq!(sender.circuit_state(&endpoint), CircuitState::Open);
+
+    sender.reset_circuit(&endpoint);
+
+    assert_eq!(sender.circuit_state(&endpoint), CircuitState::Closed);
+}
@@ -0,0 +1,477 @@
+//! Helpers shared by the integration tests.
+//!
+//! Servers are started in-process on free local addresses with
+//! their backends stored in temp folders, and clients talk to them
+//! through the `CountingHttpClient`, which records every request.
+
+#![allow(dead_code)]
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use hyperelm::prelude::*;
+use hyperelm::scaffold;
+use hyperelm::client::{ClientAppParamsBuilder, FileManifest, Respond, ResponseToken};
+use hyperelm::http::StatusHttpClient;
+use hyperelm::server::ServerHandle;
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::drivers::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+use hyperborealib::http::HttpClient;
+
+/// Reserve free local address.
+pub fn free_address() -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+
+    listener.local_addr().unwrap().to_string()
+}
+
+/// Create empty temp folder for the test.
+pub fn temp_folder(name: &str) -> PathBuf {
+    let folder = std::env::temp_dir()
+        .join(format!("hyperelm-test-{name}-{}", std::process::id()));
+
+    let _ = std::fs::remove_dir_all(&folder);
+
+    std::fs::create_dir_all(&folder).unwrap();
+
+    folder
+}
+
+pub struct TestServer(pub ServerAppParams);
+
+impl BasicServerApp for TestServer {
+    fn get_params(&self) -> &ServerAppParams {
+        &self.0
+    }
+}
+
+/// Generate default params of the server
+/// listening on a free local address.
+pub fn server_params(name: &str) -> ServerAppParams {
+    let folder = temp_folder(name);
+
+    scaffold::write_default_params(&folder).unwrap();
+
+    let mut params = scaffold::load_server_params(folder.join("server.json")).unwrap();
+
+    let address = free_address();
+
+    params.local_addresses = vec![address.clone()];
+    params.remote_addresses = vec![address];
+
+    params
+}
+
+/// Serve the router on a free local address.
+pub async fn serve(router: axum::Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.unwrap();
+    });
+
+    address
+}
+
+/// Start the server and wait until it's reachable.
+pub async fn start_server(params: ServerAppParams) -> ServerHandle {
+    let handle = hyperelm::server::spawn(TestServer(params));
+
+    handle.ready().await.unwrap();
+
+    handle
+}
+
+/// HTTP client counting the sent requests by their paths.
+#[derive(Debug, Clone, Default)]
+pub struct CountingHttpClient {
+    client: StatusHttpClient,
+    requests: Arc<Mutex<HashMap<String, usize>>>,
+
+    /// Current and maximal amount of concurrent requests by their paths.
+    concurrent: Arc<Mutex<HashMap<String, (usize, usize)>>>,
+
+    /// Channels mentioned in the requests by their paths.
+    channels: Arc<Mutex<HashMap<String, Vec<String>>>>,
+
+    /// Addresses which can't be reached by the client.
+    blocked: Arc<Mutex<HashSet<String>>>
+}
+
+impl CountingHttpClient {
+    pub fn new(client: StatusHttpClient) -> Self {
+        Self {
+            client,
+            requests: Arc::default(),
+            concurrent: Arc::default(),
+            channels: Arc::default(),
+            blocked: Arc::default()
+        }
+    }
+
+    /// Fail all the requests to the given address
+    /// as if it was unreachable.
+    pub fn block(&self, address: impl ToString) {
+        self.blocked.lock().unwrap().insert(address.to_string());
+    }
+
+    /// Check if the url points to a blocked address.
+    fn is_blocked(&self, url: &str) -> bool {
+        let Ok(url) = reqwest::Url::parse(url) else {
+            return false;
+        };
+
+        let address = format!(
+            "{}:{}",
+            url.host_str().unwrap_or_default(),
+            url.port_or_known_default().unwrap_or_default()
+        );
+
+        self.blocked.lock().unwrap().contains(&address)
+    }
+
+    /// Record started request, returning its path.
+    fn start(&self, url: &str) -> String {
+        let path = reqwest::Url::parse(url)
+            .map(|url| url.path().to_string())
+            .unwrap_or_default();
+
+        *self.requests.lock().unwrap().entry(path.clone()).or_default() += 1;
+
+        let mut concurrent = self.concurrent.lock().unwrap();
+        let (current, max) = concurrent.entry(path.clone()).or_default();
+
+        *current += 1;
+        *max = (*max).max(*current);
+
+        path
+    }
+
+    fn finish(&self, path: &str) {
+        if let Some((current, _)) = self.concurrent.lock().unwrap().get_mut(path) {
+            *current -= 1;
+        }
+    }
+
+    /// Amount of requests sent to the path.
+    pub fn count(&self, path: &str) -> usize {
+        self.requests.lock().unwrap()
+            .get(path)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Amount of requests sent to all the paths.
+    pub fn total(&self) -> usize {
+        self.requests.lock().unwrap()
+            .values()
+            .sum()
+    }
+
+    /// Channels mentioned in the requests to the path.
+    pub fn channels(&self, path: &str) -> Vec<String> {
+        self.channels.lock().unwrap()
+            .get(path)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Maximal amount of concurrent requests to the path.
+    pub fn max_concurrent(&self, path: &str) -> usize {
+        self.concurrent.lock().unwrap()
+            .get(path)
+            .map(|(_, max)| *max)
+            .unwrap_or_default()
+    }
+
+    pub fn reset(&self) {
+        self.requests.lock().unwrap().clear();
+        self.concurrent.lock().unwrap().clear();
+        self.channels.lock().unwrap().clear();
+    }
+}
+
+/// Collect values of the `channel` fields of the request.
+fn find_channels(request: &serde_json::Value, channels: &mut Vec<String>) {
+    match request {
+        serde_json::Value::Object(fields) => {
+            for (key, value) in fields {
+                match value.as_str() {
+                    Some(channel) if key == "channel" => channels.push(channel.to_string()),
+                    _ => find_channels(value, channels)
+                }
+            }
+        }
+
+        serde_json::Value::Array(values) => {
+            for value in values {
+                find_channels(value, channels);
+            }
+        }
+
+        _ => ()
+    }
+}
+
+#[async_trait::async_trait]
+impl HttpClient for CountingHttpClient {
+    async fn get_request<T: AsJson>(&self, url: &str) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+        if self.is_blocked(url) {
+            return Err(format!("{url} is unreachable").into());
+        }
+
+        let path = self.start(url);
+        let result = self.client.get_request(url).await;
+
+        self.finish(&path);
+
+        result
+    }
+
+    async fn post_request<T: AsJson + Send, F: AsJson>(&self, url: &str, request: T) -> Result<F, Box<dyn std::error::Error + Send + Sync>> {
+        if self.is_blocked(url) {
+            return Err(format!("{url} is unreachable").into());
+        }
+
+        let path = self.start(url);
+
+        if let Ok(request) = request.to_json() {
+            let mut channels = self.channels.lock().unwrap();
+
+            find_channels(&request, channels.entry(path.clone()).or_default());
+        }
+
+        let result = self.client.post_request(url, request).await;
+
+        self.finish(&path);
+
+        result
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum TestRequest {
+    Echo(String),
+    Fail,
+
+    /// Echoed from another task after the given time.
+    Deferred {
+        text: String,
+        millis: u64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum TestResponse {
+    Echo(String)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum TestMessage {
+    Text(String),
+
+    /// Handled for the given time.
+    Sleep {
+        id: u32,
+        millis: u64
+    }
+}
+
+hyperborealib::impl_as_json!(TestRequest TestResponse TestMessage);
+
+/// Messages received by the test client.
+#[derive(Debug, Default)]
+pub struct TestState {
+    pub received: Mutex<Vec<String>>,
+
+    /// Previous and current keys of the moved peers.
+    pub moved: Mutex<Vec<(PublicKey, PublicKey)>>,
+
+    /// Pinned and resolved server addresses of the peers.
+    pub endpoint_changes: Mutex<Vec<(String, String)>>,
+
+    /// Names of the called connection lifecycle hooks.
+    pub connection_events: Mutex<Vec<&'static str>>,
+
+    /// Envelopes dropped because they were expired.
+    pub expired: Mutex<Vec<serde_json::Value>>
+}
+
+pub struct TestClient {
+    pub params: ClientAppParams,
+    pub runtime: ClientRuntime,
+    pub http: CountingHttpClient,
+    pub middleware: ClientMiddleware<CountingHttpClient>,
+    pub state: Arc<TestState>,
+
+    /// Public key of the `middleware` identity.
+    middleware_public: PublicKey,
+
+    /// Middlewares of the rotated identities.
+    rotated: Mutex<HashMap<String, &'static ClientMiddleware<CountingHttpClient>>>
+}
+
+impl TestClient {
+    /// Create client of the server with given params.
+    pub fn new(server: &ServerAppParams) -> Self {
+        Self::with_params(ClientAppParams::builder()
+            .client(SecretKey::random())
+            .server(server.secret_key.public(), server.local_address()))
+    }
+
+    pub fn with_params(params: ClientAppParamsBuilder) -> Self {
+        let params = params.build().unwrap();
+
+        let http = CountingHttpClient::new(params.build_http_client().unwrap());
+
+        let middleware = ClientMiddleware::new(
+            http.clone(),
+            ClientDriver::new(ClientInfo::thin(), params.identity.secret())
+        );
+
+        let middleware_public = params.identity.secret().public();
+
+        Self {
+            runtime: ClientRuntime::new(&params).unwrap(),
+            params,
+            http,
+            middleware,
+            state: Arc::default(),
+            middleware_public,
+            rotated: Mutex::default()
+        }
+    }
+
+    /// Endpoint of the current client.
+    pub fn endpoint(&self) -> ClientEndpoint {
+        ClientEndpoint::new(&self.params.server_address, self.params.identity.public())
+    }
+}
+
+#[async_trait::async_trait]
+impl ClientApp for TestClient {
+    build_client!(
+        input: TestRequest => TestResponse, TestMessage;
+        output: TestRequest => TestResponse, TestMessage;
+
+        client: CountingHttpClient;
+        state: TestState;
+        error: String;
+
+        requests: {
+            TestRequest::Echo(text) => |_, _| async move {
+                Ok(TestResponse::Echo(text))
+            }
+
+            TestRequest::Fail => |_, _| async {
+                Err::<TestResponse, _>(ClientAppError::Custom(String::from("requested failure")))
+            }
+
+            TestRequest::Deferred { text, millis } => |_, _| async move {
+                let token = ResponseToken::new();
+
+                tokio::spawn({
+                    let token = token.clone();
+
+                    async move {
+                        tokio::time::sleep(std::time::Duration::from_millis(millis)).await;
+
+                        token.respond(TestResponse::Echo(text)).await
+                    }
+                });
+
+                Ok(Respond::Later(token))
+            }
+        };
+
+        messages: {
+            TestMessage::Text(text) => |state: Arc<TestState>, _| async move {
+                state.received.lock().unwrap().push(text);
+
+                Ok(())
+            }
+
+            TestMessage::Sleep { millis, .. } => |_, _| async move {
+                tokio::time::sleep(std::time::Duration::from_millis(millis)).await;
+
+                Ok(())
+            }
+        };
+    );
+
+    fn get_params(&self) -> &ClientAppParams {
+        &self.params
+    }
+
+    fn get_runtime(&self) -> &ClientRuntime {
+        &self.runtime
+    }
+
+    fn get_middleware(&self) -> &ClientMiddleware<Self::HttpClient> {
+        let secret = self.params.identity.secret();
+
+        if secret.public() == self.middleware_public {
+            return &self.middleware;
+        }
+
+        // Middleware of the rotated identity is leaked
+        // because it's borrowed for the client's lifetime
+        let mut rotated = self.rotated.lock().unwrap();
+
+        rotated.entry(secret.public().to_base64())
+            .or_insert_with(|| Box::leak(Box::new(ClientMiddleware::new(
+                self.http.clone(),
+                ClientDriver::new(ClientInfo::thin(), secret)
+            ))))
+    }
+
+    fn get_state(&self) -> Arc<Self::State> {
+        self.state.clone()
+    }
+
+    async fn on_peer_moved(&self, previous: PublicKey, current: PublicKey, _info: &MessageInfo) -> Result<(), ClientAppError<Self::Error>> {
+        self.state.moved.lock().unwrap().push((previous, current));
+
+        Ok(())
+    }
+
+    async fn on_endpoint_changed(&self, _peer: &PublicKey, previous: &str, current: &str) -> Result<(), ClientAppError<Self::Error>> {
+        self.state.endpoint_changes.lock().unwrap().push((previous.to_string(), current.to_string()));
+
+        Ok(())
+    }
+
+    // Echo requests of the texts starting with "cached" are cacheable
+    fn cache_policy(&self, request: &TestRequest) -> Option<std::time::Duration> {
+        match request {
+            TestRequest::Echo(text) if text.starts_with("cached") => Some(std::time::Duration::from_secs(60)),
+
+            _ => None
+        }
+    }
+
+    async fn on_connected(&self, _server: &ServerInfo) {
+        self.state.connection_events.lock().unwrap().push("connected");
+    }
+
+    async fn on_disconnected(&self, _err: &MiddlewareError) {
+        self.state.connection_events.lock().unwrap().push("disconnected");
+    }
+
+    async fn on_reconnected(&self) {
+        self.state.connection_events.lock().unwrap().push("reconnected");
+    }
+
+    async fn on_expired(&self, envelope: &serde_json::Value, _info: &MessageInfo) {
+        self.state.expired.lock().unwrap().push(envelope.clone());
+    }
+
+    // Files are received only if the download folder is set
+    fn accept_file_offer(&self, _manifest: &FileManifest, _info: &MessageInfo) -> bool {
+        true
+    }
+}
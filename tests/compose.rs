@@ -0,0 +1,217 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use hyperelm::prelude::*;
+use hyperelm::client::{ComposedClientApp, ComposeError, SubApp, SubAppError};
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::drivers::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use common::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum ChatRequest {
+    History
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct ChatHistory(Vec<String>);
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct ChatMessage(String);
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum CounterRequest {
+    Add(u64)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct CounterValue(u64);
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum CounterMessage {
+    Reset
+}
+
+hyperborealib::impl_as_json!(ChatRequest ChatHistory ChatMessage CounterRequest CounterValue CounterMessage);
+
+#[derive(Default)]
+struct Chat(Arc<Mutex<Vec<String>>>);
+
+#[async_trait::async_trait]
+impl SubApp for Chat {
+    const NAME: &'static str = "chat";
+
+    type InputRequest = ChatRequest;
+    type InputResponse = ChatHistory;
+    type InputMessage = ChatMessage;
+
+    type OutputRequest = ChatRequest;
+    type OutputResponse = ChatHistory;
+    type OutputMessage = ChatMessage;
+
+    type State = Mutex<Vec<String>>;
+
+    fn get_state(&self) -> Arc<Self::State> {
+        self.0.clone()
+    }
+
+    async fn handle_request(&self, request: ChatRequest, _info: MessageInfo) -> Result<ChatHistory, SubAppError> {
+        match request {
+            ChatRequest::History => Ok(ChatHistory(self.0.lock().unwrap().clone()))
+        }
+    }
+
+    async fn handle_message(&self, message: ChatMessage, _info: MessageInfo) -> Result<(), SubAppError> {
+        self.0.lock().unwrap().push(message.0);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Counter(Arc<AtomicU64>);
+
+#[async_trait::async_trait]
+impl SubApp for Counter {
+    const NAME: &'static str = "counter";
+
+    type InputRequest = CounterRequest;
+    type InputResponse = CounterValue;
+    type InputMessage = CounterMessage;
+
+    type OutputRequest = CounterRequest;
+    type OutputResponse = CounterValue;
+    type OutputMessage = CounterMessage;
+
+    type State = AtomicU64;
+
+    fn get_state(&self) -> Arc<Self::State> {
+        self.0.clone()
+    }
+
+    async fn handle_request(&self, request: CounterRequest, _info: MessageInfo) -> Result<CounterValue, SubAppError> {
+        match request {
+            CounterRequest::Add(value) => Ok(CounterValue(self.0.fetch_add(value, Ordering::SeqCst) + value))
+        }
+    }
+
+    async fn handle_message(&self, message: CounterMessage, _info: MessageInfo) -> Result<(), SubAppError> {
+        match message {
+            CounterMessage::Reset => self.0.store(0, Ordering::SeqCst)
+        }
+
+        Ok(())
+    }
+}
+
+fn composed(server: &ServerAppParams) -> ComposedClientApp<CountingHttpClient> {
+    let secret = SecretKey::random();
+
+    let params = ClientAppParams::builder()
+        .client(secret.clone())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(50))
+        .build()
+        .unwrap();
+
+    let middleware = ClientMiddleware::new(
+        CountingHttpClient::default(),
+        ClientDriver::new(ClientInfo::thin(), secret)
+    );
+
+    ComposedClientApp::new(params, middleware).unwrap()
+}
+
+fn endpoint(client: &ComposedClientApp<CountingHttpClient>) -> ClientEndpoint {
+    let params = client.get_params();
+
+    ClientEndpoint::new(&params.server_address, params.identity.public())
+}
+
+#[tokio::test]
+async fn envelopes_are_routed_to_sub_apps() {
+    let server = server_params("compose");
+
+    let _handle = start_server(server.clone()).await;
+
+    let receiver = composed(&server)
+        .mount(Chat::default())
+        .mount(Counter::default());
+
+    let receiver_endpoint = endpoint(&receiver);
+
+    let sender = composed(&server)
+        .mount(Chat::default())
+        .mount(Counter::default());
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    // Messages
+    sender.app::<Chat>().unwrap()
+        .send(receiver_endpoint.clone(), ChatMessage(String::from("hello"))).await
+        .unwrap();
+
+    sender.app::<Counter>().unwrap()
+        .send(receiver_endpoint.clone(), CounterMessage::Reset).await
+        .unwrap();
+
+    receiver.update_batch().await.unwrap();
+
+    let chat = receiver.app::<Chat>().unwrap();
+    let counter = receiver.app::<Counter>().unwrap();
+
+    assert_eq!(*chat.state().lock().unwrap(), ["hello"]);
+    assert_eq!(chat.metrics().messages_handled(), 1);
+    assert_eq!(counter.metrics().messages_handled(), 1);
+
+    // Requests
+    let receiver = hyperelm::client::run(receiver).await.unwrap();
+
+    let history = sender.app::<Chat>().unwrap()
+        .request(receiver_endpoint.clone(), ChatRequest::History).await
+        .unwrap();
+
+    assert_eq!(history, ChatHistory(vec![String::from("hello")]));
+
+    let value = sender.app::<Counter>().unwrap()
+        .request(receiver_endpoint, CounterRequest::Add(5)).await
+        .unwrap();
+
+    assert_eq!(value, CounterValue(5));
+
+    let counter = receiver.app::<Counter>().unwrap();
+
+    assert_eq!(counter.state().load(Ordering::SeqCst), 5);
+    assert_eq!(counter.metrics().requests_handled(), 1);
+    assert_eq!(receiver.metrics("chat").unwrap().requests_handled(), 1);
+}
+
+#[tokio::test]
+async fn unknown_sub_app_is_rejected() {
+    let server = server_params("compose-unknown");
+
+    let _handle = start_server(server.clone()).await;
+
+    let receiver = composed(&server)
+        .mount(Chat::default());
+
+    let sender = composed(&server)
+        .mount(Chat::default())
+        .mount(Counter::default());
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    sender.app::<Counter>().unwrap()
+        .send(endpoint(&receiver), CounterMessage::Reset).await
+        .unwrap();
+
+    let result = receiver.update().await;
+
+    assert!(matches!(result, Err(ClientAppError::Custom(ComposeError::UnknownApp(app))) if app == "counter"));
+    assert!(receiver.app::<Counter>().is_none());
+}
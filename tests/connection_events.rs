@@ -0,0 +1,56 @@
+mod common;
+
+use std::time::Duration;
+
+use hyperelm::prelude::*;
+use hyperelm::client::ReconnectPolicy;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+async fn wait_events(client: &TestClient, count: usize) {
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while client.state.connection_events.lock().unwrap().len() < count {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }).await.unwrap();
+}
+
+#[tokio::test]
+async fn lifecycle_hooks_fire_once_per_state_change() {
+    let server = server_params("connection-events");
+
+    let handle = start_server(server.clone()).await;
+
+    let client = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(10))
+        .reconnect_policy(ReconnectPolicy {
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(50),
+            jitter_factor: 0.0,
+            max_attempts: None
+        }));
+
+    let client = hyperelm::client::run(client).await.unwrap();
+
+    wait_events(&client, 1).await;
+
+    // Every failed update during the outage
+    // must not report it again
+    handle.shutdown();
+
+    wait_events(&client, 2).await;
+
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let _restarted = start_server(server).await;
+
+    wait_events(&client, 3).await;
+
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    assert_eq!(*client.state.connection_events.lock().unwrap(), ["connected", "disconnected", "reconnected"]);
+}
@@ -0,0 +1,146 @@
+mod common;
+
+use std::sync::Arc;
+
+use serde_json::{json, Value as Json};
+
+use hyperelm::prelude::*;
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use common::*;
+
+/// Client accepting messages encrypted
+/// to its secondary key as well.
+struct SecondaryKeyClient {
+    inner: TestClient,
+    secondary: SecretKey
+}
+
+#[async_trait::async_trait]
+impl ClientApp for SecondaryKeyClient {
+    build_client!(
+        input: TestRequest => TestResponse, TestMessage;
+        output: TestRequest => TestResponse, TestMessage;
+
+        client: CountingHttpClient;
+        state: TestState;
+        error: String;
+
+        requests: {
+            TestRequest::Echo(text) => |_, _| async move {
+                Ok(TestResponse::Echo(text))
+            }
+        };
+
+        messages: {
+            TestMessage::Text(text) => |state: Arc<TestState>, _| async move {
+                state.received.lock().unwrap().push(text);
+
+                Ok(())
+            }
+        };
+    );
+
+    fn get_params(&self) -> &ClientAppParams {
+        &self.inner.params
+    }
+
+    fn get_runtime(&self) -> &ClientRuntime {
+        &self.inner.runtime
+    }
+
+    fn get_middleware(&self) -> &ClientMiddleware<Self::HttpClient> {
+        &self.inner.middleware
+    }
+
+    fn get_state(&self) -> Arc<Self::State> {
+        self.inner.state.clone()
+    }
+
+    async fn decode_incoming(&self, info: &MessageInfo) -> Result<Json, ClientAppError<Self::Error>> {
+        let sender = &info.sender.client.public_key;
+
+        let content = match info.message.read(&self.inner.params.identity.secret(), sender) {
+            Ok(content) => content,
+            Err(_) => info.message.read(&self.secondary, sender)?
+        };
+
+        Ok(serde_json::from_slice(&content)?)
+    }
+}
+
+/// Send text message encrypted to the given key
+/// to the inbox of the receiver.
+async fn send_encrypted_to(sender: &TestClient, receiver: &ClientEndpoint, key: &PublicKey, text: &str) {
+    let middleware = sender.get_connected_middleware().await.unwrap();
+
+    let envelope = serde_json::to_vec(&json!({
+        "message": TestMessage::Text(text.to_string()).to_json().unwrap()
+    })).unwrap();
+
+    let message = Message::create(
+        &sender.params.identity.secret(),
+        key,
+        envelope,
+        sender.params.encoding,
+        sender.params.compression_level
+    ).unwrap();
+
+    middleware.send(
+        &receiver.server_address,
+        receiver.client_public.clone(),
+        &sender.params.channel.to_string(),
+        message
+    ).await.unwrap();
+}
+
+#[tokio::test]
+async fn overridden_decoding_accepts_secondary_key() {
+    let server = server_params("decode-incoming");
+
+    let _handle = start_server(server.clone()).await;
+
+    let receiver = SecondaryKeyClient {
+        inner: TestClient::new(&server),
+        secondary: SecretKey::random()
+    };
+
+    let endpoint = receiver.inner.endpoint();
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    let sender = TestClient::new(&server);
+
+    // Messages encrypted to the primary key use the default path
+    sender.send(endpoint.clone(), TestMessage::Text(String::from("primary"))).await
+        .unwrap();
+
+    send_encrypted_to(&sender, &endpoint, &receiver.secondary.public(), "secondary").await;
+
+    receiver.update_batch().await.unwrap();
+
+    assert_eq!(*receiver.inner.state.received.lock().unwrap(), ["primary", "secondary"]);
+    assert_eq!(receiver.undecodable_message_count(), 0);
+}
+
+#[tokio::test]
+async fn default_decoding_rejects_secondary_key() {
+    let server = server_params("decode-incoming-default");
+
+    let _handle = start_server(server.clone()).await;
+
+    let receiver = TestClient::new(&server);
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    let sender = TestClient::new(&server);
+
+    send_encrypted_to(&sender, &receiver.endpoint(), &SecretKey::random().public(), "secondary").await;
+
+    receiver.update_batch().await.unwrap();
+
+    assert!(receiver.state.received.lock().unwrap().is_empty());
+    assert_eq!(receiver.undecodable_message_count(), 1);
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use serde_json::json;
+
+use hyperelm::prelude::*;
+use hyperelm::client::ClientRuntimeError;
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use common::*;
+
+/// Deliver the same envelope to the receiver once more.
+async fn deliver(sender: &TestClient, receiver: &ClientEndpoint, nonce: u64, text: &str) {
+    let middleware = sender.get_connected_middleware().await.unwrap();
+
+    let envelope = json!({
+        "priority": 0,
+        "nonce": nonce,
+        "message": TestMessage::Text(text.to_string()).to_json().unwrap()
+    });
+
+    sender.send_envelope(&middleware, receiver, &sender.params.channel, &envelope).await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn duplicated_delivery_is_handled_once() {
+    let server = server_params("dedupe-duplicate");
+
+    let _handle = start_server(server.clone()).await;
+
+    let sender = TestClient::new(&server);
+    let receiver = TestClient::new(&server);
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    deliver(&sender, &receiver.endpoint(), 42, "duplicated").await;
+    deliver(&sender, &receiver.endpoint(), 42, "duplicated").await;
+
+    receiver.update_batch().await.unwrap();
+
+    assert_eq!(*receiver.state.received.lock().unwrap(), vec![String::from("duplicated")]);
+
+    // Equal payloads sent separately are different messages
+    let first = sender.send(receiver.endpoint(), TestMessage::Text(String::from("repeated"))).await.unwrap();
+    let second = sender.send(receiver.endpoint(), TestMessage::Text(String::from("repeated"))).await.unwrap();
+
+    assert_ne!(first, second);
+
+    receiver.update_batch().await.unwrap();
+
+    assert_eq!(receiver.state.received.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn dedupe_survives_restart() {
+    let server = server_params("dedupe-restart");
+
+    let _handle = start_server(server.clone()).await;
+
+    let path = temp_folder("dedupe-restart-client").join("seen.jsonl");
+    let identity = SecretKey::random();
+
+    let receiver_params = || ClientAppParams::builder()
+        .client(identity.clone())
+        .server(server.secret_key.public(), server.local_address())
+        .seen_messages_path(&path);
+
+    let sender = TestClient::new(&server);
+    let receiver = TestClient::with_params(receiver_params());
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    deliver(&sender, &receiver.endpoint(), 42, "persisted").await;
+
+    receiver.update_batch().await.unwrap();
+
+    assert_eq!(*receiver.state.received.lock().unwrap(), vec![String::from("persisted")]);
+
+    // Restart the receiver
+    drop(receiver);
+
+    let receiver = TestClient::with_params(receiver_params());
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    deliver(&sender, &receiver.endpoint(), 42, "persisted").await;
+
+    receiver.update_batch().await.unwrap();
+
+    assert!(receiver.state.received.lock().unwrap().is_empty());
+}
+
+#[test]
+fn unreadable_dedupe_file_is_reported() {
+    // Folder can't be opened as a file
+    let path = temp_folder("dedupe-unreadable");
+
+    let params = ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(SecretKey::random().public(), "127.0.0.1:8001")
+        .seen_messages_path(&path)
+        .build()
+        .unwrap();
+
+    let result = ClientRuntime::new(&params);
+
+    assert!(matches!(result, Err(ClientRuntimeError::SeenMessages { path: failed, .. }) if failed == path));
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use hyperelm::prelude::*;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+#[tokio::test]
+async fn deferred_response_reaches_the_requester() {
+    let server = server_params("deferred-response");
+
+    let _handle = start_server(server.clone()).await;
+
+    let responder = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(50)));
+
+    let responder_endpoint = responder.endpoint();
+
+    let _responder = hyperelm::client::run(responder).await.unwrap();
+
+    let requester = TestClient::new(&server);
+
+    let started_at = Instant::now();
+
+    // Response is sent from the spawned task
+    // after the request handler has returned
+    let request = requester.request(responder_endpoint, TestRequest::Deferred {
+        text: String::from("later"),
+        millis: 3000
+    });
+
+    let response = tokio::time::timeout(Duration::from_secs(15), request).await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(response, TestResponse::Echo(String::from("later")));
+    assert!(started_at.elapsed() >= Duration::from_secs(3));
+}
@@ -0,0 +1,36 @@
+#![cfg(feature = "dual-stack")]
+
+mod common;
+
+use common::*;
+
+#[tokio::test]
+async fn ipv4_and_ipv6_share_the_port() {
+    // IPv6 is not available on every machine
+    if std::net::TcpListener::bind("[::1]:0").is_err() {
+        return;
+    }
+
+    let mut server = server_params("dual-stack");
+
+    let port = free_address()
+        .rsplit_once(':')
+        .map(|(_, port)| port.to_string())
+        .unwrap();
+
+    server.local_addresses = vec![
+        format!("0.0.0.0:{port}"),
+        format!("[::]:{port}")
+    ];
+
+    server.remote_addresses = vec![format!("127.0.0.1:{port}")];
+
+    let _handle = start_server(server).await;
+
+    for address in [format!("127.0.0.1:{port}"), format!("[::1]:{port}")] {
+        let response = reqwest::get(format!("http://{address}/api/v1/info")).await
+            .unwrap();
+
+        assert!(response.status().is_success());
+    }
+}
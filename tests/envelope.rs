@@ -0,0 +1,72 @@
+mod common;
+
+use serde_json::{json, Value as Json};
+
+use hyperelm::prelude::*;
+use hyperelm::client::{canonical_json, canonical_envelope, raw_canonical_json};
+
+use common::*;
+
+/// Serialize the envelope using both the raw payload
+/// and the payload inserted into the envelope.
+fn serialize_both(envelope: Json, field: &str, payload: Json) -> (Vec<u8>, Vec<u8>) {
+    let raw = canonical_envelope(&envelope, field, &raw_canonical_json(&payload).unwrap()).unwrap();
+
+    let mut expected = envelope;
+
+    expected[field] = payload;
+
+    (raw, canonical_json(&expected).unwrap())
+}
+
+#[test]
+fn raw_envelopes_are_byte_compatible() {
+    let payloads = [
+        json!("plain string"),
+        json!(null),
+        json!([1, -2, 3.5, 1e300, u64::MAX, i64::MIN]),
+        json!({ "Text": "quotes \" and \\ slashes, \n new lines and unicode: привет, 🦀" }),
+        json!({ "z": { "b": [{ "y": 1, "x": 2 }], "a": true }, "a": {} }),
+        json!({ "Binary": "a".repeat(300 * 1024) })
+    ];
+
+    let envelopes = [
+        json!({}),
+        json!({ "priority": 0, "nonce": 123 }),
+        json!({ "id": 1, "priority": 255, "reply": "shared", "trace": { "traceparent": "00-abc-def-01" } }),
+
+        // Existing field is replaced by the payload
+        json!({ "message": "outdated", "priority": 1 })
+    ];
+
+    for envelope in &envelopes {
+        for payload in &payloads {
+            for field in ["message", "request", "0", "~"] {
+                let (raw, expected) = serialize_both(envelope.clone(), field, payload.clone());
+
+                assert_eq!(raw, expected, "envelope: {envelope}, field: {field}");
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn large_payloads_are_delivered() {
+    let server = server_params("envelope-large");
+
+    let _handle = start_server(server.clone()).await;
+
+    let sender = TestClient::new(&server);
+    let receiver = TestClient::new(&server);
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    let text = "hyperelm ".repeat(50 * 1024);
+
+    sender.send(receiver.endpoint(), TestMessage::Text(text.clone())).await
+        .unwrap();
+
+    receiver.update_batch().await.unwrap();
+
+    assert_eq!(*receiver.state.received.lock().unwrap(), [text]);
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use hyperelm::prelude::*;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+#[tokio::test]
+async fn failed_send_exposes_peer_and_channel() {
+    let server = server_params("error-context");
+
+    let _handle = start_server(server.clone()).await;
+
+    let sender = TestClient::new(&server);
+
+    // Peer connected to the server which isn't running
+    let peer = SecretKey::random().public();
+    let endpoint = ClientEndpoint::new(free_address(), peer.clone());
+
+    let err = sender.send(endpoint, TestMessage::Text(String::from("lost"))).await
+        .unwrap_err();
+
+    assert_eq!(err.peer(), Some(&peer));
+    assert_eq!(err.channel(), Some(sender.params.channel.as_str()));
+
+    // Context is shown next to the original error
+    let message = err.to_string();
+    let prefix = peer.to_base64().chars().take(8).collect::<String>();
+
+    assert!(message.contains(&format!("peer: {prefix}")));
+    assert!(message.contains(&format!("channel: {}", sender.params.channel)));
+
+    // Original error is not contextual
+    let inner = err.into_inner();
+
+    assert!(inner.peer().is_none());
+    assert!(inner.channel().is_none());
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use std::time::Duration;
+
+use hyperelm::prelude::*;
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use common::*;
+
+#[tokio::test]
+async fn expired_messages_are_not_handled() {
+    let server = server_params("expiry");
+
+    let _handle = start_server(server.clone()).await;
+
+    let receiver = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .expiry_grace(Duration::ZERO));
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    let sender = TestClient::new(&server);
+
+    sender.send_with_ttl(receiver.endpoint(), TestMessage::Text(String::from("expired")), Duration::ZERO).await
+        .unwrap();
+
+    sender.send_with_ttl(receiver.endpoint(), TestMessage::Text(String::from("fresh")), Duration::from_secs(60)).await
+        .unwrap();
+
+    // Expiry timestamps have seconds precision
+    tokio::time::sleep(Duration::from_millis(2100)).await;
+
+    receiver.update_batch().await.unwrap();
+
+    assert_eq!(*receiver.state.received.lock().unwrap(), ["fresh"]);
+
+    let expired = receiver.state.expired.lock().unwrap();
+
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0]["message"], TestMessage::Text(String::from("expired")).to_json().unwrap());
+}
+
+#[tokio::test]
+async fn grace_tolerates_clock_skew() {
+    let server = server_params("expiry-grace");
+
+    let _handle = start_server(server.clone()).await;
+
+    // Default grace is 30 seconds
+    let receiver = TestClient::new(&server);
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    let sender = TestClient::new(&server);
+
+    sender.send_with_ttl(receiver.endpoint(), TestMessage::Text(String::from("skewed")), Duration::ZERO).await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(2100)).await;
+
+    receiver.update_batch().await.unwrap();
+
+    assert_eq!(*receiver.state.received.lock().unwrap(), ["skewed"]);
+    assert!(receiver.state.expired.lock().unwrap().is_empty());
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use axum::routing::get;
+
+use hyperelm::prelude::*;
+use hyperelm::server::{Routes, ServerRunError, CAPABILITIES_PATH, STARTUP_PATH};
+
+use common::*;
+
+struct RoutesServer {
+    params: ServerAppParams,
+    routes: fn() -> Vec<Routes>
+}
+
+impl BasicServerApp for RoutesServer {
+    fn get_params(&self) -> &ServerAppParams {
+        &self.params
+    }
+
+    fn extra_routes(&self) -> Vec<Routes> {
+        (self.routes)()
+    }
+}
+
+#[tokio::test]
+async fn extra_routes_are_served_publicly() {
+    let params = server_params("extra-routes");
+
+    let handle = hyperelm::server::spawn(RoutesServer {
+        params: params.clone(),
+        routes: || vec![Routes::new().route("/hello", get(|| async { "hello" }))]
+    });
+
+    handle.ready().await.unwrap();
+
+    let address = params.local_address();
+
+    let hello = reqwest::get(format!("http://{address}/hello")).await.unwrap()
+        .text().await.unwrap();
+
+    assert_eq!(hello, "hello");
+
+    // Built-in routes are served on the public address as well
+    let capabilities = reqwest::get(format!("http://{address}{CAPABILITIES_PATH}")).await.unwrap();
+
+    assert!(capabilities.status().is_success());
+
+    // Hyperborealib routes are served by the same router
+    let info = reqwest::get(format!("http://{address}/api/v1/info")).await.unwrap();
+
+    assert!(info.status().is_success());
+}
+
+#[tokio::test]
+async fn overlapping_routes_fail_the_server() {
+    let params = server_params("extra-routes-overlap");
+
+    let result = hyperelm::server::run(RoutesServer {
+        params,
+        routes: || vec![
+            Routes::new().route("/hello/:name", get(|| async { "first" })),
+            Routes::new().route("/hello/:id", get(|| async { "second" }))
+        ]
+    }).await;
+
+    assert!(matches!(result, Err(ServerRunError::RoutesOverlap(_))));
+}
+
+#[tokio::test]
+async fn routes_overlapping_builtin_ones_fail_the_server() {
+    let params = server_params("extra-routes-overlap-builtin");
+
+    let result = hyperelm::server::run(RoutesServer {
+        params,
+        routes: || vec![Routes::new().route(STARTUP_PATH, get(|| async { "startup" }))]
+    }).await;
+
+    assert!(matches!(result, Err(ServerRunError::RoutesOverlap(err)) if err.contains(STARTUP_PATH)));
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::Value as Json;
+
+use hyperelm::prelude::*;
+use hyperelm::client::{InterceptorError, SendInterceptor, TransferOptions};
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Count sent file chunks, failing them after
+/// the limit while the interruption is enabled.
+#[derive(Debug, Default, Clone)]
+struct InterruptChunks {
+    sent: Arc<AtomicU64>,
+    limit: u64,
+    enabled: Arc<AtomicBool>
+}
+
+#[async_trait::async_trait]
+impl SendInterceptor for InterruptChunks {
+    async fn before_send(&self, envelope: &mut Json, _endpoint: &ClientEndpoint) -> Result<(), InterceptorError> {
+        if envelope.get("file_chunk").is_none() {
+            return Ok(());
+        }
+
+        if self.enabled.load(Ordering::SeqCst) && self.sent.load(Ordering::SeqCst) >= self.limit {
+            return Err("connection interrupted".into());
+        }
+
+        self.sent.fetch_add(1, Ordering::SeqCst);
+
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn interrupted_transfer_is_resumed() {
+    let server = server_params("file-transfer");
+
+    let _handle = start_server(server.clone()).await;
+
+    let downloads = temp_folder("file-transfer-downloads");
+
+    let receiver = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .download_dir(&downloads)
+        .delay(Duration::from_millis(50)));
+
+    let receiver_endpoint = receiver.endpoint();
+
+    let _receiver = hyperelm::client::run(receiver).await.unwrap();
+
+    // Interrupt the transfer after 40% of the file
+    let interrupt = InterruptChunks {
+        limit: 12,
+        enabled: Arc::new(AtomicBool::new(true)),
+        ..InterruptChunks::default()
+    };
+
+    let sender = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .send_interceptor(interrupt.clone())
+        .delay(Duration::from_millis(50)));
+
+    let content = (0..2 * 1024 * 1024)
+        .map(|_| rand::random::<u8>())
+        .collect::<Vec<_>>();
+
+    let source = temp_folder("file-transfer-source").join("file.bin");
+
+    std::fs::write(&source, &content).unwrap();
+
+    let options = TransferOptions {
+        chunk_size: CHUNK_SIZE,
+        status_timeout: Duration::from_secs(10)
+    };
+
+    let result = sender.send_file(receiver_endpoint.clone(), source.clone(), options).await;
+
+    assert!(result.is_err());
+    assert_eq!(interrupt.sent.load(Ordering::SeqCst), 12);
+
+    // Resume the transfer
+    interrupt.enabled.store(false, Ordering::SeqCst);
+
+    let manifest = sender.send_file(receiver_endpoint, source, options).await.unwrap();
+
+    assert_eq!(manifest.chunks(), 32);
+
+    // Only missing chunks are sent again
+    assert_eq!(interrupt.sent.load(Ordering::SeqCst), manifest.chunks());
+
+    // Wait for the receiver to verify and store the file
+    let received = downloads.join("file.bin");
+
+    tokio::time::timeout(Duration::from_secs(30), async {
+        while !received.exists() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }).await.unwrap();
+
+    assert_eq!(std::fs::read(received).unwrap(), content);
+}
+
+#[tokio::test]
+async fn large_files_are_rejected() {
+    let server = server_params("file-transfer-limit");
+
+    let _handle = start_server(server.clone()).await;
+
+    let receiver = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .download_dir(temp_folder("file-transfer-limit-downloads"))
+        .max_file_size(1024 * 1024));
+
+    let receiver_endpoint = receiver.endpoint();
+
+    let _receiver = hyperelm::client::run(receiver).await.unwrap();
+
+    let sender = TestClient::new(&server);
+
+    let source = temp_folder("file-transfer-limit-source").join("file.bin");
+
+    std::fs::write(&source, vec![0; 2 * 1024 * 1024]).unwrap();
+
+    let result = sender.send_file(receiver_endpoint, source, TransferOptions::default()).await;
+
+    assert!(matches!(result, Err(ClientAppError::FileRejected)));
+}
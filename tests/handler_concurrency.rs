@@ -0,0 +1,54 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use hyperelm::prelude::*;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+const HANDLERS: u32 = 8;
+const HANDLER_MILLIS: u64 = 10;
+
+/// Queue slow messages and measure time of their processing.
+async fn process_slow_messages(sender: &TestClient, receiver: &TestClient) -> Duration {
+    receiver.get_connected_middleware().await.unwrap();
+
+    for id in 0..HANDLERS {
+        sender.send(receiver.endpoint(), TestMessage::Sleep { id, millis: HANDLER_MILLIS }).await
+            .unwrap();
+    }
+
+    assert_eq!(receiver.fetch_messages().await.unwrap(), HANDLERS as usize);
+
+    let started_at = Instant::now();
+
+    receiver.update_batch().await.unwrap();
+
+    started_at.elapsed()
+}
+
+#[tokio::test]
+async fn concurrent_handlers_increase_throughput() {
+    let server = server_params("handler-concurrency");
+
+    let _handle = start_server(server.clone()).await;
+
+    let sender = TestClient::new(&server);
+
+    let sequential = TestClient::new(&server);
+
+    let concurrent = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .handler_concurrency(HANDLERS as usize));
+
+    let sequential_time = process_slow_messages(&sender, &sequential).await;
+    let concurrent_time = process_slow_messages(&sender, &concurrent).await;
+
+    assert!(sequential_time >= Duration::from_millis(HANDLERS as u64 * HANDLER_MILLIS));
+    assert!(concurrent_time * 4 < sequential_time, "{concurrent_time:?} vs {sequential_time:?}");
+
+    assert_eq!(concurrent.handler_error_count(), 0);
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use hyperelm::prelude::*;
+use hyperelm::http::HttpClientConfig;
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::drivers::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use common::*;
+
+/// Address which never accepts connections.
+const UNROUTABLE_ADDRESS: &str = "10.255.255.1:8001";
+
+const TIMEOUT: Duration = Duration::from_millis(500);
+
+#[tokio::test]
+async fn connect_timeout_is_applied() {
+    let config = HttpClientConfig {
+        connect_timeout: Some(TIMEOUT),
+        ..HttpClientConfig::default()
+    };
+
+    let client = ClientMiddleware::new(
+        config.build().unwrap(),
+        ClientDriver::new(ClientInfo::thin(), SecretKey::random())
+    );
+
+    let started_at = Instant::now();
+
+    let result = tokio::time::timeout(Duration::from_secs(10), client.get_info(UNROUTABLE_ADDRESS)).await
+        .expect("connect timeout is not applied");
+
+    assert!(result.is_err());
+    assert!(started_at.elapsed() < TIMEOUT * 4);
+}
+
+#[tokio::test]
+async fn client_app_uses_configured_timeouts() {
+    let client = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(SecretKey::random().public(), UNROUTABLE_ADDRESS)
+        .http_config(HttpClientConfig {
+            request_timeout: Some(TIMEOUT),
+            ..HttpClientConfig::default()
+        }));
+
+    let started_at = Instant::now();
+
+    let result = tokio::time::timeout(Duration::from_secs(10), client.get_connected_middleware()).await
+        .expect("request timeout is not applied");
+
+    assert!(result.is_err());
+    assert!(started_at.elapsed() < TIMEOUT * 4);
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use hyperelm::prelude::*;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+#[tokio::test]
+async fn notified_peer_requests_the_new_identity() {
+    let server = server_params("identity-rotation");
+
+    let _handle = start_server(server.clone()).await;
+
+    let responder = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(50)));
+
+    let previous = responder.endpoint();
+
+    let responder = hyperelm::client::run(responder).await.unwrap();
+
+    let requester = TestClient::new(&server);
+
+    requester.get_connected_middleware().await.unwrap();
+
+    responder.rotate_identity(SecretKey::random(), &[requester.endpoint()]).await.unwrap();
+
+    // Receive the rotation notice
+    let started_at = Instant::now();
+
+    while requester.state.moved.lock().unwrap().is_empty() {
+        assert!(started_at.elapsed() < Duration::from_secs(5), "rotation notice is not received");
+
+        requester.update_batch().await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    let (moved_from, moved_to) = requester.state.moved.lock().unwrap()[0].clone();
+
+    assert_eq!(moved_from, previous.client_public);
+    assert_eq!(moved_to, responder.params.identity.public());
+
+    // Request the peer using its new key
+    let request = requester.request(
+        ClientEndpoint::new(server.local_address(), moved_to),
+        TestRequest::Echo(String::from("moved"))
+    );
+
+    let response = tokio::time::timeout(Duration::from_secs(10), request).await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(response, TestResponse::Echo(String::from("moved")));
+}
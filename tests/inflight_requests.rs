@@ -0,0 +1,105 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use hyperelm::prelude::*;
+use hyperelm::client::OverloadBehavior;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+const REQUESTS: usize = 100;
+const MAX_INFLIGHT: usize = 10;
+
+const POLL_PATH: &str = "/api/v1/poll";
+const SEND_PATH: &str = "/api/v1/send";
+
+#[tokio::test]
+async fn concurrent_requests_share_bounded_polls() {
+    let server = server_params("inflight-requests");
+
+    let _handle = start_server(server.clone()).await;
+
+    let responder = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(50)));
+
+    let responder_endpoint = responder.endpoint();
+
+    let _responder = hyperelm::client::run(responder).await.unwrap();
+
+    let requester = Arc::new(TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .max_inflight_requests(MAX_INFLIGHT)));
+
+    let requests = (0..REQUESTS)
+        .map(|i| {
+            let requester = requester.clone();
+            let endpoint = responder_endpoint.clone();
+
+            tokio::spawn(async move {
+                requester.request(endpoint, TestRequest::Echo(i.to_string())).await
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for (i, request) in requests.into_iter().enumerate() {
+        let response = tokio::time::timeout(Duration::from_secs(30), request).await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(response, TestResponse::Echo(i.to_string()));
+    }
+
+    // Only one of the pending requests polls the server at a time
+    assert_eq!(requester.http.max_concurrent(POLL_PATH), 1);
+    assert!(requester.http.max_concurrent(SEND_PATH) <= MAX_INFLIGHT);
+    assert!(requester.http.count(SEND_PATH) >= REQUESTS);
+}
+
+#[tokio::test]
+async fn overloaded_requests_fail_fast() {
+    let server = server_params("inflight-overloaded");
+
+    let _handle = start_server(server.clone()).await;
+
+    let responder = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(50)));
+
+    let responder_endpoint = responder.endpoint();
+
+    let _responder = hyperelm::client::run(responder).await.unwrap();
+
+    let requester = Arc::new(TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .max_inflight_requests(1)
+        .overload_behavior(OverloadBehavior::Fail)));
+
+    // Occupy the only in-flight request slot
+    let slow = tokio::spawn({
+        let requester = requester.clone();
+        let endpoint = responder_endpoint.clone();
+
+        async move {
+            requester.request(endpoint, TestRequest::Deferred {
+                text: String::from("slow"),
+                millis: 1000
+            }).await
+        }
+    });
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let result = requester.request(responder_endpoint, TestRequest::Echo(String::from("fast"))).await;
+
+    assert!(matches!(result, Err(ClientAppError::Overloaded)));
+    assert!(slow.await.unwrap().is_ok());
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use hyperelm::server::{IpFilter, FORWARDED_FOR_HEADER};
+
+use common::*;
+
+const BLOCKED_IP: &str = "203.0.113.7";
+
+async fn get_info(address: &str, forwarded_for: Option<&str>) -> reqwest::StatusCode {
+    let mut request = reqwest::Client::new()
+        .get(format!("http://{address}/api/v1/info"));
+
+    if let Some(forwarded_for) = forwarded_for {
+        request = request.header(FORWARDED_FOR_HEADER, forwarded_for);
+    }
+
+    request.send().await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn rest_api_blocks_forwarded_addresses_of_trusted_proxies() {
+    let mut server = server_params("ip-filter-trusted");
+
+    server.ip_filter = Some(IpFilter {
+        allowlist: None,
+        blocklist: vec![format!("{BLOCKED_IP}/32").parse().unwrap()],
+        trusted_proxies: vec!["127.0.0.0/8".parse().unwrap()]
+    });
+
+    let _handle = start_server(server.clone()).await;
+
+    // Hyperborealib routes are filtered as well
+    assert_eq!(get_info(server.local_address(), Some(BLOCKED_IP)).await, reqwest::StatusCode::FORBIDDEN);
+    assert_eq!(get_info(server.local_address(), Some("198.51.100.1")).await, reqwest::StatusCode::OK);
+    assert_eq!(get_info(server.local_address(), None).await, reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn rest_api_ignores_forwarded_addresses_of_untrusted_sockets() {
+    let mut server = server_params("ip-filter-untrusted");
+
+    server.ip_filter = Some(IpFilter {
+        allowlist: None,
+        blocklist: vec![format!("{BLOCKED_IP}/32").parse().unwrap()],
+        trusted_proxies: vec![]
+    });
+
+    let _handle = start_server(server.clone()).await;
+
+    // Header is ignored, so clients can't spoof their address
+    assert_eq!(get_info(server.local_address(), Some(BLOCKED_IP)).await, reqwest::StatusCode::OK);
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use hyperelm::prelude::*;
+use hyperelm::client::{Direction, FileMessageJournal, JournalEntry, MessageJournal};
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use common::*;
+
+const ENTRIES: u64 = 300;
+
+#[test]
+fn entries_are_read_in_order_across_rotations() {
+    let path = temp_folder("journal-rotation").join("journal.jsonl");
+
+    // Enough files to keep all the entries
+    let journal = FileMessageJournal::new(&path, 4096, 64)
+        .with_bodies(Direction::Outgoing);
+
+    let reader = journal.reader();
+
+    let peer = SecretKey::random().public();
+
+    for i in 0..ENTRIES {
+        let envelope = format!("{{\"message\":{i}}}");
+
+        journal.record(&JournalEntry::new(Direction::Outgoing, peer.clone(), "hyperelm", envelope.as_bytes(), true))
+            .unwrap();
+    }
+
+    assert!(reader.rotated_path(2).exists());
+
+    let entries = reader.iter_range(0, u64::MAX)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert_eq!(entries.len() as u64, ENTRIES);
+
+    for (i, entry) in entries.iter().enumerate() {
+        assert_eq!(entry.direction, Direction::Outgoing);
+        assert_eq!(entry.peer, peer);
+        assert_eq!(entry.body.as_ref().unwrap()["message"].as_u64(), Some(i as u64));
+    }
+
+    // Range excludes entries recorded at other times
+    let first = entries[0].timestamp;
+
+    assert_eq!(reader.iter_range(0, first - 1).count(), 0);
+}
+
+#[tokio::test]
+async fn sent_and_received_messages_are_journaled() {
+    let server = server_params("journal");
+
+    let _handle = start_server(server.clone()).await;
+
+    let folder = temp_folder("journal-client");
+
+    let sender_journal = FileMessageJournal::new(folder.join("sender.jsonl"), 1024 * 1024, 4);
+    let receiver_journal = FileMessageJournal::new(folder.join("receiver.jsonl"), 1024 * 1024, 4)
+        .with_bodies(Direction::Incoming);
+
+    let sender_reader = sender_journal.reader();
+    let receiver_reader = receiver_journal.reader();
+
+    let sender = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .journal(sender_journal));
+
+    let receiver = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .journal(receiver_journal));
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    for text in ["first", "second", "third"] {
+        sender.send(receiver.endpoint(), TestMessage::Text(String::from(text))).await
+            .unwrap();
+    }
+
+    receiver.update_batch().await.unwrap();
+
+    let sent = sender_reader.iter_range(0, u64::MAX)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let received = receiver_reader.iter_range(0, u64::MAX)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert_eq!(sent.len(), 3);
+    assert_eq!(received.len(), 3);
+
+    // Bodies are recorded only when enabled
+    assert!(sent.iter().all(|entry| entry.direction == Direction::Outgoing && entry.body.is_none()));
+    assert!(received.iter().all(|entry| entry.direction == Direction::Incoming));
+
+    for (entry, text) in received.iter().zip(["first", "second", "third"]) {
+        assert_eq!(entry.peer, sender.params.identity.public());
+        assert_eq!(entry.body.as_ref().unwrap()["message"], TestMessage::Text(String::from(text)).to_json().unwrap());
+    }
+
+    // Same envelopes are recorded on both sides
+    let sent_hashes = sent.iter().map(|entry| &entry.hash).collect::<Vec<_>>();
+    let received_hashes = received.iter().map(|entry| &entry.hash).collect::<Vec<_>>();
+
+    assert_eq!(sent_hashes, received_hashes);
+}
+
+#[tokio::test]
+async fn journal_failures_dont_fail_messages() {
+    let server = server_params("journal-failure");
+
+    let _handle = start_server(server.clone()).await;
+
+    // Journal can't be written to a folder
+    let sender = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .journal(FileMessageJournal::new(temp_folder("journal-folder"), 1024, 1)));
+
+    let receiver = TestClient::new(&server);
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    sender.send(receiver.endpoint(), TestMessage::Text(String::from("unjournaled"))).await
+        .unwrap();
+
+    receiver.update_batch().await.unwrap();
+
+    assert_eq!(*receiver.state.received.lock().unwrap(), ["unjournaled"]);
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use hyperelm::prelude::*;
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::drivers::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use common::*;
+
+#[tokio::test]
+async fn basic_app_removes_known_servers() {
+    let params = server_params("remove-known-server");
+    let app = TestServer(params.clone());
+
+    let router = GlobalTableRouter::new(params.backend_folder.join("router")).await.unwrap();
+
+    let servers = (0..3)
+        .map(|i| Server::new(SecretKey::random().public(), format!("127.0.0.1:{}", 9000 + i)))
+        .collect::<Vec<_>>();
+
+    for server in &servers {
+        router.index_server(server.clone()).await.unwrap();
+    }
+
+    assert!(app.remove_known_server(&servers[1]).await.unwrap());
+    assert!(!app.remove_known_server(&servers[1]).await.unwrap());
+
+    // Removed records are not loaded by the app's router
+    let known = app.list_known_servers(10, 0).await.unwrap();
+
+    assert_eq!(app.count_known_servers().await.unwrap(), 2);
+
+    assert_eq!(known.len(), 2);
+    assert!(!known.contains(&servers[1]));
+    assert_eq!(app.list_known_servers(10, 1).await.unwrap().len(), 1);
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use std::time::Duration;
+
+use hyperelm::prelude::*;
+use hyperelm::client::LookupResult;
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use common::*;
+
+#[tokio::test]
+async fn lookup_keeps_routing_metadata() {
+    let server = server_params("lookup-full");
+
+    let _handle = start_server(server.clone()).await;
+
+    let peer = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(50)));
+
+    let peer_public = peer.params.identity.public();
+
+    let _peer = hyperelm::client::run(peer).await.unwrap();
+
+    let client = TestClient::new(&server);
+
+    let result = client.lookup_full(peer_public.clone(), None).await
+        .unwrap()
+        .expect("peer must be found");
+
+    assert_eq!(result.client.public_key, peer_public);
+    assert_eq!(result.client.info, ClientInfo::thin());
+    assert_eq!(result.server.public_key, server.secret_key.public());
+    assert_eq!(result.server.address, server.local_address());
+    assert!(result.available);
+
+    // All the fields survive serialization
+    let restored = LookupResult::from_json(&result.to_json().unwrap()).unwrap();
+
+    assert_eq!(restored, result);
+
+    // Existing code keeps working with endpoints
+    let endpoint = ClientEndpoint::from(&result);
+
+    assert_eq!(endpoint, client.lookup(peer_public, None).await.unwrap().unwrap());
+    assert_eq!(endpoint.server_address, server.local_address());
+
+    // Unknown clients are not found
+    assert!(client.lookup_full(SecretKey::random().public(), None).await.unwrap().is_none());
+}
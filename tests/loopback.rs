@@ -0,0 +1,55 @@
+mod common;
+
+use std::time::Duration;
+
+use hyperelm::prelude::*;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+#[tokio::test]
+async fn self_requests_make_no_http_calls() {
+    let server = server_params("loopback");
+
+    let _handle = start_server(server.clone()).await;
+
+    let client = TestClient::new(&server);
+
+    client.get_connected_middleware().await.unwrap();
+    client.http.reset();
+
+    let response = client.request(client.endpoint(), TestRequest::Echo(String::from("myself"))).await
+        .unwrap();
+
+    assert_eq!(response, TestResponse::Echo(String::from("myself")));
+
+    client.send(client.endpoint(), TestMessage::Text(String::from("note to self"))).await
+        .unwrap();
+
+    assert_eq!(*client.state.received.lock().unwrap(), ["note to self"]);
+    assert_eq!(client.http.total(), 0);
+}
+
+#[tokio::test]
+async fn disabled_loopback_uses_server() {
+    let server = server_params("loopback-disabled");
+
+    let _handle = start_server(server.clone()).await;
+
+    let client = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(50))
+        .loopback(false));
+
+    let client = hyperelm::client::run(client).await.unwrap();
+
+    client.http.reset();
+
+    let response = client.request(client.endpoint(), TestRequest::Echo(String::from("myself"))).await
+        .unwrap();
+
+    assert_eq!(response, TestResponse::Echo(String::from("myself")));
+    assert!(client.http.count("/api/v1/send") >= 2);
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use serde_json::Value as Json;
+
+use hyperelm::prelude::*;
+use hyperelm::client::{IdentityHandler, IdentityRef, MultiClient, MultiClientError};
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use common::*;
+
+/// Record received messages, failing the ones with `fail` text.
+#[derive(Debug, Clone, Default)]
+struct RecordingHandler {
+    received: Arc<Mutex<Vec<(PublicKey, Json)>>>
+}
+
+#[async_trait::async_trait]
+impl IdentityHandler for RecordingHandler {
+    async fn handle_message(&self, identity: &IdentityRef<'_>, message: Json, _info: MessageInfo) -> Result<(), MultiClientError> {
+        if message == serde_json::to_value(TestMessage::Text(String::from("fail")))? {
+            return Err(MultiClientError::Handler("requested failure".into()));
+        }
+
+        self.received.lock().unwrap().push((identity.public_key().clone(), message));
+
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn identities_share_one_client() {
+    let server = server_params("multi-client");
+
+    let _handle = start_server(server.clone()).await;
+
+    let http = CountingHttpClient::default();
+    let handler = RecordingHandler::default();
+
+    let multi = MultiClient::new(http.clone(), server.secret_key.public(), server.local_address());
+
+    let first = SecretKey::random();
+    let second = SecretKey::random();
+
+    multi.register(first.clone(), Channel::default(), handler.clone());
+    multi.register(second.clone(), Channel::default(), handler.clone());
+
+    multi.connect().await.unwrap();
+
+    assert!(multi.is_connected());
+    assert_eq!(http.count("/api/v1/connect"), 2);
+
+    // Send messages to both identities, failing one of them
+    let sender = TestClient::new(&server);
+
+    let first_endpoint = ClientEndpoint::new(server.local_address(), first.public());
+    let second_endpoint = ClientEndpoint::new(server.local_address(), second.public());
+
+    sender.send(first_endpoint.clone(), TestMessage::Text(String::from("fail"))).await.unwrap();
+    sender.send(first_endpoint, TestMessage::Text(String::from("first"))).await.unwrap();
+    sender.send(second_endpoint, TestMessage::Text(String::from("second"))).await.unwrap();
+
+    // Failed message doesn't prevent handling of the other ones
+    assert_eq!(multi.update().await, 2);
+
+    let mut received = handler.received.lock().unwrap().clone();
+
+    received.sort_by_key(|(_, message)| message.to_string());
+
+    assert_eq!(received, vec![
+        (first.public(), serde_json::to_value(TestMessage::Text(String::from("first"))).unwrap()),
+        (second.public(), serde_json::to_value(TestMessage::Text(String::from("second"))).unwrap())
+    ]);
+
+    // New identity doesn't reconnect the other ones
+    multi.register(SecretKey::random(), Channel::default(), handler.clone());
+
+    assert!(!multi.is_connected());
+
+    multi.update().await;
+
+    assert!(multi.is_connected());
+    assert_eq!(http.count("/api/v1/connect"), 3);
+}
@@ -0,0 +1,157 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::StreamExt;
+
+use hyperelm::prelude::*;
+use hyperelm::client::{Paginated, Paginator, ValidationError};
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use common::*;
+
+const ITEMS: u64 = 250;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum CatalogRequest {
+    Get(Option<String>)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+struct CatalogPage(Paginated<u64>);
+
+hyperborealib::impl_as_json!(CatalogRequest CatalogPage);
+
+/// Client serving the catalog in pages of 100 items.
+struct CatalogClient {
+    client: TestClient,
+    catalog: Arc<Paginator<u64>>
+}
+
+impl CatalogClient {
+    fn new(client: TestClient) -> Self {
+        Self {
+            client,
+            catalog: Arc::default()
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ClientApp for CatalogClient {
+    build_client!(
+        input: CatalogRequest => CatalogPage, TestMessage;
+        output: CatalogRequest => CatalogPage, TestMessage;
+
+        client: CountingHttpClient;
+        state: Paginator<u64>;
+        error: String;
+
+        requests: {
+            CatalogRequest::Get(cursor) => |catalog: Arc<Paginator<u64>>, _| async move {
+                let page = match cursor {
+                    Some(cursor) => catalog.next_page(&cursor)
+                        .map_err(|err| ClientAppError::Custom(err.to_string()))?,
+
+                    None => catalog.paginate(0..ITEMS, 100)
+                };
+
+                Ok(CatalogPage(page))
+            }
+        };
+
+        messages: {
+            TestMessage::Text(_) => |_: Arc<Paginator<u64>>, _| async move {
+                Ok(())
+            }
+        };
+    );
+
+    fn get_params(&self) -> &ClientAppParams {
+        &self.client.params
+    }
+
+    fn get_runtime(&self) -> &ClientRuntime {
+        &self.client.runtime
+    }
+
+    fn get_middleware(&self) -> &ClientMiddleware<Self::HttpClient> {
+        &self.client.middleware
+    }
+
+    fn get_state(&self) -> Arc<Self::State> {
+        self.catalog.clone()
+    }
+
+    fn validate_request(&self, request: &CatalogRequest, _info: &MessageInfo) -> Result<(), ValidationError> {
+        match request {
+            CatalogRequest::Get(Some(cursor)) => Ok(self.catalog.check_cursor(cursor)?),
+            CatalogRequest::Get(None) => Ok(())
+        }
+    }
+}
+
+fn catalog_responder(server: &ServerAppParams) -> CatalogClient {
+    CatalogClient::new(TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(50))))
+}
+
+#[tokio::test]
+async fn pages_are_streamed_in_order() {
+    let server = server_params("pagination");
+
+    let _handle = start_server(server.clone()).await;
+
+    let responder = catalog_responder(&server);
+
+    let endpoint = responder.client.endpoint();
+
+    let _responder = hyperelm::client::run(responder).await.unwrap();
+
+    let requester = CatalogClient::new(TestClient::new(&server));
+
+    let items = requester.request_paginated::<u64>(endpoint, CatalogRequest::Get)
+        .collect::<Vec<_>>().await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert_eq!(items, (0..ITEMS).collect::<Vec<_>>());
+
+    // One request per page
+    assert_eq!(requester.client.http.count("/api/v1/send"), 3);
+}
+
+#[tokio::test]
+async fn bogus_cursor_is_rejected() {
+    let server = server_params("pagination-bogus");
+
+    let _handle = start_server(server.clone()).await;
+
+    let responder = catalog_responder(&server);
+
+    let endpoint = responder.client.endpoint();
+
+    let _responder = hyperelm::client::run(responder).await.unwrap();
+
+    let requester = CatalogClient::new(TestClient::new(&server));
+
+    let results = requester.request_paginated::<u64>(endpoint, |_| CatalogRequest::Get(Some(String::from("bogus"))))
+        .collect::<Vec<_>>().await;
+
+    // Stream ends after the first error
+    assert_eq!(results.len(), 1);
+
+    let Err(ClientAppError::Remote(error)) = results[0].as_ref().map_err(ClientAppError::inner) else {
+        panic!("bogus cursor must be rejected with a remote error, got {:?}", results[0]);
+    };
+
+    assert_eq!(error.kind, "validation");
+    assert_eq!(error.field.as_deref(), Some("cursor"));
+}
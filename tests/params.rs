@@ -0,0 +1,106 @@
+mod common;
+
+use std::time::Duration;
+
+use hyperelm::prelude::*;
+use hyperelm::client::ReplyChannelStrategy;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+fn client_params(secret: &SecretKey) -> ClientAppParams {
+    ClientAppParams::builder()
+        .client(secret.clone())
+        .server(SecretKey::random().public(), "127.0.0.1:8001")
+        .channel(Channel::new("params-test").unwrap())
+        .delay(Duration::from_millis(250))
+        .reply_channel_strategy(ReplyChannelStrategy::Shared)
+        .header("Authorization", "Bearer client-token")
+        .build()
+        .unwrap()
+}
+
+fn assert_same_client(restored: &ClientAppParams, params: &ClientAppParams) {
+    assert_eq!(restored.identity.secret(), params.identity.secret());
+    assert_eq!(restored.server_public, params.server_public);
+    assert_eq!(restored.server_address, params.server_address);
+    assert_eq!(restored.channel, params.channel);
+    assert_eq!(restored.delay, params.delay);
+    assert_eq!(restored.reply_channel_strategy, params.reply_channel_strategy);
+}
+
+fn assert_same_server(restored: &ServerAppParams, params: &ServerAppParams) {
+    assert_eq!(restored.secret_key, params.secret_key);
+    assert_eq!(restored.local_addresses, params.local_addresses);
+    assert_eq!(restored.bootstrap, params.bootstrap);
+    assert_eq!(restored.traverse_delay, params.traverse_delay);
+    assert_eq!(restored.admin_token, params.admin_token);
+}
+
+#[test]
+fn client_params_round_trip() {
+    let params = client_params(&SecretKey::random());
+
+    let json = serde_json::to_string(&params).unwrap();
+    let restored = serde_json::from_str::<ClientAppParams>(&json).unwrap();
+
+    assert_same_client(&restored, &params);
+
+    let toml = toml::to_string(&params).unwrap();
+    let restored = toml::from_str::<ClientAppParams>(&toml).unwrap();
+
+    assert_same_client(&restored, &params);
+}
+
+#[test]
+fn server_params_round_trip() {
+    let mut params = server_params("params-round-trip");
+
+    params.bootstrap = vec![String::from("10.0.0.1:8001"), String::from("10.0.0.2:8001")];
+    params.traverse_delay = Duration::from_secs(123);
+    params.admin_token = Some(String::from("admin-token"));
+
+    let json = serde_json::to_string(&params).unwrap();
+    let restored = serde_json::from_str::<ServerAppParams>(&json).unwrap();
+
+    assert_same_server(&restored, &params);
+
+    let toml = toml::to_string(&params).unwrap();
+    let restored = toml::from_str::<ServerAppParams>(&toml).unwrap();
+
+    assert_same_server(&restored, &params);
+}
+
+#[test]
+fn debug_output_has_no_secrets() {
+    let secret = SecretKey::random();
+
+    let client = client_params(&secret);
+
+    let output = format!(
+        "{client:?} {client} {:?}",
+        ClientAppParams::builder().client(secret.clone())
+    );
+
+    assert!(!output.contains(&secret.to_base64()));
+    assert!(!output.contains("client-token"));
+    assert!(output.contains(&secret.public().to_base64()));
+
+    let mut server = server_params("params-debug");
+
+    server.bootstrap = (0..10).map(|i| format!("10.0.0.{i}:8001")).collect();
+    server.admin_token = Some(String::from("admin-token"));
+    server.metrics_bearer_token = Some(String::from("metrics-token"));
+
+    let output = format!("{server:?} {server} {server:#?}");
+
+    assert!(!output.contains(&server.secret_key.to_base64()));
+    assert!(!output.contains("admin-token"));
+    assert!(!output.contains("metrics-token"));
+    assert!(output.contains(&server.secret_key.public().to_base64()));
+
+    // Long bootstrap lists are truncated
+    assert!(output.contains("7 more"));
+    assert!(!output.contains("10.0.0.9:8001"));
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use std::time::Duration;
+
+use serde_json::json;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+
+use hyperelm::prelude::*;
+use hyperelm::http::{HttpStatusError, StatusHttpClient};
+use hyperelm::server::limit_payload_size;
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::http::HttpClient;
+
+use common::*;
+
+const LIMIT: usize = 4 * 1024 * 1024;
+
+#[tokio::test]
+async fn rest_api_rejects_large_payloads() {
+    let server = server_params("payload-limit-rest-api");
+
+    let _handle = start_server(server.clone()).await;
+
+    let client = StatusHttpClient::default();
+
+    // Hyperborealib routes are served by the limited router
+    let error = client.post_request::<_, serde_json::Value>(
+        &format!("http://{}/api/v1/send", server.local_address()),
+        json!({ "payload": "a".repeat(10 * 1024 * 1024) })
+    ).await.unwrap_err();
+
+    let error = HttpStatusError::find(error.as_ref()).unwrap();
+
+    assert_eq!(error.status, 413);
+    assert_eq!(error.body, Some(json!({ "error": "payload_too_large", "limit": LIMIT })));
+}
+
+#[tokio::test]
+async fn limit_layer_passes_small_payloads() {
+    let address = serve(limit_payload_size(
+        axum::Router::new().fallback(|| async {
+            axum::Json(json!({ "status": "ok" }))
+        }),
+        LIMIT
+    )).await;
+
+    let response = StatusHttpClient::default().post_request::<_, serde_json::Value>(
+        &format!("http://{address}/api/v1/send"),
+        json!({ "payload": "small" })
+    ).await.unwrap();
+
+    assert_eq!(response, json!({ "status": "ok" }));
+}
+
+#[tokio::test]
+async fn client_surfaces_server_limit() {
+    let server = server_params("payload-limit");
+
+    let _handle = start_server(server.clone()).await;
+
+    let client = TestClient::new(&server);
+
+    // Random payload can't be compressed below the limit
+    let payload = (0..10 * 1024 * 1024)
+        .map(|_| rand::random::<u8>())
+        .collect::<Vec<_>>();
+
+    let payload = BASE64.encode(payload);
+
+    let endpoint = ClientEndpoint::new(server.local_address(), SecretKey::random().public());
+
+    let result = tokio::time::timeout(
+        Duration::from_secs(30),
+        client.send(endpoint, TestMessage::Text(payload))
+    ).await.unwrap();
+
+    assert!(matches!(result.map_err(ClientAppError::into_inner), Err(ClientAppError::PayloadTooLarge { server_limit }) if server_limit == LIMIT));
+}
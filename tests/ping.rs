@@ -0,0 +1,56 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use hyperelm::prelude::*;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+#[tokio::test]
+async fn concurrent_pings_receive_their_own_messages() {
+    let server = server_params("ping-concurrent");
+
+    let _handle = start_server(server.clone()).await;
+
+    let client = Arc::new(TestClient::new(&server));
+
+    let pings = (0..8)
+        .map(|_| {
+            let client = client.clone();
+
+            tokio::spawn(async move {
+                client.ping().await
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for ping in pings {
+        assert!(ping.await.unwrap().is_ok());
+    }
+
+    assert_eq!(client.get_latency_tracker().len(), 8);
+    assert!(client.is_reachable().await);
+}
+
+#[tokio::test]
+async fn ping_is_bounded_by_timeout() {
+    let server = server_params("ping-timeout");
+
+    let _handle = start_server(server.clone()).await;
+
+    let client = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .ping_timeout(Duration::ZERO));
+
+    client.get_connected_middleware().await.unwrap();
+
+    let result = tokio::time::timeout(Duration::from_secs(5), client.ping()).await
+        .unwrap();
+
+    assert!(matches!(result, Err(ClientAppError::Timeout)));
+    assert!(!client.is_reachable().await);
+}
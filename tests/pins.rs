@@ -0,0 +1,78 @@
+mod common;
+
+use std::time::Duration;
+
+use hyperelm::prelude::*;
+use hyperelm::client::PinPolicy;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+/// Server address previously pinned for the peer.
+const PINNED_ADDRESS: &str = "10.0.0.1:8001";
+
+/// Start the server with a running responder, and a requester
+/// which has the responder pinned to a different address.
+async fn setup(name: &str, policy: PinPolicy) -> (TestClient, ClientEndpoint, impl Sized) {
+    let server = server_params(name);
+
+    let handle = start_server(server.clone()).await;
+
+    let responder = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(50)));
+
+    let endpoint = responder.endpoint();
+
+    let responder = hyperelm::client::run(responder).await.unwrap();
+
+    let requester = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .pin_policy(policy));
+
+    requester.pin(&ClientEndpoint::new(PINNED_ADDRESS, endpoint.client_public.clone()))
+        .unwrap();
+
+    (requester, endpoint, (handle, responder))
+}
+
+#[tokio::test]
+async fn strict_policy_blocks_changed_endpoint() {
+    let (requester, endpoint, _guard) = setup("pins-strict", PinPolicy::Strict).await;
+
+    let result = requester.lookup(endpoint.client_public.clone(), None).await;
+
+    let Err(ClientAppError::EndpointChanged { previous, current }) = result else {
+        panic!("lookup must fail with changed endpoint");
+    };
+
+    assert_eq!(previous, PINNED_ADDRESS);
+    assert_eq!(current, endpoint.server_address);
+
+    // Pinned address is kept
+    assert_eq!(requester.list_pins()[0].1.server_address, PINNED_ADDRESS);
+    assert!(requester.state.endpoint_changes.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn warn_policy_proceeds_and_fires_hook() {
+    let (requester, endpoint, _guard) = setup("pins-warn", PinPolicy::Warn).await;
+
+    let found = requester.lookup(endpoint.client_public.clone(), None).await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(found.server_address, endpoint.server_address);
+
+    assert_eq!(*requester.state.endpoint_changes.lock().unwrap(), [
+        (PINNED_ADDRESS.to_string(), endpoint.server_address.clone())
+    ]);
+
+    let response = requester.request(found, TestRequest::Echo(String::from("pinned"))).await
+        .unwrap();
+
+    assert_eq!(response, TestResponse::Echo(String::from("pinned")));
+}
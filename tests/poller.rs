@@ -0,0 +1,72 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use hyperelm::prelude::*;
+use hyperelm::client::PollerState;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+const MESSAGES: usize = 3;
+
+#[tokio::test]
+async fn paused_poller_drains_queued_messages_after_resume() {
+    let server = server_params("poller");
+
+    let _handle = start_server(server.clone()).await;
+
+    let receiver = Arc::new(TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(50))));
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    let poller = receiver.clone().spawn_poller();
+    let mut events = poller.subscribe(MESSAGES);
+
+    poller.pause();
+
+    assert_eq!(poller.state(), PollerState::Paused);
+
+    let sender = TestClient::new(&server);
+
+    for i in 0..MESSAGES {
+        sender.send(receiver.endpoint(), TestMessage::Text(i.to_string())).await
+            .unwrap();
+    }
+
+    // No handlers fire while paused
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    assert!(receiver.state.received.lock().unwrap().is_empty());
+    assert!(events.try_recv().is_err());
+
+    poller.resume();
+
+    let started_at = Instant::now();
+
+    while receiver.state.received.lock().unwrap().len() < MESSAGES {
+        assert!(started_at.elapsed() < Duration::from_secs(10), "queued messages weren't drained");
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    assert_eq!(*receiver.state.received.lock().unwrap(), ["0", "1", "2"]);
+
+    // Subscribers receive every processed message
+    for _ in 0..MESSAGES {
+        let info = events.try_recv().unwrap();
+
+        assert_eq!(info.sender.client.public_key, sender.params.identity.public());
+    }
+
+    poller.stop();
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    assert!(poller.is_finished());
+}
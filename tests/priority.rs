@@ -0,0 +1,69 @@
+mod common;
+
+use hyperelm::prelude::*;
+use hyperelm::client::{PriorityQueue, DEFAULT_PRIORITY};
+
+use common::*;
+
+#[test]
+fn queue_pops_higher_priorities_first() {
+    let queue = PriorityQueue::default();
+
+    queue.push(DEFAULT_PRIORITY, "normal-1");
+    queue.push(0, "low");
+    queue.push(u8::MAX, "urgent");
+    queue.push(DEFAULT_PRIORITY, "normal-2");
+
+    let order = std::iter::from_fn(|| queue.pop())
+        .collect::<Vec<_>>();
+
+    // Entries with equal priority keep their order
+    assert_eq!(order, ["urgent", "normal-1", "normal-2", "low"]);
+}
+
+#[test]
+fn low_priorities_are_not_starved() {
+    let queue = PriorityQueue::default();
+
+    queue.push(0, "low");
+
+    // Constant flow of higher priority entries
+    let popped_at = (0..100)
+        .position(|_| {
+            queue.push(10, "high");
+
+            queue.pop() == Some("low")
+        });
+
+    assert_eq!(popped_at, Some(10));
+}
+
+#[tokio::test]
+async fn incoming_messages_are_handled_by_priority() {
+    let server = server_params("priority");
+
+    let _handle = start_server(server.clone()).await;
+
+    let sender = TestClient::new(&server);
+    let receiver = TestClient::new(&server);
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    let messages = [
+        ("telemetry-1", 0),
+        ("telemetry-2", 0),
+        ("status", DEFAULT_PRIORITY),
+        ("control", u8::MAX)
+    ];
+
+    for (text, priority) in messages {
+        sender.send_with_priority(receiver.endpoint(), TestMessage::Text(text.to_string()), priority).await
+            .unwrap();
+    }
+
+    assert_eq!(receiver.fetch_messages().await.unwrap(), messages.len());
+
+    receiver.update_batch().await.unwrap();
+
+    assert_eq!(*receiver.state.received.lock().unwrap(), ["control", "status", "telemetry-1", "telemetry-2"]);
+}
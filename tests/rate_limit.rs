@@ -0,0 +1,82 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use hyperelm::prelude::*;
+use hyperelm::client::{OutboundRate, RateLimit, RateLimitMode};
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+const SENDS: usize = 100;
+
+fn limited_client(server: &ServerAppParams, mode: RateLimitMode) -> TestClient {
+    TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .outbound_rate(OutboundRate {
+            per_endpoint: RateLimit {
+                per_second: 10.0,
+                burst: 1
+            },
+            global: None,
+            mode
+        }))
+}
+
+#[tokio::test]
+async fn wait_mode_spreads_sends() {
+    let server = server_params("rate-limit-wait");
+
+    let _handle = start_server(server.clone()).await;
+
+    let receiver = TestClient::new(&server);
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    let sender = limited_client(&server, RateLimitMode::Wait);
+
+    let started_at = Instant::now();
+
+    for i in 0..SENDS {
+        sender.send(receiver.endpoint(), TestMessage::Text(i.to_string())).await
+            .unwrap();
+    }
+
+    let elapsed = started_at.elapsed();
+
+    // First message is sent immediately using the burst
+    assert!(elapsed >= Duration::from_secs(9), "{elapsed:?}");
+    assert!(elapsed < Duration::from_secs(15), "{elapsed:?}");
+
+    assert_eq!(sender.rate_limiter_stats().rejected, 0);
+}
+
+#[tokio::test]
+async fn fail_fast_mode_rejects_sends() {
+    let server = server_params("rate-limit-fail-fast");
+
+    let _handle = start_server(server.clone()).await;
+
+    let receiver = TestClient::new(&server);
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    let sender = limited_client(&server, RateLimitMode::FailFast);
+
+    let mut rejected = 0;
+
+    for i in 0..SENDS {
+        let result = sender.send(receiver.endpoint(), TestMessage::Text(i.to_string())).await;
+
+        if let Err(err) = result {
+            assert!(matches!(err.into_inner(), ClientAppError::RateLimited { .. }));
+
+            rejected += 1;
+        }
+    }
+
+    assert!(rejected >= 90, "{rejected} sends rejected");
+    assert_eq!(sender.rate_limiter_stats().rejected, rejected);
+}
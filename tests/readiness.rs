@@ -0,0 +1,55 @@
+mod common;
+
+use std::time::Duration;
+
+use hyperelm::prelude::*;
+use hyperelm::server::{wait_ready, ServerNotReady};
+
+use common::*;
+
+#[tokio::test]
+async fn clients_started_concurrently_connect() {
+    let server = server_params("readiness-concurrent");
+
+    let handle = hyperelm::server::spawn(TestServer(server.clone()));
+
+    let clients = (0..8)
+        .map(|_| {
+            let server = server.clone();
+            let handle = handle.clone();
+
+            tokio::spawn(async move {
+                handle.ready().await.unwrap();
+
+                TestClient::new(&server).get_connected_middleware().await
+                    .map(|_| ())
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for client in clients {
+        assert!(client.await.unwrap().is_ok());
+    }
+
+    assert!(wait_ready(server.local_address(), Duration::from_secs(1)).await);
+}
+
+#[tokio::test]
+async fn failed_server_is_not_ready() {
+    let server = server_params("readiness-failed");
+
+    // Occupy the server's address
+    let _listener = std::net::TcpListener::bind(server.local_address()).unwrap();
+
+    let handle = hyperelm::server::spawn(TestServer(server));
+
+    let result = tokio::time::timeout(Duration::from_secs(10), handle.ready()).await
+        .unwrap();
+
+    assert_eq!(result, Err(ServerNotReady::Stopped));
+}
+
+#[tokio::test]
+async fn unreachable_address_is_not_ready() {
+    assert!(!wait_ready(free_address(), Duration::from_millis(300)).await);
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use hyperelm::prelude::*;
+use hyperelm::client::{ReconnectPolicy, ConnectionEventHandler, ServerEndpoint};
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+#[derive(Default)]
+struct Events {
+    connected: AtomicU32,
+    reconnect_failed: AtomicU32
+}
+
+struct EventCounter(Arc<Events>);
+
+#[async_trait::async_trait]
+impl ConnectionEventHandler for EventCounter {
+    async fn on_connected(&self, _server: &ServerEndpoint) {
+        self.0.connected.fetch_add(1, Ordering::SeqCst);
+    }
+
+    async fn on_reconnect_failed(&self, _server: &ServerEndpoint, _attempts: u32) {
+        self.0.reconnect_failed.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+async fn wait_for(condition: impl Fn() -> bool) {
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while !condition() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }).await.unwrap();
+}
+
+#[tokio::test]
+async fn client_keeps_reconnecting_after_failed_attempts() {
+    let server = server_params("reconnect-retry");
+
+    let handle = start_server(server.clone()).await;
+
+    let events = Arc::new(Events::default());
+
+    let client = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(10))
+        .reconnect_policy(ReconnectPolicy {
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(50),
+            jitter_factor: 0.0,
+            max_attempts: Some(2)
+        })
+        .event_handler(EventCounter(events.clone())));
+
+    let _client = hyperelm::client::run(client).await.unwrap();
+
+    wait_for(|| events.connected.load(Ordering::SeqCst) == 1).await;
+
+    // Failed reconnection rounds are reported and repeated
+    handle.shutdown();
+
+    wait_for(|| events.reconnect_failed.load(Ordering::SeqCst) >= 2).await;
+
+    let _restarted = start_server(server).await;
+
+    wait_for(|| events.connected.load(Ordering::SeqCst) == 2).await;
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use hyperelm::prelude::*;
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::drivers::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use common::*;
+
+/// Start the home server relaying messages
+/// to the target server, and the target server.
+async fn start_servers(name: &str) -> (ServerAppParams, ServerAppParams, Vec<hyperelm::server::ServerHandle>) {
+    let target = server_params(&format!("{name}-target"));
+
+    let target_handle = start_server(target.clone()).await;
+
+    let mut home = server_params(&format!("{name}-home"));
+
+    home.relay_messages = true;
+    home.bootstrap = vec![target.local_address().to_string()];
+
+    let home_handle = start_server(home.clone()).await;
+
+    // Wait until the home server knows the target one
+    let client = ClientMiddleware::new(
+        CountingHttpClient::default(),
+        ClientDriver::new(ClientInfo::thin(), SecretKey::random())
+    );
+
+    let started_at = Instant::now();
+
+    loop {
+        let servers = client.get_servers(home.local_address()).await
+            .unwrap_or_default();
+
+        if servers.iter().any(|server| server.public_key == target.secret_key.public()) {
+            break;
+        }
+
+        assert!(started_at.elapsed() < Duration::from_secs(10), "home server didn't index the target one");
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    (home, target, vec![home_handle, target_handle])
+}
+
+/// Client connected to the home server
+/// which can't reach the target one.
+fn sender(home: &ServerAppParams, target: &ServerAppParams, relay: bool) -> TestClient {
+    let sender = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(home.secret_key.public(), home.local_address())
+        .relay_through_home(relay));
+
+    sender.http.block(target.local_address());
+
+    sender
+}
+
+#[tokio::test]
+async fn relayed_messages_reach_other_server() {
+    let (home, target, _handles) = start_servers("relay").await;
+
+    let receiver = TestClient::new(&target);
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    let sender = sender(&home, &target, true);
+
+    sender.send(receiver.endpoint(), TestMessage::Text(String::from("relayed"))).await
+        .unwrap();
+
+    // Wait until the home server forwards the message
+    let started_at = Instant::now();
+
+    while receiver.state.received.lock().unwrap().is_empty() {
+        assert!(started_at.elapsed() < Duration::from_secs(10), "relayed message wasn't delivered");
+
+        receiver.update_batch().await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    assert_eq!(*receiver.state.received.lock().unwrap(), ["relayed"]);
+}
+
+#[tokio::test]
+async fn unreachable_server_fails_without_relay() {
+    let (home, target, _handles) = start_servers("no-relay").await;
+
+    let receiver = TestClient::new(&target);
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    let sender = sender(&home, &target, false);
+
+    let result = sender.send(receiver.endpoint(), TestMessage::Text(String::from("direct"))).await;
+
+    assert!(result.is_err());
+
+    receiver.update_batch().await.unwrap();
+
+    assert!(receiver.state.received.lock().unwrap().is_empty());
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use serde_json::json;
+
+use hyperelm::prelude::*;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+const SEND_PATH: &str = "/api/v1/send";
+
+#[tokio::test]
+async fn remote_state_is_fetched_from_providers() {
+    let server = server_params("remote-state");
+
+    let _handle = start_server(server.clone()).await;
+
+    let responder = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(50)));
+
+    let roster_requests = Arc::new(AtomicUsize::new(0));
+
+    responder.state_providers().register("config", || async {
+        json!({ "theme": "dark" })
+    });
+
+    responder.state_providers().register_cacheable("roster", Duration::from_secs(60), {
+        let roster_requests = roster_requests.clone();
+
+        move || {
+            roster_requests.fetch_add(1, Ordering::SeqCst);
+
+            async {
+                json!(["amy", "bob"])
+            }
+        }
+    });
+
+    let endpoint = responder.endpoint();
+
+    let _responder = hyperelm::client::run(responder).await.unwrap();
+
+    let requester = TestClient::new(&server);
+
+    assert_eq!(requester.get_remote_state(endpoint.clone(), "config").await.unwrap(), json!({ "theme": "dark" }));
+    assert_eq!(requester.get_remote_state(endpoint.clone(), "roster").await.unwrap(), json!(["amy", "bob"]));
+
+    // Unknown keys are reported by the peer
+    let result = requester.get_remote_state(endpoint.clone(), "missing").await
+        .map_err(ClientAppError::into_inner);
+
+    let Err(ClientAppError::Remote(error)) = result else {
+        panic!("unknown key must be reported as a remote error");
+    };
+
+    assert_eq!(error.kind, "not_found");
+
+    // Cacheable state is not requested again within its TTL
+    let sent = requester.http.count(SEND_PATH);
+
+    assert_eq!(requester.get_remote_state(endpoint.clone(), "roster").await.unwrap(), json!(["amy", "bob"]));
+
+    assert_eq!(requester.http.count(SEND_PATH), sent);
+    assert_eq!(roster_requests.load(Ordering::SeqCst), 1);
+
+    // Not cacheable state is requested every time
+    requester.get_remote_state(endpoint, "config").await.unwrap();
+
+    assert_eq!(requester.http.count(SEND_PATH), sent + 1);
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use std::time::Duration;
+
+use hyperelm::prelude::*;
+use hyperelm::client::ReplyChannelStrategy;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+const POLL_PATH: &str = "/api/v1/poll";
+const REQUESTS: usize = 5;
+
+/// Check if the channel is created for a single request,
+/// formatted as `{channel}@{id}` or `{channel}@{session}-{id}`.
+fn is_request_channel(channel: &str) -> bool {
+    let Some((_, suffix)) = channel.split_once('@') else {
+        return false;
+    };
+
+    let id = suffix.rsplit('-').next().unwrap_or(suffix);
+
+    !suffix.starts_with("ack-") && !suffix.starts_with('_') && !id.is_empty() && id.chars().all(|char| char.is_ascii_digit())
+}
+
+/// Send requests using given strategy and
+/// return channels polled by the requester.
+async fn polled_channels(name: &str, strategy: ReplyChannelStrategy) -> Vec<String> {
+    let server = server_params(name);
+
+    let _handle = start_server(server.clone()).await;
+
+    let responder = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(50)));
+
+    let responder_endpoint = responder.endpoint();
+
+    let _responder = hyperelm::client::run(responder).await.unwrap();
+
+    let requester = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .reply_channel_strategy(strategy));
+
+    for i in 0..REQUESTS {
+        let request = requester.request(responder_endpoint.clone(), TestRequest::Echo(i.to_string()));
+
+        let response = tokio::time::timeout(Duration::from_secs(10), request).await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(response, TestResponse::Echo(i.to_string()));
+    }
+
+    requester.http.channels(POLL_PATH)
+}
+
+#[tokio::test]
+async fn per_request_strategy_uses_request_channels() {
+    let channels = polled_channels("reply-per-request", ReplyChannelStrategy::PerRequest).await;
+
+    let reply_channels = channels.iter()
+        .filter(|channel| is_request_channel(channel))
+        .collect::<std::collections::HashSet<_>>();
+
+    assert_eq!(reply_channels.len(), REQUESTS);
+}
+
+#[tokio::test]
+async fn shared_strategy_creates_no_request_channels() {
+    let channels = polled_channels("reply-shared", ReplyChannelStrategy::Shared).await;
+
+    assert!(channels.iter().any(|channel| channel == "hyperelm@replies"));
+
+    // Responses are received without polling per-request channels
+    assert!(!channels.iter().any(|channel| is_request_channel(channel)), "{channels:?}");
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use hyperelm::prelude::*;
+use hyperelm::client::ReplyChannelStrategy;
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use common::*;
+
+#[tokio::test]
+async fn responders_echo_session_of_the_requester() {
+    let server = server_params("reply-sessions");
+
+    let _handle = start_server(server.clone()).await;
+
+    let receiver = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(50)));
+
+    let receiver_endpoint = receiver.endpoint();
+
+    let _receiver = hyperelm::client::run(receiver).await.unwrap();
+
+    let sender = TestClient::new(&server);
+    let middleware = sender.get_connected_middleware().await.unwrap();
+
+    let channel = Channel::default();
+
+    sender.send_envelope(&middleware, &receiver_endpoint, &channel, &json!({
+        "id": 1,
+        "ping": true,
+        "session": 42
+    })).await.unwrap();
+
+    // Response is sent to the channel of the requester's session
+    let reply_channel = channel.reply_to_session(1, 42);
+
+    let started_at = Instant::now();
+
+    loop {
+        let (messages, _) = middleware.poll(&reply_channel, Some(1)).await.unwrap();
+
+        if !messages.is_empty() {
+            break;
+        }
+
+        assert!(started_at.elapsed() < Duration::from_secs(5), "no response on the session channel");
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    // Built-in requests are answered on their session channels
+    let peers = sender.request_peer_list(receiver_endpoint.clone(), None).await;
+    let state = sender.get_remote_state(receiver_endpoint, "unknown").await;
+
+    assert!(peers.is_ok());
+    assert!(matches!(state.map_err(ClientAppError::into_inner), Err(ClientAppError::Remote(_))));
+}
+
+#[tokio::test]
+async fn stale_responses_of_colliding_ids_are_ignored() {
+    let server = server_params("reply-sessions-stale");
+
+    let _handle = start_server(server.clone()).await;
+
+    let responder = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(50)));
+
+    let responder_endpoint = responder.endpoint();
+
+    let _responder = hyperelm::client::run(responder).await.unwrap();
+
+    let requester = Arc::new(TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .reply_channel_strategy(ReplyChannelStrategy::Shared)));
+
+    let request = tokio::spawn({
+        let requester = requester.clone();
+
+        async move {
+            requester.request(responder_endpoint, TestRequest::Deferred {
+                text: String::from("fresh"),
+                millis: 1500
+            }).await
+        }
+    });
+
+    // Wait until the request is sent
+    let started_at = Instant::now();
+
+    let request_id = loop {
+        if let Some((id, _)) = requester.runtime.inflight_requests.pending().first() {
+            break *id;
+        }
+
+        assert!(started_at.elapsed() < Duration::from_secs(5), "request wasn't sent");
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    };
+
+    let session = requester.runtime.inflight_requests.session();
+
+    // Response of the previous session of the requester
+    // to the request with the same id
+    let stale = TestClient::new(&server);
+    let middleware = stale.get_connected_middleware().await.unwrap();
+
+    stale.send_envelope(&middleware, &requester.endpoint(), &requester.params.channel.replies(), &json!({
+        "id": request_id,
+        "session": session.wrapping_add(1),
+        "response": TestResponse::Echo(String::from("stale")).to_json().unwrap()
+    })).await.unwrap();
+
+    let response = tokio::time::timeout(Duration::from_secs(10), request).await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(response, TestResponse::Echo(String::from("fresh")));
+}
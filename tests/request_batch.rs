@@ -0,0 +1,56 @@
+mod common;
+
+use std::time::Duration;
+
+use hyperelm::prelude::*;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+#[tokio::test]
+async fn failed_batch_items_are_returned_in_order() {
+    let server = server_params("request-batch");
+
+    let _handle = start_server(server.clone()).await;
+
+    let receiver = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(50)));
+
+    let receiver_endpoint = receiver.endpoint();
+
+    let _receiver = hyperelm::client::run(receiver).await.unwrap();
+
+    // Split requests into multiple batches
+    let sender = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .batch_max_items(16));
+
+    let failing = [7, 23, 41];
+
+    let requests = (0..50)
+        .map(|i| {
+            if failing.contains(&i) {
+                TestRequest::Fail
+            } else {
+                TestRequest::Echo(i.to_string())
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let responses = sender.request_batch(receiver_endpoint, requests).await.unwrap();
+
+    assert_eq!(responses.len(), 50);
+    assert_eq!(responses.iter().filter(|response| response.is_ok()).count(), 47);
+
+    for (i, response) in responses.into_iter().enumerate() {
+        if failing.contains(&i) {
+            assert!(response.is_err());
+        } else {
+            assert_eq!(response.unwrap(), TestResponse::Echo(i.to_string()));
+        }
+    }
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use axum::extract::Request;
+use axum::http::{Method, StatusCode};
+use axum::middleware::{from_fn, Next};
+use axum::response::{IntoResponse, Response};
+
+use hyperelm::prelude::*;
+use hyperelm::client::ClientAppParamsBuilder;
+use hyperelm::server::HttpLayer;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+const API_KEY_HEADER: &str = "x-api-key";
+const API_KEY: &str = "secret";
+
+/// Reject POST requests without the API key.
+///
+/// Info requests of the readiness check are still allowed.
+struct RequireApiKey;
+
+impl HttpLayer for RequireApiKey {
+    fn apply(&self, router: axum::Router) -> axum::Router {
+        router.layer(from_fn(require_api_key))
+    }
+}
+
+async fn require_api_key(request: Request, next: Next) -> Response {
+    let authorized = request.method() != Method::POST || request.headers()
+        .get(API_KEY_HEADER)
+        .is_some_and(|value| value == API_KEY);
+
+    if !authorized {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    next.run(request).await
+}
+
+struct ApiKeyServer(ServerAppParams);
+
+impl BasicServerApp for ApiKeyServer {
+    fn get_params(&self) -> &ServerAppParams {
+        &self.0
+    }
+
+    fn http_layers(&self) -> Vec<Box<dyn HttpLayer>> {
+        vec![Box::new(RequireApiKey)]
+    }
+}
+
+fn client_params(server: &ServerAppParams) -> ClientAppParamsBuilder {
+    ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+}
+
+#[tokio::test]
+async fn extra_headers_are_sent_by_built_middleware() {
+    let server = server_params("request-headers");
+
+    let handle = hyperelm::server::spawn(ApiKeyServer(server.clone()));
+
+    handle.ready().await.unwrap();
+
+    let address = server.local_address().to_string();
+
+    let with_header = client_params(&server)
+        .header(API_KEY_HEADER, API_KEY)
+        .build()
+        .unwrap();
+
+    let without_header = client_params(&server)
+        .build()
+        .unwrap();
+
+    let connected = with_header.build_middleware().unwrap()
+        .connect_to(&address, server.secret_key.public()).await;
+
+    let rejected = without_header.build_middleware().unwrap()
+        .connect_to(&address, server.secret_key.public()).await;
+
+    assert!(connected.is_ok());
+    assert!(rejected.is_err());
+
+    // Client applications send the headers as well
+    let client = TestClient::with_params(client_params(&server).header(API_KEY_HEADER, API_KEY));
+
+    assert!(client.get_connected_middleware().await.is_ok());
+}
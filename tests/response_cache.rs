@@ -0,0 +1,51 @@
+mod common;
+
+use std::time::Duration;
+
+use hyperelm::prelude::*;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+const SEND_PATH: &str = "/api/v1/send";
+
+#[tokio::test]
+async fn cacheable_requests_are_sent_once() {
+    let server = server_params("response-cache");
+
+    let _handle = start_server(server.clone()).await;
+
+    let responder = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(50)));
+
+    let endpoint = responder.endpoint();
+
+    let _responder = hyperelm::client::run(responder).await.unwrap();
+
+    let requester = TestClient::new(&server);
+
+    let request = TestRequest::Echo(String::from("cached profile"));
+
+    let first = requester.request(endpoint.clone(), request.clone()).await.unwrap();
+    let second = requester.request(endpoint.clone(), request.clone()).await.unwrap();
+
+    assert_eq!(first, second);
+    assert_eq!(requester.http.count(SEND_PATH), 1);
+
+    // Forced refresh goes to the network
+    let refreshed = requester.request_bypass_cache(endpoint.clone(), request.clone()).await.unwrap();
+
+    assert_eq!(refreshed, first);
+    assert_eq!(requester.http.count(SEND_PATH), 2);
+
+    // Not cacheable requests are always sent
+    for _ in 0..2 {
+        requester.request(endpoint.clone(), TestRequest::Echo(String::from("profile"))).await
+            .unwrap();
+    }
+
+    assert_eq!(requester.http.count(SEND_PATH), 4);
+}
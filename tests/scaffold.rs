@@ -0,0 +1,77 @@
+mod common;
+
+use hyperelm::prelude::*;
+use hyperelm::scaffold;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+#[test]
+fn generated_params_round_trip() {
+    let folder = temp_folder("scaffold-round-trip");
+
+    scaffold::write_default_params(&folder).unwrap();
+
+    let server_secret = scaffold::read_secret_key(folder.join("server.key")).unwrap();
+    let client_secret = scaffold::read_secret_key(folder.join("client.key")).unwrap();
+
+    let server = scaffold::load_server_params(folder.join("server.json")).unwrap();
+    let client = scaffold::load_client_params(folder.join("client.json")).unwrap()
+        .build()
+        .unwrap();
+
+    assert_eq!(server.secret_key, server_secret);
+    assert_eq!(client.identity.secret(), client_secret);
+    assert_eq!(client.server_public, server_secret.public());
+
+    // Secret keys are stored as base64 strings
+    let json = serde_json::to_value(&server).unwrap();
+
+    assert_eq!(json["secret_key"], server_secret.to_base64());
+
+    let restored = serde_json::from_value::<ServerAppParams>(json).unwrap();
+
+    assert_eq!(restored.secret_key, server.secret_key);
+    assert_eq!(restored.local_addresses, server.local_addresses);
+    assert_eq!(restored.traverse_delay, server.traverse_delay);
+
+    let restored = toml::from_str::<ClientAppParams>(&toml::to_string(&client).unwrap()).unwrap();
+
+    assert_eq!(restored.identity.secret(), client_secret);
+    assert_eq!(restored.server_address, client.server_address);
+    assert_eq!(restored.channel, client.channel);
+}
+
+#[test]
+fn malformed_keys_are_refused() {
+    let folder = temp_folder("scaffold-malformed");
+
+    scaffold::write_default_params(&folder).unwrap();
+
+    let server_secret = scaffold::read_secret_key(folder.join("server.key")).unwrap();
+    let client_secret = scaffold::read_secret_key(folder.join("client.key")).unwrap();
+
+    let server = std::fs::read_to_string(folder.join("server.json")).unwrap()
+        .replace(&server_secret.to_base64(), "not a key");
+
+    let client = std::fs::read_to_string(folder.join("client.json")).unwrap()
+        .replace(&client_secret.to_base64(), "bm90IGEga2V5");
+
+    std::fs::write(folder.join("server.json"), server).unwrap();
+    std::fs::write(folder.join("client.json"), client).unwrap();
+    std::fs::write(folder.join("server.key"), "not a key").unwrap();
+
+    let err = scaffold::load_server_params(folder.join("server.json")).unwrap_err();
+
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("invalid secret key"));
+
+    let err = scaffold::load_client_params(folder.join("client.json")).unwrap_err();
+
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    let err = scaffold::read_secret_key(folder.join("server.key")).unwrap_err();
+
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
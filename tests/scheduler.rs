@@ -0,0 +1,75 @@
+mod common;
+
+use std::time::{Duration, SystemTime};
+
+use hyperelm::prelude::*;
+use hyperelm::client::Scheduler;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+#[test]
+fn due_messages_are_taken_in_time_order() {
+    let scheduler = Scheduler::new(false);
+
+    let endpoint = ClientEndpoint::new("127.0.0.1:1", SecretKey::random().public());
+
+    let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+
+    let third = scheduler.schedule(endpoint.clone(), serde_json::json!(3), now + Duration::from_secs(3));
+    let first = scheduler.schedule(endpoint.clone(), serde_json::json!(1), now + Duration::from_secs(1));
+    let second = scheduler.schedule(endpoint, serde_json::json!(2), now + Duration::from_secs(2));
+
+    assert!(scheduler.cancel(second));
+    assert!(!scheduler.cancel(second));
+
+    assert!(scheduler.take_due(now).is_empty());
+
+    let due = scheduler.take_due(now + Duration::from_secs(5))
+        .into_iter()
+        .map(|message| message.id)
+        .collect::<Vec<_>>();
+
+    assert_eq!(due, [first, third]);
+    assert!(scheduler.is_empty());
+}
+
+#[tokio::test]
+async fn scheduled_messages_are_sent_in_order() {
+    let server = server_params("scheduler");
+
+    let _handle = start_server(server.clone()).await;
+
+    let receiver = TestClient::new(&server);
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    let sender = TestClient::new(&server);
+
+    let now = SystemTime::now();
+
+    let schedule = |text: &str, delay: u64| {
+        sender.schedule_send(receiver.endpoint(), TestMessage::Text(String::from(text)), now + Duration::from_millis(delay))
+            .unwrap()
+    };
+
+    schedule("third", 300);
+    schedule("first", 100);
+
+    let canceled = schedule("second", 200);
+
+    assert!(sender.cancel_scheduled(canceled));
+
+    // Nothing is due yet
+    assert_eq!(sender.send_scheduled().await, 0);
+
+    tokio::time::sleep(Duration::from_millis(400)).await;
+
+    assert_eq!(sender.send_scheduled().await, 2);
+    assert_eq!(sender.send_scheduled().await, 0);
+
+    receiver.update_batch().await.unwrap();
+
+    assert_eq!(*receiver.state.received.lock().unwrap(), ["first", "third"]);
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use std::time::Duration;
+
+use hyperelm::prelude::*;
+use hyperelm::server::ServerRunError;
+
+use common::*;
+
+/// Server failing to open its router.
+struct BrokenRouterServer(TestServer);
+
+#[async_trait::async_trait]
+impl ServerApp for BrokenRouterServer {
+    type Router = <TestServer as ServerApp>::Router;
+    type Traversal = <TestServer as ServerApp>::Traversal;
+    type MessagesInbox = <TestServer as ServerApp>::MessagesInbox;
+
+    type HttpClient = <TestServer as ServerApp>::HttpClient;
+    type HttpServer = <TestServer as ServerApp>::HttpServer;
+
+    type Error = std::io::Error;
+
+    async fn get_router(&self) -> Result<Self::Router, Self::Error> {
+        Err(std::io::Error::other("router is broken"))
+    }
+
+    async fn get_traversal(&self) -> Result<Self::Traversal, Self::Error> {
+        self.0.get_traversal().await
+    }
+
+    async fn get_messages_inbox(&self) -> Result<Self::MessagesInbox, Self::Error> {
+        self.0.get_messages_inbox().await
+    }
+
+    async fn get_http_client(&self) -> Result<Self::HttpClient, Self::Error> {
+        self.0.get_http_client().await
+    }
+
+    async fn get_http_server(&self) -> Result<Self::HttpServer, Self::Error> {
+        self.0.get_http_server().await
+    }
+
+    fn get_params(&self) -> &ServerAppParams {
+        ServerApp::get_params(&self.0)
+    }
+}
+
+#[tokio::test]
+async fn middleware_init_failure_is_returned() {
+    let mut params = server_params("server-errors-middleware");
+
+    params.init_retries = 0;
+    params.init_retry_delay = Duration::ZERO;
+
+    let result = tokio::time::timeout(
+        Duration::from_secs(10),
+        hyperelm::server::run(BrokenRouterServer(TestServer(params)))
+    ).await.unwrap();
+
+    assert!(matches!(result, Err(ServerRunError::MiddlewareInit(err)) if err.to_string() == "router is broken"));
+}
+
+#[tokio::test]
+async fn bind_failure_is_returned() {
+    let params = server_params("server-errors-bind");
+
+    // Occupy the server's address
+    let _listener = std::net::TcpListener::bind(params.local_address()).unwrap();
+
+    let result = tokio::time::timeout(
+        Duration::from_secs(10),
+        hyperelm::server::run(TestServer(params))
+    ).await.unwrap();
+
+    assert!(matches!(result, Err(ServerRunError::Bind(_))));
+}
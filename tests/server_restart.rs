@@ -0,0 +1,46 @@
+mod common;
+
+use std::time::Duration;
+
+use hyperelm::prelude::*;
+use hyperelm::server::STARTUP_PATH;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+#[tokio::test]
+async fn server_restart_is_detected() {
+    let server = server_params("server-restart");
+
+    let handle = start_server(server.clone()).await;
+
+    let client = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .restart_check_interval(Some(Duration::ZERO))
+        .auto_reconnect(false));
+
+    // Startup token is recorded on connect
+    client.get_connected_middleware().await.unwrap();
+
+    assert_eq!(client.http.count(STARTUP_PATH), 1);
+
+    assert!(!client.check_server_restart().await.unwrap());
+
+    // Restart the server on the same address
+    handle.shutdown();
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while reqwest::get(format!("http://{}{STARTUP_PATH}", server.local_address())).await.is_ok() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }).await.unwrap();
+
+    let restarted = start_server(server.clone()).await;
+
+    assert_ne!(restarted.startup_token(), handle.startup_token());
+
+    assert!(client.check_server_restart().await.unwrap());
+    assert!(!client.check_server_restart().await.unwrap());
+}
@@ -0,0 +1,29 @@
+mod common;
+
+use hyperelm::prelude::*;
+use hyperelm::client::Direction;
+
+use common::*;
+
+#[tokio::test]
+async fn processed_messages_are_counted() {
+    let server = server_params("server-stats");
+
+    let handle = start_server(server.clone()).await;
+
+    let sender = TestClient::new(&server);
+    let receiver = TestClient::new(&server);
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    for i in 0..3 {
+        sender.send(receiver.endpoint(), TestMessage::Text(format!("message {i}"))).await.unwrap();
+    }
+
+    assert_eq!(handle.stats().messages_processed(Direction::Incoming), 3);
+
+    receiver.update().await.unwrap();
+
+    assert_eq!(receiver.state.received.lock().unwrap().len(), 3);
+    assert_eq!(handle.stats().messages_processed(Direction::Outgoing), 3);
+}
@@ -0,0 +1,42 @@
+#![cfg(feature = "group-encryption")]
+
+mod common;
+
+use hyperelm::prelude::*;
+use hyperelm::client::ChannelSecurity;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+#[tokio::test]
+async fn shared_secret_channel_messages_are_received() {
+    let server = server_params("shared-channel");
+
+    let _handle = start_server(server.clone()).await;
+
+    let channel = Channel::new("shared").unwrap();
+    let security = ChannelSecurity::shared_secret([7; 32]);
+
+    let sender = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .channel_security(channel.clone(), security.clone()));
+
+    let receiver = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .channel_security(channel.clone(), security));
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    sender.send_to_channel(&channel, &[receiver.endpoint()], TestMessage::Text(String::from("shared"))).await
+        .unwrap();
+
+    // Shared channel is polled and its message decrypted
+    assert_eq!(receiver.fetch_messages().await.unwrap(), 1);
+
+    receiver.update_batch().await.unwrap();
+
+    assert_eq!(*receiver.state.received.lock().unwrap(), ["shared"]);
+}
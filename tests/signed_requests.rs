@@ -0,0 +1,80 @@
+mod common;
+
+use serde_json::json;
+
+use hyperelm::server::*;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+const KNOWN_ADDRESS: &str = "known.example:8001";
+
+/// Serve REST API verifying signatures of the server-to-server
+/// requests with the key of the single known server.
+async fn serve_rest_api(known: PublicKey) -> String {
+    let router = axum::Router::new().fallback(|| async {
+        axum::Json(json!({ "status": "ok" }))
+    });
+
+    serve(require_signed_paths(
+        router,
+        SERVER_TO_SERVER_PATHS,
+        1024 * 1024,
+        move |address: String| {
+            let known = known.clone();
+
+            async move {
+                (address == KNOWN_ADDRESS).then_some(known)
+            }
+        }
+    )).await
+}
+
+async fn get_signed(server: &str, path: &str, secret: &SecretKey, address: &str) -> reqwest::StatusCode {
+    let (signature, timestamp) = sign_request(secret, "GET", path, &[]);
+
+    reqwest::Client::new()
+        .get(format!("http://{server}{path}"))
+        .header(SIGNATURE_HEADER, signature)
+        .header(TIMESTAMP_HEADER, timestamp)
+        .header(ADDRESS_HEADER, address)
+        .send().await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn rest_api_verifies_known_server_keys() {
+    let known = SecretKey::random();
+    let server = serve_rest_api(known.public()).await;
+
+    // Signed by the known server
+    let status = get_signed(&server, "/api/v1/servers", &known, KNOWN_ADDRESS).await;
+
+    assert_eq!(status, reqwest::StatusCode::OK);
+
+    // Signed by another key claiming the known address
+    let status = get_signed(&server, "/api/v1/servers", &SecretKey::random(), KNOWN_ADDRESS).await;
+
+    assert_eq!(status, reqwest::StatusCode::UNAUTHORIZED);
+
+    // Signed by the server unknown to the router
+    let status = get_signed(&server, "/api/v1/servers", &known, "unknown.example:8001").await;
+
+    assert_eq!(status, reqwest::StatusCode::UNAUTHORIZED);
+
+    // Not signed at all
+    let status = reqwest::get(format!("http://{server}/api/v1/clients")).await
+        .unwrap()
+        .status();
+
+    assert_eq!(status, reqwest::StatusCode::UNAUTHORIZED);
+
+    // Client endpoints are not verified
+    let status = reqwest::get(format!("http://{server}/api/v1/info")).await
+        .unwrap()
+        .status();
+
+    assert_eq!(status, reqwest::StatusCode::OK);
+}
@@ -0,0 +1,34 @@
+#![cfg(feature = "tls-self-signed")]
+
+mod common;
+
+use hyperelm::prelude::*;
+use hyperelm::server::generate_self_signed_tls_cert;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+#[tokio::test]
+async fn rest_api_is_served_over_https() {
+    let mut server = server_params("tls");
+
+    server.tls = Some(generate_self_signed_tls_cert(&server.backend_folder.join("tls")).unwrap());
+
+    // Server checks its readiness over HTTPS
+    let _handle = start_server(server.clone()).await;
+
+    let client = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .https(true)
+        .accept_invalid_certs(true));
+
+    assert!(client.get_connected_middleware().await.is_ok());
+
+    // Plain HTTP requests are not served
+    let result = reqwest::get(format!("http://{}/api/v1/info", server.local_address())).await
+        .and_then(|response| response.error_for_status());
+
+    assert!(result.is_err());
+}
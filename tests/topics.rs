@@ -0,0 +1,66 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use hyperelm::prelude::*;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+const TOPIC: &str = "news";
+
+/// Wait until the topic has given amount of subscribers.
+async fn wait_subscribers(publisher: &TestClient, count: usize) {
+    let started_at = Instant::now();
+
+    while publisher.runtime.topics.subscribers(TOPIC).len() != count {
+        assert!(started_at.elapsed() < Duration::from_secs(5), "topic doesn't have {count} subscribers");
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+#[tokio::test]
+async fn published_messages_reach_current_subscribers() {
+    let server = server_params("topics");
+
+    let _handle = start_server(server.clone()).await;
+
+    let publisher = TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(50)));
+
+    let provider = publisher.endpoint();
+
+    let publisher = hyperelm::client::run(publisher).await.unwrap();
+
+    let staying = TestClient::new(&server);
+    let leaving = TestClient::new(&server);
+
+    staying.subscribe(TOPIC, provider.clone()).await.unwrap();
+    leaving.subscribe(TOPIC, provider.clone()).await.unwrap();
+
+    wait_subscribers(&publisher, 2).await;
+
+    let delivered = publisher.publish(TOPIC, TestMessage::Text(String::from("everyone"))).await
+        .unwrap();
+
+    assert_eq!(delivered, 2);
+
+    leaving.unsubscribe(TOPIC, provider).await.unwrap();
+
+    wait_subscribers(&publisher, 1).await;
+
+    let delivered = publisher.publish(TOPIC, TestMessage::Text(String::from("staying"))).await
+        .unwrap();
+
+    assert_eq!(delivered, 1);
+
+    staying.update_batch().await.unwrap();
+    leaving.update_batch().await.unwrap();
+
+    assert_eq!(*staying.state.received.lock().unwrap(), ["everyone", "staying"]);
+    assert_eq!(*leaving.state.received.lock().unwrap(), ["everyone"]);
+}
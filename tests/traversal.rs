@@ -0,0 +1,95 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use hyperelm::prelude::*;
+use hyperelm::server::{traverse_bfs, TraversalConfig, ServerHandle};
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::drivers::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use common::*;
+
+const SERVERS_PATH: &str = "/api/v1/servers";
+
+/// Start the server which knows only the given one.
+async fn start_linked_server(name: &str, next: Option<&ServerAppParams>) -> (ServerAppParams, ServerHandle) {
+    let mut params = server_params(name);
+
+    // Don't let servers discover the whole chain themselves
+    params.traversal_config = TraversalConfig {
+        max_depth: Some(0),
+        max_nodes: None
+    };
+
+    params.bootstrap = next.map(|next| vec![next.local_address().to_string()])
+        .unwrap_or_default();
+
+    let handle = start_server(params.clone()).await;
+
+    // Wait until the next server is indexed
+    if let Some(next) = next {
+        let client = ClientMiddleware::new(
+            CountingHttpClient::default(),
+            ClientDriver::new(ClientInfo::thin(), SecretKey::random())
+        );
+
+        let started_at = Instant::now();
+
+        loop {
+            let servers = client.get_servers(params.local_address()).await
+                .unwrap_or_default();
+
+            if servers.iter().any(|server| server.public_key == next.secret_key.public()) {
+                break;
+            }
+
+            assert!(started_at.elapsed() < Duration::from_secs(10), "{name} didn't index the next server");
+
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    (params, handle)
+}
+
+#[tokio::test]
+async fn traversal_stops_at_max_depth() {
+    // Chain of 4 servers, each one knowing only the next one
+    let (fourth, _fourth_handle) = start_linked_server("traversal-4", None).await;
+    let (third, _third_handle) = start_linked_server("traversal-3", Some(&fourth)).await;
+    let (second, _second_handle) = start_linked_server("traversal-2", Some(&third)).await;
+    let (first, _first_handle) = start_linked_server("traversal-1", Some(&second)).await;
+
+    let router = GlobalTableRouter::new(temp_folder("traversal-router")).await.unwrap();
+
+    router.index_server(Server::new(first.secret_key.public(), first.local_address())).await.unwrap();
+
+    let http = CountingHttpClient::default();
+
+    let client = ClientMiddleware::new(
+        http.clone(),
+        ClientDriver::new(ClientInfo::thin(), SecretKey::random())
+    );
+
+    let config = TraversalConfig {
+        max_depth: Some(2),
+        max_nodes: None
+    };
+
+    let indexed = traverse_bfs(&client, &router, config, |_| true).await.unwrap();
+
+    let known = router.servers().await.unwrap()
+        .into_iter()
+        .map(|server| server.public_key)
+        .collect::<Vec<_>>();
+
+    // Only the first two levels are requested
+    assert_eq!(http.count(SERVERS_PATH), 2);
+    assert_eq!(indexed, 2);
+
+    assert!(known.contains(&second.secret_key.public()));
+    assert!(known.contains(&third.secret_key.public()));
+    assert!(!known.contains(&fourth.secret_key.public()));
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use hyperelm::prelude::*;
+
+use common::*;
+
+#[tokio::test]
+async fn undecodable_messages_are_counted() {
+    let server = server_params("undecodable-messages");
+
+    let _handle = start_server(server.clone()).await;
+
+    let sender = TestClient::new(&server);
+    let receiver = TestClient::new(&server);
+
+    receiver.get_connected_middleware().await.unwrap();
+
+    // Send envelope which is not a valid JSON
+    let middleware = sender.get_connected_middleware().await.unwrap();
+
+    sender.send_raw_envelope(&middleware, &receiver.endpoint(), &Channel::default(), b"not a json".to_vec()).await
+        .unwrap();
+
+    sender.send(receiver.endpoint(), TestMessage::Text(String::from("valid"))).await
+        .unwrap();
+
+    // Invalid message is dropped without failing the valid one
+    assert_eq!(receiver.fetch_messages().await.unwrap(), 1);
+    assert_eq!(receiver.undecodable_message_count(), 1);
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use hyperelm::prelude::*;
+use hyperelm::client::{Validator, ValidationError};
+
+use hyperborealib::crypto::prelude::*;
+use hyperborealib::rest_api::prelude::*;
+
+use common::*;
+
+/// Client accepting echo requests with
+/// non-empty texts of at most 16 characters.
+struct ValidatingClient(TestClient);
+
+#[async_trait::async_trait]
+impl ClientApp for ValidatingClient {
+    build_client!(
+        input: TestRequest => TestResponse, TestMessage;
+        output: TestRequest => TestResponse, TestMessage;
+
+        client: CountingHttpClient;
+        state: TestState;
+        error: String;
+
+        requests: {
+            TestRequest::Echo(text) => |state: Arc<TestState>, _| async move {
+                state.received.lock().unwrap().push(text.clone());
+
+                Ok(TestResponse::Echo(text))
+            }
+        };
+
+        messages: {
+            TestMessage::Text(text) => |state: Arc<TestState>, _| async move {
+                state.received.lock().unwrap().push(text);
+
+                Ok(())
+            }
+        };
+    );
+
+    fn get_params(&self) -> &ClientAppParams {
+        &self.0.params
+    }
+
+    fn get_runtime(&self) -> &ClientRuntime {
+        &self.0.runtime
+    }
+
+    fn get_middleware(&self) -> &ClientMiddleware<Self::HttpClient> {
+        &self.0.middleware
+    }
+
+    fn get_state(&self) -> Arc<Self::State> {
+        self.0.state.clone()
+    }
+
+    fn validate_request(&self, request: &TestRequest, _info: &MessageInfo) -> Result<(), ValidationError> {
+        match request {
+            TestRequest::Echo(text) => Validator::new()
+                .non_empty("text", text)
+                .max_len("text", text, 16)
+                .finish(),
+
+            _ => Ok(())
+        }
+    }
+}
+
+#[tokio::test]
+async fn invalid_requests_are_rejected_remotely() {
+    let server = server_params("validation");
+
+    let _handle = start_server(server.clone()).await;
+
+    let responder = ValidatingClient(TestClient::with_params(ClientAppParams::builder()
+        .client(SecretKey::random())
+        .server(server.secret_key.public(), server.local_address())
+        .delay(Duration::from_millis(50))));
+
+    let endpoint = responder.0.endpoint();
+    let state = responder.0.state.clone();
+
+    let _responder = hyperelm::client::run(responder).await.unwrap();
+
+    let requester = TestClient::new(&server);
+
+    // Rejected request never reaches the handler
+    let result = requester.request(endpoint.clone(), TestRequest::Echo(String::from("   "))).await
+        .map_err(ClientAppError::into_inner);
+
+    let Err(ClientAppError::Remote(error)) = result else {
+        panic!("request must be rejected with a remote error, got {result:?}");
+    };
+
+    assert_eq!(error.kind, "validation");
+    assert_eq!(error.field.as_deref(), Some("text"));
+
+    let result = requester.request(endpoint.clone(), TestRequest::Echo("a".repeat(17))).await;
+
+    assert!(matches!(result.map_err(ClientAppError::into_inner), Err(ClientAppError::Remote(error)) if error.kind == "validation"));
+
+    // Valid request passes
+    let response = requester.request(endpoint, TestRequest::Echo(String::from("valid"))).await
+        .unwrap();
+
+    assert_eq!(response, TestResponse::Echo(String::from("valid")));
+    assert_eq!(*state.received.lock().unwrap(), ["valid"]);
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use std::time::Duration;
+
+use hyperelm::prelude::*;
+
+use hyperborealib::crypto::prelude::*;
+
+use common::*;
+
+const LOOKUP_PATH: &str = "/api/v1/lookup";
+
+#[tokio::test]
+async fn warmed_up_peers_are_requested_without_lookups() {
+    let server = server_params("warm-up");
+
+    let _handle = start_server(server.clone()).await;
+
+    let mut peers = Vec::new();
+
+    for _ in 0..2 {
+        let peer = TestClient::with_params(ClientAppParams::builder()
+            .client(SecretKey::random())
+            .server(server.secret_key.public(), server.local_address())
+            .delay(Duration::from_millis(50)));
+
+        peer.get_connected_middleware().await.unwrap();
+
+        peers.push(peer.params.identity.public());
+
+        let _peer = hyperelm::client::run(peer).await.unwrap();
+    }
+
+    let client = TestClient::new(&server);
+
+    let report = client.warm_up(&peers).await;
+
+    for peer in &peers {
+        assert!(report.get(peer).is_some_and(|status| status.is_resolved()));
+    }
+
+    assert_eq!(client.http.count(LOOKUP_PATH), 2);
+
+    // Endpoints are taken from the peer cache
+    client.http.reset();
+
+    for peer in &peers {
+        let endpoint = client.lookup_cached(peer.clone(), None).await
+            .unwrap()
+            .unwrap();
+
+        let response = client.request(endpoint, TestRequest::Echo(String::from("warm"))).await
+            .unwrap();
+
+        assert_eq!(response, TestResponse::Echo(String::from("warm")));
+    }
+
+    assert_eq!(client.http.count(LOOKUP_PATH), 0);
+}